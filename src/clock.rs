@@ -0,0 +1,98 @@
+//! Clock abstraction for time-dependent logic.
+//!
+//! Components that compare against the current time, such as
+//! [`JwtValidator`](crate::jwt::JwtValidator), read it from a [`Clock`], so
+//! they can be tested deterministically with a [`FixedClock`].
+
+use std::{
+    sync::{Arc, Mutex, PoisonError},
+    time::Duration,
+};
+
+use web_time::SystemTime;
+
+use crate::MaybeSendSync;
+
+/// Trait for reading the current time.
+pub trait Clock: MaybeSendSync {
+    /// Returns the current time.
+    fn now(&self) -> SystemTime;
+}
+
+impl<C: Clock + ?Sized> Clock for Arc<C> {
+    fn now(&self) -> SystemTime {
+        (**self).now()
+    }
+}
+
+impl<C: Clock + ?Sized> Clock for &C {
+    fn now(&self) -> SystemTime {
+        (**self).now()
+    }
+}
+
+/// The system clock, the default for every component using a [`Clock`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+/// A clock that only moves when told to, for tests.
+///
+/// Clones share the same time, so a clone can be handed to the component
+/// under test while the original is advanced.
+#[derive(Debug, Clone)]
+pub struct FixedClock {
+    now: Arc<Mutex<SystemTime>>,
+}
+
+impl FixedClock {
+    /// Creates a clock stopped at the given time.
+    #[must_use]
+    pub fn new(now: SystemTime) -> Self {
+        Self {
+            now: Arc::new(Mutex::new(now)),
+        }
+    }
+
+    /// Sets the current time.
+    pub fn set(&self, now: SystemTime) {
+        *self.lock() = now;
+    }
+
+    /// Moves the current time forward.
+    pub fn advance(&self, duration: Duration) {
+        *self.lock() += duration;
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, SystemTime> {
+        self.now.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Clock for FixedClock {
+    fn now(&self) -> SystemTime {
+        *self.lock()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use web_time::UNIX_EPOCH;
+
+    use super::*;
+
+    #[test]
+    fn test_fixed_clock_advances_across_clones() {
+        let clock = FixedClock::new(UNIX_EPOCH);
+        let clone = clock.clone();
+
+        clock.advance(Duration::from_secs(5));
+
+        assert_eq!(clone.now(), UNIX_EPOCH + Duration::from_secs(5));
+    }
+}
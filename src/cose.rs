@@ -0,0 +1,189 @@
+//! CBOR Object Signing and Encryption (COSE) types per RFC 9052.
+//!
+//! This module provides the minimal CBOR encoding needed to produce
+//! `COSE_Sign1` structures with a [`CoseSigner`](crate::signer::CoseSigner).
+
+use bytes::Bytes;
+
+const MAJOR_UNSIGNED: u8 = 0;
+const MAJOR_NEGATIVE: u8 = 1;
+const MAJOR_BYTES: u8 = 2;
+const MAJOR_TEXT: u8 = 3;
+const MAJOR_ARRAY: u8 = 4;
+const MAJOR_MAP: u8 = 5;
+const MAJOR_TAG: u8 = 6;
+
+/// The CBOR tag for a `COSE_Sign1` structure (RFC 9052 §4.2).
+const TAG_COSE_SIGN1: u64 = 18;
+
+/// The header label of the algorithm parameter.
+const LABEL_ALG: i64 = 1;
+/// The header label of the key ID parameter.
+const LABEL_KID: i64 = 4;
+
+/// Returns the COSE algorithm identifier for a JWS algorithm, if the algorithm
+/// is registered for both, with the same signature format.
+///
+/// See <https://www.iana.org/assignments/cose/cose.xhtml#algorithms>.
+#[must_use]
+pub fn algorithm_for_jws(jws_algorithm: &str) -> Option<i64> {
+    match jws_algorithm {
+        "ES256" => Some(-7),
+        "EdDSA" => Some(-8),
+        "ES384" => Some(-35),
+        "ES512" => Some(-36),
+        "PS256" => Some(-37),
+        "PS384" => Some(-38),
+        "PS512" => Some(-39),
+        "ES256K" => Some(-47),
+        #[cfg(feature = "unstable-pqc")]
+        "ML-DSA-44" => Some(-48),
+        #[cfg(feature = "unstable-pqc")]
+        "ML-DSA-65" => Some(-49),
+        #[cfg(feature = "unstable-pqc")]
+        "ML-DSA-87" => Some(-50),
+        "RS256" => Some(-257),
+        "RS384" => Some(-258),
+        "RS512" => Some(-259),
+        _ => None,
+    }
+}
+
+fn write_head(out: &mut Vec<u8>, major: u8, value: u64) {
+    // Additional information values for a 1, 2, 4 or 8-byte argument.
+    const ARG_U8: u8 = 0x18;
+    const ARG_U16: u8 = 0x19;
+    const ARG_U32: u8 = 0x1a;
+    const ARG_U64: u8 = 0x1b;
+
+    let major = major << 5;
+    if let Ok(value) = u8::try_from(value) {
+        if value < ARG_U8 {
+            out.push(major | value);
+        } else {
+            out.extend_from_slice(&[major | ARG_U8, value]);
+        }
+    } else if let Ok(value) = u16::try_from(value) {
+        out.push(major | ARG_U16);
+        out.extend_from_slice(&value.to_be_bytes());
+    } else if let Ok(value) = u32::try_from(value) {
+        out.push(major | ARG_U32);
+        out.extend_from_slice(&value.to_be_bytes());
+    } else {
+        out.push(major | ARG_U64);
+        out.extend_from_slice(&value.to_be_bytes());
+    }
+}
+
+fn write_int(out: &mut Vec<u8>, value: i64) {
+    match u64::try_from(value) {
+        Ok(value) => write_head(out, MAJOR_UNSIGNED, value),
+        // -1 - value is non-negative for any negative value.
+        Err(_) => write_head(out, MAJOR_NEGATIVE, (-1 - value).unsigned_abs()),
+    }
+}
+
+fn write_bytes(out: &mut Vec<u8>, bytes: &[u8]) {
+    write_head(out, MAJOR_BYTES, bytes.len() as u64);
+    out.extend_from_slice(bytes);
+}
+
+/// Encodes a protected header containing the algorithm and key ID.
+///
+/// The result is the serialized header map, to be wrapped in a byte string.
+#[must_use]
+pub fn encode_protected_header(cose_algorithm: i64, key_id: Option<&[u8]>) -> Vec<u8> {
+    let mut out = Vec::new();
+    write_head(&mut out, MAJOR_MAP, if key_id.is_some() { 2 } else { 1 });
+    write_int(&mut out, LABEL_ALG);
+    write_int(&mut out, cose_algorithm);
+    if let Some(key_id) = key_id {
+        write_int(&mut out, LABEL_KID);
+        write_bytes(&mut out, key_id);
+    }
+    out
+}
+
+/// Builds the `Sig_structure` signed for a `COSE_Sign1` (RFC 9052 §4.4).
+#[must_use]
+pub fn sig_structure1(protected_header: &[u8], external_aad: &[u8], payload: &[u8]) -> Vec<u8> {
+    const CONTEXT: &str = "Signature1";
+    let mut out = Vec::with_capacity(
+        16 + CONTEXT.len() + protected_header.len() + external_aad.len() + payload.len(),
+    );
+    write_head(&mut out, MAJOR_ARRAY, 4);
+    write_head(&mut out, MAJOR_TEXT, CONTEXT.len() as u64);
+    out.extend_from_slice(CONTEXT.as_bytes());
+    write_bytes(&mut out, protected_header);
+    write_bytes(&mut out, external_aad);
+    write_bytes(&mut out, payload);
+    out
+}
+
+/// A `COSE_Sign1` signature, together with the protected header it covers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sign1 {
+    protected_header: Bytes,
+    signature: Bytes,
+}
+
+impl Sign1 {
+    pub(crate) fn new(protected_header: Bytes, signature: Bytes) -> Self {
+        Self {
+            protected_header,
+            signature,
+        }
+    }
+
+    /// Returns the serialized protected header.
+    #[must_use]
+    pub fn protected_header(&self) -> &Bytes {
+        &self.protected_header
+    }
+
+    /// Returns the signature bytes.
+    #[must_use]
+    pub fn signature(&self) -> &Bytes {
+        &self.signature
+    }
+
+    /// Serializes the tagged `COSE_Sign1` structure for the given payload,
+    /// with an empty unprotected header.
+    #[must_use]
+    pub fn to_cbor(&self, payload: &[u8]) -> Vec<u8> {
+        let mut out = Vec::with_capacity(
+            16 + self.protected_header.len() + payload.len() + self.signature.len(),
+        );
+        write_head(&mut out, MAJOR_TAG, TAG_COSE_SIGN1);
+        write_head(&mut out, MAJOR_ARRAY, 4);
+        write_bytes(&mut out, &self.protected_header);
+        write_head(&mut out, MAJOR_MAP, 0);
+        write_bytes(&mut out, payload);
+        write_bytes(&mut out, &self.signature);
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_protected_header() {
+        assert_eq!(encode_protected_header(-7, None), [0xa1, 0x01, 0x26]);
+        assert_eq!(
+            encode_protected_header(-257, Some(b"k")),
+            [0xa2, 0x01, 0x39, 0x01, 0x00, 0x04, 0x41, b'k']
+        );
+    }
+
+    #[test]
+    fn test_sig_structure1() {
+        let structure = sig_structure1(&[0xa1, 0x01, 0x26], b"", b"hi");
+
+        let mut expected = vec![0x84, 0x6a];
+        expected.extend_from_slice(b"Signature1");
+        expected.extend_from_slice(&[0x43, 0xa1, 0x01, 0x26, 0x40, 0x42, b'h', b'i']);
+        assert_eq!(structure, expected);
+    }
+}
@@ -0,0 +1,13 @@
+//! OAuth 2.0 Demonstrating Proof of Possession (DPoP) per RFC 9449.
+//!
+//! A DPoP proof is a JWT, signed with the client's key and sent in the `DPoP`
+//! header of each HTTP request, binding the request (and any access token it
+//! presents) to that key. [`DpopProofBuilder`] creates proofs with a
+//! [`JwsSigner`](crate::signer::JwsSigner), and keeps track of the nonces
+//! servers require in proofs with a [`DpopNonceStore`].
+
+mod nonce;
+mod proof;
+
+pub use nonce::{DpopNonceStore, InMemoryDpopNonceStore, USE_DPOP_NONCE};
+pub use proof::{DpopError, DpopProofBuilder};
@@ -0,0 +1,100 @@
+//! Server-provided DPoP nonces (RFC 9449 §8, §9).
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, PoisonError, RwLock},
+};
+
+use crate::{MaybeSend, MaybeSendSync};
+
+/// The error code of a `DPoP-Nonce` challenge, returned by authorization and
+/// resource servers that require a (fresh) nonce in DPoP proofs.
+pub const USE_DPOP_NONCE: &str = "use_dpop_nonce";
+
+/// Trait for storing the latest nonce provided by each server in its
+/// `DPoP-Nonce` response header.
+///
+/// Nonces are keyed by the server's origin (scheme, host and port), as each
+/// server issues its own. Operations are infallible: a store backed by an
+/// external service should treat failures as a missing nonce, which costs at
+/// most one more challenge from the server.
+pub trait DpopNonceStore: MaybeSendSync + Clone {
+    /// Asynchronously returns the latest nonce provided by the server at the
+    /// given origin.
+    fn nonce(&self, origin: &str) -> impl Future<Output = Option<String>> + MaybeSend;
+
+    /// Asynchronously records a nonce provided by the server at the given
+    /// origin, replacing the previous one.
+    fn set_nonce(&self, origin: &str, nonce: &str) -> impl Future<Output = ()> + MaybeSend;
+}
+
+/// A nonce store backed by an in-memory map of origin to nonce.
+///
+/// Clones share the same map, so a nonce received on one request is used by
+/// every clone.
+#[derive(Debug, Clone, Default)]
+pub struct InMemoryDpopNonceStore {
+    nonces: Arc<RwLock<HashMap<String, String>>>,
+}
+
+impl InMemoryDpopNonceStore {
+    /// Creates an empty store.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl DpopNonceStore for InMemoryDpopNonceStore {
+    async fn nonce(&self, origin: &str) -> Option<String> {
+        self.nonces
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(origin)
+            .cloned()
+    }
+
+    async fn set_nonce(&self, origin: &str, nonce: &str) {
+        self.nonces
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(origin.to_owned(), nonce.to_owned());
+    }
+}
+
+/// Returns the origin of an absolute URI: its scheme and authority.
+pub(super) fn origin(uri: &str) -> &str {
+    let start = uri.find("://").map_or(0, |index| index + 3);
+    let end = uri[start..]
+        .find(['/', '?', '#'])
+        .map_or(uri.len(), |index| start + index);
+    &uri[..end]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_clones_share_nonces() {
+        let store = InMemoryDpopNonceStore::new();
+
+        store.clone().set_nonce("https://as.example", "n-1").await;
+
+        assert_eq!(
+            store.nonce("https://as.example").await.as_deref(),
+            Some("n-1")
+        );
+        assert_eq!(store.nonce("https://rs.example").await, None);
+    }
+
+    #[test]
+    fn test_origin() {
+        assert_eq!(
+            origin("https://server.example.com:8443/token?x=1"),
+            "https://server.example.com:8443"
+        );
+        assert_eq!(origin("https://rs.example#top"), "https://rs.example");
+        assert_eq!(origin("https://rs.example"), "https://rs.example");
+    }
+}
@@ -0,0 +1,400 @@
+use base64::{Engine, prelude::BASE64_URL_SAFE_NO_PAD};
+use bon::bon;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use snafu::prelude::*;
+use web_time::UNIX_EPOCH;
+
+use crate::{
+    MaybeSendSync,
+    clock::{Clock, SystemClock},
+    dpop::{DpopNonceStore, InMemoryDpopNonceStore, USE_DPOP_NONCE, nonce::origin},
+    jwk::PublicJwk,
+    jws::Header,
+    jwt::TypPolicy,
+    signer::{self, JwsSigner},
+};
+
+/// Errors returned when creating a DPoP proof.
+#[derive(Debug, Snafu)]
+#[non_exhaustive]
+pub enum DpopError<E: std::error::Error + MaybeSendSync + 'static> {
+    /// The JWK's `alg` parameter doesn't match the signer's algorithm.
+    #[snafu(display("JWK algorithm '{jwk}' doesn't match signer algorithm '{signer}'"))]
+    AlgorithmMismatch {
+        /// The JWK's `alg` parameter.
+        jwk: String,
+        /// The signer's JWS algorithm.
+        signer: String,
+    },
+    /// The public JWK could not be serialized for the `jwk` header parameter.
+    #[snafu(display("Failed to serialize public JWK"))]
+    InvalidJwk {
+        /// The serialization error.
+        source: serde_json::Error,
+    },
+    /// The system's random number generator failed to produce a `jti`.
+    #[snafu(display("Failed to generate a random 'jti'"))]
+    Random,
+    /// The proof could not be signed.
+    #[snafu(display("Failed to sign DPoP proof"))]
+    Sign {
+        /// The signing error.
+        source: signer::Error<E>,
+    },
+}
+
+/// The claims of a DPoP proof (RFC 9449 §4.2).
+#[derive(Serialize)]
+struct ProofClaims<'a> {
+    jti: String,
+    htm: &'a str,
+    htu: &'a str,
+    iat: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ath: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    nonce: Option<&'a str>,
+}
+
+/// Creates DPoP proofs (RFC 9449 §4) for HTTP requests, signed with a
+/// [`JwsSigner`] whose public key is carried in the `jwk` header parameter.
+///
+/// Each proof has `typ` set to `dpop+jwt`, a random `jti`, the request's
+/// method (`htm`) and URI without query or fragment (`htu`), and the current
+/// time (`iat`). When the request presents an access token, its hash is
+/// added as `ath`.
+///
+/// Servers may require proofs to carry a nonce they provide in the
+/// `DPoP-Nonce` response header. Nonces recorded with
+/// [`DpopProofBuilder::handle_challenge`] or
+/// [`DpopProofBuilder::update_nonce`] are kept in a [`DpopNonceStore`] (in
+/// memory by default, see [`DpopProofBuilder::with_nonce_store`]), and added
+/// as `nonce` to later proofs for the same server.
+///
+/// ```no_run
+/// # async fn example(
+/// #     signer: impl chewie_crypto::signer::JwsSigner,
+/// #     jwk: chewie_crypto::jwk::PublicJwk,
+/// # ) -> Result<(), Box<dyn std::error::Error>> {
+/// use chewie_crypto::dpop::DpopProofBuilder;
+///
+/// let dpop = DpopProofBuilder::new(signer, jwk);
+/// let uri = "https://resource.example.org/protected";
+/// let proof = dpop
+///     .proof("GET", uri)
+///     .access_token("Kz~8mXK1EalYznwH-LC-1fBAo.4Ljp~zsPE_NeO.gxU")
+///     .sign()
+///     .await?;
+/// // Send the request; if the response is a `use_dpop_nonce` error:
+/// # let (error, dpop_nonce) = (Some("use_dpop_nonce"), Some("eyJ7S_zG.eyJH0-Z.HX4w-7v"));
+/// if dpop.handle_challenge(uri, error, dpop_nonce).await {
+///     // Retry with a new proof, which carries the nonce.
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct DpopProofBuilder<S, C = SystemClock, N = InMemoryDpopNonceStore> {
+    signer: S,
+    jwk: PublicJwk,
+    clock: C,
+    nonces: N,
+}
+
+impl<S: JwsSigner> DpopProofBuilder<S> {
+    /// Creates a proof builder signing with the given signer, whose public
+    /// key is `jwk`.
+    #[must_use]
+    pub fn new(signer: S, jwk: PublicJwk) -> Self {
+        Self {
+            signer,
+            jwk,
+            clock: SystemClock,
+            nonces: InMemoryDpopNonceStore::new(),
+        }
+    }
+}
+
+#[bon]
+impl<S: JwsSigner, C: Clock, N: DpopNonceStore> DpopProofBuilder<S, C, N> {
+    /// Reads the current time from the given clock.
+    pub fn with_clock<C2: Clock>(self, clock: C2) -> DpopProofBuilder<S, C2, N> {
+        DpopProofBuilder {
+            signer: self.signer,
+            jwk: self.jwk,
+            clock,
+            nonces: self.nonces,
+        }
+    }
+
+    /// Keeps server-provided nonces in the given store, such as one shared
+    /// by several clients of the same servers.
+    pub fn with_nonce_store<N2: DpopNonceStore>(self, nonces: N2) -> DpopProofBuilder<S, C, N2> {
+        DpopProofBuilder {
+            signer: self.signer,
+            jwk: self.jwk,
+            clock: self.clock,
+            nonces,
+        }
+    }
+
+    /// Returns the signer.
+    pub fn signer(&self) -> &S {
+        &self.signer
+    }
+
+    /// Returns the nonce store.
+    pub fn nonce_store(&self) -> &N {
+        &self.nonces
+    }
+
+    /// Returns the public JWK.
+    #[must_use]
+    pub fn jwk(&self) -> &PublicJwk {
+        &self.jwk
+    }
+
+    /// Returns the JWK thumbprint of the public key, which access tokens are
+    /// bound to (`jkt`), and which is sent as `dpop_jkt` in authorization
+    /// requests.
+    #[must_use]
+    pub fn thumbprint(&self) -> Option<String> {
+        self.jwk.thumbprint()
+    }
+
+    /// Creates a builder for a proof of an HTTP request with the given method
+    /// and URI (call `sign()` to finish).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the JWK's `alg` doesn't match the signer, no
+    /// random `jti` can be generated, or signing fails.
+    #[builder(finish_fn = sign)]
+    pub async fn proof(
+        &self,
+        #[builder(start_fn)] htm: &str,
+        #[builder(start_fn)] htu: &str,
+        /// The access token presented with the request, whose hash is added
+        /// as `ath`.
+        access_token: Option<&str>,
+        /// The nonce to use, instead of the one most recently provided by
+        /// the server, if any.
+        nonce: Option<&str>,
+    ) -> Result<String, DpopError<S::Error>> {
+        let algorithm = self.signer.jws_algorithm();
+        if let Some(jwk) = self.jwk.algorithm() {
+            ensure!(
+                jwk == algorithm,
+                AlgorithmMismatchSnafu {
+                    jwk,
+                    signer: algorithm,
+                }
+            );
+        }
+        let stored = match nonce {
+            Some(_) => None,
+            None => self.nonces.nonce(origin(htu)).await,
+        };
+        let mut jti = [0; 16];
+        getrandom::getrandom(&mut jti).ok().context(RandomSnafu)?;
+        let claims = ProofClaims {
+            jti: BASE64_URL_SAFE_NO_PAD.encode(jti),
+            htm,
+            htu: target_uri(htu),
+            iat: self
+                .clock
+                .now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |duration| duration.as_secs()),
+            ath: access_token.map(|token| BASE64_URL_SAFE_NO_PAD.encode(Sha256::digest(token))),
+            nonce: nonce.or(stored.as_deref()),
+        };
+        let jwk = serde_json::to_value(&self.jwk).context(InvalidJwkSnafu)?;
+        let header = TypPolicy::DPOP
+            .apply(&Header::default())
+            .and_then(|header| header.with_param("jwk", jwk))
+            .map_err(|source| signer::Error::InvalidHeader { source })
+            .context(SignSnafu)?;
+        self.signer
+            .sign_jwt(&claims, &header)
+            .await
+            .context(SignSnafu)
+    }
+
+    /// Records the nonce from the `DPoP-Nonce` header of a response to a
+    /// request to the given URI, for later proofs to the same server.
+    ///
+    /// Servers may provide a new nonce in any response, so it should be
+    /// recorded whenever present.
+    pub async fn update_nonce(&self, htu: &str, nonce: &str) {
+        self.nonces.set_nonce(origin(htu), nonce).await;
+    }
+
+    /// Handles a response to a request to the given URI, returning whether it
+    /// is a nonce challenge that should be retried with a new proof.
+    ///
+    /// `error` is the response's error code: the `error` member of an
+    /// authorization server's JSON error response, or the `error` parameter
+    /// of a resource server's `WWW-Authenticate` header. `dpop_nonce` is its
+    /// `DPoP-Nonce` header, which is recorded if present. A retry is only
+    /// advised for a `use_dpop_nonce` error with a nonce that differs from the
+    /// one previously recorded, so a server can't cause endless retries.
+    pub async fn handle_challenge(
+        &self,
+        htu: &str,
+        error: Option<&str>,
+        dpop_nonce: Option<&str>,
+    ) -> bool {
+        let Some(nonce) = dpop_nonce else {
+            return false;
+        };
+        let origin = origin(htu);
+        let previous = self.nonces.nonce(origin).await;
+        self.nonces.set_nonce(origin, nonce).await;
+        error == Some(USE_DPOP_NONCE) && previous.as_deref() != Some(nonce)
+    }
+}
+
+/// Returns the URI without its query and fragment, as required for `htu`.
+fn target_uri(uri: &str) -> &str {
+    uri.split(['?', '#']).next().unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use serde_json::{Map, Value, json};
+
+    use super::*;
+    use crate::{clock::FixedClock, signer::MockSigner};
+
+    const JWK: &str = r#"{
+        "kty": "EC",
+        "crv": "P-256",
+        "x": "l8tFrhx-34tV3hRICRDY9zCkDlpBhF42UQUfWVAWBFs",
+        "y": "9VE4jf_Ok_o64zbTTlcuNJajHmt6v9TDVrU0CdvGRDA"
+    }"#;
+
+    fn dpop(jwk: &str) -> DpopProofBuilder<MockSigner, FixedClock, InMemoryDpopNonceStore> {
+        let signer = MockSigner::builder().signature(b"sig".as_slice()).build();
+        DpopProofBuilder::new(signer, serde_json::from_str(jwk).unwrap()).with_clock(
+            FixedClock::new(UNIX_EPOCH + Duration::from_secs(1_562_262_616)),
+        )
+    }
+
+    fn decode(part: &str) -> Map<String, Value> {
+        serde_json::from_slice(&BASE64_URL_SAFE_NO_PAD.decode(part).unwrap()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_proof_with_access_token_and_nonce() {
+        let proof = dpop(JWK)
+            .proof("GET", "https://resource.example.org/protected?x=1#top")
+            .access_token("Kz~8mXK1EalYznwH-LC-1fBAo.4Ljp~zsPE_NeO.gxU")
+            .nonce("eyJ7S_zG.eyJH0-Z.HX4w-7v")
+            .sign()
+            .await
+            .unwrap();
+
+        let parts: Vec<_> = proof.split('.').collect();
+        let header = decode(parts[0]);
+        let mut claims = decode(parts[1]);
+        let jti = claims.remove("jti").unwrap();
+
+        assert_eq!(header["typ"], "dpop+jwt");
+        assert_eq!(header["alg"], "ES256");
+        assert_eq!(header["jwk"], serde_json::from_str::<Value>(JWK).unwrap());
+        assert_eq!(jti.as_str().unwrap().len(), 22);
+        assert_eq!(
+            Value::Object(claims),
+            json!({
+                "htm": "GET",
+                "htu": "https://resource.example.org/protected",
+                "iat": 1_562_262_616,
+                // RFC 9449 §7.1.
+                "ath": "fUHyO2r2Z3DZ53EsNrWBb0xWXoaNy59IiKCAqksmQEo",
+                "nonce": "eyJ7S_zG.eyJH0-Z.HX4w-7v",
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_proofs_have_unique_jti() {
+        let dpop = dpop(JWK);
+        let jti = async || {
+            let proof = dpop
+                .proof("POST", "https://server.example.com/token")
+                .sign()
+                .await
+                .unwrap();
+            let claims = decode(proof.split('.').nth(1).unwrap());
+            assert!(!claims.contains_key("ath"));
+            claims["jti"].clone()
+        };
+
+        assert_ne!(jti().await, jti().await);
+    }
+
+    #[tokio::test]
+    async fn test_jwk_algorithm_must_match_signer() {
+        let jwk = JWK.replacen('{', r#"{"alg": "ES384","#, 1);
+
+        let result = dpop(&jwk)
+            .proof("POST", "https://server.example.com/token")
+            .sign()
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(DpopError::AlgorithmMismatch { jwk, .. }) if jwk == "ES384"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_nonce_challenge_is_retried_with_nonce() {
+        let dpop = dpop(JWK);
+        let token_uri = "https://server.example.com/token";
+        let nonce = async |uri: &str| {
+            let proof = dpop.proof("POST", uri).sign().await.unwrap();
+            decode(proof.split('.').nth(1).unwrap()).remove("nonce")
+        };
+
+        assert_eq!(nonce(token_uri).await, None);
+        assert!(
+            dpop.handle_challenge(token_uri, Some("use_dpop_nonce"), Some("n-1"))
+                .await
+        );
+        assert_eq!(nonce(token_uri).await.unwrap(), "n-1");
+        assert_eq!(nonce("https://resource.example.org/").await, None);
+
+        // The same nonce again means the retry failed, so don't loop.
+        assert!(
+            !dpop
+                .handle_challenge(token_uri, Some("use_dpop_nonce"), Some("n-1"))
+                .await
+        );
+        assert!(!dpop.handle_challenge(token_uri, None, Some("n-2")).await);
+        assert_eq!(nonce(token_uri).await.unwrap(), "n-2");
+    }
+
+    #[tokio::test]
+    async fn test_explicit_nonce_overrides_stored() {
+        let dpop = dpop(JWK);
+        dpop.update_nonce("https://server.example.com/token", "stored")
+            .await;
+
+        let proof = dpop
+            .proof("POST", "https://server.example.com/token")
+            .nonce("explicit")
+            .sign()
+            .await
+            .unwrap();
+
+        assert_eq!(
+            decode(proof.split('.').nth(1).unwrap())["nonce"],
+            "explicit"
+        );
+    }
+}
@@ -0,0 +1,423 @@
+//! HTTP Message Signatures per RFC 9421.
+//!
+//! This module builds the signature base for a set of covered components.
+//! The component values are extracted by the caller (e.g. from their HTTP
+//! library's request type), and signed with an
+//! [`HttpMessageSigner`](crate::signer::HttpMessageSigner).
+
+use std::fmt::Write;
+
+use bon::Builder;
+use snafu::prelude::*;
+
+/// Errors building a signature base.
+#[derive(Debug, Snafu)]
+#[non_exhaustive]
+pub enum HttpSigError {
+    /// The component name is not a field name or derived component name.
+    #[snafu(display("Invalid component name '{name}'"))]
+    InvalidComponentName {
+        /// The rejected name.
+        name: String,
+    },
+    /// The component value contains characters that can't appear in a field
+    /// value, such as CR or LF.
+    #[snafu(display("Invalid value for component '{name}'"))]
+    InvalidComponentValue {
+        /// The component name.
+        name: String,
+    },
+    /// The component is already covered (RFC 9421 §2.5).
+    #[snafu(display("Component '{name}' is covered more than once"))]
+    DuplicateComponent {
+        /// The duplicated name.
+        name: String,
+    },
+    /// The signature label is not a structured field key.
+    #[snafu(display("Invalid signature label '{label}'"))]
+    InvalidLabel {
+        /// The rejected label.
+        label: String,
+    },
+    /// A signature parameter contains characters that can't appear in a
+    /// structured field string.
+    #[snafu(display("Invalid value for signature parameter '{name}'"))]
+    InvalidParameter {
+        /// The parameter name.
+        name: &'static str,
+    },
+}
+
+/// Returns the RFC 9421 algorithm name for a JWS algorithm, if the algorithm
+/// is registered for both, with the same signature format.
+///
+/// See <https://www.iana.org/assignments/http-message-signature/http-message-signature.xhtml>.
+#[must_use]
+pub fn algorithm_for_jws(jws_algorithm: &str) -> Option<&'static str> {
+    match jws_algorithm {
+        "PS512" => Some("rsa-pss-sha512"),
+        "RS256" => Some("rsa-v1_5-sha256"),
+        "HS256" => Some("hmac-sha256"),
+        "ES256" => Some("ecdsa-p256-sha256"),
+        "ES384" => Some("ecdsa-p384-sha384"),
+        "EdDSA" => Some("ed25519"),
+        _ => None,
+    }
+}
+
+/// The covered components of a message, in signing order.
+///
+/// Component names are HTTP field names (lowercase) or derived component
+/// names (such as `@method`). Component parameters (such as `;sf`) are not
+/// supported.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Components {
+    components: Vec<(String, String)>,
+}
+
+impl Components {
+    /// Creates an empty set of components.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a component, returning the updated set.
+    ///
+    /// The name is lowercased, and leading and trailing whitespace is removed
+    /// from the value.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the name is invalid or already covered, or the value
+    /// contains characters other than visible ASCII, spaces and tabs.
+    pub fn with(mut self, name: &str, value: &str) -> Result<Self, HttpSigError> {
+        self.push(name, value)?;
+        Ok(self)
+    }
+
+    /// Adds a component.
+    ///
+    /// The name is lowercased, and leading and trailing whitespace is removed
+    /// from the value.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the name is invalid or already covered, or the value
+    /// contains characters other than visible ASCII, spaces and tabs.
+    pub fn push(&mut self, name: &str, value: &str) -> Result<(), HttpSigError> {
+        let name = name.to_ascii_lowercase();
+        let value = value.trim();
+        ensure!(is_component_name(&name), InvalidComponentNameSnafu { name });
+        ensure!(
+            !self.components.iter().any(|(covered, _)| *covered == name),
+            DuplicateComponentSnafu { name }
+        );
+        ensure!(
+            value.bytes().all(|b| matches!(b, b'\t' | b' '..=b'~')),
+            InvalidComponentValueSnafu { name }
+        );
+        self.components.push((name, value.to_owned()));
+        Ok(())
+    }
+
+    /// Returns an iterator over the component names and values.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.components
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+    }
+}
+
+/// Signature parameters set by the caller (RFC 9421 §2.3).
+///
+/// The `keyid` and `alg` parameters are set from the signer.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Builder)]
+#[builder(builder_type(
+    doc {
+        /// Builder for creating a [`SignatureParams`] value.
+    }
+))]
+pub struct SignatureParams {
+    /// The creation time, as a Unix timestamp.
+    created: Option<u64>,
+    /// The expiration time, as a Unix timestamp.
+    expires: Option<u64>,
+    /// A nonce for the signature.
+    #[builder(into)]
+    nonce: Option<String>,
+    /// An application-specific tag for the signature.
+    #[builder(into)]
+    tag: Option<String>,
+    /// Whether to include the `alg` parameter. Defaults to `false`, as RFC 9421
+    /// recommends determining the algorithm from the key.
+    #[builder(default)]
+    include_alg: bool,
+}
+
+impl SignatureParams {
+    /// Returns whether the `alg` parameter is included.
+    #[must_use]
+    pub fn include_alg(&self) -> bool {
+        self.include_alg
+    }
+}
+
+/// Returns `true` if `name` is a field name, or a derived component name
+/// other than `@signature-params`.
+fn is_component_name(name: &str) -> bool {
+    let token = name.strip_prefix('@').unwrap_or(name);
+    let is_tchar = |b: u8| b.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&b);
+    !token.is_empty() && token.bytes().all(is_tchar) && name != "@signature-params"
+}
+
+/// Returns `true` if `value` can be serialized as a structured field string.
+fn is_sf_string(value: &str) -> bool {
+    value.bytes().all(|b| matches!(b, b' '..=b'~'))
+}
+
+/// Checks that `label` is a structured field key, as required of signature
+/// labels.
+pub(crate) fn check_label(label: &str) -> Result<(), HttpSigError> {
+    let mut bytes = label.bytes();
+    let valid = bytes
+        .next()
+        .is_some_and(|b| b.is_ascii_lowercase() || b == b'*')
+        && bytes.all(|b| {
+            b.is_ascii_lowercase() || b.is_ascii_digit() || matches!(b, b'_' | b'-' | b'.' | b'*')
+        });
+    ensure!(valid, InvalidLabelSnafu { label });
+    Ok(())
+}
+
+fn push_sf_string(out: &mut String, value: &str) {
+    out.push('"');
+    for c in value.chars() {
+        if matches!(c, '"' | '\\') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out.push('"');
+}
+
+/// Serializes the `@signature-params` value: the inner list of component
+/// names, followed by the parameters.
+///
+/// # Errors
+///
+/// Returns an error if a string parameter contains characters other than
+/// printable ASCII.
+pub fn signature_params(
+    components: &Components,
+    params: &SignatureParams,
+    key_id: Option<&str>,
+    algorithm: Option<&str>,
+) -> Result<String, HttpSigError> {
+    let mut out = String::from("(");
+    for (i, (name, _)) in components.iter().enumerate() {
+        if i > 0 {
+            out.push(' ');
+        }
+        push_sf_string(&mut out, name);
+    }
+    out.push(')');
+    if let Some(created) = params.created {
+        let _ = write!(out, ";created={created}");
+    }
+    if let Some(expires) = params.expires {
+        let _ = write!(out, ";expires={expires}");
+    }
+    let strings = [
+        ("keyid", key_id),
+        ("alg", algorithm),
+        ("nonce", params.nonce.as_deref()),
+        ("tag", params.tag.as_deref()),
+    ];
+    for (name, value) in strings {
+        if let Some(value) = value {
+            ensure!(is_sf_string(value), InvalidParameterSnafu { name });
+            out.push(';');
+            out.push_str(name);
+            out.push('=');
+            push_sf_string(&mut out, value);
+        }
+    }
+    Ok(out)
+}
+
+/// Builds the signature base (RFC 9421 §2.5) from the components and the
+/// serialized `@signature-params` value.
+///
+/// # Errors
+///
+/// Returns an error if the `@signature-params` value contains characters
+/// other than printable ASCII.
+pub fn signature_base(
+    components: &Components,
+    signature_params: &str,
+) -> Result<String, HttpSigError> {
+    ensure!(
+        is_sf_string(signature_params),
+        InvalidParameterSnafu {
+            name: "@signature-params"
+        }
+    );
+    let mut out = String::new();
+    for (name, value) in components.iter() {
+        push_sf_string(&mut out, name);
+        out.push_str(": ");
+        out.push_str(value);
+        out.push('\n');
+    }
+    out.push_str("\"@signature-params\": ");
+    out.push_str(signature_params);
+    Ok(out)
+}
+
+/// The header field values of an HTTP message signature.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpSignature {
+    signature_input: String,
+    signature: String,
+}
+
+impl HttpSignature {
+    pub(crate) fn new(signature_input: String, signature: String) -> Self {
+        Self {
+            signature_input,
+            signature,
+        }
+    }
+
+    /// Returns the value of the `Signature-Input` header field.
+    #[must_use]
+    pub fn signature_input(&self) -> &str {
+        &self.signature_input
+    }
+
+    /// Returns the value of the `Signature` header field.
+    #[must_use]
+    pub fn signature(&self) -> &str {
+        &self.signature
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Example from https://www.rfc-editor.org/rfc/rfc9421.html#appendix-B.2.6
+    #[test]
+    fn test_signature_base_rfc9421_b26() {
+        let components = Components::new()
+            .with("date", "Tue, 20 Apr 2021 02:07:55 GMT")
+            .and_then(|c| c.with("@method", "POST"))
+            .and_then(|c| c.with("@path", "/foo"))
+            .and_then(|c| c.with("@authority", "example.com"))
+            .and_then(|c| c.with("Content-Type", "application/json"))
+            .and_then(|c| c.with("content-length", "18"))
+            .unwrap();
+        let params = SignatureParams::builder().created(1_618_884_473).build();
+
+        let signature_params =
+            signature_params(&components, &params, Some("test-key-ed25519"), None).unwrap();
+
+        assert_eq!(
+            signature_base(&components, &signature_params).unwrap(),
+            concat!(
+                "\"date\": Tue, 20 Apr 2021 02:07:55 GMT\n",
+                "\"@method\": POST\n",
+                "\"@path\": /foo\n",
+                "\"@authority\": example.com\n",
+                "\"content-type\": application/json\n",
+                "\"content-length\": 18\n",
+                "\"@signature-params\": (\"date\" \"@method\" \"@path\" \"@authority\" ",
+                "\"content-type\" \"content-length\");created=1618884473;keyid=\"test-key-ed25519\"",
+            )
+        );
+    }
+
+    #[test]
+    fn test_push_rejects_invalid_values() {
+        for value in ["a\r\nx-injected: 1", "a\nb", "a\0b", "caf\u{e9}"] {
+            let result = Components::new().with("x-header", value);
+
+            assert!(
+                matches!(&result, Err(HttpSigError::InvalidComponentValue { name }) if name == "x-header"),
+                "{value:?} was not rejected"
+            );
+        }
+    }
+
+    #[test]
+    fn test_push_rejects_invalid_names() {
+        for name in [
+            "",
+            "@",
+            "x header",
+            "x\"y",
+            "@signature-params",
+            "caf\u{e9}",
+        ] {
+            let result = Components::new().with(name, "value");
+
+            assert!(
+                matches!(result, Err(HttpSigError::InvalidComponentName { .. })),
+                "{name:?} was not rejected"
+            );
+        }
+    }
+
+    #[test]
+    fn test_push_rejects_duplicates() {
+        let result = Components::new()
+            .with("Date", "Tue, 20 Apr 2021 02:07:55 GMT")
+            .unwrap()
+            .with("date", "Wed, 21 Apr 2021 02:07:55 GMT");
+
+        assert!(
+            matches!(&result, Err(HttpSigError::DuplicateComponent { name }) if name == "date")
+        );
+    }
+
+    #[test]
+    fn test_signature_params_rejects_non_ascii_strings() {
+        let components = Components::new().with("@method", "POST").unwrap();
+        let params = SignatureParams::builder().nonce("n\u{f6}nce").build();
+
+        let result = signature_params(&components, &params, None, None);
+
+        assert!(matches!(
+            result,
+            Err(HttpSigError::InvalidParameter { name: "nonce" })
+        ));
+    }
+
+    #[test]
+    fn test_signature_base_rejects_line_breaks_in_params() {
+        let components = Components::new().with("@method", "POST").unwrap();
+
+        let result = signature_base(&components, "();created=1\n\"@path\": /");
+
+        assert!(matches!(
+            result,
+            Err(HttpSigError::InvalidParameter {
+                name: "@signature-params"
+            })
+        ));
+    }
+
+    #[test]
+    fn test_check_label() {
+        for label in ["sig1", "*", "sig-b.2_x*"] {
+            assert!(check_label(label).is_ok(), "{label:?} was rejected");
+        }
+        for label in ["", "Sig1", "1sig", "sig 1", "sig=1", "s\u{ef}g"] {
+            assert!(
+                matches!(check_label(label), Err(HttpSigError::InvalidLabel { .. })),
+                "{label:?} was not rejected"
+            );
+        }
+    }
+}
@@ -0,0 +1,162 @@
+//! JSON Web Algorithms (JWA) registry for JWS.
+//!
+//! The signer and verifier traits identify algorithms by their JWS name; this
+//! registry describes the algorithms the crate knows about, such as the key
+//! type they require.
+//!
+//! Post-quantum algorithms (ML-DSA, and composite ML-DSA/classical
+//! algorithms) are only registered with the `unstable-pqc` feature. Their
+//! names follow IETF drafts, and may change in minor releases until the
+//! drafts are published.
+
+/// The family of a JWS algorithm.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum AlgorithmFamily {
+    /// HMAC with SHA-2 (`HS*`).
+    Hmac,
+    /// RSASSA-PKCS1-v1_5 with SHA-2 (`RS*`).
+    RsaPkcs1,
+    /// RSASSA-PSS with SHA-2 (`PS*`).
+    RsaPss,
+    /// ECDSA with SHA-2 (`ES*`).
+    Ecdsa,
+    /// `EdDSA` (`EdDSA`, `Ed25519` and `Ed448`).
+    EdDsa,
+    /// ML-DSA (FIPS 204), per draft-ietf-cose-dilithium.
+    #[cfg(feature = "unstable-pqc")]
+    MlDsa,
+    /// Composite ML-DSA and classical signatures, per
+    /// draft-ietf-jose-pq-composite-sigs.
+    #[cfg(feature = "unstable-pqc")]
+    Composite,
+}
+
+/// A registered JWS algorithm.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JwsAlgorithm {
+    name: &'static str,
+    family: AlgorithmFamily,
+    key_type: &'static str,
+}
+
+impl JwsAlgorithm {
+    const fn new(name: &'static str, family: AlgorithmFamily, key_type: &'static str) -> Self {
+        Self {
+            name,
+            family,
+            key_type,
+        }
+    }
+
+    /// Returns the JWS algorithm name (`alg`).
+    #[must_use]
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Returns the algorithm family.
+    #[must_use]
+    pub fn family(&self) -> AlgorithmFamily {
+        self.family
+    }
+
+    /// Returns the JWK key type (`kty`) the algorithm uses.
+    #[must_use]
+    pub fn key_type(&self) -> &'static str {
+        self.key_type
+    }
+
+    /// Returns `true` if the algorithm uses a symmetric key.
+    #[must_use]
+    pub fn is_symmetric(&self) -> bool {
+        self.family == AlgorithmFamily::Hmac
+    }
+
+    /// Returns `true` if the algorithm is designed to resist quantum attacks,
+    /// including composite algorithms.
+    #[must_use]
+    pub fn is_post_quantum(&self) -> bool {
+        #[cfg(feature = "unstable-pqc")]
+        if matches!(
+            self.family,
+            AlgorithmFamily::MlDsa | AlgorithmFamily::Composite
+        ) {
+            return true;
+        }
+        false
+    }
+}
+
+const CLASSICAL: &[JwsAlgorithm] = &[
+    JwsAlgorithm::new("HS256", AlgorithmFamily::Hmac, "oct"),
+    JwsAlgorithm::new("HS384", AlgorithmFamily::Hmac, "oct"),
+    JwsAlgorithm::new("HS512", AlgorithmFamily::Hmac, "oct"),
+    JwsAlgorithm::new("RS256", AlgorithmFamily::RsaPkcs1, "RSA"),
+    JwsAlgorithm::new("RS384", AlgorithmFamily::RsaPkcs1, "RSA"),
+    JwsAlgorithm::new("RS512", AlgorithmFamily::RsaPkcs1, "RSA"),
+    JwsAlgorithm::new("PS256", AlgorithmFamily::RsaPss, "RSA"),
+    JwsAlgorithm::new("PS384", AlgorithmFamily::RsaPss, "RSA"),
+    JwsAlgorithm::new("PS512", AlgorithmFamily::RsaPss, "RSA"),
+    JwsAlgorithm::new("ES256", AlgorithmFamily::Ecdsa, "EC"),
+    JwsAlgorithm::new("ES256K", AlgorithmFamily::Ecdsa, "EC"),
+    JwsAlgorithm::new("ES384", AlgorithmFamily::Ecdsa, "EC"),
+    JwsAlgorithm::new("ES512", AlgorithmFamily::Ecdsa, "EC"),
+    JwsAlgorithm::new("EdDSA", AlgorithmFamily::EdDsa, "OKP"),
+    JwsAlgorithm::new("Ed25519", AlgorithmFamily::EdDsa, "OKP"),
+    JwsAlgorithm::new("Ed448", AlgorithmFamily::EdDsa, "OKP"),
+];
+
+// Both ML-DSA and composite keys use the "Algorithm Key Pair" key type.
+#[cfg(feature = "unstable-pqc")]
+const POST_QUANTUM: &[JwsAlgorithm] = &[
+    JwsAlgorithm::new("ML-DSA-44", AlgorithmFamily::MlDsa, "AKP"),
+    JwsAlgorithm::new("ML-DSA-65", AlgorithmFamily::MlDsa, "AKP"),
+    JwsAlgorithm::new("ML-DSA-87", AlgorithmFamily::MlDsa, "AKP"),
+    JwsAlgorithm::new("ML-DSA-44-ES256", AlgorithmFamily::Composite, "AKP"),
+    JwsAlgorithm::new("ML-DSA-65-ES256", AlgorithmFamily::Composite, "AKP"),
+    JwsAlgorithm::new("ML-DSA-87-ES384", AlgorithmFamily::Composite, "AKP"),
+    JwsAlgorithm::new("ML-DSA-44-Ed25519", AlgorithmFamily::Composite, "AKP"),
+    JwsAlgorithm::new("ML-DSA-65-Ed25519", AlgorithmFamily::Composite, "AKP"),
+    JwsAlgorithm::new("ML-DSA-87-Ed448", AlgorithmFamily::Composite, "AKP"),
+];
+
+#[cfg(not(feature = "unstable-pqc"))]
+const POST_QUANTUM: &[JwsAlgorithm] = &[];
+
+/// Returns an iterator over the registered JWS algorithms.
+pub fn algorithms() -> impl Iterator<Item = &'static JwsAlgorithm> {
+    CLASSICAL.iter().chain(POST_QUANTUM)
+}
+
+/// Looks up a registered JWS algorithm by name.
+#[must_use]
+pub fn lookup(name: &str) -> Option<&'static JwsAlgorithm> {
+    algorithms().find(|alg| alg.name == name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lookup() {
+        let alg = lookup("PS256").unwrap();
+
+        assert_eq!(alg.family(), AlgorithmFamily::RsaPss);
+        assert_eq!(alg.key_type(), "RSA");
+        assert!(!alg.is_post_quantum());
+        assert!(lookup("none").is_none());
+    }
+
+    #[cfg(feature = "unstable-pqc")]
+    #[test]
+    fn test_lookup_post_quantum() {
+        let alg = lookup("ML-DSA-65").unwrap();
+
+        assert_eq!(alg.family(), AlgorithmFamily::MlDsa);
+        assert_eq!(alg.key_type(), "AKP");
+        assert!(alg.is_post_quantum());
+        assert!(lookup("ML-DSA-44-ES256").unwrap().is_post_quantum());
+    }
+}
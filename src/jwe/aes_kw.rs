@@ -0,0 +1,210 @@
+//! The `A128KW` and `A256KW` key management algorithms (RFC 7518 §4.4).
+
+use std::borrow::Cow;
+
+use aws_lc_rs::key_wrap::{AES_128, AES_256, AesBlockCipher, KeyEncryptionKey, KeyWrap};
+use secrecy::{ExposeSecret, SecretBox, SecretSlice, zeroize::Zeroizing};
+use serde_json::{Map, Value};
+use snafu::prelude::*;
+
+use crate::{
+    jwe::{
+        ContentKey, DEFAULT_CONTENT_ENCRYPTION, JweDecrypter, JweEncrypter, check_jwk,
+        content_key_length,
+        error::{CryptoSnafu, InvalidKeySnafu, KeyManagementError},
+        generate_cek,
+    },
+    jwk::{PrivateJwk, PrivateKey},
+};
+
+/// Wraps and unwraps CEKs with a shared symmetric key, using AES Key Wrap
+/// (RFC 3394).
+///
+/// The algorithm follows from the key size: `A128KW` for 16-byte keys, and
+/// `A256KW` for 32-byte keys. As the key is shared, this is both a
+/// [`JweEncrypter`] and a [`JweDecrypter`].
+#[derive(Debug, Clone)]
+pub struct AesKeyWrap {
+    key: SecretSlice<u8>,
+    cipher: &'static AesBlockCipher,
+    algorithm: &'static str,
+    key_id: Option<String>,
+    enc: Cow<'static, str>,
+}
+
+impl AesKeyWrap {
+    /// Creates a key wrapper from a symmetric (`oct`) JWK.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the JWK isn't a 16 or 32-byte symmetric key, or its
+    /// `alg` or `use` parameters rule out the matching algorithm.
+    pub fn from_jwk(jwk: &PrivateJwk) -> Result<Self, KeyManagementError> {
+        let PrivateKey::Symmetric(key) = jwk.key() else {
+            return InvalidKeySnafu {
+                reason: "not a symmetric key",
+            }
+            .fail();
+        };
+        let key = key.key_value();
+        let (cipher, algorithm) = match key.expose_secret().len() {
+            16 => (&AES_128, "A128KW"),
+            32 => (&AES_256, "A256KW"),
+            _ => {
+                return InvalidKeySnafu {
+                    reason: "AES key wrap keys must be 16 or 32 bytes",
+                }
+                .fail();
+            }
+        };
+        check_jwk(jwk.algorithm(), jwk.key_use(), &[algorithm])?;
+        Ok(Self {
+            key: key.clone(),
+            cipher,
+            algorithm,
+            key_id: jwk.kid().map(str::to_owned),
+            enc: Cow::Borrowed(DEFAULT_CONTENT_ENCRYPTION),
+        })
+    }
+
+    /// Sets the content encryption algorithm (`enc`) the recipient expects,
+    /// instead of `A128CBC-HS256`.
+    #[must_use]
+    pub fn with_content_encryption(mut self, enc: impl Into<Cow<'static, str>>) -> Self {
+        self.enc = enc.into();
+        self
+    }
+
+    fn kek(&self) -> Result<KeyEncryptionKey<AesBlockCipher>, KeyManagementError> {
+        KeyEncryptionKey::new(self.cipher, self.key.expose_secret())
+            .ok()
+            .context(CryptoSnafu)
+    }
+
+    fn wrap(&self, cek: SecretBox<[u8]>) -> Result<ContentKey, KeyManagementError> {
+        let mut encrypted_key = vec![0; cek.expose_secret().len() + 8];
+        self.kek()?
+            .wrap(cek.expose_secret(), &mut encrypted_key)
+            .ok()
+            .context(CryptoSnafu)?;
+        Ok(ContentKey::new(
+            cek,
+            encrypted_key,
+            self.algorithm,
+            self.key_id.clone(),
+        ))
+    }
+}
+
+impl JweEncrypter for AesKeyWrap {
+    type Error = KeyManagementError;
+
+    fn key_management_algorithm(&self) -> Cow<'_, str> {
+        Cow::Borrowed(self.algorithm)
+    }
+
+    fn content_encryption_algorithm(&self) -> Cow<'_, str> {
+        Cow::Borrowed(&self.enc)
+    }
+
+    fn key_id(&self) -> Option<Cow<'_, str>> {
+        self.key_id.as_deref().map(Cow::Borrowed)
+    }
+
+    async fn encrypt_key(&self, enc: &str) -> Result<ContentKey, Self::Error> {
+        self.wrap(generate_cek(enc)?)
+    }
+
+    async fn wrap_key(&self, cek: &[u8], _enc: &str) -> Result<Option<ContentKey>, Self::Error> {
+        self.wrap(SecretBox::new(Box::from(cek))).map(Some)
+    }
+}
+
+impl JweDecrypter for AesKeyWrap {
+    type Error = KeyManagementError;
+
+    fn key_management_algorithm(&self) -> Cow<'_, str> {
+        Cow::Borrowed(self.algorithm)
+    }
+
+    fn key_id(&self) -> Option<Cow<'_, str>> {
+        self.key_id.as_deref().map(Cow::Borrowed)
+    }
+
+    async fn decrypt_key_unchecked(
+        &self,
+        _header: &Map<String, Value>,
+        encrypted_key: &[u8],
+        enc: &str,
+    ) -> Result<SecretBox<[u8]>, Self::Error> {
+        let len = content_key_length(enc).context(CryptoSnafu)?;
+        ensure!(encrypted_key.len() == len + 8, CryptoSnafu);
+        let mut cek = Zeroizing::new(vec![0; len]);
+        self.kek()?
+            .unwrap(encrypted_key, &mut cek)
+            .ok()
+            .context(CryptoSnafu)?;
+        Ok(SecretBox::new(Box::from(cek.as_slice())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn jwk(k: &str) -> PrivateJwk {
+        serde_json::from_str(&json!({"kty": "oct", "k": k, "kid": "kw-1"}).to_string()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_unwrap_rfc_3394_vector() {
+        // RFC 3394 §4.3: 128 bits of key data wrapped with a 256-bit KEK.
+        let wrapper =
+            AesKeyWrap::from_jwk(&jwk("AAECAwQFBgcICQoLDA0ODxAREhMUFRYXGBkaGxwdHh8")).unwrap();
+        let encrypted_key =
+            hex::decode("64E8C3F9CE0F5BA263E9777905818A2A93C8191E7D6E8AE7").unwrap();
+        let Value::Object(header) = json!({"alg": "A256KW", "enc": "A128GCM"}) else {
+            unreachable!()
+        };
+
+        let cek = wrapper.decrypt_key(&header, &encrypted_key).await.unwrap();
+
+        assert_eq!(JweDecrypter::key_management_algorithm(&wrapper), "A256KW");
+        assert_eq!(
+            cek.expose_secret(),
+            hex::decode("00112233445566778899AABBCCDDEEFF").unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_round_trip() {
+        let wrapper = AesKeyWrap::from_jwk(&jwk("GawgguFyGrWKav7AX4VKUg")).unwrap();
+        let Value::Object(header) = json!({"alg": "A128KW", "enc": "A256GCM", "kid": "kw-1"})
+        else {
+            unreachable!()
+        };
+
+        let key = wrapper.encrypt_key("A256GCM").await.unwrap();
+        let cek = wrapper
+            .decrypt_key(&header, key.encrypted_key())
+            .await
+            .unwrap();
+
+        assert_eq!(key.encrypted_key().len(), 40);
+        assert_eq!(cek.expose_secret(), key.cek());
+
+        let mut tampered = key.encrypted_key().to_vec();
+        tampered[0] ^= 1;
+        assert!(wrapper.decrypt_key(&header, &tampered).await.is_err());
+    }
+
+    #[test]
+    fn test_from_jwk_requires_aes_key_size() {
+        assert!(matches!(
+            AesKeyWrap::from_jwk(&jwk("AAEC")),
+            Err(KeyManagementError::InvalidKey { .. })
+        ));
+    }
+}
@@ -0,0 +1,407 @@
+//! JWE Compact Serialization (RFC 7516 §7.1).
+
+use std::{fmt, str::FromStr};
+
+use base64::{Engine, prelude::BASE64_URL_SAFE_NO_PAD};
+use bon::bon;
+use bytes::Bytes;
+use secrecy::ExposeSecret;
+use serde_json::{Map, Value};
+use snafu::prelude::*;
+
+use crate::jwe::{
+    ContentEncryption, EncryptedContent, JweDecrypter, JweEncrypter, additional_authenticated_data,
+    error::{
+        ContentEncryptionSnafu, Error, MalformedJweError, MalformedJweSnafu, UnderlyingSnafu,
+        UnsupportedEncryptionSnafu,
+    },
+};
+
+/// A JWE in Compact Serialization: the protected header, encrypted key,
+/// initialization vector, ciphertext and authentication tag, each
+/// base64url-encoded and joined with `.`.
+///
+/// A JWE is created with [`CompactJwe::builder`], which builds the protected
+/// header, has the encrypter generate and protect a CEK, and encrypts the
+/// plaintext. Its [`Display`](fmt::Display) implementation yields the
+/// serialization. Received tokens are read with [`CompactJwe::parse`], and
+/// decrypted with [`CompactJwe::decrypt`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompactJwe {
+    encoded_header: String,
+    header: Map<String, Value>,
+    encrypted_key: Bytes,
+    content: EncryptedContent,
+}
+
+#[bon]
+impl CompactJwe {
+    /// Creates a builder encrypting the plaintext to the encrypter's
+    /// recipient (call `encrypt()` to finish).
+    ///
+    /// The protected header holds `alg`, `enc` and `kid` from the encrypter,
+    /// the parameters set by its key management algorithm (such as `epk`),
+    /// and the parameters set on the builder. Builder parameters with the
+    /// same names as those from the encrypter are overridden.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the encrypter's content encryption algorithm is
+    /// unknown, or key management or content encryption fails.
+    #[builder(finish_fn = encrypt)]
+    pub async fn new<E: JweEncrypter>(
+        #[builder(start_fn)] encrypter: &E,
+        #[builder(start_fn)] plaintext: &[u8],
+        /// The media type of the complete JWE (`typ`).
+        #[builder(into)]
+        typ: Option<String>,
+        /// The media type of the plaintext (`cty`), such as `JWT` for a
+        /// nested JWT.
+        #[builder(into)]
+        cty: Option<String>,
+        /// Additional protected header parameters.
+        #[builder(default)]
+        params: Map<String, Value>,
+    ) -> Result<Self, Error<E::Error>> {
+        let enc = encrypter.content_encryption_algorithm();
+        let content_encryption = ContentEncryption::from_name(&enc)
+            .context(UnsupportedEncryptionSnafu { enc: &*enc })?;
+        let key = encrypter.encrypt_key(&enc).await.context(UnderlyingSnafu)?;
+
+        let mut header = params;
+        if let Some(typ) = typ {
+            header.insert("typ".into(), typ.into());
+        }
+        if let Some(cty) = cty {
+            header.insert("cty".into(), cty.into());
+        }
+        header.insert("alg".into(), key.algorithm().into());
+        header.insert("enc".into(), enc.as_ref().into());
+        if let Some(kid) = key.key_id() {
+            header.insert("kid".into(), kid.into());
+        }
+        header.extend(key.header_params().clone());
+        let encoded_header =
+            BASE64_URL_SAFE_NO_PAD.encode(Value::Object(header.clone()).to_string());
+
+        let content = content_encryption
+            .encrypt(
+                key.cek(),
+                &additional_authenticated_data(&encoded_header, None),
+                plaintext,
+            )
+            .context(ContentEncryptionSnafu)?;
+        Ok(Self {
+            encoded_header,
+            header,
+            encrypted_key: Bytes::copy_from_slice(key.encrypted_key()),
+            content,
+        })
+    }
+}
+
+impl CompactJwe {
+    /// Parses the compact serialization of a JWE, **without** decrypting it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the token doesn't have five base64url-encoded
+    /// parts, the header isn't a JSON object, the JWE is compressed (`zip`),
+    /// which isn't supported, or it has critical extensions (`crit`), none of
+    /// which are understood.
+    pub fn parse(token: &str) -> Result<Self, MalformedJweError> {
+        let [encoded_header, encrypted_key, iv, ciphertext, tag] = token
+            .split('.')
+            .collect::<Vec<_>>()
+            .try_into()
+            .ok()
+            .context(MalformedJweSnafu {
+                reason: "expected five parts",
+            })?;
+        let header = BASE64_URL_SAFE_NO_PAD
+            .decode(encoded_header)
+            .ok()
+            .and_then(|header| serde_json::from_slice::<Map<String, Value>>(&header).ok())
+            .context(MalformedJweSnafu {
+                reason: "header is not a base64url-encoded JSON object",
+            })?;
+        ensure!(
+            !header.contains_key("zip"),
+            MalformedJweSnafu {
+                reason: "compression is not supported",
+            }
+        );
+        ensure!(
+            !header.contains_key("crit"),
+            MalformedJweSnafu {
+                reason: "critical extensions are not supported",
+            }
+        );
+        let decode = |part: &str| {
+            BASE64_URL_SAFE_NO_PAD
+                .decode(part)
+                .ok()
+                .context(MalformedJweSnafu {
+                    reason: "part is not base64url-encoded",
+                })
+        };
+        Ok(Self {
+            encoded_header: encoded_header.to_owned(),
+            header,
+            encrypted_key: decode(encrypted_key)?.into(),
+            content: EncryptedContent::new(decode(iv)?, decode(ciphertext)?, decode(tag)?),
+        })
+    }
+
+    /// Returns the protected header.
+    ///
+    /// The header is only authenticated once the JWE has been decrypted;
+    /// before that, its values are only fit for choosing how to decrypt.
+    #[must_use]
+    pub fn header(&self) -> &Map<String, Value> {
+        &self.header
+    }
+
+    /// Returns the base64url-encoded protected header, as serialized.
+    #[must_use]
+    pub fn encoded_header(&self) -> &str {
+        &self.encoded_header
+    }
+
+    /// Returns the encrypted key, which is empty for direct key agreement.
+    #[must_use]
+    pub fn encrypted_key(&self) -> &Bytes {
+        &self.encrypted_key
+    }
+
+    /// Returns the initialization vector, ciphertext and authentication tag.
+    #[must_use]
+    pub fn content(&self) -> &EncryptedContent {
+        &self.content
+    }
+
+    /// Recovers the CEK with the decrypter and decrypts the plaintext, which
+    /// also authenticates the protected header.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the header doesn't match the decrypter, the CEK
+    /// can't be recovered, or the content fails authentication.
+    pub async fn decrypt<D: JweDecrypter>(
+        &self,
+        decrypter: &D,
+    ) -> Result<Vec<u8>, Error<D::Error>> {
+        let cek = decrypter
+            .decrypt_key(&self.header, &self.encrypted_key)
+            .await?;
+        // `decrypt_key` checked that `enc` is known.
+        let enc = self
+            .header
+            .get("enc")
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+        let content_encryption =
+            ContentEncryption::from_name(enc).context(UnsupportedEncryptionSnafu { enc })?;
+        content_encryption
+            .decrypt(
+                cek.expose_secret(),
+                &additional_authenticated_data(&self.encoded_header, None),
+                &self.content,
+            )
+            .context(ContentEncryptionSnafu)
+    }
+}
+
+impl FromStr for CompactJwe {
+    type Err = MalformedJweError;
+
+    fn from_str(token: &str) -> Result<Self, Self::Err> {
+        Self::parse(token)
+    }
+}
+
+impl fmt::Display for CompactJwe {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}.{}.{}.{}.{}",
+            self.encoded_header,
+            BASE64_URL_SAFE_NO_PAD.encode(&self.encrypted_key),
+            BASE64_URL_SAFE_NO_PAD.encode(self.content.iv()),
+            BASE64_URL_SAFE_NO_PAD.encode(self.content.ciphertext()),
+            BASE64_URL_SAFE_NO_PAD.encode(self.content.tag()),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{borrow::Cow, convert::Infallible};
+
+    use secrecy::SecretBox;
+    use serde_json::json;
+
+    use super::*;
+    use crate::jwe::{ContentKey, content_key_length};
+
+    /// Uses a fixed shared key as the CEK (`dir`).
+    #[derive(Debug, Clone)]
+    struct DirectKey;
+
+    fn cek(enc: &str) -> SecretBox<[u8]> {
+        SecretBox::new(vec![7; content_key_length(enc).unwrap()].into_boxed_slice())
+    }
+
+    impl JweEncrypter for DirectKey {
+        type Error = Infallible;
+
+        fn key_management_algorithm(&self) -> Cow<'_, str> {
+            "dir".into()
+        }
+
+        fn content_encryption_algorithm(&self) -> Cow<'_, str> {
+            "A256GCM".into()
+        }
+
+        fn key_id(&self) -> Option<Cow<'_, str>> {
+            Some("dir-1".into())
+        }
+
+        async fn encrypt_key(&self, enc: &str) -> Result<ContentKey, Self::Error> {
+            Ok(ContentKey::new(
+                cek(enc),
+                Vec::new(),
+                "dir",
+                Some("dir-1".into()),
+            ))
+        }
+    }
+
+    impl JweDecrypter for DirectKey {
+        type Error = Infallible;
+
+        fn key_management_algorithm(&self) -> Cow<'_, str> {
+            "dir".into()
+        }
+
+        fn key_id(&self) -> Option<Cow<'_, str>> {
+            Some("dir-1".into())
+        }
+
+        async fn decrypt_key_unchecked(
+            &self,
+            _header: &Map<String, Value>,
+            _encrypted_key: &[u8],
+            enc: &str,
+        ) -> Result<SecretBox<[u8]>, Self::Error> {
+            Ok(cek(enc))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_round_trip() {
+        let Value::Object(params) = json!({"alg": "none", "iss": "https://as.example"}) else {
+            unreachable!()
+        };
+
+        let token = CompactJwe::builder(&DirectKey, b"hello")
+            .typ("example+jwe")
+            .cty("text/plain")
+            .params(params)
+            .encrypt()
+            .await
+            .unwrap()
+            .to_string();
+        let jwe = CompactJwe::parse(&token).unwrap();
+
+        assert_eq!(
+            Value::Object(jwe.header().clone()),
+            json!({
+                "alg": "dir",
+                "enc": "A256GCM",
+                "kid": "dir-1",
+                "typ": "example+jwe",
+                "cty": "text/plain",
+                "iss": "https://as.example",
+            })
+        );
+        assert!(jwe.encrypted_key().is_empty());
+        assert_eq!(jwe.content().iv().len(), 12);
+        assert_eq!(jwe.content().tag().len(), 16);
+        assert_eq!(jwe.to_string(), token);
+        assert_eq!(jwe.decrypt(&DirectKey).await.unwrap(), b"hello");
+    }
+
+    #[tokio::test]
+    async fn test_tampered_header_is_rejected() {
+        let jwe = CompactJwe::builder(&DirectKey, b"hello")
+            .encrypt()
+            .await
+            .unwrap()
+            .to_string();
+        let mut parts: Vec<_> = jwe.split('.').collect();
+        let header = BASE64_URL_SAFE_NO_PAD.encode(
+            json!({"alg": "dir", "enc": "A256GCM", "kid": "dir-1", "cty": "JWT"}).to_string(),
+        );
+        parts[0] = &header;
+
+        let result = CompactJwe::parse(&parts.join("."))
+            .unwrap()
+            .decrypt(&DirectKey)
+            .await;
+
+        assert!(matches!(result, Err(Error::ContentEncryption { .. })));
+    }
+
+    #[test]
+    fn test_parse_rejects_malformed_tokens() {
+        let zip = BASE64_URL_SAFE_NO_PAD
+            .encode(json!({"alg": "dir", "enc": "A256GCM", "zip": "DEF"}).to_string());
+        let crit = BASE64_URL_SAFE_NO_PAD
+            .encode(json!({"alg": "dir", "enc": "A256GCM", "crit": ["exp"], "exp": 0}).to_string());
+
+        for (token, reason) in [
+            ("a.b.c", "expected five parts"),
+            ("e30.a.b.c.d.e", "expected five parts"),
+            (
+                "W10..AA.AA.AA",
+                "header is not a base64url-encoded JSON object",
+            ),
+            ("e30..AA.A!.AA", "part is not base64url-encoded"),
+            (&format!("{zip}..AA.AA.AA"), "compression is not supported"),
+            (
+                &format!("{crit}..AA.AA.AA"),
+                "critical extensions are not supported",
+            ),
+        ] {
+            assert_eq!(
+                token.parse::<CompactJwe>(),
+                Err(MalformedJweError { reason }),
+                "{token}"
+            );
+        }
+    }
+
+    #[cfg(all(feature = "jwe-aes-kw", native))]
+    #[tokio::test]
+    async fn test_decrypt_rfc_7516_a3_vector() {
+        use crate::{jwe::AesKeyWrap, jwk::PrivateJwk};
+
+        let jwk: PrivateJwk =
+            serde_json::from_str(r#"{"kty":"oct","k":"GawgguFyGrWKav7AX4VKUg"}"#).unwrap();
+        let token = "eyJhbGciOiJBMTI4S1ciLCJlbmMiOiJBMTI4Q0JDLUhTMjU2In0.\
+                     6KB707dM9YTIgHtLvtgWQ8mKwboJW3of9locizkDTHzBC2IlrT1oOQ.\
+                     AxY8DCtDaGlsbGljb3RoZQ.\
+                     KDlTtXchhZTGufMYmOYGS4HffxPSUrfmqCHXaI9wOGY.\
+                     U0m_YmjN04DJvceFICbCVQ";
+
+        let jwe = CompactJwe::parse(token).unwrap();
+        let plaintext = jwe
+            .decrypt(&AesKeyWrap::from_jwk(&jwk).unwrap())
+            .await
+            .unwrap();
+
+        assert_eq!(plaintext, b"Live long and prosper.");
+        assert_eq!(jwe.to_string(), token);
+    }
+}
@@ -0,0 +1,424 @@
+//! JWE content encryption algorithms (RFC 7518 §5).
+
+#[cfg(feature = "jwe")]
+use aes::{Aes128, Aes192, Aes256};
+#[cfg(feature = "jwe")]
+use aes_gcm::{
+    AeadCore, AeadInPlace, Aes128Gcm, Aes256Gcm, AesGcm, KeyInit, Nonce, Tag,
+    aead::{OsRng, consts::U12, rand_core::RngCore},
+};
+#[cfg(feature = "jwe")]
+use bytes::Bytes;
+#[cfg(feature = "jwe")]
+use cbc::cipher::{BlockCipher, BlockDecryptMut, BlockEncryptMut, KeyIvInit, block_padding::Pkcs7};
+#[cfg(feature = "jwe")]
+use hmac::{
+    Mac, SimpleHmac,
+    digest::{Digest, core_api::BlockSizeUser},
+};
+#[cfg(feature = "jwe")]
+use sha2::{Sha256, Sha384, Sha512};
+#[cfg(feature = "jwe")]
+use snafu::prelude::*;
+
+#[cfg(feature = "jwe")]
+use crate::jwe::error::{
+    ContentEncryptionError, DecryptionSnafu, EncryptionSnafu, InvalidKeyLengthSnafu,
+};
+
+/// A JWE content encryption algorithm (`enc`).
+///
+/// With the `jwe` feature, content can be encrypted and decrypted with a
+/// content encryption key (CEK), such as one produced by a
+/// [`JweEncrypter`](super::JweEncrypter).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ContentEncryption {
+    /// AES-128-CBC with HMAC SHA-256 (`A128CBC-HS256`).
+    A128CbcHs256,
+    /// AES-192-CBC with HMAC SHA-384 (`A192CBC-HS384`).
+    A192CbcHs384,
+    /// AES-256-CBC with HMAC SHA-512 (`A256CBC-HS512`).
+    A256CbcHs512,
+    /// AES-128-GCM (`A128GCM`).
+    A128Gcm,
+    /// AES-192-GCM (`A192GCM`).
+    A192Gcm,
+    /// AES-256-GCM (`A256GCM`).
+    A256Gcm,
+}
+
+impl ContentEncryption {
+    /// Looks up a content encryption algorithm by its `enc` name.
+    #[must_use]
+    pub fn from_name(enc: &str) -> Option<Self> {
+        match enc {
+            "A128CBC-HS256" => Some(Self::A128CbcHs256),
+            "A192CBC-HS384" => Some(Self::A192CbcHs384),
+            "A256CBC-HS512" => Some(Self::A256CbcHs512),
+            "A128GCM" => Some(Self::A128Gcm),
+            "A192GCM" => Some(Self::A192Gcm),
+            "A256GCM" => Some(Self::A256Gcm),
+            _ => None,
+        }
+    }
+
+    /// Returns the `enc` name.
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::A128CbcHs256 => "A128CBC-HS256",
+            Self::A192CbcHs384 => "A192CBC-HS384",
+            Self::A256CbcHs512 => "A256CBC-HS512",
+            Self::A128Gcm => "A128GCM",
+            Self::A192Gcm => "A192GCM",
+            Self::A256Gcm => "A256GCM",
+        }
+    }
+
+    /// Returns the CEK length in bytes.
+    ///
+    /// For the CBC-HMAC algorithms, this is the combined length of the MAC
+    /// and encryption keys.
+    #[must_use]
+    pub fn key_length(self) -> usize {
+        match self {
+            Self::A128Gcm => 16,
+            Self::A192Gcm => 24,
+            Self::A128CbcHs256 | Self::A256Gcm => 32,
+            Self::A192CbcHs384 => 48,
+            Self::A256CbcHs512 => 64,
+        }
+    }
+
+    /// Returns the initialization vector length in bytes.
+    #[must_use]
+    pub fn iv_length(self) -> usize {
+        if self.is_gcm() { 12 } else { 16 }
+    }
+
+    /// Returns the authentication tag length in bytes.
+    #[must_use]
+    pub fn tag_length(self) -> usize {
+        if self.is_gcm() {
+            16
+        } else {
+            self.key_length() / 2
+        }
+    }
+
+    fn is_gcm(self) -> bool {
+        matches!(self, Self::A128Gcm | Self::A192Gcm | Self::A256Gcm)
+    }
+}
+
+#[cfg(feature = "jwe")]
+impl ContentEncryption {
+    /// Encrypts the plaintext with a CEK and a random initialization vector,
+    /// authenticating the additional data.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the CEK has the wrong length.
+    pub fn encrypt(
+        self,
+        cek: &[u8],
+        aad: &[u8],
+        plaintext: &[u8],
+    ) -> Result<EncryptedContent, ContentEncryptionError> {
+        self.check_key(cek)?;
+        match self {
+            Self::A128CbcHs256 => cbc_hmac_encrypt::<Aes128, Sha256>(cek, aad, plaintext),
+            Self::A192CbcHs384 => cbc_hmac_encrypt::<Aes192, Sha384>(cek, aad, plaintext),
+            Self::A256CbcHs512 => cbc_hmac_encrypt::<Aes256, Sha512>(cek, aad, plaintext),
+            Self::A128Gcm => gcm_encrypt::<Aes128Gcm>(cek, aad, plaintext),
+            Self::A192Gcm => gcm_encrypt::<AesGcm<Aes192, U12>>(cek, aad, plaintext),
+            Self::A256Gcm => gcm_encrypt::<Aes256Gcm>(cek, aad, plaintext),
+        }
+    }
+
+    /// Verifies the authentication tag over the ciphertext and additional
+    /// data, and decrypts the ciphertext with a CEK.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the CEK has the wrong length, or the content
+    /// doesn't decrypt. The cause of a decryption failure (a malformed
+    /// initialization vector or tag, a tag mismatch, or bad padding) is
+    /// deliberately not reported.
+    pub fn decrypt(
+        self,
+        cek: &[u8],
+        aad: &[u8],
+        content: &EncryptedContent,
+    ) -> Result<Vec<u8>, ContentEncryptionError> {
+        self.check_key(cek)?;
+        ensure!(
+            content.iv.len() == self.iv_length() && content.tag.len() == self.tag_length(),
+            DecryptionSnafu
+        );
+        match self {
+            Self::A128CbcHs256 => cbc_hmac_decrypt::<Aes128, Sha256>(cek, aad, content),
+            Self::A192CbcHs384 => cbc_hmac_decrypt::<Aes192, Sha384>(cek, aad, content),
+            Self::A256CbcHs512 => cbc_hmac_decrypt::<Aes256, Sha512>(cek, aad, content),
+            Self::A128Gcm => gcm_decrypt::<Aes128Gcm>(cek, aad, content),
+            Self::A192Gcm => gcm_decrypt::<AesGcm<Aes192, U12>>(cek, aad, content),
+            Self::A256Gcm => gcm_decrypt::<Aes256Gcm>(cek, aad, content),
+        }
+    }
+
+    fn check_key(self, cek: &[u8]) -> Result<(), ContentEncryptionError> {
+        ensure!(
+            cek.len() == self.key_length(),
+            InvalidKeyLengthSnafu {
+                expected: self.key_length(),
+                actual: cek.len(),
+            }
+        );
+        Ok(())
+    }
+}
+
+/// The output of content encryption: the initialization vector, ciphertext
+/// and authentication tag of a JWE.
+#[cfg(feature = "jwe")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EncryptedContent {
+    iv: Bytes,
+    ciphertext: Bytes,
+    tag: Bytes,
+}
+
+#[cfg(feature = "jwe")]
+impl EncryptedContent {
+    /// Creates encrypted content from its parts, such as those of a parsed
+    /// JWE.
+    pub fn new(iv: impl Into<Bytes>, ciphertext: impl Into<Bytes>, tag: impl Into<Bytes>) -> Self {
+        Self {
+            iv: iv.into(),
+            ciphertext: ciphertext.into(),
+            tag: tag.into(),
+        }
+    }
+
+    /// Returns the initialization vector.
+    #[must_use]
+    pub fn iv(&self) -> &Bytes {
+        &self.iv
+    }
+
+    /// Returns the ciphertext.
+    #[must_use]
+    pub fn ciphertext(&self) -> &Bytes {
+        &self.ciphertext
+    }
+
+    /// Returns the authentication tag.
+    #[must_use]
+    pub fn tag(&self) -> &Bytes {
+        &self.tag
+    }
+}
+
+/// Returns the additional authenticated data for content encryption (RFC 7516
+/// §5.1): the encoded protected header, followed by a `.` and the encoded
+/// `aad` member of the JSON serialization, if any.
+#[must_use]
+pub fn additional_authenticated_data(protected: &str, aad: Option<&str>) -> Vec<u8> {
+    match aad {
+        Some(aad) => [protected, ".", aad].concat().into_bytes(),
+        None => protected.as_bytes().to_vec(),
+    }
+}
+
+/// Encrypts with AES-CBC and authenticates with HMAC (RFC 7518 §5.2.2.1).
+#[cfg(feature = "jwe")]
+fn cbc_hmac_encrypt<C, D>(
+    cek: &[u8],
+    aad: &[u8],
+    plaintext: &[u8],
+) -> Result<EncryptedContent, ContentEncryptionError>
+where
+    C: BlockCipher + BlockEncryptMut + KeyInit,
+    D: Digest + BlockSizeUser,
+{
+    let (mac_key, enc_key) = cek.split_at(cek.len() / 2);
+    let mut iv = [0; 16];
+    OsRng.fill_bytes(&mut iv);
+    let ciphertext = cbc::Encryptor::<C>::new_from_slices(enc_key, &iv)
+        .ok()
+        .context(EncryptionSnafu)?
+        .encrypt_padded_vec_mut::<Pkcs7>(plaintext);
+    let tag = cbc_hmac_tag::<D>(mac_key, aad, &iv, &ciphertext)?
+        .finalize()
+        .into_bytes();
+    Ok(EncryptedContent::new(
+        iv.to_vec(),
+        ciphertext,
+        tag[..mac_key.len()].to_vec(),
+    ))
+}
+
+/// Verifies with HMAC and decrypts with AES-CBC (RFC 7518 §5.2.2.2).
+#[cfg(feature = "jwe")]
+fn cbc_hmac_decrypt<C, D>(
+    cek: &[u8],
+    aad: &[u8],
+    content: &EncryptedContent,
+) -> Result<Vec<u8>, ContentEncryptionError>
+where
+    C: BlockCipher + BlockDecryptMut + KeyInit,
+    D: Digest + BlockSizeUser,
+{
+    let (mac_key, enc_key) = cek.split_at(cek.len() / 2);
+    cbc_hmac_tag::<D>(mac_key, aad, &content.iv, &content.ciphertext)?
+        .verify_truncated_left(&content.tag)
+        .ok()
+        .context(DecryptionSnafu)?;
+    cbc::Decryptor::<C>::new_from_slices(enc_key, &content.iv)
+        .ok()
+        .context(DecryptionSnafu)?
+        .decrypt_padded_vec_mut::<Pkcs7>(&content.ciphertext)
+        .ok()
+        .context(DecryptionSnafu)
+}
+
+/// Computes the HMAC over `AAD || IV || ciphertext || AL`, where `AL` is the
+/// bit length of the AAD.
+#[cfg(feature = "jwe")]
+fn cbc_hmac_tag<D: Digest + BlockSizeUser>(
+    mac_key: &[u8],
+    aad: &[u8],
+    iv: &[u8],
+    ciphertext: &[u8],
+) -> Result<SimpleHmac<D>, ContentEncryptionError> {
+    let mut mac = <SimpleHmac<D> as KeyInit>::new_from_slice(mac_key)
+        .ok()
+        .context(EncryptionSnafu)?;
+    let aad_bits = u64::try_from(aad.len()).unwrap_or(u64::MAX / 8) * 8;
+    mac.update(aad);
+    mac.update(iv);
+    mac.update(ciphertext);
+    mac.update(&aad_bits.to_be_bytes());
+    Ok(mac)
+}
+
+#[cfg(feature = "jwe")]
+fn gcm_encrypt<A: AeadInPlace + KeyInit>(
+    cek: &[u8],
+    aad: &[u8],
+    plaintext: &[u8],
+) -> Result<EncryptedContent, ContentEncryptionError> {
+    let cipher = A::new_from_slice(cek).ok().context(EncryptionSnafu)?;
+    let iv = A::generate_nonce(&mut OsRng);
+    let mut ciphertext = plaintext.to_vec();
+    let tag = cipher
+        .encrypt_in_place_detached(&iv, aad, &mut ciphertext)
+        .ok()
+        .context(EncryptionSnafu)?;
+    Ok(EncryptedContent::new(iv.to_vec(), ciphertext, tag.to_vec()))
+}
+
+#[cfg(feature = "jwe")]
+fn gcm_decrypt<A: AeadInPlace + AeadCore<NonceSize = U12> + KeyInit>(
+    cek: &[u8],
+    aad: &[u8],
+    content: &EncryptedContent,
+) -> Result<Vec<u8>, ContentEncryptionError> {
+    let cipher = A::new_from_slice(cek).ok().context(DecryptionSnafu)?;
+    let mut plaintext = content.ciphertext.to_vec();
+    cipher
+        .decrypt_in_place_detached(
+            Nonce::from_slice(&content.iv),
+            aad,
+            &mut plaintext,
+            Tag::from_slice(&content.tag),
+        )
+        .ok()
+        .context(DecryptionSnafu)?;
+    Ok(plaintext)
+}
+
+#[cfg(all(test, feature = "jwe"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_a128cbc_hs256_rfc_7518_vector() {
+        // RFC 7518 Appendix B.1.
+        let cek: Vec<u8> = (0..32).collect();
+        let aad = b"The second principle of Auguste Kerckhoffs";
+        let content = EncryptedContent::new(
+            hex::decode("1af38c2dc2b96ffdd86694092341bc04").unwrap(),
+            hex::decode(
+                "c80edfa32ddf39d5ef00c0b468834279a2e46a1b8049f792f76bfe54b903a9c9\
+                 a94ac9b47ad2655c5f10f9aef71427e2fc6f9b3f399a221489f16362c7032336\
+                 09d45ac69864e3321cf82935ac4096c86e133314c54019e8ca7980dfa4b9cf1b\
+                 384c486f3a54c51078158ee5d79de59fbd34d848b3d69550a67646344427ade5\
+                 4b8851ffb598f7f80074b9473c82e2db",
+            )
+            .unwrap(),
+            hex::decode("652c3fa36b0a7c5b3219fab3a30bc1c4").unwrap(),
+        );
+
+        let plaintext = ContentEncryption::A128CbcHs256
+            .decrypt(&cek, aad, &content)
+            .unwrap();
+
+        assert_eq!(
+            plaintext,
+            b"A cipher system must not be required to be secret, and it must be able to fall \
+              into the hands of the enemy without inconvenience"
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let aad = additional_authenticated_data("eyJlbmMiOiJBMjU2R0NNIn0", None);
+
+        for enc in [
+            ContentEncryption::A128CbcHs256,
+            ContentEncryption::A192CbcHs384,
+            ContentEncryption::A256CbcHs512,
+            ContentEncryption::A128Gcm,
+            ContentEncryption::A192Gcm,
+            ContentEncryption::A256Gcm,
+        ] {
+            let cek = vec![7; enc.key_length()];
+            let content = enc.encrypt(&cek, &aad, b"payload").unwrap();
+
+            assert_eq!(content.iv().len(), enc.iv_length());
+            assert_eq!(content.tag().len(), enc.tag_length());
+            assert_eq!(enc.decrypt(&cek, &aad, &content).unwrap(), b"payload");
+            assert_eq!(ContentEncryption::from_name(enc.name()), Some(enc));
+        }
+    }
+
+    #[test]
+    fn test_decrypt_rejects_tampering() {
+        for enc in [ContentEncryption::A128CbcHs256, ContentEncryption::A256Gcm] {
+            let cek = vec![7; enc.key_length()];
+            let content = enc.encrypt(&cek, b"header", b"payload").unwrap();
+            let mut tag = content.tag().to_vec();
+            tag[0] ^= 1;
+            let tampered =
+                EncryptedContent::new(content.iv().clone(), content.ciphertext().clone(), tag);
+            let truncated = EncryptedContent::new(
+                content.iv().clone(),
+                content.ciphertext().clone(),
+                content.tag().slice(..8),
+            );
+
+            assert!(matches!(
+                enc.decrypt(&cek, b"other header", &content),
+                Err(ContentEncryptionError::Decryption)
+            ));
+            assert!(enc.decrypt(&cek, b"header", &tampered).is_err());
+            assert!(enc.decrypt(&cek, b"header", &truncated).is_err());
+            assert!(matches!(
+                enc.decrypt(&cek[1..], b"header", &content),
+                Err(ContentEncryptionError::InvalidKeyLength { .. })
+            ));
+        }
+    }
+}
@@ -0,0 +1,423 @@
+//! The `ECDH-ES` key management algorithm (RFC 7518 §4.6), in Direct Key
+//! Agreement mode.
+
+use std::{borrow::Cow, sync::Arc};
+
+use aws_lc_rs::agreement::{
+    self, ECDH_P256, ECDH_P384, ECDH_P521, PrivateKey as AgreementKey, UnparsedPublicKey, X25519,
+};
+use base64::{Engine, prelude::BASE64_URL_SAFE_NO_PAD};
+use secrecy::{ExposeSecret, SecretBox};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+use snafu::prelude::*;
+
+use crate::{
+    jwe::{
+        ContentKey, DEFAULT_CONTENT_ENCRYPTION, JweDecrypter, JweEncrypter, check_jwk,
+        content_key_length,
+        error::{
+            CryptoSnafu, InvalidHeaderSnafu, InvalidKeySnafu, KeyManagementError,
+            UnsupportedContentEncryptionSnafu,
+        },
+    },
+    jwk::{EcPublicKey, OkpPublicKey, PrivateJwk, PrivateKey, PublicJwk, PublicKey},
+};
+
+const ALGORITHM: &str = "ECDH-ES";
+
+/// Derives CEKs by key agreement with an ephemeral key and the recipient's
+/// EC (`P-256`, `P-384`, `P-521`) or `X25519` public key.
+///
+/// The ephemeral public key is returned as the `epk` header parameter, and no
+/// encrypted key is sent.
+#[derive(Debug, Clone)]
+pub struct EcdhEsEncrypter {
+    curve: Curve,
+    public_key: Vec<u8>,
+    key_id: Option<String>,
+    enc: Cow<'static, str>,
+}
+
+impl EcdhEsEncrypter {
+    /// Creates an encrypter from the recipient's EC or OKP public JWK.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the JWK isn't for a supported curve, or its `alg`
+    /// or `use` parameters rule out `ECDH-ES`.
+    pub fn from_jwk(jwk: &PublicJwk) -> Result<Self, KeyManagementError> {
+        check_jwk(jwk.algorithm(), jwk.key_use(), &[ALGORITHM])?;
+        let (curve, public_key) = Curve::public_key(jwk.key())?;
+        Ok(Self {
+            curve,
+            public_key,
+            key_id: jwk.kid().map(str::to_owned),
+            enc: Cow::Borrowed(DEFAULT_CONTENT_ENCRYPTION),
+        })
+    }
+
+    /// Sets the content encryption algorithm (`enc`) the recipient expects,
+    /// instead of `A128CBC-HS256`.
+    #[must_use]
+    pub fn with_content_encryption(mut self, enc: impl Into<Cow<'static, str>>) -> Self {
+        self.enc = enc.into();
+        self
+    }
+}
+
+impl JweEncrypter for EcdhEsEncrypter {
+    type Error = KeyManagementError;
+
+    fn key_management_algorithm(&self) -> Cow<'_, str> {
+        Cow::Borrowed(ALGORITHM)
+    }
+
+    fn content_encryption_algorithm(&self) -> Cow<'_, str> {
+        Cow::Borrowed(&self.enc)
+    }
+
+    fn key_id(&self) -> Option<Cow<'_, str>> {
+        self.key_id.as_deref().map(Cow::Borrowed)
+    }
+
+    async fn encrypt_key(&self, enc: &str) -> Result<ContentKey, Self::Error> {
+        let len = content_key_length(enc).context(UnsupportedContentEncryptionSnafu { enc })?;
+        let algorithm = self.curve.algorithm();
+        let ephemeral = AgreementKey::generate(algorithm)
+            .ok()
+            .context(CryptoSnafu)?;
+        let epk = ephemeral.compute_public_key().ok().context(CryptoSnafu)?;
+        let cek = agreement::agree(
+            &ephemeral,
+            UnparsedPublicKey::new(algorithm, &self.public_key),
+            KeyManagementError::Crypto,
+            |z| Ok(concat_kdf(z, enc, &[], &[], len)),
+        )?;
+        Ok(
+            ContentKey::new(cek, Vec::new(), ALGORITHM, self.key_id.clone())
+                .with_header_param("epk", self.curve.to_jwk(epk.as_ref())),
+        )
+    }
+}
+
+/// Derives CEKs by key agreement with a static EC (`P-256`, `P-384`,
+/// `P-521`) or `X25519` private key and the sender's ephemeral public key.
+#[derive(Debug, Clone)]
+pub struct EcdhEsDecrypter {
+    curve: Curve,
+    private_key: Arc<AgreementKey>,
+    key_id: Option<String>,
+}
+
+impl EcdhEsDecrypter {
+    /// Creates a decrypter from an EC or OKP private JWK.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the JWK isn't for a supported curve, or its `alg`
+    /// or `use` parameters rule out `ECDH-ES`.
+    pub fn from_jwk(jwk: &PrivateJwk) -> Result<Self, KeyManagementError> {
+        check_jwk(jwk.algorithm(), jwk.key_use(), &[ALGORITHM])?;
+        let (crv, d) = match jwk.key() {
+            PrivateKey::Ec(key) => (key.curve(), key.private_scalar()),
+            PrivateKey::Okp(key) => (key.curve(), key.private_key()),
+            _ => {
+                return InvalidKeySnafu {
+                    reason: "not an EC or OKP key",
+                }
+                .fail();
+            }
+        };
+        let curve = Curve::from_crv(crv)?;
+        let private_key = AgreementKey::from_private_key(curve.algorithm(), d.expose_secret())
+            .ok()
+            .context(InvalidKeySnafu {
+                reason: "invalid private key",
+            })?;
+        Ok(Self {
+            curve,
+            private_key: Arc::new(private_key),
+            key_id: jwk.kid().map(str::to_owned),
+        })
+    }
+}
+
+impl JweDecrypter for EcdhEsDecrypter {
+    type Error = KeyManagementError;
+
+    fn key_management_algorithm(&self) -> Cow<'_, str> {
+        Cow::Borrowed(ALGORITHM)
+    }
+
+    fn key_id(&self) -> Option<Cow<'_, str>> {
+        self.key_id.as_deref().map(Cow::Borrowed)
+    }
+
+    async fn decrypt_key_unchecked(
+        &self,
+        header: &Map<String, Value>,
+        _encrypted_key: &[u8],
+        enc: &str,
+    ) -> Result<SecretBox<[u8]>, Self::Error> {
+        let len = content_key_length(enc).context(UnsupportedContentEncryptionSnafu { enc })?;
+        let epk = header
+            .get("epk")
+            .and_then(|epk| PublicKey::deserialize(epk).ok())
+            .context(InvalidHeaderSnafu { name: "epk" })?;
+        let (curve, epk) = Curve::public_key(&epk)
+            .ok()
+            .filter(|(curve, _)| *curve == self.curve)
+            .context(InvalidHeaderSnafu { name: "epk" })?;
+        let apu = party_info(header, "apu")?;
+        let apv = party_info(header, "apv")?;
+        agreement::agree(
+            &self.private_key,
+            UnparsedPublicKey::new(curve.algorithm(), &epk),
+            KeyManagementError::Crypto,
+            |z| Ok(concat_kdf(z, enc, &apu, &apv, len)),
+        )
+    }
+}
+
+/// The curves supported for key agreement.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Curve {
+    P256,
+    P384,
+    P521,
+    X25519,
+}
+
+impl Curve {
+    fn from_crv(crv: &str) -> Result<Self, KeyManagementError> {
+        match crv {
+            "P-256" => Ok(Self::P256),
+            "P-384" => Ok(Self::P384),
+            "P-521" => Ok(Self::P521),
+            "X25519" => Ok(Self::X25519),
+            _ => InvalidKeySnafu {
+                reason: format!("unsupported curve '{crv}'"),
+            }
+            .fail(),
+        }
+    }
+
+    fn crv(self) -> &'static str {
+        match self {
+            Self::P256 => "P-256",
+            Self::P384 => "P-384",
+            Self::P521 => "P-521",
+            Self::X25519 => "X25519",
+        }
+    }
+
+    fn algorithm(self) -> &'static agreement::Algorithm {
+        match self {
+            Self::P256 => &ECDH_P256,
+            Self::P384 => &ECDH_P384,
+            Self::P521 => &ECDH_P521,
+            Self::X25519 => &X25519,
+        }
+    }
+
+    /// Returns the curve and encoded public key (uncompressed point for EC
+    /// curves) of a JWK.
+    fn public_key(key: &PublicKey) -> Result<(Self, Vec<u8>), KeyManagementError> {
+        match key {
+            PublicKey::Ec(key) => {
+                let curve = Self::from_crv(key.curve())?;
+                ensure!(
+                    curve != Self::X25519,
+                    InvalidKeySnafu {
+                        reason: "X25519 keys must be OKP keys",
+                    }
+                );
+                Ok((curve, [&[0x04], key.x(), key.y()].concat()))
+            }
+            PublicKey::Okp(key) => {
+                let curve = Self::from_crv(key.curve())?;
+                ensure!(
+                    curve == Self::X25519,
+                    InvalidKeySnafu {
+                        reason: "OKP keys must be X25519 keys",
+                    }
+                );
+                Ok((curve, key.x().to_vec()))
+            }
+            _ => InvalidKeySnafu {
+                reason: "not an EC or OKP key",
+            }
+            .fail(),
+        }
+    }
+
+    /// Encodes a public key computed by `aws-lc-rs` as a JWK, for `epk`.
+    fn to_jwk(self, public_key: &[u8]) -> Value {
+        let key: PublicKey = if self == Self::X25519 {
+            OkpPublicKey::builder()
+                .crv(self.crv())
+                .x(public_key.iter().copied())
+                .into()
+        } else {
+            let (x, y) = public_key[1..].split_at(public_key.len() / 2);
+            EcPublicKey::builder()
+                .crv(self.crv())
+                .x(x.iter().copied())
+                .y(y.iter().copied())
+                .into()
+        };
+        serde_json::to_value(key).unwrap_or_default()
+    }
+}
+
+/// Decodes the optional `apu` or `apv` header parameter.
+fn party_info(header: &Map<String, Value>, name: &str) -> Result<Vec<u8>, KeyManagementError> {
+    let Some(value) = header.get(name) else {
+        return Ok(Vec::new());
+    };
+    value
+        .as_str()
+        .and_then(|value| BASE64_URL_SAFE_NO_PAD.decode(value).ok())
+        .context(InvalidHeaderSnafu { name })
+}
+
+/// Derives a key of `len` bytes from the shared secret with the Concat KDF
+/// (NIST SP 800-56A §5.8.1), as parameterized by RFC 7518 §4.6.2.
+fn concat_kdf(z: &[u8], enc: &str, apu: &[u8], apv: &[u8], len: usize) -> SecretBox<[u8]> {
+    fn length_prefixed(hasher: &mut Sha256, data: &[u8]) {
+        hasher.update(u32::try_from(data.len()).unwrap_or(u32::MAX).to_be_bytes());
+        hasher.update(data);
+    }
+
+    let mut key = Vec::with_capacity(len.next_multiple_of(32));
+    for counter in 1_u32.. {
+        if key.len() >= len {
+            break;
+        }
+        let mut hasher = Sha256::new();
+        hasher.update(counter.to_be_bytes());
+        hasher.update(z);
+        length_prefixed(&mut hasher, enc.as_bytes());
+        length_prefixed(&mut hasher, apu);
+        length_prefixed(&mut hasher, apv);
+        hasher.update(u32::try_from(len * 8).unwrap_or(u32::MAX).to_be_bytes());
+        key.extend_from_slice(&hasher.finalize());
+    }
+    key.truncate(len);
+    SecretBox::new(key.into_boxed_slice())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_decrypt_rfc_7518_vector() {
+        // RFC 7518 Appendix C.
+        let bob: PrivateJwk = serde_json::from_str(
+            &json!({
+                "kty": "EC",
+                "crv": "P-256",
+                "x": "weNJy2HscCSM6AEDTDg04biOvhFhyyWvOHQfeF_PxMQ",
+                "y": "e8lnCO-AlStT-NJVX-crhB7QRYhiix03illJOVAOyck",
+                "d": "VEmDZpDXXK8p8N0Cndsxs924q6nS1RXFASRl6BfUqdw"
+            })
+            .to_string(),
+        )
+        .unwrap();
+        let Value::Object(header) = json!({
+            "alg": "ECDH-ES",
+            "enc": "A128GCM",
+            "apu": "QWxpY2U",
+            "apv": "Qm9i",
+            "epk": {
+                "kty": "EC",
+                "crv": "P-256",
+                "x": "gI0GAILBdu7T53akrFmMyGcsF3n5dO7MmwNBHKW5SV0",
+                "y": "SLW_xSffzlPWrHEVI30DHM_4egVwt3NQqeUD7nMFpps"
+            }
+        }) else {
+            unreachable!()
+        };
+
+        let decrypter = EcdhEsDecrypter::from_jwk(&bob).unwrap();
+        let cek = decrypter.decrypt_key(&header, &[]).await.unwrap();
+
+        assert_eq!(
+            BASE64_URL_SAFE_NO_PAD.encode(cek.expose_secret()),
+            "VqqN6vgjbSBcIijNcacQGg"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_round_trip() {
+        for jwk in [
+            json!({
+                "kty": "EC",
+                "crv": "P-256",
+                "x": "weNJy2HscCSM6AEDTDg04biOvhFhyyWvOHQfeF_PxMQ",
+                "y": "e8lnCO-AlStT-NJVX-crhB7QRYhiix03illJOVAOyck",
+                "d": "VEmDZpDXXK8p8N0Cndsxs924q6nS1RXFASRl6BfUqdw"
+            }),
+            // RFC 7748 §6.1 (Bob).
+            json!({
+                "kty": "OKP",
+                "crv": "X25519",
+                "x": "3p7bfXt9wbTTW2HC7OQ1Nz-DQ8hbeGdNrfx-FG-IK08",
+                "d": "XasIfmJKikt54X-Lg4AO5m87sSkmGLb9HC-LJ_-I4Os"
+            }),
+        ] {
+            let jwk: PrivateJwk = serde_json::from_str(&jwk.to_string()).unwrap();
+            let encrypter = EcdhEsEncrypter::from_jwk(&jwk.to_public().unwrap()).unwrap();
+            let decrypter = EcdhEsDecrypter::from_jwk(&jwk).unwrap();
+
+            let key = encrypter.encrypt_key("A256CBC-HS512").await.unwrap();
+            let mut header = key.header_params().clone();
+            header.insert("alg".into(), "ECDH-ES".into());
+            header.insert("enc".into(), "A256CBC-HS512".into());
+            let cek = decrypter.decrypt_key(&header, &[]).await.unwrap();
+
+            assert!(key.encrypted_key().is_empty());
+            assert_eq!(cek.expose_secret().len(), 64);
+            assert_eq!(cek.expose_secret(), key.cek());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_decrypt_requires_epk_on_same_curve() {
+        let jwk: PrivateJwk = serde_json::from_str(
+            &json!({
+                "kty": "OKP",
+                "crv": "X25519",
+                "x": "3p7bfXt9wbTTW2HC7OQ1Nz-DQ8hbeGdNrfx-FG-IK08",
+                "d": "XasIfmJKikt54X-Lg4AO5m87sSkmGLb9HC-LJ_-I4Os"
+            })
+            .to_string(),
+        )
+        .unwrap();
+        let decrypter = EcdhEsDecrypter::from_jwk(&jwk).unwrap();
+        let Value::Object(header) = json!({
+            "alg": "ECDH-ES",
+            "enc": "A128GCM",
+            "epk": {
+                "kty": "EC",
+                "crv": "P-256",
+                "x": "gI0GAILBdu7T53akrFmMyGcsF3n5dO7MmwNBHKW5SV0",
+                "y": "SLW_xSffzlPWrHEVI30DHM_4egVwt3NQqeUD7nMFpps"
+            }
+        }) else {
+            unreachable!()
+        };
+
+        assert!(matches!(
+            decrypter.decrypt_key(&header, &[]).await,
+            Err(crate::jwe::Error::UnderlyingError {
+                source: KeyManagementError::InvalidHeader { .. }
+            })
+        ));
+    }
+}
@@ -0,0 +1,134 @@
+use snafu::Snafu;
+
+use crate::MaybeSendSync;
+
+/// The error type returned by JWE operations.
+#[derive(Debug, Snafu)]
+#[snafu(visibility(pub(super)))]
+#[non_exhaustive]
+pub enum Error<E: std::error::Error + MaybeSendSync + 'static> {
+    /// The `alg` header parameter doesn't match the decrypter's algorithm.
+    #[snafu(display("Key management algorithm '{actual}' doesn't match expected '{expected}'"))]
+    AlgorithmMismatch {
+        /// The decrypter's key management algorithm.
+        expected: String,
+        /// The `alg` header parameter.
+        actual: String,
+    },
+    /// The `kid` header parameter doesn't match the decrypter's key ID.
+    #[snafu(display("Key ID doesn't match the decrypter's key"))]
+    KeyIdMismatch,
+    /// The content encryption algorithm is unknown.
+    #[snafu(display("Unsupported content encryption algorithm '{enc}'"))]
+    UnsupportedEncryption {
+        /// The `enc` header parameter.
+        enc: String,
+    },
+    /// The JWE is malformed.
+    #[snafu(display("Malformed JWE: {reason}"))]
+    Malformed {
+        /// What is wrong with the JWE.
+        reason: &'static str,
+    },
+    /// The key management algorithm derives the CEK, so the JWE can't have
+    /// other recipients.
+    #[snafu(display(
+        "Key management algorithm '{algorithm}' can't be used with several recipients"
+    ))]
+    SingleRecipient {
+        /// The key management algorithm.
+        algorithm: String,
+    },
+    /// The content could not be encrypted or decrypted.
+    #[cfg(feature = "jwe")]
+    #[snafu(display("Content encryption failed"))]
+    ContentEncryption {
+        /// The content encryption error.
+        source: ContentEncryptionError,
+    },
+    /// The error from the underlying implementation.
+    UnderlyingError {
+        /// The source error.
+        source: E,
+    },
+}
+
+#[cfg(feature = "jwe")]
+impl<E: std::error::Error + MaybeSendSync + 'static> From<MalformedJweError> for Error<E> {
+    fn from(error: MalformedJweError) -> Self {
+        Self::Malformed {
+            reason: error.reason,
+        }
+    }
+}
+
+/// The error returned when a serialized JWE can't be parsed.
+#[cfg(feature = "jwe")]
+#[derive(Debug, Clone, PartialEq, Eq, Snafu)]
+#[snafu(display("Malformed JWE: {reason}"), visibility(pub(super)))]
+pub struct MalformedJweError {
+    /// What is wrong with the JWE.
+    pub reason: &'static str,
+}
+
+/// The error type returned by the local key management algorithm
+/// implementations, such as [`AesKeyWrap`](super::AesKeyWrap).
+///
+/// Failures of the key management operation itself are deliberately opaque,
+/// so decryption errors don't act as an oracle (RFC 7516 §11.5).
+#[cfg(all(
+    any(
+        feature = "jwe-rsa-oaep",
+        feature = "jwe-ecdh-es",
+        feature = "jwe-aes-kw"
+    ),
+    native
+))]
+#[derive(Debug, Snafu)]
+#[snafu(visibility(pub(super)))]
+#[non_exhaustive]
+pub enum KeyManagementError {
+    /// The JWK can't be used with the key management algorithm.
+    #[snafu(display("Invalid key: {reason}"))]
+    InvalidKey {
+        /// Why the key was rejected.
+        reason: String,
+    },
+    /// The content encryption algorithm is unknown.
+    #[snafu(display("Unsupported content encryption algorithm '{enc}'"))]
+    UnsupportedContentEncryption {
+        /// The content encryption algorithm.
+        enc: String,
+    },
+    /// A header parameter needed to recover the CEK is missing or malformed.
+    #[snafu(display("Missing or invalid '{name}' header parameter"))]
+    InvalidHeader {
+        /// The header parameter name.
+        name: String,
+    },
+    /// The key management operation failed.
+    #[snafu(display("Key management operation failed"))]
+    Crypto,
+}
+
+/// The error type returned by [`ContentEncryption`](super::ContentEncryption).
+#[cfg(feature = "jwe")]
+#[derive(Debug, Snafu)]
+#[snafu(visibility(pub(super)))]
+#[non_exhaustive]
+pub enum ContentEncryptionError {
+    /// The CEK has the wrong length for the algorithm.
+    #[snafu(display("Invalid CEK length {actual}, expected {expected}"))]
+    InvalidKeyLength {
+        /// The algorithm's key length.
+        expected: usize,
+        /// The CEK length.
+        actual: usize,
+    },
+    /// The content could not be encrypted.
+    #[snafu(display("Failed to encrypt content"))]
+    Encryption,
+    /// The content could not be decrypted or authenticated.
+    #[snafu(display("Failed to decrypt content"))]
+    Decryption,
+}
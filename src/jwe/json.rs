@@ -0,0 +1,691 @@
+//! JWE JSON Serialization (RFC 7516 §7.2).
+
+use base64::{Engine, prelude::BASE64_URL_SAFE_NO_PAD};
+use bon::bon;
+use secrecy::{ExposeSecret, SecretBox};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use snafu::prelude::*;
+
+use crate::jwe::{
+    ContentEncryption, ContentKey, EncryptedContent, JweDecrypter, JweEncrypter,
+    additional_authenticated_data,
+    error::{
+        ContentEncryptionError, ContentEncryptionSnafu, Error, MalformedJweError,
+        MalformedJweSnafu, SingleRecipientSnafu, UnderlyingSnafu, UnsupportedEncryptionSnafu,
+    },
+};
+
+/// The recipients of a JWE in the JSON serialization, sharing one content
+/// encryption key (CEK).
+///
+/// The first recipient's encrypter produces the CEK and determines the
+/// content encryption algorithm (`enc`). The CEK is then wrapped for each
+/// further recipient with [`JweEncrypter::wrap_key`], so they must all accept
+/// that `enc`. Algorithms that derive the CEK, such as `ECDH-ES`, can only be
+/// used with a single recipient.
+#[derive(Debug)]
+pub struct JweRecipients {
+    cek: SecretBox<[u8]>,
+    enc: ContentEncryption,
+    recipients: Vec<JsonRecipient>,
+}
+
+impl JweRecipients {
+    /// Creates the recipients from the first, whose encrypter produces the
+    /// CEK.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the encrypter's content encryption algorithm is
+    /// unknown, or key management fails.
+    pub async fn new<E: JweEncrypter>(encrypter: &E) -> Result<Self, Error<E::Error>> {
+        let enc = encrypter.content_encryption_algorithm();
+        let content_encryption = ContentEncryption::from_name(&enc)
+            .context(UnsupportedEncryptionSnafu { enc: &*enc })?;
+        let key = encrypter.encrypt_key(&enc).await.context(UnderlyingSnafu)?;
+        Ok(Self {
+            cek: SecretBox::new(Box::from(key.cek())),
+            enc: content_encryption,
+            recipients: vec![JsonRecipient::from_content_key(&key)],
+        })
+    }
+
+    /// Adds a recipient, wrapping the CEK with its encrypter.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if this or the first recipient's key management
+    /// algorithm derives the CEK, or key management fails.
+    pub async fn push_recipient<E: JweEncrypter>(
+        &mut self,
+        encrypter: &E,
+    ) -> Result<(), Error<E::Error>> {
+        if let Some(direct) = self
+            .recipients
+            .iter()
+            .find(|recipient| recipient.encrypted_key.is_empty())
+        {
+            return SingleRecipientSnafu {
+                algorithm: direct
+                    .header_param("alg")
+                    .and_then(Value::as_str)
+                    .unwrap_or_default(),
+            }
+            .fail();
+        }
+        let key = encrypter
+            .wrap_key(self.cek.expose_secret(), self.enc.name())
+            .await
+            .context(UnderlyingSnafu)?
+            .context(SingleRecipientSnafu {
+                algorithm: encrypter.key_management_algorithm(),
+            })?;
+        self.recipients.push(JsonRecipient::from_content_key(&key));
+        Ok(())
+    }
+
+    /// Adds a recipient, returning the updated recipients.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the recipient can't be added (see
+    /// [`JweRecipients::push_recipient`]).
+    pub async fn with_recipient<E: JweEncrypter>(
+        mut self,
+        encrypter: &E,
+    ) -> Result<Self, Error<E::Error>> {
+        self.push_recipient(encrypter).await?;
+        Ok(self)
+    }
+
+    /// Returns the content encryption algorithm.
+    #[must_use]
+    pub fn content_encryption(&self) -> ContentEncryption {
+        self.enc
+    }
+}
+
+/// A JWE in the JSON serialization, which can be encrypted to several
+/// recipients.
+///
+/// This serializes to the general form, and deserializes from both the
+/// general and flattened forms; [`JsonJwe::to_flattened`] yields the
+/// flattened form of a JWE with a single recipient.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "RawJsonJwe")]
+pub struct JsonJwe {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    protected: Option<String>,
+    #[serde(skip_serializing_if = "Map::is_empty")]
+    unprotected: Map<String, Value>,
+    recipients: Vec<JsonRecipient>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    aad: Option<String>,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    iv: String,
+    ciphertext: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    tag: String,
+}
+
+#[bon]
+impl JsonJwe {
+    /// Creates a builder encrypting the plaintext to the recipients (call
+    /// `encrypt()` to finish).
+    ///
+    /// The protected header holds `enc` and the parameters set on the
+    /// builder, and each recipient's header holds its `alg`, `kid` and the
+    /// parameters set by its key management algorithm. `alg`, `enc` and
+    /// `kid` are removed from the builder's parameters.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if content encryption fails.
+    #[builder(finish_fn = encrypt)]
+    pub fn new(
+        #[builder(start_fn)] recipients: &JweRecipients,
+        #[builder(start_fn)] plaintext: &[u8],
+        /// The media type of the complete JWE (`typ`).
+        #[builder(into)]
+        typ: Option<String>,
+        /// The media type of the plaintext (`cty`), such as `JWT` for a
+        /// nested JWT.
+        #[builder(into)]
+        cty: Option<String>,
+        /// Additional protected header parameters.
+        #[builder(default)]
+        params: Map<String, Value>,
+        /// Header parameters shared by all recipients, which are **not**
+        /// integrity protected.
+        #[builder(default)]
+        unprotected: Map<String, Value>,
+        /// Additional authenticated data (`aad`), which is integrity
+        /// protected but not encrypted.
+        #[builder(into)]
+        aad: Option<Vec<u8>>,
+    ) -> Result<Self, ContentEncryptionError> {
+        let mut header = params;
+        let mut unprotected = unprotected;
+        for name in ["alg", "enc", "kid"] {
+            header.remove(name);
+            unprotected.remove(name);
+        }
+        if let Some(typ) = typ {
+            header.insert("typ".into(), typ.into());
+        }
+        if let Some(cty) = cty {
+            header.insert("cty".into(), cty.into());
+        }
+        header.insert("enc".into(), recipients.enc.name().into());
+        let protected = BASE64_URL_SAFE_NO_PAD.encode(Value::Object(header).to_string());
+        let aad = aad.map(|aad| BASE64_URL_SAFE_NO_PAD.encode(aad));
+
+        let content = recipients.enc.encrypt(
+            recipients.cek.expose_secret(),
+            &additional_authenticated_data(&protected, aad.as_deref()),
+            plaintext,
+        )?;
+        Ok(Self {
+            protected: Some(protected),
+            unprotected,
+            recipients: recipients.recipients.clone(),
+            aad,
+            iv: BASE64_URL_SAFE_NO_PAD.encode(content.iv()),
+            ciphertext: BASE64_URL_SAFE_NO_PAD.encode(content.ciphertext()),
+            tag: BASE64_URL_SAFE_NO_PAD.encode(content.tag()),
+        })
+    }
+}
+
+impl JsonJwe {
+    /// Returns the base64url-encoded protected header, if any.
+    #[must_use]
+    pub fn protected(&self) -> Option<&str> {
+        self.protected.as_deref()
+    }
+
+    /// Returns the shared unprotected header parameter with the given name.
+    #[must_use]
+    pub fn unprotected_param(&self, name: &str) -> Option<&Value> {
+        self.unprotected.get(name)
+    }
+
+    /// Returns the recipients.
+    #[must_use]
+    pub fn recipients(&self) -> &[JsonRecipient] {
+        &self.recipients
+    }
+
+    /// Returns the base64url-encoded additional authenticated data, if any.
+    #[must_use]
+    pub fn aad(&self) -> Option<&str> {
+        self.aad.as_deref()
+    }
+
+    /// Returns the base64url-encoded initialization vector.
+    #[must_use]
+    pub fn iv(&self) -> &str {
+        &self.iv
+    }
+
+    /// Returns the base64url-encoded ciphertext.
+    #[must_use]
+    pub fn ciphertext(&self) -> &str {
+        &self.ciphertext
+    }
+
+    /// Returns the base64url-encoded authentication tag.
+    #[must_use]
+    pub fn tag(&self) -> &str {
+        &self.tag
+    }
+
+    /// Returns the flattened serialization, or `None` if the JWE doesn't
+    /// have exactly one recipient.
+    #[must_use]
+    pub fn to_flattened(&self) -> Option<Value> {
+        let [recipient] = self.recipients.as_slice() else {
+            return None;
+        };
+        let Ok(Value::Object(mut jwe)) = serde_json::to_value(self) else {
+            return None;
+        };
+        jwe.remove("recipients");
+        if let Ok(Value::Object(recipient)) = serde_json::to_value(recipient) {
+            jwe.extend(recipient);
+        }
+        Some(Value::Object(jwe))
+    }
+
+    /// Recovers the CEK with the decrypter and decrypts the plaintext.
+    ///
+    /// The decrypter's recipient is the first whose `alg` matches the
+    /// decrypter's algorithm and whose `kid`, if any, matches its key ID.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the JWE is malformed or has critical extensions
+    /// (`crit`), none of which are understood, no recipient matches the
+    /// decrypter, the CEK can't be recovered, or the content fails
+    /// authentication.
+    pub async fn decrypt<D: JweDecrypter>(
+        &self,
+        decrypter: &D,
+    ) -> Result<Vec<u8>, Error<D::Error>> {
+        let protected = match &self.protected {
+            Some(protected) => BASE64_URL_SAFE_NO_PAD
+                .decode(protected)
+                .ok()
+                .and_then(|header| serde_json::from_slice::<Map<String, Value>>(&header).ok())
+                .context(MalformedJweSnafu {
+                    reason: "header is not a base64url-encoded JSON object",
+                })?,
+            None => Map::new(),
+        };
+        let headers = self
+            .recipients
+            .iter()
+            .map(|recipient| joint_header(&protected, &self.unprotected, &recipient.header))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let algorithm = decrypter.key_management_algorithm();
+        let key_id = decrypter.key_id();
+        // Without a match, the first recipient yields the mismatch error.
+        let index = headers
+            .iter()
+            .position(|header| {
+                header.get("alg").and_then(Value::as_str) == Some(algorithm.as_ref())
+                    && match (header.get("kid"), &key_id) {
+                        (Some(kid), Some(expected)) => kid.as_str() == Some(expected.as_ref()),
+                        _ => true,
+                    }
+            })
+            .unwrap_or_default();
+        let header = &headers[index];
+        let encrypted_key = decode(&self.recipients[index].encrypted_key)?;
+        let content = EncryptedContent::new(
+            decode(&self.iv)?,
+            decode(&self.ciphertext)?,
+            decode(&self.tag)?,
+        );
+
+        let cek = decrypter.decrypt_key(header, &encrypted_key).await?;
+        // `decrypt_key` checked that `enc` is known.
+        let enc = header
+            .get("enc")
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+        let content_encryption =
+            ContentEncryption::from_name(enc).context(UnsupportedEncryptionSnafu { enc })?;
+        content_encryption
+            .decrypt(
+                cek.expose_secret(),
+                &additional_authenticated_data(
+                    self.protected.as_deref().unwrap_or_default(),
+                    self.aad.as_deref(),
+                ),
+                &content,
+            )
+            .context(ContentEncryptionSnafu)
+    }
+}
+
+/// A single recipient of a [`JsonJwe`], with its unprotected header and
+/// encrypted key.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JsonRecipient {
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
+    header: Map<String, Value>,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    encrypted_key: String,
+}
+
+impl JsonRecipient {
+    fn from_content_key(key: &ContentKey) -> Self {
+        let mut header = Map::from_iter([("alg".to_owned(), key.algorithm().into())]);
+        if let Some(kid) = key.key_id() {
+            header.insert("kid".into(), kid.into());
+        }
+        header.extend(key.header_params().clone());
+        Self {
+            header,
+            encrypted_key: BASE64_URL_SAFE_NO_PAD.encode(key.encrypted_key()),
+        }
+    }
+
+    /// Returns the recipient's header parameter with the given name.
+    #[must_use]
+    pub fn header_param(&self, name: &str) -> Option<&Value> {
+        self.header.get(name)
+    }
+
+    /// Returns the base64url-encoded encrypted key, which is empty for
+    /// direct key agreement.
+    #[must_use]
+    pub fn encrypted_key(&self) -> &str {
+        &self.encrypted_key
+    }
+}
+
+/// Either serialization, as received.
+#[derive(Deserialize)]
+struct RawJsonJwe {
+    protected: Option<String>,
+    #[serde(default)]
+    unprotected: Map<String, Value>,
+    recipients: Option<Vec<JsonRecipient>>,
+    header: Option<Map<String, Value>>,
+    encrypted_key: Option<String>,
+    aad: Option<String>,
+    #[serde(default)]
+    iv: String,
+    ciphertext: String,
+    #[serde(default)]
+    tag: String,
+}
+
+impl TryFrom<RawJsonJwe> for JsonJwe {
+    type Error = MalformedJweError;
+
+    fn try_from(raw: RawJsonJwe) -> Result<Self, Self::Error> {
+        let recipients = match (raw.recipients, raw.header, raw.encrypted_key) {
+            (Some(recipients), None, None) => {
+                ensure!(
+                    !recipients.is_empty(),
+                    MalformedJweSnafu {
+                        reason: "no recipients",
+                    }
+                );
+                recipients
+            }
+            (None, header, encrypted_key) => vec![JsonRecipient {
+                header: header.unwrap_or_default(),
+                encrypted_key: encrypted_key.unwrap_or_default(),
+            }],
+            _ => {
+                return MalformedJweSnafu {
+                    reason: "mixes the general and flattened serializations",
+                }
+                .fail();
+            }
+        };
+        Ok(Self {
+            protected: raw.protected,
+            unprotected: raw.unprotected,
+            recipients,
+            aad: raw.aad,
+            iv: raw.iv,
+            ciphertext: raw.ciphertext,
+            tag: raw.tag,
+        })
+    }
+}
+
+/// Merges the headers that apply to a recipient, whose parameter names must
+/// be disjoint (RFC 7516 §7.2.1).
+fn joint_header(
+    protected: &Map<String, Value>,
+    unprotected: &Map<String, Value>,
+    recipient: &Map<String, Value>,
+) -> Result<Map<String, Value>, MalformedJweError> {
+    let mut header = protected.clone();
+    for (name, value) in unprotected.iter().chain(recipient) {
+        ensure!(
+            header.insert(name.clone(), value.clone()).is_none(),
+            MalformedJweSnafu {
+                reason: "duplicate header parameter",
+            }
+        );
+    }
+    ensure!(
+        !header.contains_key("zip"),
+        MalformedJweSnafu {
+            reason: "compression is not supported",
+        }
+    );
+    ensure!(
+        !header.contains_key("crit"),
+        MalformedJweSnafu {
+            reason: "critical extensions are not supported",
+        }
+    );
+    Ok(header)
+}
+
+fn decode(part: &str) -> Result<Vec<u8>, MalformedJweError> {
+    BASE64_URL_SAFE_NO_PAD
+        .decode(part)
+        .ok()
+        .context(MalformedJweSnafu {
+            reason: "part is not base64url-encoded",
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{borrow::Cow, convert::Infallible};
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::jwe::content_key_length;
+
+    /// Uses a fixed shared key as the CEK (`dir`).
+    #[derive(Debug, Clone)]
+    struct DirectKey;
+
+    fn cek(enc: &str) -> SecretBox<[u8]> {
+        SecretBox::new(vec![7; content_key_length(enc).unwrap()].into_boxed_slice())
+    }
+
+    impl JweEncrypter for DirectKey {
+        type Error = Infallible;
+
+        fn key_management_algorithm(&self) -> Cow<'_, str> {
+            "dir".into()
+        }
+
+        fn key_id(&self) -> Option<Cow<'_, str>> {
+            Some("dir-1".into())
+        }
+
+        async fn encrypt_key(&self, enc: &str) -> Result<ContentKey, Self::Error> {
+            Ok(ContentKey::new(
+                cek(enc),
+                Vec::new(),
+                "dir",
+                Some("dir-1".into()),
+            ))
+        }
+    }
+
+    impl JweDecrypter for DirectKey {
+        type Error = Infallible;
+
+        fn key_management_algorithm(&self) -> Cow<'_, str> {
+            "dir".into()
+        }
+
+        fn key_id(&self) -> Option<Cow<'_, str>> {
+            Some("dir-1".into())
+        }
+
+        async fn decrypt_key_unchecked(
+            &self,
+            _header: &Map<String, Value>,
+            _encrypted_key: &[u8],
+            enc: &str,
+        ) -> Result<SecretBox<[u8]>, Self::Error> {
+            Ok(cek(enc))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_flattened_round_trip() {
+        let Value::Object(unprotected) = json!({"jku": "https://rp.example/jwks"}) else {
+            unreachable!()
+        };
+        let recipients = JweRecipients::new(&DirectKey).await.unwrap();
+
+        let jwe = JsonJwe::builder(&recipients, b"hello")
+            .cty("text/plain")
+            .unprotected(unprotected)
+            .aad(b"context")
+            .encrypt()
+            .unwrap();
+        let flattened = jwe.to_flattened().unwrap();
+        let parsed: JsonJwe = serde_json::from_value(flattened.clone()).unwrap();
+
+        assert_eq!(flattened["header"], json!({"alg": "dir", "kid": "dir-1"}));
+        assert_eq!(flattened["aad"], "Y29udGV4dA");
+        assert_eq!(flattened.get("encrypted_key"), None);
+        assert_eq!(parsed, jwe);
+        assert_eq!(parsed.decrypt(&DirectKey).await.unwrap(), b"hello");
+    }
+
+    #[tokio::test]
+    async fn test_tampered_aad_is_rejected() {
+        let recipients = JweRecipients::new(&DirectKey).await.unwrap();
+        let jwe = JsonJwe::builder(&recipients, b"hello")
+            .aad(b"context")
+            .encrypt()
+            .unwrap();
+        let mut json = serde_json::to_value(&jwe).unwrap();
+        json["aad"] = "b3RoZXI".into();
+
+        let result = serde_json::from_value::<JsonJwe>(json)
+            .unwrap()
+            .decrypt(&DirectKey)
+            .await;
+
+        assert!(matches!(result, Err(Error::ContentEncryption { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_direct_key_agreement_has_single_recipient() {
+        let mut recipients = JweRecipients::new(&DirectKey).await.unwrap();
+
+        let result = recipients.push_recipient(&DirectKey).await;
+
+        assert!(matches!(
+            result,
+            Err(Error::SingleRecipient { algorithm }) if algorithm == "dir"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_decrypt_rejects_critical_extensions() {
+        let recipients = JweRecipients::new(&DirectKey).await.unwrap();
+        let jwe = JsonJwe::builder(&recipients, b"hello")
+            .encrypt()
+            .unwrap()
+            .to_flattened()
+            .unwrap();
+        let crit = json!({"crit": ["exp"], "exp": 0});
+        let mut protected = jwe.clone();
+        protected["protected"] = BASE64_URL_SAFE_NO_PAD.encode(crit.to_string()).into();
+        let mut unprotected = jwe.clone();
+        unprotected["unprotected"] = crit.clone();
+        let mut recipient = jwe;
+        recipient["header"]["crit"] = crit["crit"].clone();
+        recipient["header"]["exp"] = crit["exp"].clone();
+
+        for json in [protected, unprotected, recipient] {
+            let result = serde_json::from_value::<JsonJwe>(json.clone())
+                .unwrap()
+                .decrypt(&DirectKey)
+                .await;
+
+            assert!(
+                matches!(
+                    result,
+                    Err(Error::Malformed {
+                        reason: "critical extensions are not supported"
+                    })
+                ),
+                "{json}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_deserialize_rejects_mixed_serializations() {
+        let result = serde_json::from_value::<JsonJwe>(json!({
+            "protected": "e30",
+            "recipients": [{"header": {"alg": "dir"}}],
+            "header": {"alg": "dir"},
+            "ciphertext": "",
+        }));
+
+        assert!(result.is_err());
+    }
+
+    #[cfg(all(feature = "jwe-aes-kw", native))]
+    mod aes_kw {
+        use super::*;
+        use crate::{jwe::AesKeyWrap, jwk::PrivateJwk};
+
+        fn wrapper(k: &str, kid: &str) -> AesKeyWrap {
+            let jwk: PrivateJwk =
+                serde_json::from_str(&json!({"kty": "oct", "k": k, "kid": kid}).to_string())
+                    .unwrap();
+            AesKeyWrap::from_jwk(&jwk).unwrap()
+        }
+
+        #[tokio::test]
+        async fn test_general_serialization_with_several_recipients() {
+            let first = wrapper("GawgguFyGrWKav7AX4VKUg", "rp-1");
+            let second = wrapper("AAECAwQFBgcICQoLDA0ODxAREhMUFRYXGBkaGxwdHh8", "rp-2");
+            let recipients = JweRecipients::new(&first)
+                .await
+                .unwrap()
+                .with_recipient(&second)
+                .await
+                .unwrap();
+
+            let jwe = JsonJwe::builder(&recipients, b"hello").encrypt().unwrap();
+            let json = serde_json::to_value(&jwe).unwrap();
+
+            assert_eq!(jwe.to_flattened(), None);
+            assert_eq!(json["protected"], "eyJlbmMiOiJBMTI4Q0JDLUhTMjU2In0");
+            assert_eq!(
+                json["recipients"][0]["header"],
+                json!({"alg": "A128KW", "kid": "rp-1"})
+            );
+            assert_eq!(
+                json["recipients"][1]["header"],
+                json!({"alg": "A256KW", "kid": "rp-2"})
+            );
+            assert_eq!(jwe.decrypt(&first).await.unwrap(), b"hello");
+            assert_eq!(jwe.decrypt(&second).await.unwrap(), b"hello");
+            assert!(matches!(
+                jwe.decrypt(&wrapper("GawgguFyGrWKav7AX4VKUg", "rp-3"))
+                    .await,
+                Err(Error::KeyIdMismatch)
+            ));
+        }
+
+        #[tokio::test]
+        async fn test_decrypt_rfc_7516_a5_vector() {
+            let json = json!({
+                "protected": "eyJlbmMiOiJBMTI4Q0JDLUhTMjU2In0",
+                "unprotected": {"jku": "https://server.example.com/keys.jwks"},
+                "header": {"alg": "A128KW", "kid": "7"},
+                "encrypted_key": "6KB707dM9YTIgHtLvtgWQ8mKwboJW3of9locizkDTHzBC2IlrT1oOQ",
+                "iv": "AxY8DCtDaGlsbGljb3RoZQ",
+                "ciphertext": "KDlTtXchhZTGufMYmOYGS4HffxPSUrfmqCHXaI9wOGY",
+                "tag": "Mz-VPPyU4RlcuYv1IwIvzw",
+            });
+
+            let jwe: JsonJwe = serde_json::from_value(json.clone()).unwrap();
+            let plaintext = jwe
+                .decrypt(&wrapper("GawgguFyGrWKav7AX4VKUg", "7"))
+                .await
+                .unwrap();
+
+            assert_eq!(plaintext, b"Live long and prosper.");
+            assert_eq!(jwe.to_flattened(), Some(json));
+        }
+    }
+}
@@ -0,0 +1,87 @@
+use bytes::Bytes;
+use secrecy::{ExposeSecret, SecretBox};
+use serde_json::{Map, Value};
+
+/// A content encryption key (CEK), together with how it was protected for
+/// the recipient, as returned by
+/// [`JweEncrypter::encrypt_key`](crate::jwe::JweEncrypter::encrypt_key).
+///
+/// The key management algorithm and key ID are those of the key that
+/// actually protected the CEK, so the JWE header built from them can't
+/// disagree with it, even if the encrypter's key is rotated concurrently.
+pub struct ContentKey {
+    cek: SecretBox<[u8]>,
+    encrypted_key: Bytes,
+    algorithm: String,
+    key_id: Option<String>,
+    header_params: Map<String, Value>,
+}
+
+impl ContentKey {
+    /// Creates a content key.
+    ///
+    /// `encrypted_key` is empty for algorithms that don't transmit the CEK,
+    /// such as `dir` and `ECDH-ES`.
+    pub fn new(
+        cek: SecretBox<[u8]>,
+        encrypted_key: impl Into<Bytes>,
+        algorithm: impl Into<String>,
+        key_id: Option<String>,
+    ) -> Self {
+        Self {
+            cek,
+            encrypted_key: encrypted_key.into(),
+            algorithm: algorithm.into(),
+            key_id,
+            header_params: Map::new(),
+        }
+    }
+
+    /// Adds a header parameter the recipient needs to recover the CEK, such
+    /// as `epk` for `ECDH-ES`, or `iv` and `tag` for `A256GCMKW`.
+    #[must_use]
+    pub fn with_header_param(mut self, name: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.header_params.insert(name.into(), value.into());
+        self
+    }
+
+    /// Returns the CEK.
+    #[must_use]
+    pub fn cek(&self) -> &[u8] {
+        self.cek.expose_secret()
+    }
+
+    /// Returns the encrypted key, to be sent in the JWE.
+    #[must_use]
+    pub fn encrypted_key(&self) -> &Bytes {
+        &self.encrypted_key
+    }
+
+    /// Returns the key management algorithm (`alg`).
+    #[must_use]
+    pub fn algorithm(&self) -> &str {
+        &self.algorithm
+    }
+
+    /// Returns the key ID (`kid`) of the recipient's key, if any.
+    #[must_use]
+    pub fn key_id(&self) -> Option<&str> {
+        self.key_id.as_deref()
+    }
+
+    /// Returns the header parameters set by the key management algorithm.
+    #[must_use]
+    pub fn header_params(&self) -> &Map<String, Value> {
+        &self.header_params
+    }
+}
+
+impl std::fmt::Debug for ContentKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ContentKey")
+            .field("algorithm", &self.algorithm)
+            .field("key_id", &self.key_id)
+            .field("header_params", &self.header_params)
+            .finish_non_exhaustive()
+    }
+}
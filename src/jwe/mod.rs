@@ -0,0 +1,116 @@
+//! JSON Web Encryption (JWE) traits per RFC 7516.
+//!
+//! Encryption happens in two layers: a key management algorithm (`alg`)
+//! protects a content encryption key (CEK) for the recipient, and a content
+//! encryption algorithm (`enc`) encrypts the plaintext with the CEK. The
+//! [`JweEncrypter`] and [`JweDecrypter`] traits cover the first layer, so
+//! backends holding recipient keys (local keys, a KMS or an HSM) can be
+//! plugged in independently of content encryption. [`ContentEncryption`]
+//! covers the second layer, and `CompactJwe` and `JsonJwe` put both
+//! together into the compact and JSON serializations, with the `jwe` feature.
+//!
+//! Local implementations of the common key management algorithms, constructed
+//! from JWKs, are available behind features:
+//!
+//! | Feature        | Algorithms          | Types                                      |
+//! |----------------|---------------------|--------------------------------------------|
+//! | `jwe-rsa-oaep` | `RSA-OAEP-256`      | `RsaOaepEncrypter`, `RsaOaepDecrypter`     |
+//! | `jwe-ecdh-es`  | `ECDH-ES`           | `EcdhEsEncrypter`, `EcdhEsDecrypter`       |
+//! | `jwe-aes-kw`   | `A128KW`, `A256KW`  | `AesKeyWrap`                               |
+
+#[cfg(all(feature = "jwe-aes-kw", native))]
+mod aes_kw;
+#[cfg(feature = "jwe")]
+mod compact;
+mod content;
+#[cfg(all(feature = "jwe-ecdh-es", native))]
+mod ecdh_es;
+mod error;
+#[cfg(feature = "jwe")]
+mod json;
+mod key;
+#[cfg(all(feature = "jwe-rsa-oaep", native))]
+mod rsa_oaep;
+mod r#trait;
+
+#[cfg(all(feature = "jwe-aes-kw", native))]
+pub use aes_kw::AesKeyWrap;
+#[cfg(feature = "jwe")]
+pub use compact::CompactJwe;
+#[cfg(feature = "jwe")]
+pub use content::EncryptedContent;
+pub use content::{ContentEncryption, additional_authenticated_data};
+#[cfg(all(feature = "jwe-ecdh-es", native))]
+pub use ecdh_es::{EcdhEsDecrypter, EcdhEsEncrypter};
+pub use error::Error;
+#[cfg(all(
+    any(
+        feature = "jwe-rsa-oaep",
+        feature = "jwe-ecdh-es",
+        feature = "jwe-aes-kw"
+    ),
+    native
+))]
+pub use error::KeyManagementError;
+#[cfg(feature = "jwe")]
+pub use error::{ContentEncryptionError, MalformedJweError};
+#[cfg(feature = "jwe")]
+pub use json::{JsonJwe, JsonRecipient, JweRecipients};
+pub use key::ContentKey;
+#[cfg(all(feature = "jwe-rsa-oaep", native))]
+pub use rsa_oaep::{RsaOaepDecrypter, RsaOaepEncrypter};
+pub use r#trait::{JweDecrypter, JweEncrypter};
+
+/// The content encryption algorithm used when the recipient hasn't asked for
+/// another, as in `OpenID` Connect.
+const DEFAULT_CONTENT_ENCRYPTION: &str = "A128CBC-HS256";
+
+/// Returns the CEK length in bytes for a content encryption algorithm
+/// (RFC 7518 §5.1), or `None` if the algorithm is unknown.
+#[must_use]
+pub fn content_key_length(enc: &str) -> Option<usize> {
+    ContentEncryption::from_name(enc).map(ContentEncryption::key_length)
+}
+
+/// Generates a random CEK for a content encryption algorithm.
+#[cfg(all(any(feature = "jwe-rsa-oaep", feature = "jwe-aes-kw"), native))]
+fn generate_cek(enc: &str) -> Result<secrecy::SecretBox<[u8]>, KeyManagementError> {
+    use snafu::OptionExt;
+
+    let len = content_key_length(enc).context(error::UnsupportedContentEncryptionSnafu { enc })?;
+    let mut cek = vec![0; len].into_boxed_slice();
+    aws_lc_rs::rand::fill(&mut cek).map_err(|_| KeyManagementError::Crypto)?;
+    Ok(secrecy::SecretBox::new(cek))
+}
+
+/// Checks that a JWK's `alg` and `use` parameters, if present, allow it to be
+/// used with a key management algorithm.
+#[cfg(all(
+    any(
+        feature = "jwe-rsa-oaep",
+        feature = "jwe-ecdh-es",
+        feature = "jwe-aes-kw"
+    ),
+    native
+))]
+fn check_jwk(
+    algorithm: Option<&str>,
+    key_use: Option<crate::jwk::KeyUse>,
+    accepted: &[&str],
+) -> Result<(), KeyManagementError> {
+    if let Some(algorithm) = algorithm {
+        snafu::ensure!(
+            accepted.contains(&algorithm),
+            error::InvalidKeySnafu {
+                reason: format!("key is for '{algorithm}'"),
+            }
+        );
+    }
+    snafu::ensure!(
+        key_use != Some(crate::jwk::KeyUse::Sign),
+        error::InvalidKeySnafu {
+            reason: "key is for signing",
+        }
+    );
+    Ok(())
+}
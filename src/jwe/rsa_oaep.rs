@@ -0,0 +1,283 @@
+//! The `RSA-OAEP-256` key management algorithm (RFC 7518 §4.3).
+
+use std::{borrow::Cow, sync::Arc};
+
+use aws_lc_rs::{
+    encoding::AsDer,
+    rsa::{
+        KeyPair, KeyPairComponents, OAEP_SHA256_MGF1SHA256, OaepPrivateDecryptingKey,
+        OaepPublicEncryptingKey, PrivateDecryptingKey, PublicEncryptingKey, PublicKeyComponents,
+    },
+};
+use secrecy::{ExposeSecret, SecretBox, zeroize::Zeroizing};
+use serde_json::{Map, Value};
+use snafu::prelude::*;
+
+use crate::{
+    jwe::{
+        ContentKey, DEFAULT_CONTENT_ENCRYPTION, JweDecrypter, JweEncrypter, check_jwk,
+        content_key_length,
+        error::{CryptoSnafu, InvalidKeySnafu, KeyManagementError},
+        generate_cek,
+    },
+    jwk::{PrivateJwk, PrivateKey, PublicJwk, PublicKey},
+};
+
+const ALGORITHM: &str = "RSA-OAEP-256";
+
+/// Encrypts CEKs to an RSA public key with `RSA-OAEP-256`.
+#[derive(Debug, Clone)]
+pub struct RsaOaepEncrypter {
+    key: Arc<OaepPublicEncryptingKey>,
+    key_id: Option<String>,
+    enc: Cow<'static, str>,
+}
+
+impl RsaOaepEncrypter {
+    /// Creates an encrypter from the recipient's RSA public JWK.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the JWK isn't a valid RSA key, or its `alg` or
+    /// `use` parameters rule out `RSA-OAEP-256`.
+    pub fn from_jwk(jwk: &PublicJwk) -> Result<Self, KeyManagementError> {
+        check_jwk(jwk.algorithm(), jwk.key_use(), &[ALGORITHM])?;
+        let PublicKey::Rsa(public_key) = jwk.key() else {
+            return InvalidKeySnafu {
+                reason: "not an RSA key",
+            }
+            .fail();
+        };
+        let components = PublicKeyComponents {
+            n: public_key.modulus(),
+            e: public_key.public_exponent(),
+        };
+        let key: PublicEncryptingKey = components.try_into().ok().context(InvalidKeySnafu {
+            reason: "invalid RSA public key",
+        })?;
+        Ok(Self {
+            key: Arc::new(
+                OaepPublicEncryptingKey::new(key).map_err(|_| KeyManagementError::Crypto)?,
+            ),
+            key_id: jwk.kid().map(str::to_owned),
+            enc: Cow::Borrowed(DEFAULT_CONTENT_ENCRYPTION),
+        })
+    }
+
+    /// Sets the content encryption algorithm (`enc`) the recipient expects,
+    /// instead of `A128CBC-HS256`.
+    #[must_use]
+    pub fn with_content_encryption(mut self, enc: impl Into<Cow<'static, str>>) -> Self {
+        self.enc = enc.into();
+        self
+    }
+
+    fn wrap(&self, cek: SecretBox<[u8]>) -> Result<ContentKey, KeyManagementError> {
+        let mut encrypted_key = vec![0; self.key.ciphertext_size()];
+        let len = self
+            .key
+            .encrypt(
+                &OAEP_SHA256_MGF1SHA256,
+                cek.expose_secret(),
+                &mut encrypted_key,
+                None,
+            )
+            .ok()
+            .context(CryptoSnafu)?
+            .len();
+        encrypted_key.truncate(len);
+        Ok(ContentKey::new(
+            cek,
+            encrypted_key,
+            ALGORITHM,
+            self.key_id.clone(),
+        ))
+    }
+}
+
+impl JweEncrypter for RsaOaepEncrypter {
+    type Error = KeyManagementError;
+
+    fn key_management_algorithm(&self) -> Cow<'_, str> {
+        Cow::Borrowed(ALGORITHM)
+    }
+
+    fn content_encryption_algorithm(&self) -> Cow<'_, str> {
+        Cow::Borrowed(&self.enc)
+    }
+
+    fn key_id(&self) -> Option<Cow<'_, str>> {
+        self.key_id.as_deref().map(Cow::Borrowed)
+    }
+
+    async fn encrypt_key(&self, enc: &str) -> Result<ContentKey, Self::Error> {
+        self.wrap(generate_cek(enc)?)
+    }
+
+    async fn wrap_key(&self, cek: &[u8], _enc: &str) -> Result<Option<ContentKey>, Self::Error> {
+        self.wrap(SecretBox::new(Box::from(cek))).map(Some)
+    }
+}
+
+/// Decrypts CEKs encrypted to an RSA private key with `RSA-OAEP-256`.
+#[derive(Debug, Clone)]
+pub struct RsaOaepDecrypter {
+    key: Arc<OaepPrivateDecryptingKey>,
+    key_id: Option<String>,
+}
+
+impl RsaOaepDecrypter {
+    /// Creates a decrypter from an RSA private JWK.
+    ///
+    /// The JWK must include the CRT parameters (`p`, `q`, `dp`, `dq` and
+    /// `qi`).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the JWK isn't a valid RSA key, or its `alg` or
+    /// `use` parameters rule out `RSA-OAEP-256`.
+    #[allow(non_snake_case)]
+    pub fn from_jwk(jwk: &PrivateJwk) -> Result<Self, KeyManagementError> {
+        check_jwk(jwk.algorithm(), jwk.key_use(), &[ALGORITHM])?;
+        let PrivateKey::Rsa(private_key) = jwk.key() else {
+            return InvalidKeySnafu {
+                reason: "not an RSA key",
+            }
+            .fail();
+        };
+        let ((p, q), (dP, dQ, qInv)) =
+            private_key
+                .primes()
+                .zip(private_key.crt_values())
+                .context(InvalidKeySnafu {
+                    reason: "missing RSA CRT parameters",
+                })?;
+        let components = KeyPairComponents {
+            public_key: PublicKeyComponents {
+                n: private_key.modulus(),
+                e: private_key.public_exponent(),
+            },
+            d: private_key.private_exponent().expose_secret(),
+            p: p.expose_secret(),
+            q: q.expose_secret(),
+            dP: dP.expose_secret(),
+            dQ: dQ.expose_secret(),
+            qInv: qInv.expose_secret(),
+        };
+        let key = KeyPair::from_components(&components)
+            .ok()
+            .and_then(|key_pair| key_pair.as_der().ok())
+            .and_then(|der| PrivateDecryptingKey::from_pkcs8(der.as_ref()).ok())
+            .context(InvalidKeySnafu {
+                reason: "invalid RSA private key",
+            })?;
+        Ok(Self {
+            key: Arc::new(
+                OaepPrivateDecryptingKey::new(key).map_err(|_| KeyManagementError::Crypto)?,
+            ),
+            key_id: jwk.kid().map(str::to_owned),
+        })
+    }
+}
+
+impl JweDecrypter for RsaOaepDecrypter {
+    type Error = KeyManagementError;
+
+    fn key_management_algorithm(&self) -> Cow<'_, str> {
+        Cow::Borrowed(ALGORITHM)
+    }
+
+    fn key_id(&self) -> Option<Cow<'_, str>> {
+        self.key_id.as_deref().map(Cow::Borrowed)
+    }
+
+    async fn decrypt_key_unchecked(
+        &self,
+        _header: &Map<String, Value>,
+        encrypted_key: &[u8],
+        enc: &str,
+    ) -> Result<SecretBox<[u8]>, Self::Error> {
+        let mut cek = Zeroizing::new(vec![0; self.key.min_output_size()]);
+        let len = self
+            .key
+            .decrypt(&OAEP_SHA256_MGF1SHA256, encrypted_key, &mut cek, None)
+            .ok()
+            .context(CryptoSnafu)?
+            .len();
+        ensure!(content_key_length(enc) == Some(len), CryptoSnafu);
+        Ok(SecretBox::new(Box::from(&cek[..len])))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    const JWK: &str = r#"{"kty":"RSA","kid":"enc-1","n":"t6H0ApmjFsICXprkE-WZ5rCDk7wCCS5cBLtYjJFePrcBRvoKF8VCBFUT-NT252zQ4HM7v-ht2b6i9tZIOkzcFQ3dqcmzF9VM_sBZQ4GZ1fFi4-3HLLxK4roKsn2xUjfUt1LxqcEjgAtYCVHwNVMf8Z7QM9t23FHWgthDh-QZwU-E-lmVcqAnS6Ytc6M6U3PP1lGZ1OPoOn3ldUu23q288OPG8ixbrsSSEqPfWXc_S9E56kMAamgm1WATXV15Oxwts4TdYXsP8qOHiHL_I-h0bjybfRxlLtxPVHT8QVY14gV0elwt7OftiL5lYuUwk1d5UyjbQHd_s98X5fVlDv4E6Q","e":"AQAB","d":"UIY9zMgXerrPtDGi01DLKJx9zlJS6DjClDadMWLrm3-_mSK7O0o-VU0DiYnFzZzriYkuicyAI0xUBP7ZRGuA1OC0M6ills1ryoiJfC9ASM9lqIKT5TuacQrY9wVkNSQTbOJ74480pIkU7V1XguK1LsyQCzVxTVK7yLvR_Sya2KfqDJc4fQXK2f8XEnt0AmY7naBrpobcM8HsI8cH_qEScaVCafeZPwzeThrNSxvAlmXQ1_jacuw-Vgxmw3HTzP8Tw7edfQoLwSabB1RRcR7rPP-iyApVCE76UjIquJc3vGkH152TxwtPFPRije4Uz2GIeHxfXuszDU3APJMGEdC6yw","p":"6KtnB4OcDo5lGzIPQnqr7m9w2tVjexz4sFcj5wyJr9SqwSsAFZNBGjRmO3KLBJPik9SantB-JFHMVLxYf9EftafjaP-31o-3Yeb6KKutiQSwpxd1VAxYSJ6BEtiWr_vJOcFGcL3xASrAz_NniP2Bh69wotYnOtUbfKXv4q9zIBs","q":"ygvHiHCUPboPrABpda1Dc20RjxiinyVCnOSnRvHmwVZt_1TEvoTSZMPuKQpczAFTSushLqCw-YBC4n-AqtbB4RgDhR0wbrrM3F5DYQT2iOjK-cRY3kGL3TB-m9GoOvuRPWKZRi-gni4Y9AV3uWoxW7OPLIr7kYOJFMOzTSQWp0s","dp":"bAPOw_1EgLl4lMSLswgyM3JHCUDRe0E67tq1jzkR3rAzSZRF6L5BFgJHh8eoZEqngIvN8HbIaOIUoy4BQ-149CNp_r_gv9pefzP05Na2rIqh3CCW1psYjTYQpAgrBK42f_qLUiHL9ge8FY5hfYZ7ENvQxbf35Gc06Iw8rpdXMgs","dq":"FyTAR2W15VgxwV8OtXcSWd9tenm07wvilHwwmRTOfOj8kpnQK46lVvuGhVb1iDzCoLtMQr4PZ5UElFNMlsUg0jrg9FeCm7x0CO4XQ77Ayq_3yCJwl4zki4Kpjgm2_oKggWqQ6hQ8jsHzA8i4jRmX68wmo1CMPn_VrPY5zKVMnv8","qi":"uKNCFKyKRtYADYpYWfycLS5R79qFjJSlvgWbur8qG4atGjnTWBbKx_Kz8V47TaVMKh9isMh0xKVSXyszJqQ8pyU5ay-xEl3cgh5S6MPSyIhIsxoM8j8IMh_lOjBsI4tx34u9NRdcnkuI91RiR31DNCMVFbsFQjSVxpqtnc0kYnA"}"#;
+
+    fn header(enc: &str) -> Map<String, Value> {
+        let Value::Object(header) = json!({"alg": ALGORITHM, "enc": enc, "kid": "enc-1"}) else {
+            unreachable!()
+        };
+        header
+    }
+
+    #[tokio::test]
+    async fn test_round_trip() {
+        let jwk: PrivateJwk = serde_json::from_str(JWK).unwrap();
+        let encrypter = RsaOaepEncrypter::from_jwk(&jwk.to_public().unwrap())
+            .unwrap()
+            .with_content_encryption("A256GCM");
+        let decrypter = RsaOaepDecrypter::from_jwk(&jwk).unwrap();
+
+        let key = encrypter.encrypt_key("A256GCM").await.unwrap();
+        let cek = decrypter
+            .decrypt_key(&header("A256GCM"), key.encrypted_key())
+            .await
+            .unwrap();
+
+        assert_eq!(encrypter.content_encryption_algorithm(), "A256GCM");
+        assert_eq!(key.key_id(), Some("enc-1"));
+        assert_eq!(key.encrypted_key().len(), 256);
+        assert_eq!(cek.expose_secret(), key.cek());
+    }
+
+    #[tokio::test]
+    async fn test_decrypt_rejects_tampered_key() {
+        let jwk: PrivateJwk = serde_json::from_str(JWK).unwrap();
+        let encrypter = RsaOaepEncrypter::from_jwk(&jwk.to_public().unwrap()).unwrap();
+        let decrypter = RsaOaepDecrypter::from_jwk(&jwk).unwrap();
+
+        let key = encrypter.encrypt_key("A128CBC-HS256").await.unwrap();
+        let mut tampered = key.encrypted_key().to_vec();
+        tampered[0] ^= 1;
+
+        assert!(
+            decrypter
+                .decrypt_key(&header("A128CBC-HS256"), &tampered)
+                .await
+                .is_err()
+        );
+        // The CEK length must match the content encryption algorithm.
+        assert!(
+            decrypter
+                .decrypt_key(&header("A256CBC-HS512"), key.encrypted_key())
+                .await
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_from_jwk_rejects_other_algorithms() {
+        let mut jwk: Value = serde_json::from_str(JWK).unwrap();
+        jwk["alg"] = "RSA1_5".into();
+        let jwk: PrivateJwk = serde_json::from_str(&jwk.to_string()).unwrap();
+
+        assert!(matches!(
+            RsaOaepDecrypter::from_jwk(&jwk),
+            Err(KeyManagementError::InvalidKey { .. })
+        ));
+    }
+}
@@ -0,0 +1,320 @@
+//! Asynchronous JWE key management traits.
+
+use std::{borrow::Cow, sync::Arc};
+
+use secrecy::SecretBox;
+use serde_json::{Map, Value};
+use snafu::prelude::*;
+
+use crate::{
+    MaybeSend, MaybeSendSync,
+    jwe::{
+        ContentKey, DEFAULT_CONTENT_ENCRYPTION, content_key_length,
+        error::{
+            AlgorithmMismatchSnafu, Error, KeyIdMismatchSnafu, UnderlyingSnafu,
+            UnsupportedEncryptionSnafu,
+        },
+    },
+};
+
+/// Trait for encrypting to a recipient's key, producing a content encryption
+/// key (CEK) per an RFC 7516 (JWE) / RFC 7518 (JWA) key management algorithm.
+///
+/// This is the counterpart of [`JwsSigner`](crate::signer::JwsSigner) for
+/// encryption: it's typically backed by a relying party's public key, e.g.
+/// for encrypted ID tokens or userinfo responses.
+pub trait JweEncrypter: MaybeSendSync + Clone {
+    /// The error type returned by this encrypter's operations.
+    type Error: std::error::Error + MaybeSendSync + 'static;
+
+    /// Returns the key management algorithm identifier (`alg`), e.g.
+    /// `RSA-OAEP-256`.
+    fn key_management_algorithm(&self) -> Cow<'_, str>;
+
+    /// Returns the content encryption algorithm (`enc`) the recipient
+    /// expects.
+    ///
+    /// The default implementation returns `A128CBC-HS256`, the `OpenID`
+    /// Connect default.
+    fn content_encryption_algorithm(&self) -> Cow<'_, str> {
+        Cow::Borrowed(DEFAULT_CONTENT_ENCRYPTION)
+    }
+
+    /// Returns the key ID of the recipient's key, for the `kid` header
+    /// parameter.
+    fn key_id(&self) -> Option<Cow<'_, str>>;
+
+    /// Asynchronously produces a CEK for the given content encryption
+    /// algorithm, protected for the recipient.
+    ///
+    /// Depending on the algorithm, the CEK is generated and then encrypted
+    /// (e.g. `RSA-OAEP-256`, `A256KW`), or derived and not transmitted (e.g.
+    /// `ECDH-ES`, `dir`).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the content encryption algorithm is unsupported,
+    /// or the key management operation fails.
+    fn encrypt_key(
+        &self,
+        enc: &str,
+    ) -> impl Future<Output = Result<ContentKey, Self::Error>> + MaybeSend;
+
+    /// Asynchronously protects an existing CEK for the recipient, so a JWE
+    /// can be encrypted to several recipients (RFC 7516 §7.2.1).
+    ///
+    /// Returns `None` if the algorithm derives the CEK rather than encrypting
+    /// it (e.g. `ECDH-ES`, `dir`), as such algorithms can only be used with
+    /// a single recipient. The default implementation returns `None`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the key management operation fails.
+    fn wrap_key(
+        &self,
+        cek: &[u8],
+        enc: &str,
+    ) -> impl Future<Output = Result<Option<ContentKey>, Self::Error>> + MaybeSend {
+        let _ = (cek, enc);
+        async { Ok(None) }
+    }
+}
+
+/// Trait for recovering the content encryption key (CEK) of a JWE encrypted
+/// to this recipient's key.
+///
+/// This is the counterpart of [`JwsVerifier`](crate::verifier::JwsVerifier)
+/// for encryption, backed by the recipient's private key.
+pub trait JweDecrypter: MaybeSendSync + Clone {
+    /// The error type returned by this decrypter's operations.
+    type Error: std::error::Error + MaybeSendSync + 'static;
+
+    /// Returns the key management algorithm identifier (`alg`) this
+    /// decrypter accepts.
+    fn key_management_algorithm(&self) -> Cow<'_, str>;
+
+    /// Returns the key ID of the decrypter's key.
+    fn key_id(&self) -> Option<Cow<'_, str>>;
+
+    /// Asynchronously recovers the CEK for the given content encryption
+    /// algorithm.
+    ///
+    /// This should not be called directly, as it does not check the header
+    /// against the decrypter's algorithm and key ID.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the encrypted key or header parameters are
+    /// invalid, or the key management operation fails.
+    fn decrypt_key_unchecked(
+        &self,
+        header: &Map<String, Value>,
+        encrypted_key: &[u8],
+        enc: &str,
+    ) -> impl Future<Output = Result<SecretBox<[u8]>, Self::Error>> + MaybeSend;
+
+    /// Asynchronously recovers the CEK of a JWE, given its joint header and
+    /// encrypted key.
+    ///
+    /// The `alg` header parameter must match this decrypter's algorithm, the
+    /// `kid` parameter (if both are present) its key ID, and `enc` must be a
+    /// known content encryption algorithm.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the header doesn't match this decrypter, or the
+    /// key can't be recovered.
+    fn decrypt_key(
+        &self,
+        header: &Map<String, Value>,
+        encrypted_key: &[u8],
+    ) -> impl Future<Output = Result<SecretBox<[u8]>, Error<Self::Error>>> + MaybeSend {
+        async move {
+            let expected = self.key_management_algorithm();
+            let alg = header.get("alg").and_then(Value::as_str);
+            ensure!(
+                alg == Some(expected.as_ref()),
+                AlgorithmMismatchSnafu {
+                    expected: expected.as_ref(),
+                    actual: alg.unwrap_or_default(),
+                }
+            );
+            if let (Some(kid), Some(expected)) = (header.get("kid"), self.key_id()) {
+                ensure!(kid.as_str() == Some(expected.as_ref()), KeyIdMismatchSnafu);
+            }
+            let enc = header
+                .get("enc")
+                .and_then(Value::as_str)
+                .unwrap_or_default();
+            ensure!(
+                content_key_length(enc).is_some(),
+                UnsupportedEncryptionSnafu { enc }
+            );
+            self.decrypt_key_unchecked(header, encrypted_key, enc)
+                .await
+                .context(UnderlyingSnafu)
+        }
+    }
+}
+
+macro_rules! forward_jwe_encrypter {
+    ($($ty:ty),+) => {$(
+        impl<E: JweEncrypter> JweEncrypter for $ty {
+            type Error = E::Error;
+
+            fn key_management_algorithm(&self) -> Cow<'_, str> {
+                (**self).key_management_algorithm()
+            }
+
+            fn content_encryption_algorithm(&self) -> Cow<'_, str> {
+                (**self).content_encryption_algorithm()
+            }
+
+            fn key_id(&self) -> Option<Cow<'_, str>> {
+                (**self).key_id()
+            }
+
+            fn encrypt_key(
+                &self,
+                enc: &str,
+            ) -> impl Future<Output = Result<ContentKey, Self::Error>> + MaybeSend {
+                (**self).encrypt_key(enc)
+            }
+
+            fn wrap_key(
+                &self,
+                cek: &[u8],
+                enc: &str,
+            ) -> impl Future<Output = Result<Option<ContentKey>, Self::Error>> + MaybeSend {
+                (**self).wrap_key(cek, enc)
+            }
+        }
+    )+};
+}
+
+forward_jwe_encrypter!(Arc<E>, &E);
+
+macro_rules! forward_jwe_decrypter {
+    ($($ty:ty),+) => {$(
+        impl<D: JweDecrypter> JweDecrypter for $ty {
+            type Error = D::Error;
+
+            fn key_management_algorithm(&self) -> Cow<'_, str> {
+                (**self).key_management_algorithm()
+            }
+
+            fn key_id(&self) -> Option<Cow<'_, str>> {
+                (**self).key_id()
+            }
+
+            fn decrypt_key_unchecked(
+                &self,
+                header: &Map<String, Value>,
+                encrypted_key: &[u8],
+                enc: &str,
+            ) -> impl Future<Output = Result<SecretBox<[u8]>, Self::Error>> + MaybeSend {
+                (**self).decrypt_key_unchecked(header, encrypted_key, enc)
+            }
+        }
+    )+};
+}
+
+forward_jwe_decrypter!(Arc<D>, &D);
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+
+    use secrecy::ExposeSecret;
+    use serde_json::json;
+
+    use super::*;
+
+    /// Uses a fixed shared key as the CEK (`dir`).
+    #[derive(Debug, Clone)]
+    struct DirectKey;
+
+    impl JweEncrypter for DirectKey {
+        type Error = Infallible;
+
+        fn key_management_algorithm(&self) -> Cow<'_, str> {
+            "dir".into()
+        }
+
+        fn key_id(&self) -> Option<Cow<'_, str>> {
+            Some("key-1".into())
+        }
+
+        async fn encrypt_key(&self, _enc: &str) -> Result<ContentKey, Self::Error> {
+            let cek = SecretBox::new(Box::from([7; 32].as_slice()));
+            Ok(ContentKey::new(
+                cek,
+                Vec::new(),
+                "dir",
+                Some("key-1".into()),
+            ))
+        }
+    }
+
+    impl JweDecrypter for DirectKey {
+        type Error = Infallible;
+
+        fn key_management_algorithm(&self) -> Cow<'_, str> {
+            "dir".into()
+        }
+
+        fn key_id(&self) -> Option<Cow<'_, str>> {
+            Some("key-1".into())
+        }
+
+        async fn decrypt_key_unchecked(
+            &self,
+            _header: &Map<String, Value>,
+            _encrypted_key: &[u8],
+            _enc: &str,
+        ) -> Result<SecretBox<[u8]>, Self::Error> {
+            Ok(SecretBox::new(Box::from([7; 32].as_slice())))
+        }
+    }
+
+    fn header(value: Value) -> Map<String, Value> {
+        let Value::Object(header) = value else {
+            unreachable!()
+        };
+        header
+    }
+
+    #[tokio::test]
+    async fn test_round_trip() {
+        let key = DirectKey.encrypt_key("A128CBC-HS256").await.unwrap();
+        let header =
+            header(json!({"alg": key.algorithm(), "enc": "A128CBC-HS256", "kid": key.key_id()}));
+
+        let cek = DirectKey
+            .decrypt_key(&header, key.encrypted_key())
+            .await
+            .unwrap();
+
+        assert_eq!(cek.expose_secret(), key.cek());
+    }
+
+    #[tokio::test]
+    async fn test_decrypt_key_checks_header() {
+        let wrong_alg = header(json!({"alg": "RSA-OAEP-256", "enc": "A256GCM"}));
+        let wrong_kid = header(json!({"alg": "dir", "enc": "A256GCM", "kid": "key-2"}));
+        let unknown_enc = header(json!({"alg": "dir", "enc": "A256XYZ"}));
+
+        assert!(matches!(
+            DirectKey.decrypt_key(&wrong_alg, &[]).await,
+            Err(Error::AlgorithmMismatch { .. })
+        ));
+        assert!(matches!(
+            DirectKey.decrypt_key(&wrong_kid, &[]).await,
+            Err(Error::KeyIdMismatch)
+        ));
+        assert!(matches!(
+            DirectKey.decrypt_key(&unknown_enc, &[]).await,
+            Err(Error::UnsupportedEncryption { .. })
+        ));
+    }
+}
@@ -7,9 +7,12 @@
 
 mod serde_utils;
 
-use crate::jwk::serde_utils::{base64url, base64url_uint};
+use crate::jwk::serde_utils::{base64url, base64url_uint, secret_base64url};
+use base64::{Engine, prelude::BASE64_URL_SAFE_NO_PAD};
 use bon::Builder;
+use secrecy::SecretSlice;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 
 /// A JSON Web Key Set (RFC 7517 §5).
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
@@ -22,7 +25,7 @@ pub struct PublicJwks {
 #[derive(Debug, Serialize, Deserialize, Builder, PartialEq, Clone)]
 #[builder(derive(Into), builder_type(
     doc {
-        /// Builder for creating a [PublicJwk] value (call `build()` or `into()` to finish).
+        /// Builder for creating a [`PublicJwk`] value (call `build()` or `into()` to finish).
     }
 ))]
 pub struct PublicJwk {
@@ -42,6 +45,106 @@ pub struct PublicJwk {
     kid: Option<String>,
 }
 
+impl PublicJwk {
+    /// Returns the key material.
+    #[must_use]
+    pub fn key(&self) -> &PublicKey {
+        &self.key
+    }
+
+    /// Returns the `use` parameter.
+    #[must_use]
+    pub fn key_use(&self) -> Option<KeyUse> {
+        self.key_use
+    }
+
+    /// Returns the `key_ops` parameter.
+    #[must_use]
+    pub fn key_operations(&self) -> Option<&[KeyOperation]> {
+        self.key_operations.as_deref()
+    }
+
+    /// Returns the `alg` parameter.
+    #[must_use]
+    pub fn algorithm(&self) -> Option<&str> {
+        self.algorithm.as_deref()
+    }
+
+    /// Returns the `kid` parameter.
+    #[must_use]
+    pub fn kid(&self) -> Option<&str> {
+        self.kid.as_deref()
+    }
+
+    /// Computes the base64url-encoded SHA-256 JWK thumbprint (RFC 7638).
+    ///
+    /// Returns `None` for unknown key types, and `AKP` keys without an `alg`.
+    #[must_use]
+    pub fn thumbprint(&self) -> Option<String> {
+        // Required members only, in lexicographic order (RFC 7638 §3.2).
+        #[derive(Serialize)]
+        #[serde(untagged)]
+        enum Members<'a> {
+            Rsa {
+                #[serde(with = "base64url_uint")]
+                e: &'a [u8],
+                kty: &'static str,
+                #[serde(with = "base64url_uint")]
+                n: &'a [u8],
+            },
+            Ec {
+                crv: &'a str,
+                kty: &'static str,
+                #[serde(with = "base64url")]
+                x: &'a [u8],
+                #[serde(with = "base64url")]
+                y: &'a [u8],
+            },
+            Okp {
+                crv: &'a str,
+                kty: &'static str,
+                #[serde(with = "base64url")]
+                x: &'a [u8],
+            },
+            #[cfg(feature = "unstable-pqc")]
+            Akp {
+                alg: &'a str,
+                kty: &'static str,
+                #[serde(rename = "pub", with = "base64url")]
+                public: &'a [u8],
+            },
+        }
+
+        let members = match &self.key {
+            PublicKey::Rsa(key) => Members::Rsa {
+                e: &key.e,
+                kty: "RSA",
+                n: &key.n,
+            },
+            PublicKey::Ec(key) => Members::Ec {
+                crv: &key.crv,
+                kty: "EC",
+                x: &key.x,
+                y: &key.y,
+            },
+            PublicKey::Okp(key) => Members::Okp {
+                crv: &key.crv,
+                kty: "OKP",
+                x: &key.x,
+            },
+            #[cfg(feature = "unstable-pqc")]
+            PublicKey::Akp(key) => Members::Akp {
+                alg: self.algorithm.as_deref()?,
+                kty: "AKP",
+                public: &key.public,
+            },
+            PublicKey::UnknownOrPrivate => return None,
+        };
+        let json = serde_json::to_vec(&members).ok()?;
+        Some(BASE64_URL_SAFE_NO_PAD.encode(Sha256::digest(json)))
+    }
+}
+
 /// Key use parameter (RFC 7517 §4.2).
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Copy)]
 pub enum KeyUse {
@@ -98,16 +201,35 @@ pub enum PublicKey {
     /// An Octet Key Pair public key.
     #[serde(rename = "OKP")]
     Okp(OkpPublicKey),
+    /// An Algorithm Key Pair public key, used by post-quantum algorithms.
+    #[cfg(feature = "unstable-pqc")]
+    #[serde(rename = "AKP")]
+    Akp(AkpPublicKey),
     /// Unknown or private key.
     #[serde(skip, other)]
     UnknownOrPrivate,
 }
 
+impl PublicKey {
+    /// Returns the key type (`kty`), or `None` for unknown key types.
+    #[must_use]
+    pub fn kty(&self) -> Option<&'static str> {
+        match self {
+            Self::Rsa(_) => Some("RSA"),
+            Self::Ec(_) => Some("EC"),
+            Self::Okp(_) => Some("OKP"),
+            #[cfg(feature = "unstable-pqc")]
+            Self::Akp(_) => Some("AKP"),
+            Self::UnknownOrPrivate => None,
+        }
+    }
+}
+
 /// An RSA public key.
 #[derive(Debug, Serialize, Deserialize, Builder, PartialEq, Clone)]
 #[builder(derive(Into), builder_type(
     doc {
-        /// Builder for creating an [RsaPublicKey] value (call `build()` or `into()` to finish).
+        /// Builder for creating an [`RsaPublicKey`] value (call `build()` or `into()` to finish).
     }
 ))]
 pub struct RsaPublicKey {
@@ -119,6 +241,20 @@ pub struct RsaPublicKey {
     e: Vec<u8>,
 }
 
+impl RsaPublicKey {
+    /// Returns the modulus (`n`).
+    #[must_use]
+    pub fn modulus(&self) -> &[u8] {
+        &self.n
+    }
+
+    /// Returns the public exponent (`e`).
+    #[must_use]
+    pub fn public_exponent(&self) -> &[u8] {
+        &self.e
+    }
+}
+
 impl From<RsaPublicKey> for PublicKey {
     fn from(value: RsaPublicKey) -> Self {
         Self::Rsa(value)
@@ -141,7 +277,7 @@ where
 #[derive(Debug, Serialize, Deserialize, Builder, PartialEq, Clone)]
 #[builder(derive(Into), builder_type(
     doc {
-        /// Builder for creating a [EcPublicKey] value (call `build()` or `into()` to finish).
+        /// Builder for creating a [`EcPublicKey`] value (call `build()` or `into()` to finish).
     }
 ))]
 pub struct EcPublicKey {
@@ -155,6 +291,26 @@ pub struct EcPublicKey {
     y: Vec<u8>,
 }
 
+impl EcPublicKey {
+    /// Returns the curve (`crv`), such as `P-256`.
+    #[must_use]
+    pub fn curve(&self) -> &str {
+        &self.crv
+    }
+
+    /// Returns the `x` coordinate.
+    #[must_use]
+    pub fn x(&self) -> &[u8] {
+        &self.x
+    }
+
+    /// Returns the `y` coordinate.
+    #[must_use]
+    pub fn y(&self) -> &[u8] {
+        &self.y
+    }
+}
+
 impl From<EcPublicKey> for PublicKey {
     fn from(value: EcPublicKey) -> Self {
         Self::Ec(value)
@@ -176,7 +332,7 @@ where
 #[derive(Debug, Serialize, Deserialize, Builder, PartialEq, Clone)]
 #[builder(derive(Into), builder_type(
     doc {
-        /// Builder for creating a [OkpPublicKey] value (call `build()` or `into()` to finish).
+        /// Builder for creating a [`OkpPublicKey`] value (call `build()` or `into()` to finish).
     }
 ))]
 pub struct OkpPublicKey {
@@ -187,6 +343,20 @@ pub struct OkpPublicKey {
     x: Vec<u8>,
 }
 
+impl OkpPublicKey {
+    /// Returns the curve (`crv`), such as `Ed25519`.
+    #[must_use]
+    pub fn curve(&self) -> &str {
+        &self.crv
+    }
+
+    /// Returns the public key (`x`).
+    #[must_use]
+    pub fn x(&self) -> &[u8] {
+        &self.x
+    }
+}
+
 impl From<OkpPublicKey> for PublicKey {
     fn from(value: OkpPublicKey) -> Self {
         Self::Okp(value)
@@ -202,9 +372,358 @@ where
     }
 }
 
+/// An Algorithm Key Pair public key.
+///
+/// Parameters are defined in draft-ietf-cose-dilithium. The key is bound to
+/// the single algorithm in the JWK's (required) `alg` parameter, such as
+/// `ML-DSA-65`.
+#[cfg(feature = "unstable-pqc")]
+#[derive(Debug, Serialize, Deserialize, Builder, PartialEq, Clone)]
+#[builder(derive(Into), builder_type(
+    doc {
+        /// Builder for creating an [`AkpPublicKey`] value (call `build()` or `into()` to finish).
+    }
+))]
+pub struct AkpPublicKey {
+    #[builder(with = <_>::from_iter)]
+    #[serde(rename = "pub", with = "base64url")]
+    public: Vec<u8>,
+}
+
+#[cfg(feature = "unstable-pqc")]
+impl From<AkpPublicKey> for PublicKey {
+    fn from(value: AkpPublicKey) -> Self {
+        Self::Akp(value)
+    }
+}
+
+#[cfg(feature = "unstable-pqc")]
+impl<S: akp_public_key_builder::State> From<AkpPublicKeyBuilder<S>> for PublicKey
+where
+    S: akp_public_key_builder::IsComplete,
+{
+    fn from(value: AkpPublicKeyBuilder<S>) -> Self {
+        Self::Akp(value.build())
+    }
+}
+
+/// A private JSON Web Key (RFC 7517 §4), such as a signing key held in a
+/// secret manager.
+///
+/// Private parameters are held as [`SecretSlice`]s, so they are redacted from
+/// `Debug` output and zeroized on drop. Use [`PrivateJwk::to_public`] to
+/// derive the JWK to publish.
+#[derive(Debug, Deserialize, Clone)]
+pub struct PrivateJwk {
+    #[serde(flatten)]
+    key: PrivateKey,
+    #[serde(rename = "use")]
+    key_use: Option<KeyUse>,
+    #[serde(rename = "key_ops")]
+    key_operations: Option<Vec<KeyOperation>>,
+    #[serde(rename = "alg")]
+    algorithm: Option<String>,
+    kid: Option<String>,
+}
+
+impl PrivateJwk {
+    /// Returns the key material.
+    #[must_use]
+    pub fn key(&self) -> &PrivateKey {
+        &self.key
+    }
+
+    /// Returns the `use` parameter.
+    #[must_use]
+    pub fn key_use(&self) -> Option<KeyUse> {
+        self.key_use
+    }
+
+    /// Returns the `key_ops` parameter.
+    #[must_use]
+    pub fn key_operations(&self) -> Option<&[KeyOperation]> {
+        self.key_operations.as_deref()
+    }
+
+    /// Returns the `alg` parameter.
+    #[must_use]
+    pub fn algorithm(&self) -> Option<&str> {
+        self.algorithm.as_deref()
+    }
+
+    /// Returns the `kid` parameter.
+    #[must_use]
+    pub fn kid(&self) -> Option<&str> {
+        self.kid.as_deref()
+    }
+
+    /// Returns the public JWK, or `None` for symmetric (`oct`) keys.
+    ///
+    /// Private-key operations in `key_ops` are mapped to their public
+    /// counterparts (`sign` to `verify`, `decrypt` to `encrypt`, ...).
+    #[must_use]
+    pub fn to_public(&self) -> Option<PublicJwk> {
+        let key = self.key.public_key()?;
+        let key_operations = self.key_operations.as_ref().map(|ops| {
+            ops.iter()
+                .map(|op| match op {
+                    KeyOperation::Sign => KeyOperation::Verify,
+                    KeyOperation::Decrypt => KeyOperation::Encrypt,
+                    KeyOperation::UnwrapKey => KeyOperation::WrapKey,
+                    op => *op,
+                })
+                .collect()
+        });
+        Some(PublicJwk {
+            key,
+            key_use: self.key_use,
+            key_operations,
+            algorithm: self.algorithm.clone(),
+            kid: self.kid.clone(),
+        })
+    }
+}
+
+/// The parts of a private key that vary structurally between types (RFC 7517 §4).
+#[derive(Debug, Deserialize, Clone)]
+#[serde(tag = "kty")]
+#[non_exhaustive]
+pub enum PrivateKey {
+    /// An RSA private key.
+    #[serde(rename = "RSA")]
+    Rsa(RsaPrivateKey),
+    /// An Elliptic Curve private key.
+    #[serde(rename = "EC")]
+    Ec(EcPrivateKey),
+    /// An Octet Key Pair private key.
+    #[serde(rename = "OKP")]
+    Okp(OkpPrivateKey),
+    /// A symmetric key.
+    #[serde(rename = "oct")]
+    Symmetric(SymmetricKey),
+    /// An Algorithm Key Pair private key, used by post-quantum algorithms.
+    #[cfg(feature = "unstable-pqc")]
+    #[serde(rename = "AKP")]
+    Akp(AkpPrivateKey),
+}
+
+impl PrivateKey {
+    /// Returns the key type (`kty`).
+    #[must_use]
+    pub fn kty(&self) -> &'static str {
+        match self {
+            Self::Rsa(_) => "RSA",
+            Self::Ec(_) => "EC",
+            Self::Okp(_) => "OKP",
+            Self::Symmetric(_) => "oct",
+            #[cfg(feature = "unstable-pqc")]
+            Self::Akp(_) => "AKP",
+        }
+    }
+
+    /// Returns the public key, or `None` for symmetric keys.
+    #[must_use]
+    pub fn public_key(&self) -> Option<PublicKey> {
+        match self {
+            Self::Rsa(key) => Some(PublicKey::Rsa(key.public_key())),
+            Self::Ec(key) => Some(PublicKey::Ec(key.public_key())),
+            Self::Okp(key) => Some(PublicKey::Okp(key.public_key())),
+            Self::Symmetric(_) => None,
+            #[cfg(feature = "unstable-pqc")]
+            Self::Akp(key) => Some(PublicKey::Akp(key.public_key())),
+        }
+    }
+}
+
+/// An RSA private key.
+///
+/// Parameters are defined in RFC 7518 §6.3. The CRT parameters are optional,
+/// but are either all present or all absent.
+#[derive(Debug, Deserialize, Clone)]
+pub struct RsaPrivateKey {
+    #[serde(with = "base64url_uint")]
+    n: Vec<u8>,
+    #[serde(with = "base64url_uint")]
+    e: Vec<u8>,
+    #[serde(with = "secret_base64url")]
+    d: SecretSlice<u8>,
+    #[serde(default, deserialize_with = "secret_base64url::deserialize_option")]
+    p: Option<SecretSlice<u8>>,
+    #[serde(default, deserialize_with = "secret_base64url::deserialize_option")]
+    q: Option<SecretSlice<u8>>,
+    #[serde(default, deserialize_with = "secret_base64url::deserialize_option")]
+    dp: Option<SecretSlice<u8>>,
+    #[serde(default, deserialize_with = "secret_base64url::deserialize_option")]
+    dq: Option<SecretSlice<u8>>,
+    #[serde(default, deserialize_with = "secret_base64url::deserialize_option")]
+    qi: Option<SecretSlice<u8>>,
+}
+
+impl RsaPrivateKey {
+    /// Returns the public key.
+    #[must_use]
+    pub fn public_key(&self) -> RsaPublicKey {
+        RsaPublicKey {
+            n: self.n.clone(),
+            e: self.e.clone(),
+        }
+    }
+
+    /// Returns the modulus (`n`).
+    #[must_use]
+    pub fn modulus(&self) -> &[u8] {
+        &self.n
+    }
+
+    /// Returns the public exponent (`e`).
+    #[must_use]
+    pub fn public_exponent(&self) -> &[u8] {
+        &self.e
+    }
+
+    /// Returns the private exponent (`d`).
+    #[must_use]
+    pub fn private_exponent(&self) -> &SecretSlice<u8> {
+        &self.d
+    }
+
+    /// Returns the first and second prime factors (`p`, `q`), if present.
+    #[must_use]
+    pub fn primes(&self) -> Option<(&SecretSlice<u8>, &SecretSlice<u8>)> {
+        self.p.as_ref().zip(self.q.as_ref())
+    }
+
+    /// Returns the CRT exponents and coefficient (`dp`, `dq`, `qi`), if present.
+    #[must_use]
+    pub fn crt_values(&self) -> Option<(&SecretSlice<u8>, &SecretSlice<u8>, &SecretSlice<u8>)> {
+        Some((self.dp.as_ref()?, self.dq.as_ref()?, self.qi.as_ref()?))
+    }
+}
+
+/// An Elliptic Curve private key.
+///
+/// Parameters are defined in RFC 7518 §6.2.
+#[derive(Debug, Deserialize, Clone)]
+pub struct EcPrivateKey {
+    crv: String,
+    #[serde(with = "base64url")]
+    x: Vec<u8>,
+    #[serde(with = "base64url")]
+    y: Vec<u8>,
+    #[serde(with = "secret_base64url")]
+    d: SecretSlice<u8>,
+}
+
+impl EcPrivateKey {
+    /// Returns the public key.
+    #[must_use]
+    pub fn public_key(&self) -> EcPublicKey {
+        EcPublicKey {
+            crv: self.crv.clone(),
+            x: self.x.clone(),
+            y: self.y.clone(),
+        }
+    }
+
+    /// Returns the curve (`crv`), such as `P-256`.
+    #[must_use]
+    pub fn curve(&self) -> &str {
+        &self.crv
+    }
+
+    /// Returns the private scalar (`d`).
+    #[must_use]
+    pub fn private_scalar(&self) -> &SecretSlice<u8> {
+        &self.d
+    }
+}
+
+/// An Octet Key Pair private key.
+///
+/// Parameters are defined in RFC 8037 §2.
+#[derive(Debug, Deserialize, Clone)]
+pub struct OkpPrivateKey {
+    crv: String,
+    #[serde(with = "base64url")]
+    x: Vec<u8>,
+    #[serde(with = "secret_base64url")]
+    d: SecretSlice<u8>,
+}
+
+impl OkpPrivateKey {
+    /// Returns the public key.
+    #[must_use]
+    pub fn public_key(&self) -> OkpPublicKey {
+        OkpPublicKey {
+            crv: self.crv.clone(),
+            x: self.x.clone(),
+        }
+    }
+
+    /// Returns the curve (`crv`), such as `Ed25519`.
+    #[must_use]
+    pub fn curve(&self) -> &str {
+        &self.crv
+    }
+
+    /// Returns the private key (`d`).
+    #[must_use]
+    pub fn private_key(&self) -> &SecretSlice<u8> {
+        &self.d
+    }
+}
+
+/// A symmetric key.
+///
+/// Parameters are defined in RFC 7518 §6.4.
+#[derive(Debug, Deserialize, Clone)]
+pub struct SymmetricKey {
+    #[serde(with = "secret_base64url")]
+    k: SecretSlice<u8>,
+}
+
+impl SymmetricKey {
+    /// Returns the key value (`k`).
+    #[must_use]
+    pub fn key_value(&self) -> &SecretSlice<u8> {
+        &self.k
+    }
+}
+
+/// An Algorithm Key Pair private key.
+///
+/// Parameters are defined in draft-ietf-cose-dilithium; `priv` holds the
+/// seed the key pair is derived from.
+#[cfg(feature = "unstable-pqc")]
+#[derive(Debug, Deserialize, Clone)]
+pub struct AkpPrivateKey {
+    #[serde(rename = "pub", with = "base64url")]
+    public: Vec<u8>,
+    #[serde(rename = "priv", with = "secret_base64url")]
+    private: SecretSlice<u8>,
+}
+
+#[cfg(feature = "unstable-pqc")]
+impl AkpPrivateKey {
+    /// Returns the public key.
+    #[must_use]
+    pub fn public_key(&self) -> AkpPublicKey {
+        AkpPublicKey {
+            public: self.public.clone(),
+        }
+    }
+
+    /// Returns the private key seed (`priv`).
+    #[must_use]
+    pub fn private_key(&self) -> &SecretSlice<u8> {
+        &self.private
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use base64::{Engine, prelude::BASE64_URL_SAFE_NO_PAD};
+    use secrecy::ExposeSecret;
 
     use super::*;
 
@@ -247,10 +766,71 @@ mod tests {
         assert_eq!(jwks.keys, vec![key1, key2]);
     }
 
+    // Example from https://www.rfc-editor.org/rfc/rfc7638.html#section-3.1
+    #[test]
+    fn test_thumbprint_rfc7638() {
+        let jwk: PublicJwk = serde_json::from_str(r#"{"kty":"RSA","n":"0vx7agoebGcQSuuPiLJXZptN9nndrQmbXEps2aiAFbWhM78LhWx4cbbfAAtVT86zwu1RK7aPFFxuhDR1L6tSoc_BJECPebWKRXjBZCiFV4n3oknjhMstn64tZ_2W-5JsGY4Hc5n9yBXArwl93lqt7_RN5w6Cf0h4QyQ5v-65YGjQR0_FDW2QvzqY368QQMicAtaSqzs8KJZgnYb9c7d0zgdAZHzu6qMQvRL5hajrn1n91CbOpbISD08qNLyrdkt-bFTWhAI4vMQFh6WeZu0fM4lFd2NcRwr3XPksINHaQ-G_xBniIqbw0Ls1jF44-csFCur-kEgU8awapJzKnqDKgw","e":"AQAB","alg":"RS256","kid":"2011-04-29"}"#).unwrap();
+
+        assert_eq!(
+            jwk.thumbprint().as_deref(),
+            Some("NzbLsXh8uDCcd-6MNwXF4W_7noWXFZAfHkxZsRGC9Xs")
+        );
+    }
+
+    // Example private key from https://www.rfc-editor.org/rfc/rfc7517.html#appendix-A.2
+    #[test]
+    fn test_private_jwk_to_public() {
+        let jwk: PrivateJwk = serde_json::from_str(r#"{"kty":"EC","crv":"P-256","x":"MKBCTNIcKUSDii11ySs3526iDZ8AiTo7Tu6KPAqv7D4","y":"4Etl6SRW2YiLUrN5vfvVHuhp7x8PxltmWWlbbM4IFyM","d":"870MB6gfuTJ4HtUnUvYMyJpr5eUZNP4Bk43bVdj3eAE","use":"enc","key_ops":["decrypt"],"kid":"1"}"#).unwrap();
+
+        let PrivateKey::Ec(key) = jwk.key() else {
+            unreachable!("expected an EC key");
+        };
+        assert_eq!(key.curve(), "P-256");
+        assert_eq!(key.private_scalar().expose_secret().len(), 32);
+        assert!(!format!("{jwk:?}").contains("870MB6"));
+
+        let public = jwk.to_public().unwrap();
+        assert_eq!(public.kid(), Some("1"));
+        assert_eq!(public.key_operations(), Some(&[KeyOperation::Encrypt][..]));
+        assert_eq!(
+            public.thumbprint(),
+            serde_json::from_str::<PublicJwk>(r#"{"kty":"EC","crv":"P-256","x":"MKBCTNIcKUSDii11ySs3526iDZ8AiTo7Tu6KPAqv7D4","y":"4Etl6SRW2YiLUrN5vfvVHuhp7x8PxltmWWlbbM4IFyM"}"#)
+                .unwrap()
+                .thumbprint()
+        );
+    }
+
+    #[test]
+    fn test_private_jwk_requires_private_parameters() {
+        let public_only = r#"{"kty":"EC","crv":"P-256","x":"MKBCTNIcKUSDii11ySs3526iDZ8AiTo7Tu6KPAqv7D4","y":"4Etl6SRW2YiLUrN5vfvVHuhp7x8PxltmWWlbbM4IFyM"}"#;
+
+        assert!(serde_json::from_str::<PrivateJwk>(public_only).is_err());
+
+        let symmetric: PrivateJwk = serde_json::from_str(r#"{"kty":"oct","k":"AQID"}"#).unwrap();
+        assert_eq!(symmetric.key().kty(), "oct");
+        assert!(symmetric.to_public().is_none());
+    }
+
     #[test]
     fn test_unknown_curve_parses() {
         // Unknown curve should parse successfully
         let unknown_curve = r#"{"kty":"EC","crv":"brainpoolP256r1","x":"MKBCTNIcKUSDii11ySs3526iDZ8AiTo7Tu6KPAqv7D4","y":"4Etl6SRW2YiLUrN5vfvVHuhp7x8PxltmWWlbbM4IFyM"}"#;
         let _: PublicJwk = serde_json::from_str(unknown_curve).unwrap();
     }
+
+    #[cfg(feature = "unstable-pqc")]
+    #[test]
+    fn test_akp_key_parses() {
+        let jwk: PublicJwk =
+            serde_json::from_str(r#"{"kty":"AKP","alg":"ML-DSA-44","pub":"AQID","kid":"pq"}"#)
+                .unwrap();
+
+        assert_eq!(jwk.key().kty(), Some("AKP"));
+        assert_eq!(
+            jwk.key(),
+            &PublicKey::from(AkpPublicKey::builder().public([1, 2, 3]))
+        );
+        assert_eq!(jwk.algorithm(), Some("ML-DSA-44"));
+        assert!(jwk.thumbprint().is_some());
+    }
 }
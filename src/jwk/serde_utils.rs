@@ -29,3 +29,23 @@ pub mod base64url_uint {
         URL_SAFE_NO_PAD.decode(s).map_err(serde::de::Error::custom)
     }
 }
+
+pub mod secret_base64url {
+    use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+    use secrecy::SecretSlice;
+    use serde::{Deserialize, Deserializer};
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(de: D) -> Result<SecretSlice<u8>, D::Error> {
+        let s: &str = Deserialize::deserialize(de)?;
+        let bytes = URL_SAFE_NO_PAD
+            .decode(s)
+            .map_err(serde::de::Error::custom)?;
+        Ok(bytes.into())
+    }
+
+    pub fn deserialize_option<'de, D: Deserializer<'de>>(
+        de: D,
+    ) -> Result<Option<SecretSlice<u8>>, D::Error> {
+        deserialize(de).map(Some)
+    }
+}
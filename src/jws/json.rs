@@ -0,0 +1,213 @@
+//! JWS JSON serialization (RFC 7515 §7.2).
+
+use std::collections::BTreeMap;
+
+use base64::{Engine, prelude::BASE64_URL_SAFE_NO_PAD};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use snafu::prelude::*;
+
+use crate::{
+    jws::{
+        DuplicateParameterSnafu, HeaderError, MalformedProtectedHeaderSnafu, PROTECTED_ONLY_PARAMS,
+        ReservedParameterSnafu,
+    },
+    signer::SignedBytes,
+};
+
+/// A JWS in the general JSON serialization, which can carry several signatures
+/// over the same payload.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JsonJws {
+    payload: String,
+    signatures: Vec<JsonSignature>,
+}
+
+impl JsonJws {
+    /// Creates a JWS for the given payload, with no signatures.
+    #[must_use]
+    pub fn new(payload: &[u8]) -> Self {
+        Self {
+            payload: BASE64_URL_SAFE_NO_PAD.encode(payload),
+            signatures: Vec::new(),
+        }
+    }
+
+    /// Adds a signature, returning the updated JWS.
+    #[must_use]
+    pub fn with_signature(mut self, signature: JsonSignature) -> Self {
+        self.push_signature(signature);
+        self
+    }
+
+    /// Adds a signature.
+    pub fn push_signature(&mut self, signature: JsonSignature) {
+        self.signatures.push(signature);
+    }
+
+    /// Returns the base64url-encoded payload.
+    #[must_use]
+    pub fn payload(&self) -> &str {
+        &self.payload
+    }
+
+    /// Returns the signatures.
+    #[must_use]
+    pub fn signatures(&self) -> &[JsonSignature] {
+        &self.signatures
+    }
+}
+
+/// A single signature of a [`JsonJws`], with its protected and unprotected headers.
+///
+/// The unprotected header is not covered by the signature, so it must only
+/// carry hints (e.g. a `kid` for a verifier that can't read the protected
+/// header, or a timestamp). Its parameter names must not repeat those of the
+/// protected header.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JsonSignature {
+    protected: String,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    header: BTreeMap<String, Value>,
+    signature: String,
+}
+
+impl JsonSignature {
+    /// Creates a signature from the result of [`JwsSigner::sign_with_header`](crate::signer::JwsSigner::sign_with_header).
+    ///
+    /// Returns `None` if the signature was not produced over a protected header.
+    #[must_use]
+    pub fn from_signed(signed: &SignedBytes) -> Option<Self> {
+        Some(Self {
+            protected: signed.protected_header()?.to_owned(),
+            header: BTreeMap::new(),
+            signature: BASE64_URL_SAFE_NO_PAD.encode(signed.signature()),
+        })
+    }
+
+    /// Returns the base64url-encoded protected header.
+    #[must_use]
+    pub fn protected(&self) -> &str {
+        &self.protected
+    }
+
+    /// Returns the unprotected header parameter with the given name.
+    #[must_use]
+    pub fn unprotected_param(&self, name: &str) -> Option<&Value> {
+        self.header.get(name)
+    }
+
+    /// Returns the base64url-encoded signature.
+    #[must_use]
+    pub fn signature(&self) -> &str {
+        &self.signature
+    }
+
+    /// Adds an unprotected header parameter, returning the previous value with that name.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the parameter must be integrity protected (`alg`,
+    /// `crit` and `b64`), the protected header can't be decoded, or it already
+    /// contains a parameter with this name.
+    pub fn insert_unprotected(
+        &mut self,
+        name: impl Into<String>,
+        value: impl Into<Value>,
+    ) -> Result<Option<Value>, HeaderError> {
+        let name = name.into();
+        ensure!(
+            !PROTECTED_ONLY_PARAMS.contains(&name.as_str()),
+            ReservedParameterSnafu { name }
+        );
+        let json = BASE64_URL_SAFE_NO_PAD
+            .decode(&self.protected)
+            .ok()
+            .context(MalformedProtectedHeaderSnafu)?;
+        let protected: Map<String, Value> = serde_json::from_slice(&json)
+            .ok()
+            .context(MalformedProtectedHeaderSnafu)?;
+        ensure!(
+            !protected.contains_key(&name),
+            DuplicateParameterSnafu { name }
+        );
+        Ok(self.header.insert(name, value.into()))
+    }
+
+    /// Adds an unprotected header parameter, returning the updated signature.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the parameter can't be added (see [`JsonSignature::insert_unprotected`]).
+    pub fn with_unprotected(
+        mut self,
+        name: impl Into<String>,
+        value: impl Into<Value>,
+    ) -> Result<Self, HeaderError> {
+        self.insert_unprotected(name, value)?;
+        Ok(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jws::{Header, encode_protected_header};
+
+    fn signed() -> SignedBytes {
+        let header = Header::builder().typ("JWT").build();
+        SignedBytes::builder()
+            .signature(b"sig".as_slice())
+            .jws_algorithm("ES256")
+            .protected_header(encode_protected_header("ES256", None, &[], &header).unwrap())
+            .build()
+    }
+
+    #[test]
+    fn test_general_serialization_with_unprotected_header() {
+        let signature = JsonSignature::from_signed(&signed())
+            .unwrap()
+            .with_unprotected("kid", "key-1")
+            .unwrap();
+
+        let jws = JsonJws::new(b"{}").with_signature(signature);
+
+        assert_eq!(
+            serde_json::to_value(&jws).unwrap(),
+            serde_json::json!({
+                "payload": "e30",
+                "signatures": [{
+                    "protected": "eyJhbGciOiJFUzI1NiIsInR5cCI6IkpXVCJ9",
+                    "header": { "kid": "key-1" },
+                    "signature": "c2ln",
+                }],
+            })
+        );
+    }
+
+    #[test]
+    fn test_protected_only_unprotected_params_fail() {
+        let signature = JsonSignature::from_signed(&signed()).unwrap();
+
+        for param in ["alg", "crit", "b64"] {
+            let result = signature.clone().insert_unprotected(param, "value");
+
+            assert!(matches!(
+                result,
+                Err(HeaderError::ReservedParameter { name }) if name == param
+            ));
+        }
+    }
+
+    #[test]
+    fn test_unprotected_param_duplicating_protected_fails() {
+        let result = JsonSignature::from_signed(&signed())
+            .unwrap()
+            .insert_unprotected("typ", "other");
+
+        assert!(matches!(
+            result,
+            Err(HeaderError::DuplicateParameter { name }) if name == "typ"
+        ));
+    }
+}
@@ -0,0 +1,338 @@
+//! JSON Web Signature (JWS) types per RFC 7515.
+//!
+//! This module provides the protected header used when signing, helpers
+//! for assembling the compact serialization, and the JSON serialization.
+
+mod json;
+
+use std::collections::BTreeMap;
+
+use base64::{Engine, prelude::BASE64_URL_SAFE_NO_PAD};
+use bon::Builder;
+use serde::Serialize;
+use serde_json::Value;
+use snafu::prelude::*;
+
+pub use json::{JsonJws, JsonSignature};
+
+/// Errors that can occur when building a JWS protected header.
+#[derive(Debug, Snafu)]
+#[non_exhaustive]
+pub enum HeaderError {
+    /// The header could not be serialized to JSON.
+    #[snafu(display("Failed to serialize protected header"))]
+    Serialize {
+        /// The underlying serialization error.
+        source: serde_json::Error,
+    },
+    /// The parameter is set by the signer or through a dedicated field, must be
+    /// integrity protected, or is unsupported, so it can't be added as an
+    /// additional or unprotected parameter.
+    #[snafu(display("Header parameter '{name}' can't be set as an additional parameter"))]
+    ReservedParameter {
+        /// The name of the parameter.
+        name: String,
+    },
+    /// A parameter the signer marks as critical is missing from the header.
+    #[snafu(display("Critical header parameter '{name}' is missing"))]
+    MissingCriticalParameter {
+        /// The name of the parameter.
+        name: String,
+    },
+    /// A parameter defined by RFC 7515, or otherwise reserved, was marked as
+    /// critical, which is not allowed.
+    #[snafu(display("Header parameter '{name}' can't be marked as critical"))]
+    InvalidCriticalParameter {
+        /// The name of the parameter.
+        name: String,
+    },
+    /// The signer did not return the protected header it signed.
+    #[snafu(display("Signer did not return the signed protected header"))]
+    MissingProtectedHeader,
+    /// The protected header is not valid base64url-encoded JSON.
+    #[snafu(display("Malformed protected header"))]
+    MalformedProtectedHeader,
+    /// An unprotected header parameter repeats a protected header parameter.
+    #[snafu(display("Header parameter '{name}' is already in the protected header"))]
+    DuplicateParameter {
+        /// The name of the parameter.
+        name: String,
+    },
+    /// The `typ` parameter doesn't match the required type.
+    #[snafu(display("Header type {actual:?} doesn't match required '{expected}'"))]
+    TypMismatch {
+        /// The required `typ`.
+        expected: String,
+        /// The `typ` parameter.
+        actual: Option<String>,
+    },
+    /// The signer's algorithm can't be used for this kind of signature.
+    #[snafu(display("Algorithm '{algorithm}' is not supported here"))]
+    UnsupportedAlgorithm {
+        /// The JWS algorithm.
+        algorithm: String,
+    },
+}
+
+/// Parameters that can't be set through [`Header::insert_param`].
+///
+/// `b64` (RFC 7797) is included because the payload is always base64url-encoded
+/// in the signing input and serializations, so a header claiming otherwise
+/// would produce JWSs no verifier accepts.
+const RESERVED_PARAMS: &[&str] = &["alg", "kid", "typ", "cty", "crit", "b64"];
+
+/// Parameters that can't be set in a JWS JSON unprotected header.
+///
+/// `crit` must be integrity protected (RFC 7515 §4.1.11), as must `b64`
+/// (RFC 7797 §3), and verifiers take `alg` from the protected header.
+const PROTECTED_ONLY_PARAMS: &[&str] = &["alg", "crit", "b64"];
+
+/// Parameters defined by RFC 7515, which must not appear in `crit` (§4.1.11).
+const REGISTERED_PARAMS: &[&str] = &[
+    "alg", "jku", "jwk", "kid", "x5u", "x5c", "x5t", "x5t#S256", "typ", "cty", "crit",
+];
+
+/// Caller-controlled parameters of a JWS protected header (RFC 7515 §4.1).
+///
+/// The `alg` and `kid` parameters are deliberately absent: they are always
+/// filled in by the signer, so they can't disagree with the key that
+/// actually produces the signature.
+///
+/// Other parameters (e.g. a DPoP `nonce`, or `x5t`) can be added with
+/// [`Header::insert_param`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Builder)]
+#[builder(derive(Into), builder_type(
+    doc {
+        /// Builder for creating a [`Header`] value (call `build()` or `into()` to finish).
+    }
+))]
+pub struct Header {
+    /// The media type of the complete JWS (`typ`), e.g. `JWT`.
+    #[builder(into)]
+    typ: Option<String>,
+    /// The media type of the secured content (`cty`).
+    #[builder(into)]
+    cty: Option<String>,
+    #[builder(skip)]
+    params: BTreeMap<String, Value>,
+}
+
+impl Header {
+    /// Returns the `typ` parameter.
+    #[must_use]
+    pub fn typ(&self) -> Option<&str> {
+        self.typ.as_deref()
+    }
+
+    /// Returns the `cty` parameter.
+    #[must_use]
+    pub fn cty(&self) -> Option<&str> {
+        self.cty.as_deref()
+    }
+
+    /// Sets the `typ` parameter, unless already set.
+    pub(crate) fn or_typ(mut self, typ: &str) -> Self {
+        self.typ.get_or_insert_with(|| typ.to_owned());
+        self
+    }
+
+    /// Returns the additional parameter with the given name.
+    #[must_use]
+    pub fn param(&self, name: &str) -> Option<&Value> {
+        self.params.get(name)
+    }
+
+    /// Returns an iterator over the additional parameters, ordered by name.
+    pub fn params(&self) -> impl Iterator<Item = (&str, &Value)> {
+        self.params
+            .iter()
+            .map(|(name, value)| (name.as_str(), value))
+    }
+
+    /// Adds an additional parameter, returning the previous value with that name.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the parameter is `alg`, `kid` or `crit` (which are
+    /// set by the signer), `typ` or `cty` (which have dedicated fields), or
+    /// `b64` (unencoded payloads aren't supported).
+    pub fn insert_param(
+        &mut self,
+        name: impl Into<String>,
+        value: impl Into<Value>,
+    ) -> Result<Option<Value>, HeaderError> {
+        let name = name.into();
+        ensure!(
+            !RESERVED_PARAMS.contains(&name.as_str()),
+            ReservedParameterSnafu { name }
+        );
+        Ok(self.params.insert(name, value.into()))
+    }
+
+    /// Adds an additional parameter, returning the updated header.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the parameter is reserved (see [`Header::insert_param`]).
+    pub fn with_param(
+        mut self,
+        name: impl Into<String>,
+        value: impl Into<Value>,
+    ) -> Result<Self, HeaderError> {
+        self.insert_param(name, value)?;
+        Ok(self)
+    }
+}
+
+#[derive(Serialize)]
+struct ProtectedHeader<'a> {
+    alg: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    kid: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    typ: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cty: Option<&'a str>,
+    #[serde(skip_serializing_if = "<[String]>::is_empty")]
+    crit: &'a [String],
+    #[serde(flatten)]
+    params: &'a BTreeMap<String, Value>,
+}
+
+/// Serializes and base64url-encodes a protected header.
+///
+/// The `critical` parameter names are emitted as the `crit` parameter, and
+/// must each be present in the header's additional parameters.
+///
+/// # Errors
+///
+/// Returns an error if a critical parameter is missing, defined by RFC 7515 or
+/// reserved (such as `b64`), or the header can't be serialized.
+pub fn encode_protected_header(
+    jws_algorithm: &str,
+    key_id: Option<&str>,
+    critical: &[String],
+    header: &Header,
+) -> Result<String, HeaderError> {
+    for name in critical {
+        ensure!(
+            !REGISTERED_PARAMS.contains(&name.as_str())
+                && !RESERVED_PARAMS.contains(&name.as_str()),
+            InvalidCriticalParameterSnafu { name }
+        );
+        ensure!(
+            header.params.contains_key(name),
+            MissingCriticalParameterSnafu { name }
+        );
+    }
+    let json = serde_json::to_vec(&ProtectedHeader {
+        alg: jws_algorithm,
+        kid: key_id,
+        typ: header.typ(),
+        cty: header.cty(),
+        crit: critical,
+        params: &header.params,
+    })
+    .context(SerializeSnafu)?;
+    Ok(BASE64_URL_SAFE_NO_PAD.encode(json))
+}
+
+/// Builds the JWS signing input (`BASE64URL(header) || '.' || BASE64URL(payload)`).
+#[must_use]
+pub fn signing_input(encoded_header: &str, payload: &[u8]) -> String {
+    let mut input = String::with_capacity(encoded_header.len() + 1 + payload.len().div_ceil(3) * 4);
+    input.push_str(encoded_header);
+    input.push('.');
+    BASE64_URL_SAFE_NO_PAD.encode_string(payload, &mut input);
+    input
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_protected_header_omits_absent_params() {
+        let encoded = encode_protected_header("ES256", None, &[], &Header::default()).unwrap();
+
+        let json = BASE64_URL_SAFE_NO_PAD.decode(encoded).unwrap();
+        assert_eq!(json, br#"{"alg":"ES256"}"#);
+    }
+
+    #[test]
+    fn test_encode_protected_header_includes_params() {
+        let header = Header::builder().typ("JWT").build();
+
+        let encoded = encode_protected_header("ES256", Some("key-1"), &[], &header).unwrap();
+
+        let json = BASE64_URL_SAFE_NO_PAD.decode(encoded).unwrap();
+        assert_eq!(json, br#"{"alg":"ES256","kid":"key-1","typ":"JWT"}"#);
+    }
+
+    #[test]
+    fn test_encode_protected_header_includes_additional_params() {
+        let header = Header::builder()
+            .typ("dpop+jwt")
+            .build()
+            .with_param("nonce", "server-nonce")
+            .unwrap();
+
+        let encoded = encode_protected_header("ES256", None, &[], &header).unwrap();
+
+        let json = BASE64_URL_SAFE_NO_PAD.decode(encoded).unwrap();
+        assert_eq!(
+            json,
+            br#"{"alg":"ES256","typ":"dpop+jwt","nonce":"server-nonce"}"#
+        );
+    }
+
+    #[test]
+    fn test_encode_protected_header_includes_crit() {
+        let header = Header::default().with_param("exp", 1_700_000_000).unwrap();
+
+        let encoded = encode_protected_header("ES256", None, &["exp".into()], &header).unwrap();
+
+        let json = BASE64_URL_SAFE_NO_PAD.decode(encoded).unwrap();
+        assert_eq!(json, br#"{"alg":"ES256","crit":["exp"],"exp":1700000000}"#);
+    }
+
+    #[test]
+    fn test_unencoded_payload_is_rejected() {
+        assert!(matches!(
+            Header::default().with_param("b64", false),
+            Err(HeaderError::ReservedParameter { name }) if name == "b64"
+        ));
+        assert!(matches!(
+            encode_protected_header("ES256", None, &["b64".into()], &Header::default()),
+            Err(HeaderError::InvalidCriticalParameter { name }) if name == "b64"
+        ));
+    }
+
+    #[test]
+    fn test_encode_protected_header_missing_critical_param_fails() {
+        let result = encode_protected_header("ES256", None, &["exp".into()], &Header::default());
+
+        assert!(matches!(
+            result,
+            Err(HeaderError::MissingCriticalParameter { name }) if name == "exp"
+        ));
+    }
+
+    #[test]
+    fn test_insert_reserved_param_fails() {
+        let result = Header::default().insert_param("kid", "other-key");
+
+        assert!(matches!(
+            result,
+            Err(HeaderError::ReservedParameter { name }) if name == "kid"
+        ));
+    }
+
+    #[test]
+    fn test_signing_input() {
+        assert_eq!(
+            signing_input("eyJhbGciOiJub25lIn0", b"{}"),
+            "eyJhbGciOiJub25lIn0.e30"
+        );
+    }
+}
@@ -0,0 +1,381 @@
+use bon::Builder;
+use serde::{Deserialize, Deserializer, Serialize, de::DeserializeOwned};
+use serde_json::{Map, Value};
+use snafu::prelude::*;
+use web_time::{SystemTime, UNIX_EPOCH};
+
+use crate::jwt::Confirmation;
+
+/// Errors that can occur when adding or reading custom claims.
+#[derive(Debug, Snafu)]
+#[non_exhaustive]
+pub enum ClaimsError {
+    /// The claim is registered, so it has a dedicated field.
+    #[snafu(display("Claim '{name}' can't be set as a custom claim"))]
+    ReservedClaim {
+        /// The name of the claim.
+        name: String,
+    },
+    /// The claim could not be converted to or from JSON.
+    #[snafu(display("Invalid value for claim '{name}'"))]
+    InvalidClaim {
+        /// The name of the claim.
+        name: String,
+        /// The underlying serialization error.
+        source: serde_json::Error,
+    },
+}
+
+/// Registered claims (RFC 7519 §4.1), which can't be set as custom claims.
+const REGISTERED_CLAIMS: &[&str] = &["iss", "sub", "aud", "exp", "nbf", "iat", "jti"];
+
+/// The `aud` claim: a single audience, or several (RFC 7519 §4.1.3).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Audience {
+    /// A single audience, serialized as a string.
+    Single(String),
+    /// Several audiences, serialized as an array.
+    Multiple(Vec<String>),
+}
+
+impl Audience {
+    /// Returns `true` if the audience is, or includes, the given value.
+    #[must_use]
+    pub fn contains(&self, audience: &str) -> bool {
+        self.iter().any(|aud| aud == audience)
+    }
+
+    /// Returns an iterator over the audiences.
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        match self {
+            Self::Single(aud) => std::slice::from_ref(aud).iter(),
+            Self::Multiple(auds) => auds.iter(),
+        }
+        .map(String::as_str)
+    }
+}
+
+impl From<&str> for Audience {
+    fn from(audience: &str) -> Self {
+        Self::Single(audience.to_owned())
+    }
+}
+
+impl From<String> for Audience {
+    fn from(audience: String) -> Self {
+        Self::Single(audience)
+    }
+}
+
+impl From<Vec<String>> for Audience {
+    fn from(audiences: Vec<String>) -> Self {
+        Self::Multiple(audiences)
+    }
+}
+
+/// The claims set of a JWT (RFC 7519 §4).
+///
+/// The registered claims have dedicated fields; times are stored as whole
+/// seconds since the Unix epoch (`NumericDate`), and fractional values are
+/// truncated when parsing. Other claims, such as `scope` or `client_id`, are
+/// added with [`JwtClaims::insert_claim`] and read with [`JwtClaims::claim`].
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, Builder)]
+#[builder(derive(Into), builder_type(
+    doc {
+        /// Builder for creating a [`JwtClaims`] value (call `build()` or `into()` to finish).
+    }
+))]
+pub struct JwtClaims {
+    /// The issuer (`iss`).
+    #[builder(into)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    iss: Option<String>,
+    /// The subject (`sub`).
+    #[builder(into)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sub: Option<String>,
+    /// The audience (`aud`).
+    #[builder(into)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    aud: Option<Audience>,
+    /// The expiration time (`exp`).
+    #[builder(with = |time: SystemTime| unix_seconds(time))]
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "numeric_date"
+    )]
+    exp: Option<u64>,
+    /// The time before which the JWT must not be accepted (`nbf`).
+    #[builder(with = |time: SystemTime| unix_seconds(time))]
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "numeric_date"
+    )]
+    nbf: Option<u64>,
+    /// The time the JWT was issued (`iat`).
+    #[builder(with = |time: SystemTime| unix_seconds(time))]
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "numeric_date"
+    )]
+    iat: Option<u64>,
+    /// The unique identifier of the JWT (`jti`).
+    #[builder(into)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    jti: Option<String>,
+    #[builder(skip)]
+    #[serde(flatten)]
+    custom: Map<String, Value>,
+}
+
+impl JwtClaims {
+    /// Returns the `iss` claim.
+    #[must_use]
+    pub fn iss(&self) -> Option<&str> {
+        self.iss.as_deref()
+    }
+
+    /// Returns the `sub` claim.
+    #[must_use]
+    pub fn sub(&self) -> Option<&str> {
+        self.sub.as_deref()
+    }
+
+    /// Returns the `aud` claim.
+    #[must_use]
+    pub fn aud(&self) -> Option<&Audience> {
+        self.aud.as_ref()
+    }
+
+    /// Returns the `exp` claim.
+    #[must_use]
+    pub fn exp(&self) -> Option<SystemTime> {
+        self.exp.and_then(from_unix_seconds)
+    }
+
+    /// Returns the `nbf` claim.
+    #[must_use]
+    pub fn nbf(&self) -> Option<SystemTime> {
+        self.nbf.and_then(from_unix_seconds)
+    }
+
+    /// Returns the `iat` claim.
+    #[must_use]
+    pub fn iat(&self) -> Option<SystemTime> {
+        self.iat.and_then(from_unix_seconds)
+    }
+
+    /// Returns the `jti` claim.
+    #[must_use]
+    pub fn jti(&self) -> Option<&str> {
+        self.jti.as_deref()
+    }
+
+    /// Returns the custom claim with the given name, deserialized as `T`, or
+    /// `None` if it's absent.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the claim isn't a valid `T`.
+    pub fn claim<T: DeserializeOwned>(&self, name: &str) -> Result<Option<T>, ClaimsError> {
+        self.custom
+            .get(name)
+            .map(|value| T::deserialize(value).context(InvalidClaimSnafu { name }))
+            .transpose()
+    }
+
+    /// Returns the custom claim with the given name as JSON.
+    #[must_use]
+    pub fn claim_value(&self, name: &str) -> Option<&Value> {
+        self.custom.get(name)
+    }
+
+    /// Returns an iterator over the custom claims, ordered by name.
+    pub fn custom_claims(&self) -> impl Iterator<Item = (&str, &Value)> {
+        self.custom
+            .iter()
+            .map(|(name, value)| (name.as_str(), value))
+    }
+
+    /// Adds a custom claim, returning the previous value with that name.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the claim is registered (which have dedicated
+    /// fields), or the value can't be serialized.
+    pub fn insert_claim(
+        &mut self,
+        name: impl Into<String>,
+        value: impl Serialize,
+    ) -> Result<Option<Value>, ClaimsError> {
+        let name = name.into();
+        ensure!(
+            !REGISTERED_CLAIMS.contains(&name.as_str()),
+            ReservedClaimSnafu { name }
+        );
+        let value = serde_json::to_value(value).context(InvalidClaimSnafu { name: &name })?;
+        Ok(self.custom.insert(name, value))
+    }
+
+    /// Adds a custom claim, returning the updated claims.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the claim is reserved (see [`JwtClaims::insert_claim`]).
+    pub fn with_claim(
+        mut self,
+        name: impl Into<String>,
+        value: impl Serialize,
+    ) -> Result<Self, ClaimsError> {
+        self.insert_claim(name, value)?;
+        Ok(self)
+    }
+
+    /// Returns the `cnf` claim (RFC 7800), or `None` if it's absent.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the claim is malformed, or uses no supported
+    /// confirmation method.
+    pub fn cnf(&self) -> Result<Option<Confirmation>, ClaimsError> {
+        self.claim("cnf")
+    }
+
+    /// Sets the `cnf` claim (RFC 7800), binding the token to a key, returning
+    /// the updated claims.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the confirmation can't be serialized.
+    pub fn with_cnf(self, cnf: &Confirmation) -> Result<Self, ClaimsError> {
+        self.with_claim("cnf", cnf)
+    }
+
+    /// Removes a custom claim, returning its value.
+    pub fn remove_claim(&mut self, name: &str) -> Option<Value> {
+        self.custom.remove(name)
+    }
+}
+
+/// Converts a time to whole seconds since the Unix epoch, clamping times
+/// before the epoch to zero.
+fn unix_seconds(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs())
+}
+
+/// Converts seconds since the Unix epoch to a time, or `None` if the platform
+/// can't represent it.
+fn from_unix_seconds(seconds: u64) -> Option<SystemTime> {
+    UNIX_EPOCH.checked_add(std::time::Duration::from_secs(seconds))
+}
+
+/// Deserializes a `NumericDate`, which may be fractional, rejecting times the
+/// platform can't represent.
+fn numeric_date<'de, D: Deserializer<'de>>(de: D) -> Result<Option<u64>, D::Error> {
+    let Some(number) = Option::<serde_json::Number>::deserialize(de)? else {
+        return Ok(None);
+    };
+    let seconds = match (number.as_u64(), number.as_f64()) {
+        (Some(seconds), _) => seconds,
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        (None, Some(seconds)) if seconds.is_finite() && seconds >= 0.0 => seconds as u64,
+        _ => return Err(serde::de::Error::custom("invalid NumericDate")),
+    };
+    from_unix_seconds(seconds)
+        .map(|_| Some(seconds))
+        .ok_or_else(|| serde::de::Error::custom("NumericDate out of range"))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_serialize_registered_and_custom_claims() {
+        let issued = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let claims = JwtClaims::builder()
+            .iss("https://issuer.example")
+            .sub("client-1")
+            .aud("https://api.example")
+            .iat(issued)
+            .exp(issued + Duration::from_secs(300))
+            .build()
+            .with_claim("scope", "read write")
+            .unwrap();
+
+        assert_eq!(
+            serde_json::to_value(&claims).unwrap(),
+            json!({
+                "iss": "https://issuer.example",
+                "sub": "client-1",
+                "aud": "https://api.example",
+                "iat": 1_700_000_000,
+                "exp": 1_700_000_300,
+                "scope": "read write",
+            })
+        );
+    }
+
+    #[test]
+    fn test_deserialize_audience_array_and_fractional_dates() {
+        let claims: JwtClaims = serde_json::from_value(json!({
+            "aud": ["a", "b"],
+            "exp": 1_700_000_000.75,
+            "cnf": {"jkt": "thumbprint"},
+        }))
+        .unwrap();
+
+        assert!(claims.aud().unwrap().contains("b"));
+        assert_eq!(
+            claims.exp(),
+            Some(UNIX_EPOCH + Duration::from_secs(1_700_000_000))
+        );
+        assert_eq!(
+            claims.claim::<Map<String, Value>>("cnf").unwrap().unwrap()["jkt"],
+            "thumbprint"
+        );
+        assert!(claims.claim::<String>("cnf").is_err());
+        assert!(claims.claim::<String>("scope").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_cnf_claim() {
+        let cnf = Confirmation::x5t_s256(b"certificate");
+        let claims = JwtClaims::default().with_cnf(&cnf).unwrap();
+
+        assert_eq!(claims.cnf().unwrap(), Some(cnf));
+        assert!(JwtClaims::default().cnf().unwrap().is_none());
+        assert!(
+            JwtClaims::default()
+                .with_claim("cnf", "key")
+                .unwrap()
+                .cnf()
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_out_of_range_dates_are_rejected() {
+        for exp in [json!(u64::MAX), json!(1e300)] {
+            assert!(serde_json::from_value::<JwtClaims>(json!({"exp": exp})).is_err());
+        }
+        assert!(serde_json::from_value::<JwtClaims>(json!({"nbf": -1})).is_err());
+    }
+
+    #[test]
+    fn test_registered_claims_are_reserved() {
+        assert!(matches!(
+            JwtClaims::default().with_claim("exp", 0),
+            Err(ClaimsError::ReservedClaim { .. })
+        ));
+    }
+}
@@ -0,0 +1,163 @@
+use base64::{Engine, prelude::BASE64_URL_SAFE_NO_PAD};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::jwk::PublicJwk;
+
+/// The `cnf` (confirmation) claim of a sender-constrained token (RFC 7800),
+/// identifying the key its presenter must prove possession of.
+///
+/// An issuer adds it with [`JwtClaims::with_cnf`](crate::jwt::JwtClaims::with_cnf),
+/// and a resource server reads it with [`JwtClaims::cnf`](crate::jwt::JwtClaims::cnf),
+/// then checks the key the request was authenticated with using
+/// [`Confirmation::matches_jwk`] or [`Confirmation::matches_certificate`].
+///
+/// When parsing, a claim with several supported members is read as the first
+/// of `jkt`, `jwk` and `x5t#S256`; other members are ignored.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "RawConfirmation")]
+#[non_exhaustive]
+pub enum Confirmation {
+    /// The SHA-256 JWK thumbprint of a DPoP key (`jkt`, RFC 9449 §6.1).
+    #[serde(rename = "jkt")]
+    JwkThumbprint(String),
+    /// The public key itself (`jwk`, RFC 7800 §3.2).
+    #[serde(rename = "jwk")]
+    Jwk(PublicJwk),
+    /// The SHA-256 thumbprint of a mutual-TLS client certificate
+    /// (`x5t#S256`, RFC 8705 §3.1).
+    #[serde(rename = "x5t#S256")]
+    CertificateThumbprint(String),
+}
+
+impl Confirmation {
+    /// Creates a `jkt` confirmation for the given key, as for DPoP-bound
+    /// tokens.
+    ///
+    /// Returns `None` if the key has no thumbprint (see
+    /// [`PublicJwk::thumbprint`]).
+    #[must_use]
+    pub fn jkt(jwk: &PublicJwk) -> Option<Self> {
+        jwk.thumbprint().map(Self::JwkThumbprint)
+    }
+
+    /// Creates an `x5t#S256` confirmation for the given DER-encoded
+    /// certificate, as for certificate-bound tokens.
+    #[must_use]
+    pub fn x5t_s256(certificate: &[u8]) -> Self {
+        Self::CertificateThumbprint(certificate_thumbprint(certificate))
+    }
+
+    /// Returns `true` if the given key is the confirmed key.
+    ///
+    /// Keys are compared by thumbprint, so a `jwk` confirmation matches the
+    /// same key with different optional members (such as `kid`). Always
+    /// `false` for certificate confirmations.
+    #[must_use]
+    pub fn matches_jwk(&self, jwk: &PublicJwk) -> bool {
+        let expected = match self {
+            Self::JwkThumbprint(thumbprint) => Some(thumbprint.clone()),
+            Self::Jwk(confirmed) => confirmed.thumbprint(),
+            Self::CertificateThumbprint(_) => None,
+        };
+        expected.is_some_and(|expected| jwk.thumbprint().as_ref() == Some(&expected))
+    }
+
+    /// Returns `true` if the given DER-encoded certificate is the confirmed
+    /// certificate. Always `false` for key confirmations.
+    #[must_use]
+    pub fn matches_certificate(&self, certificate: &[u8]) -> bool {
+        match self {
+            Self::CertificateThumbprint(thumbprint) => {
+                *thumbprint == certificate_thumbprint(certificate)
+            }
+            Self::JwkThumbprint(_) | Self::Jwk(_) => false,
+        }
+    }
+}
+
+fn certificate_thumbprint(certificate: &[u8]) -> String {
+    BASE64_URL_SAFE_NO_PAD.encode(Sha256::digest(certificate))
+}
+
+/// The supported members of a `cnf` claim.
+#[derive(Deserialize)]
+struct RawConfirmation {
+    jkt: Option<String>,
+    jwk: Option<PublicJwk>,
+    #[serde(rename = "x5t#S256")]
+    x5t_s256: Option<String>,
+}
+
+impl TryFrom<RawConfirmation> for Confirmation {
+    type Error = &'static str;
+
+    fn try_from(raw: RawConfirmation) -> Result<Self, Self::Error> {
+        raw.jkt
+            .map(Self::JwkThumbprint)
+            .or(raw.jwk.map(Self::Jwk))
+            .or(raw.x5t_s256.map(Self::CertificateThumbprint))
+            .ok_or("no supported confirmation method")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    // RFC 7638 §3.1.
+    const JWK: &str = r#"{
+        "kty": "RSA",
+        "n": "0vx7agoebGcQSuuPiLJXZptN9nndrQmbXEps2aiAFbWhM78LhWx4cbbfAAtVT86zwu1RK7aPFFxuhDR1L6tSoc_BJECPebWKRXjBZCiFV4n3oknjhMstn64tZ_2W-5JsGY4Hc5n9yBXArwl93lqt7_RN5w6Cf0h4QyQ5v-65YGjQR0_FDW2QvzqY368QQMicAtaSqzs8KJZgnYb9c7d0zgdAZHzu6qMQvRL5hajrn1n91CbOpbISD08qNLyrdkt-bFTWhAI4vMQFh6WeZu0fM4lFd2NcRwr3XPksINHaQ-G_xBniIqbw0Ls1jF44-csFCur-kEgU8awapJzKnqDKgw",
+        "e": "AQAB",
+        "alg": "RS256",
+        "kid": "2011-04-29"
+    }"#;
+
+    #[test]
+    fn test_jkt_roundtrip_and_match() {
+        let jwk: PublicJwk = serde_json::from_str(JWK).unwrap();
+        let cnf = Confirmation::jkt(&jwk).unwrap();
+
+        assert_eq!(
+            serde_json::to_value(&cnf).unwrap(),
+            json!({"jkt": "NzbLsXh8uDCcd-6MNwXF4W_7noWXFZAfHkxZsRGC9Xs"})
+        );
+        assert_eq!(
+            serde_json::from_value::<Confirmation>(serde_json::to_value(&cnf).unwrap()).unwrap(),
+            cnf
+        );
+        assert!(cnf.matches_jwk(&jwk));
+        assert!(!cnf.matches_certificate(b"certificate"));
+    }
+
+    #[test]
+    fn test_jwk_matches_by_thumbprint() {
+        let cnf: Confirmation = serde_json::from_str(&format!(r#"{{"jwk": {JWK}}}"#)).unwrap();
+        let jwk: PublicJwk =
+            serde_json::from_str(&JWK.replace(r#""kid": "2011-04-29""#, r#""kid": "other""#))
+                .unwrap();
+
+        assert!(cnf.matches_jwk(&jwk));
+        assert!(!cnf.matches_certificate(b"certificate"));
+    }
+
+    #[test]
+    fn test_x5t_s256() {
+        let cnf = Confirmation::x5t_s256(b"certificate");
+
+        let parsed: Confirmation =
+            serde_json::from_value(json!({"x5t#S256": certificate_thumbprint(b"certificate")}))
+                .unwrap();
+        assert_eq!(parsed, cnf);
+        assert!(cnf.matches_certificate(b"certificate"));
+        assert!(!cnf.matches_certificate(b"other"));
+    }
+
+    #[test]
+    fn test_unsupported_method_is_rejected() {
+        assert!(serde_json::from_value::<Confirmation>(json!({"kid": "key-1"})).is_err());
+    }
+}
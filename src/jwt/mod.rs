@@ -0,0 +1,21 @@
+//! JSON Web Token (JWT) types per RFC 7519.
+//!
+//! This module provides the claims set carried by a JWT, encoding of compact
+//! JWTs with a [`JwsSigner`](crate::signer::JwsSigner), and their validation.
+//! With the `jwe` feature, JWTs can also be nested: signed, then encrypted.
+
+mod claims;
+mod confirmation;
+#[cfg(feature = "jwe")]
+mod nested;
+mod token;
+mod typ;
+mod validator;
+
+pub use claims::{Audience, ClaimsError, JwtClaims};
+pub use confirmation::Confirmation;
+#[cfg(feature = "jwe")]
+pub use nested::{NestedEncodeError, NestedValidateError};
+pub use token::{Jwt, MalformedHeaderError, UntrustedHeader};
+pub use typ::TypPolicy;
+pub use validator::{JwtError, JwtValidator, ValidatedJwt};
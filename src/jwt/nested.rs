@@ -0,0 +1,301 @@
+use serde::Serialize;
+use serde_json::Value;
+use snafu::prelude::*;
+
+use crate::{
+    MaybeSendSync,
+    clock::Clock,
+    jwe::{self, CompactJwe, JweDecrypter, JweEncrypter},
+    jws::Header,
+    jwt::{Jwt, JwtError, JwtValidator, TypPolicy, ValidatedJwt},
+    signer::{self, JwsSigner},
+    verifier::CompactVerifier,
+};
+
+/// Errors returned by [`Jwt::encode_nested`].
+#[derive(Debug, Snafu)]
+#[non_exhaustive]
+pub enum NestedEncodeError<S, E>
+where
+    S: std::error::Error + MaybeSendSync + 'static,
+    E: std::error::Error + MaybeSendSync + 'static,
+{
+    /// The inner JWT could not be signed.
+    #[snafu(display("Failed to sign nested JWT"))]
+    Sign {
+        /// The signing error.
+        source: signer::Error<S>,
+    },
+    /// The signed JWT could not be encrypted.
+    #[snafu(display("Failed to encrypt nested JWT"))]
+    Encrypt {
+        /// The encryption error.
+        source: jwe::Error<E>,
+    },
+}
+
+/// Errors returned by [`JwtValidator::validate_nested`].
+#[derive(Debug, Snafu)]
+#[non_exhaustive]
+pub enum NestedValidateError<D, V>
+where
+    D: std::error::Error + MaybeSendSync + 'static,
+    V: std::error::Error + MaybeSendSync + 'static,
+{
+    /// The JWE could not be decrypted.
+    #[snafu(display("Failed to decrypt nested JWT"))]
+    Decrypt {
+        /// The decryption error.
+        source: jwe::Error<D>,
+    },
+    /// The JWE's `cty` header parameter isn't `JWT`.
+    #[snafu(display("JWE content type {actual:?} is not 'JWT'"))]
+    ContentTypeMismatch {
+        /// The `cty` header parameter.
+        actual: Option<String>,
+    },
+    /// The decrypted content is not a compact JWT.
+    #[snafu(display("Nested JWT is not a compact JWT"))]
+    MalformedContent,
+    /// The inner JWT is invalid.
+    #[snafu(display("Nested JWT is invalid"))]
+    Validate {
+        /// The validation error.
+        source: JwtError<V>,
+    },
+}
+
+impl Jwt {
+    /// Signs the claims, then encrypts the signed JWT to a recipient,
+    /// returning the compact serialization of the nested JWT (RFC 7519
+    /// §5.2).
+    ///
+    /// The inner JWT is signed as by [`Jwt::encode`]. The JWE header sets
+    /// `cty` to `JWT`, and `alg`, `enc` and `kid` from the encrypter.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if signing or encryption fails.
+    pub async fn encode_nested<C, S, E>(
+        claims: &C,
+        signer: &S,
+        header: &Header,
+        encrypter: &E,
+    ) -> Result<String, NestedEncodeError<S::Error, E::Error>>
+    where
+        C: Serialize + ?Sized,
+        S: JwsSigner,
+        E: JweEncrypter,
+    {
+        let jwt = signer.sign_jwt(claims, header).await.context(SignSnafu)?;
+        let jwe = CompactJwe::builder(encrypter, jwt.as_bytes())
+            .cty("JWT")
+            .encrypt()
+            .await
+            .context(EncryptSnafu)?;
+        Ok(jwe.to_string())
+    }
+}
+
+impl<V: CompactVerifier, C: Clock> JwtValidator<V, C> {
+    /// Decrypts a nested JWT, then verifies and validates the inner JWT.
+    ///
+    /// The JWE's `cty` header parameter must be `JWT`, and the inner JWT is
+    /// checked as by [`JwtValidator::validate`], including its `typ`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the JWE can't be decrypted, doesn't contain a JWT,
+    /// or the inner JWT is invalid.
+    pub async fn validate_nested<D: JweDecrypter>(
+        &self,
+        token: &str,
+        decrypter: &D,
+    ) -> Result<ValidatedJwt, NestedValidateError<D::Error, V::Error>> {
+        let jwe = CompactJwe::parse(token)
+            .map_err(jwe::Error::from)
+            .context(DecryptSnafu)?;
+        let plaintext = jwe.decrypt(decrypter).await.context(DecryptSnafu)?;
+        let cty = jwe.header().get("cty").and_then(Value::as_str);
+        ensure!(
+            TypPolicy::JWT.matches(cty),
+            ContentTypeMismatchSnafu {
+                actual: cty.map(str::to_owned),
+            }
+        );
+        let jwt = std::str::from_utf8(&plaintext)
+            .ok()
+            .context(MalformedContentSnafu)?;
+        self.validate(jwt).await.context(ValidateSnafu)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{borrow::Cow, convert::Infallible, time::Duration};
+
+    use secrecy::SecretBox;
+    use serde_json::Map;
+    use web_time::SystemTime;
+
+    use super::*;
+    use crate::{
+        jwe::{ContentKey, content_key_length},
+        jwt::JwtClaims,
+        signer::MockSigner,
+        verifier::JwsVerifier,
+    };
+
+    /// Uses a fixed shared key as the CEK (`dir`).
+    #[derive(Debug, Clone)]
+    struct DirectKey;
+
+    fn cek(enc: &str) -> SecretBox<[u8]> {
+        SecretBox::new(vec![7; content_key_length(enc).unwrap()].into_boxed_slice())
+    }
+
+    impl JweEncrypter for DirectKey {
+        type Error = Infallible;
+
+        fn key_management_algorithm(&self) -> Cow<'_, str> {
+            "dir".into()
+        }
+
+        fn key_id(&self) -> Option<Cow<'_, str>> {
+            None
+        }
+
+        async fn encrypt_key(&self, enc: &str) -> Result<ContentKey, Self::Error> {
+            Ok(ContentKey::new(cek(enc), Vec::new(), "dir", None))
+        }
+    }
+
+    impl JweDecrypter for DirectKey {
+        type Error = Infallible;
+
+        fn key_management_algorithm(&self) -> Cow<'_, str> {
+            "dir".into()
+        }
+
+        fn key_id(&self) -> Option<Cow<'_, str>> {
+            None
+        }
+
+        async fn decrypt_key_unchecked(
+            &self,
+            _header: &Map<String, Value>,
+            _encrypted_key: &[u8],
+            enc: &str,
+        ) -> Result<SecretBox<[u8]>, Self::Error> {
+            Ok(cek(enc))
+        }
+    }
+
+    /// Accepts signatures equal to a fixed value.
+    #[derive(Debug, Clone)]
+    struct MockVerifier;
+
+    impl JwsVerifier for MockVerifier {
+        type Error = Infallible;
+
+        fn jws_algorithm(&self) -> Cow<'_, str> {
+            "ES256".into()
+        }
+
+        fn key_id(&self) -> Option<Cow<'_, str>> {
+            None
+        }
+
+        async fn verify_unchecked(
+            &self,
+            _input: &[u8],
+            signature: &[u8],
+        ) -> Result<bool, Self::Error> {
+            Ok(signature == b"sig")
+        }
+    }
+
+    async fn nested_token() -> String {
+        let claims = JwtClaims::builder()
+            .sub("alice")
+            .exp(SystemTime::now() + Duration::from_secs(300))
+            .build();
+        let signer = MockSigner::builder().signature(b"sig".as_slice()).build();
+        Jwt::encode_nested(&claims, &signer, &Header::default(), &DirectKey)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_round_trip() {
+        let token = nested_token().await;
+
+        let header = Jwt::peek_header(&token).unwrap();
+        let jwt = JwtValidator::builder(MockVerifier)
+            .typ(TypPolicy::JWT)
+            .build()
+            .validate_nested(&token, &DirectKey)
+            .await
+            .unwrap();
+
+        assert_eq!(token.split('.').count(), 5);
+        assert_eq!(header.cty(), Some("JWT"));
+        assert_eq!(header.param("enc").unwrap(), "A128CBC-HS256");
+        assert_eq!(jwt.claims().sub(), Some("alice"));
+    }
+
+    #[tokio::test]
+    async fn test_inner_jwt_is_type_checked() {
+        let result = JwtValidator::builder(MockVerifier)
+            .typ(TypPolicy::ACCESS_TOKEN)
+            .build()
+            .validate_nested(&nested_token().await, &DirectKey)
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(NestedValidateError::Validate {
+                source: JwtError::TypMismatch { .. }
+            })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_content_type_must_be_jwt() {
+        let token = CompactJwe::builder(&DirectKey, b"{}")
+            .cty("json")
+            .encrypt()
+            .await
+            .unwrap()
+            .to_string();
+
+        let result = JwtValidator::builder(MockVerifier)
+            .build()
+            .validate_nested(&token, &DirectKey)
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(NestedValidateError::ContentTypeMismatch { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_tampered_jwe_is_rejected() {
+        let token = nested_token().await;
+        let mut parts: Vec<_> = token.split('.').collect();
+        parts[3] = "AAAA";
+
+        let result = JwtValidator::builder(MockVerifier)
+            .build()
+            .validate_nested(&parts.join("."), &DirectKey)
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(NestedValidateError::Decrypt {
+                source: jwe::Error::ContentEncryption { .. }
+            })
+        ));
+    }
+}
@@ -0,0 +1,202 @@
+use base64::{Engine, prelude::BASE64_URL_SAFE_NO_PAD};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use snafu::prelude::*;
+
+use crate::{
+    jws::Header,
+    jwt::TypPolicy,
+    signer::{Error, JwsSigner},
+};
+
+/// The error returned when a token's header can't be read.
+#[derive(Debug, Clone, PartialEq, Eq, Snafu)]
+#[snafu(display("Malformed JWT header: {reason}"))]
+pub struct MalformedHeaderError {
+    /// What is wrong with the header.
+    pub reason: &'static str,
+}
+
+/// The protected header of a JWT that has **not** been verified.
+///
+/// Anyone can write any header, so the values are only fit for choosing how
+/// to verify the token, such as selecting a key by `kid` or routing by tenant.
+/// They must not be trusted until the token is validated, e.g. with a
+/// [`JwtValidator`](crate::jwt::JwtValidator).
+#[derive(Debug, Clone, PartialEq)]
+pub struct UntrustedHeader {
+    params: Map<String, Value>,
+}
+
+impl UntrustedHeader {
+    /// Returns the unverified `alg` parameter.
+    #[must_use]
+    pub fn alg(&self) -> Option<&str> {
+        self.str_param("alg")
+    }
+
+    /// Returns the unverified `kid` parameter.
+    #[must_use]
+    pub fn kid(&self) -> Option<&str> {
+        self.str_param("kid")
+    }
+
+    /// Returns the unverified `typ` parameter.
+    #[must_use]
+    pub fn typ(&self) -> Option<&str> {
+        self.str_param("typ")
+    }
+
+    /// Returns the unverified `cty` parameter.
+    #[must_use]
+    pub fn cty(&self) -> Option<&str> {
+        self.str_param("cty")
+    }
+
+    /// Returns the unverified parameter with the given name.
+    #[must_use]
+    pub fn param(&self, name: &str) -> Option<&Value> {
+        self.params.get(name)
+    }
+
+    fn str_param(&self, name: &str) -> Option<&str> {
+        self.params.get(name).and_then(Value::as_str)
+    }
+}
+
+/// Compact JWT operations.
+///
+/// Tokens are signed through [`JwsSigner::sign_jwt`]: `alg` and `kid` come
+/// from the signer, and are checked against the key that produces the
+/// signature, so a concurrent key rotation can't yield a token whose header
+/// doesn't match its signature.
+#[derive(Debug)]
+pub struct Jwt {
+    _private: (),
+}
+
+impl Jwt {
+    /// Signs the claims, returning the compact serialization.
+    ///
+    /// The claims are usually a [`JwtClaims`](crate::jwt::JwtClaims), but can
+    /// be any serializable value. The header sets `typ` (which defaults to
+    /// `JWT`), `cty` and any additional parameters.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the claims can't be serialized, the header can't be
+    /// built, the key metadata changed while signing, or the signing
+    /// operation fails.
+    pub async fn encode<C, S>(
+        claims: &C,
+        signer: &S,
+        header: &Header,
+    ) -> Result<String, Error<S::Error>>
+    where
+        C: Serialize + ?Sized,
+        S: JwsSigner,
+    {
+        signer.sign_jwt(claims, header).await
+    }
+
+    /// Signs the claims with an explicit `typ`, returning the compact
+    /// serialization.
+    ///
+    /// The header's `typ` is set from the policy, such as `at+jwt` for
+    /// [`TypPolicy::ACCESS_TOKEN`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the header sets a `typ` that doesn't satisfy the
+    /// policy, or for any reason [`Jwt::encode`] fails.
+    pub async fn encode_typed<C, S>(
+        claims: &C,
+        signer: &S,
+        header: &Header,
+        typ: &TypPolicy,
+    ) -> Result<String, Error<S::Error>>
+    where
+        C: Serialize + ?Sized,
+        S: JwsSigner,
+    {
+        let header = typ
+            .apply(header)
+            .map_err(|source| Error::InvalidHeader { source })?;
+        signer.sign_jwt(claims, &header).await
+    }
+
+    /// Parses the protected header of a compact JWT, **without** verifying
+    /// the token.
+    ///
+    /// The rest of the token isn't inspected, so a successful peek says
+    /// nothing about whether the token is valid.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the header isn't a base64url-encoded JSON object.
+    pub fn peek_header(token: &str) -> Result<UntrustedHeader, MalformedHeaderError> {
+        let (encoded_header, _) = token.split_once('.').context(MalformedHeaderSnafu {
+            reason: "expected a compact JWT",
+        })?;
+        let json =
+            BASE64_URL_SAFE_NO_PAD
+                .decode(encoded_header)
+                .ok()
+                .context(MalformedHeaderSnafu {
+                    reason: "header is not base64url",
+                })?;
+        let params = serde_json::from_slice(&json)
+            .ok()
+            .context(MalformedHeaderSnafu {
+                reason: "header is not a JSON object",
+            })?;
+        Ok(UntrustedHeader { params })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use base64::{Engine, prelude::BASE64_URL_SAFE_NO_PAD};
+    use bytes::Bytes;
+    use serde_json::{Value, json};
+
+    use super::*;
+    use crate::{jwt::JwtClaims, signer::MockSigner};
+
+    fn decode_part(part: &str) -> Value {
+        serde_json::from_slice(&BASE64_URL_SAFE_NO_PAD.decode(part).unwrap()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_encode_compact_jwt() {
+        let signer = MockSigner::builder()
+            .key_id("key-1")
+            .signature(Bytes::from_static(b"sig"))
+            .build();
+        let claims = JwtClaims::builder().sub("client-1").build();
+        let header = Header::builder().typ("at+jwt").build();
+
+        let token = Jwt::encode(&claims, &signer, &header).await.unwrap();
+
+        let parts: Vec<_> = token.split('.').collect();
+        assert_eq!(
+            decode_part(parts[0]),
+            json!({"alg": "ES256", "kid": "key-1", "typ": "at+jwt"})
+        );
+        assert_eq!(decode_part(parts[1]), json!({"sub": "client-1"}));
+        assert_eq!(parts[2], BASE64_URL_SAFE_NO_PAD.encode("sig"));
+        signer.assert_signed(format!("{}.{}", parts[0], parts[1]).as_bytes());
+        let header = Jwt::peek_header(&token).unwrap();
+        assert_eq!(
+            (header.alg(), header.kid(), header.typ()),
+            (Some("ES256"), Some("key-1"), Some("at+jwt"))
+        );
+    }
+
+    #[test]
+    fn test_peek_header_rejects_malformed_tokens() {
+        for token in ["no-dots", "!!.e30.", "WyJhIl0.e30."] {
+            assert!(Jwt::peek_header(token).is_err(), "{token}");
+        }
+    }
+}
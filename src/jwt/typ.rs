@@ -0,0 +1,113 @@
+use std::borrow::Cow;
+
+use crate::jws::{Header, HeaderError};
+
+/// Policy for the `typ` header parameter of a JWT.
+///
+/// Explicit typing (RFC 8725 §3.11) prevents one kind of token being accepted
+/// as another, such as a DPoP proof presented as an access token, when both
+/// are signed by the same key. Values are compared as media types: case
+/// insensitively, with an optional `application/` prefix (RFC 7515 §4.1.9).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TypPolicy {
+    expected: Option<Cow<'static, str>>,
+}
+
+impl TypPolicy {
+    /// Accepts any `typ`, or none.
+    pub const ANY: Self = Self { expected: None };
+    /// Requires `JWT`, for general-purpose tokens.
+    pub const JWT: Self = Self::exact_static("JWT");
+    /// Requires `at+jwt`, for OAuth 2.0 access tokens (RFC 9068).
+    pub const ACCESS_TOKEN: Self = Self::exact_static("at+jwt");
+    /// Requires `dpop+jwt`, for DPoP proofs (RFC 9449).
+    pub const DPOP: Self = Self::exact_static("dpop+jwt");
+    /// Requires `logout+jwt`, for `OpenID` Connect logout tokens.
+    pub const LOGOUT: Self = Self::exact_static("logout+jwt");
+    /// Requires `secevent+jwt`, for security event tokens (RFC 8417).
+    pub const SECURITY_EVENT: Self = Self::exact_static("secevent+jwt");
+
+    const fn exact_static(typ: &'static str) -> Self {
+        Self {
+            expected: Some(Cow::Borrowed(typ)),
+        }
+    }
+
+    /// Requires the given `typ`.
+    pub fn exact(typ: impl Into<Cow<'static, str>>) -> Self {
+        Self {
+            expected: Some(typ.into()),
+        }
+    }
+
+    /// Returns the required `typ`, or `None` if any is accepted.
+    #[must_use]
+    pub fn expected(&self) -> Option<&str> {
+        self.expected.as_deref()
+    }
+
+    /// Returns `true` if the `typ` parameter satisfies the policy.
+    #[must_use]
+    pub fn matches(&self, typ: Option<&str>) -> bool {
+        match (&self.expected, typ) {
+            (None, _) => true,
+            (Some(expected), Some(typ)) => {
+                strip_application(expected).eq_ignore_ascii_case(strip_application(typ))
+            }
+            (Some(_), None) => false,
+        }
+    }
+
+    /// Returns the header with the required `typ` set, for encoding.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the header already sets a different `typ`.
+    pub fn apply(&self, header: &Header) -> Result<Header, HeaderError> {
+        let Some(expected) = &self.expected else {
+            return Ok(header.clone());
+        };
+        if !self.matches(header.typ().or(Some(expected))) {
+            return Err(HeaderError::TypMismatch {
+                expected: expected.to_string(),
+                actual: header.typ().map(str::to_owned),
+            });
+        }
+        Ok(header.clone().or_typ(expected))
+    }
+}
+
+fn strip_application(typ: &str) -> &str {
+    match typ.get(..12) {
+        Some(prefix) if prefix.eq_ignore_ascii_case("application/") => &typ[12..],
+        _ => typ,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches_media_types() {
+        let policy = TypPolicy::ACCESS_TOKEN;
+
+        assert!(policy.matches(Some("at+jwt")));
+        assert!(policy.matches(Some("application/AT+JWT")));
+        assert!(!policy.matches(Some("JWT")));
+        assert!(!policy.matches(None));
+        assert!(TypPolicy::ANY.matches(None));
+    }
+
+    #[test]
+    fn test_apply_sets_typ_or_rejects_mismatch() {
+        let header = TypPolicy::DPOP.apply(&Header::default()).unwrap();
+        assert_eq!(header.typ(), Some("dpop+jwt"));
+
+        let header = Header::builder().typ("JWT").build();
+        assert!(matches!(
+            TypPolicy::DPOP.apply(&header),
+            Err(HeaderError::TypMismatch { .. })
+        ));
+    }
+}
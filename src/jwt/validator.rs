@@ -0,0 +1,453 @@
+use std::time::Duration;
+
+use bon::bon;
+use serde_json::{Map, Value};
+use snafu::prelude::*;
+use web_time::SystemTime;
+
+use crate::{
+    MaybeSendSync,
+    clock::{Clock, SystemClock},
+    jwt::{JwtClaims, TypPolicy},
+    verifier::{CompactVerifier, VerifiedBytes},
+};
+
+/// Errors returned by [`JwtValidator`].
+#[derive(Debug, Snafu)]
+#[non_exhaustive]
+pub enum JwtError<E: std::error::Error + MaybeSendSync + 'static> {
+    /// The signature could not be verified.
+    #[snafu(display("JWT verification failed"))]
+    Verify {
+        /// The error from the verifier.
+        source: E,
+    },
+    /// The payload is not a JSON claims set.
+    #[snafu(display("Malformed JWT claims"))]
+    MalformedClaims {
+        /// The underlying parsing error.
+        source: serde_json::Error,
+    },
+    /// A required claim is missing.
+    #[snafu(display("Missing '{name}' claim"))]
+    MissingClaim {
+        /// The name of the claim.
+        name: &'static str,
+    },
+    /// The JWT has expired (`exp`).
+    #[snafu(display("JWT expired at {exp:?}"))]
+    Expired {
+        /// The `exp` claim.
+        exp: SystemTime,
+    },
+    /// The JWT is not valid yet (`nbf`).
+    #[snafu(display("JWT not valid before {nbf:?}"))]
+    NotYetValid {
+        /// The `nbf` claim.
+        nbf: SystemTime,
+    },
+    /// The JWT was issued in the future (`iat`).
+    #[snafu(display("JWT issued in the future at {iat:?}"))]
+    IssuedInFuture {
+        /// The `iat` claim.
+        iat: SystemTime,
+    },
+    /// The `iss` claim is not the expected issuer.
+    #[snafu(display("Unexpected issuer {actual:?}"))]
+    IssuerMismatch {
+        /// The `iss` claim.
+        actual: Option<String>,
+    },
+    /// The `typ` header parameter doesn't satisfy the [`TypPolicy`].
+    #[snafu(display("JWT type {actual:?} doesn't match required '{expected}'"))]
+    TypMismatch {
+        /// The required `typ`.
+        expected: String,
+        /// The `typ` header parameter.
+        actual: Option<String>,
+    },
+    /// The `aud` claim doesn't include an expected audience.
+    #[snafu(display("JWT audience doesn't match"))]
+    AudienceMismatch,
+}
+
+/// A JWT that passed validation.
+#[derive(Debug, Clone)]
+pub struct ValidatedJwt {
+    verified: VerifiedBytes,
+    claims: JwtClaims,
+}
+
+impl ValidatedJwt {
+    /// Returns the parsed protected header.
+    #[must_use]
+    pub fn header(&self) -> &Map<String, Value> {
+        self.verified.protected_header()
+    }
+
+    /// Returns the claims.
+    #[must_use]
+    pub fn claims(&self) -> &JwtClaims {
+        &self.claims
+    }
+
+    /// Returns the verified JWS, including the key that verified it.
+    #[must_use]
+    pub fn verified(&self) -> &VerifiedBytes {
+        &self.verified
+    }
+
+    /// Consumes this value, returning the claims.
+    #[must_use]
+    pub fn into_claims(self) -> JwtClaims {
+        self.claims
+    }
+}
+
+/// Validates compact JWTs, as a resource server or relying party does before
+/// trusting their claims (RFC 7519 §7.2).
+///
+/// The signature is checked with a [`CompactVerifier`], such as a
+/// [`JwsVerifier`](crate::verifier::JwsVerifier) for a single key or a
+/// [`JwksVerifier`](crate::verifier::JwksVerifier) for an issuer's JWKS. Then:
+///
+/// - `typ` must satisfy the [`TypPolicy`], if configured;
+/// - `exp` must be in the future, and is required unless disabled;
+/// - `nbf` and `iat`, if present, must not be in the future;
+/// - `iss` must be the expected issuer, if configured;
+/// - `aud` must include one of the expected audiences, if configured.
+///
+/// Times are compared with a leeway, to tolerate clock skew between the
+/// issuer and this host. The current time is read from the system clock,
+/// unless another [`Clock`] is set with [`JwtValidator::with_clock`].
+#[derive(Debug, Clone)]
+pub struct JwtValidator<V, C = SystemClock> {
+    verifier: V,
+    issuer: Option<String>,
+    audiences: Vec<String>,
+    leeway: Duration,
+    require_exp: bool,
+    typ: TypPolicy,
+    clock: C,
+}
+
+#[bon]
+impl<V: CompactVerifier> JwtValidator<V> {
+    /// Creates a builder for a validator using the given verifier.
+    #[builder]
+    pub fn new(
+        #[builder(start_fn)] verifier: V,
+        /// The expected `iss` claim. Defaults to accepting any issuer.
+        #[builder(into)]
+        issuer: Option<String>,
+        /// The audiences to accept, one of which must be in the `aud` claim.
+        /// Defaults to accepting any audience.
+        #[builder(default, with = |audiences: impl IntoIterator<Item = impl Into<String>>| {
+            audiences.into_iter().map(Into::into).collect()
+        })]
+        audiences: Vec<String>,
+        /// The clock skew tolerated when checking times. Defaults to 60
+        /// seconds.
+        #[builder(default = Duration::from_secs(60))]
+        leeway: Duration,
+        /// Whether the `exp` claim is required. Defaults to `true`.
+        #[builder(default = true)]
+        require_exp: bool,
+        /// The policy for the `typ` header parameter. Defaults to
+        /// [`TypPolicy::ANY`]; set it whenever the issuer uses explicit
+        /// typing, such as [`TypPolicy::ACCESS_TOKEN`].
+        #[builder(default)]
+        typ: TypPolicy,
+    ) -> Self {
+        Self {
+            verifier,
+            issuer,
+            audiences,
+            leeway,
+            require_exp,
+            typ,
+            clock: SystemClock,
+        }
+    }
+}
+
+impl<V: CompactVerifier, C: Clock> JwtValidator<V, C> {
+    /// Reads the current time from the given clock.
+    pub fn with_clock<C2: Clock>(self, clock: C2) -> JwtValidator<V, C2> {
+        JwtValidator {
+            verifier: self.verifier,
+            issuer: self.issuer,
+            audiences: self.audiences,
+            leeway: self.leeway,
+            require_exp: self.require_exp,
+            typ: self.typ,
+            clock,
+        }
+    }
+
+    /// Returns the verifier.
+    pub fn verifier(&self) -> &V {
+        &self.verifier
+    }
+
+    /// Verifies and validates a compact JWT.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the signature can't be verified, the payload isn't
+    /// a claims set, or a claim is invalid.
+    pub async fn validate(&self, token: &str) -> Result<ValidatedJwt, JwtError<V::Error>> {
+        let verified = self.verifier.verify_jws(token).await.context(VerifySnafu)?;
+        let typ = verified
+            .protected_header()
+            .get("typ")
+            .and_then(Value::as_str);
+        ensure!(
+            self.typ.matches(typ),
+            TypMismatchSnafu {
+                expected: self.typ.expected().unwrap_or_default(),
+                actual: typ.map(str::to_owned),
+            }
+        );
+        let claims: JwtClaims =
+            serde_json::from_slice(verified.payload()).context(MalformedClaimsSnafu)?;
+        self.validate_claims(&claims, self.clock.now())?;
+        Ok(ValidatedJwt { verified, claims })
+    }
+
+    fn validate_claims(
+        &self,
+        claims: &JwtClaims,
+        now: SystemTime,
+    ) -> Result<(), JwtError<V::Error>> {
+        // Times too far in the future to add the leeway to are treated as
+        // unbounded, rather than overflowing.
+        let latest = now.checked_add(self.leeway);
+        match claims.exp() {
+            Some(exp) => ensure!(
+                exp.checked_add(self.leeway)
+                    .is_none_or(|deadline| now < deadline),
+                ExpiredSnafu { exp }
+            ),
+            None => ensure!(!self.require_exp, MissingClaimSnafu { name: "exp" }),
+        }
+        if let Some(nbf) = claims.nbf() {
+            ensure!(
+                latest.is_none_or(|latest| nbf <= latest),
+                NotYetValidSnafu { nbf }
+            );
+        }
+        if let Some(iat) = claims.iat() {
+            ensure!(
+                latest.is_none_or(|latest| iat <= latest),
+                IssuedInFutureSnafu { iat }
+            );
+        }
+        if let Some(issuer) = &self.issuer {
+            ensure!(
+                claims.iss() == Some(issuer.as_str()),
+                IssuerMismatchSnafu {
+                    actual: claims.iss().map(str::to_owned)
+                }
+            );
+        }
+        if !self.audiences.is_empty() {
+            let aud = claims.aud().context(MissingClaimSnafu { name: "aud" })?;
+            ensure!(
+                self.audiences.iter().any(|expected| aud.contains(expected)),
+                AudienceMismatchSnafu
+            );
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{borrow::Cow, convert::Infallible};
+
+    use super::*;
+    use crate::{
+        clock::FixedClock,
+        jws::Header,
+        jwt::Jwt,
+        signer::MockSigner,
+        verifier::{Error, JwsVerifier},
+    };
+
+    /// Accepts signatures equal to a fixed value.
+    #[derive(Debug, Clone)]
+    struct MockVerifier;
+
+    impl JwsVerifier for MockVerifier {
+        type Error = Infallible;
+
+        fn jws_algorithm(&self) -> Cow<'_, str> {
+            "ES256".into()
+        }
+
+        fn key_id(&self) -> Option<Cow<'_, str>> {
+            None
+        }
+
+        async fn verify_unchecked(
+            &self,
+            _input: &[u8],
+            signature: &[u8],
+        ) -> Result<bool, Self::Error> {
+            Ok(signature == b"sig")
+        }
+    }
+
+    async fn token(claims: &JwtClaims, signature: &'static [u8]) -> String {
+        let signer = MockSigner::builder().signature(signature).build();
+        Jwt::encode(claims, &signer, &Header::default())
+            .await
+            .unwrap()
+    }
+
+    fn validator() -> JwtValidator<MockVerifier> {
+        JwtValidator::builder(MockVerifier)
+            .issuer("https://issuer.example")
+            .audiences(["https://api.example"])
+            .build()
+    }
+
+    fn claims() -> JwtClaims {
+        let now = SystemTime::now();
+        JwtClaims::builder()
+            .iss("https://issuer.example")
+            .aud(vec!["https://api.example".to_owned(), "other".to_owned()])
+            .iat(now)
+            .exp(now + Duration::from_secs(300))
+            .build()
+    }
+
+    #[tokio::test]
+    async fn test_valid_jwt() {
+        let claims = claims().with_claim("scope", "read").unwrap();
+
+        let jwt = validator()
+            .validate(&token(&claims, b"sig").await)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            jwt.claims().claim::<String>("scope").unwrap().unwrap(),
+            "read"
+        );
+        assert_eq!(jwt.header()["typ"], "JWT");
+    }
+
+    #[tokio::test]
+    async fn test_typ_policy_is_enforced() {
+        let validator = JwtValidator::builder(MockVerifier)
+            .typ(TypPolicy::ACCESS_TOKEN)
+            .build();
+        let signer = MockSigner::builder().signature(b"sig".as_slice()).build();
+        let access_token = Jwt::encode_typed(
+            &claims(),
+            &signer,
+            &Header::default(),
+            &TypPolicy::ACCESS_TOKEN,
+        )
+        .await
+        .unwrap();
+
+        assert!(validator.validate(&access_token).await.is_ok());
+        assert!(matches!(
+            validator.validate(&token(&claims(), b"sig").await).await,
+            Err(JwtError::TypMismatch { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_invalid_signature_fails() {
+        let result = validator().validate(&token(&claims(), b"bad").await).await;
+
+        assert!(matches!(
+            result,
+            Err(JwtError::Verify {
+                source: Error::InvalidSignature
+            })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_expiry_uses_clock() {
+        let clock = FixedClock::new(SystemTime::now());
+        let validator = validator().with_clock(clock.clone());
+        let token = token(&claims(), b"sig").await;
+
+        clock.advance(Duration::from_secs(360));
+
+        assert!(matches!(
+            validator.validate(&token).await,
+            Err(JwtError::Expired { .. })
+        ));
+    }
+
+    #[test]
+    fn test_claim_checks() {
+        let validator = validator();
+        let now = SystemTime::now();
+
+        let expired = JwtClaims::builder()
+            .exp(now - Duration::from_secs(120))
+            .build();
+        assert!(matches!(
+            validator.validate_claims(&expired, now),
+            Err(JwtError::Expired { .. })
+        ));
+        let skewed = claims();
+        assert!(
+            validator
+                .validate_claims(&skewed, now - Duration::from_secs(30))
+                .is_ok()
+        );
+        assert!(matches!(
+            validator.validate_claims(&skewed, now - Duration::from_secs(120)),
+            Err(JwtError::IssuedInFuture { .. })
+        ));
+        let wrong_issuer = JwtClaims::builder()
+            .iss("https://evil.example")
+            .exp(now + Duration::from_secs(300))
+            .build();
+        assert!(matches!(
+            validator.validate_claims(&wrong_issuer, now),
+            Err(JwtError::IssuerMismatch { .. })
+        ));
+        let wrong_audience = JwtClaims::builder()
+            .iss("https://issuer.example")
+            .aud("other")
+            .exp(now + Duration::from_secs(300))
+            .build();
+        assert!(matches!(
+            validator.validate_claims(&wrong_audience, now),
+            Err(JwtError::AudienceMismatch)
+        ));
+    }
+
+    #[test]
+    fn test_extreme_times_do_not_overflow() {
+        let validator = JwtValidator::builder(MockVerifier).build();
+        let now = SystemTime::now();
+        // The latest time representable on common platforms, so adding the
+        // leeway overflows.
+        let far: JwtClaims = serde_json::from_value(serde_json::json!({
+            "exp": i64::MAX,
+        }))
+        .unwrap();
+        let not_yet: JwtClaims = serde_json::from_value(serde_json::json!({
+            "exp": i64::MAX,
+            "nbf": i64::MAX,
+        }))
+        .unwrap();
+
+        assert!(validator.validate_claims(&far, now).is_ok());
+        assert!(matches!(
+            validator.validate_claims(&not_yet, now),
+            Err(JwtError::NotYetValid { .. })
+        ));
+    }
+}
@@ -0,0 +1,460 @@
+//! Composable decorators for secrets and signers.
+//!
+//! A [`Layer`] wraps a secret or signer in a decorator, such as a timeout or
+//! metrics, so a stack of decorators can be defined once with a
+//! [`LayerBuilder`] and applied to every source and signer alike, e.g.
+//! `EnvVarSecret::string("CLIENT_SECRET").with_layer(&layers)`.
+//!
+//! Layers added to a [`LayerBuilder`] first are outermost. Each layer is
+//! implemented for the kinds of component it can wrap, told apart by the
+//! [`ForSecret`] and [`ForSigner`] markers, which are inferred.
+
+use std::{borrow::Cow, sync::Arc, time::Duration};
+
+use crate::{
+    metrics::MetricsRecorder,
+    secrets::{
+        AuditedSecret, CachedSecret, MetricsSecret, RetrySecret, Secret, SecretAccessAudit,
+        TimeoutSecret,
+    },
+    signer::{AuditSigner, JwsSigner, MetricsSigner, SignAuditHook, TimeoutSigner},
+};
+
+/// Marks a [`Layer`] implementation wrapping a [`Secret`].
+#[derive(Debug)]
+pub enum ForSecret {}
+
+/// Marks a [`Layer`] implementation wrapping a [`JwsSigner`].
+#[derive(Debug)]
+pub enum ForSigner {}
+
+/// Wraps a component (a secret or signer) in a decorator.
+///
+/// `K` is [`ForSecret`] or [`ForSigner`], so a layer can wrap both kinds of
+/// component, even one that implements both traits.
+pub trait Layer<S, K> {
+    /// The wrapped component.
+    type Service;
+
+    /// Wraps the component.
+    fn layer(&self, inner: S) -> Self::Service;
+}
+
+impl<S, K, L: Layer<S, K>> Layer<S, K> for &L {
+    type Service = L::Service;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        (**self).layer(inner)
+    }
+}
+
+/// Applies a [`Layer`] to a component, as a method.
+pub trait WithLayer: Sized {
+    /// Wraps this component with the layer.
+    fn with_layer<L: Layer<Self, K>, K>(self, layer: L) -> L::Service {
+        layer.layer(self)
+    }
+}
+
+impl<T> WithLayer for T {}
+
+/// A layer that returns the component unchanged.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Identity;
+
+impl<S, K> Layer<S, K> for Identity {
+    type Service = S;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        inner
+    }
+}
+
+/// Two layers, with `Outer` wrapping the result of `Inner`.
+#[derive(Debug, Clone)]
+pub struct Stack<Inner, Outer> {
+    inner: Inner,
+    outer: Outer,
+}
+
+impl<Inner, Outer> Stack<Inner, Outer> {
+    /// Combines two layers.
+    pub fn new(inner: Inner, outer: Outer) -> Self {
+        Self { inner, outer }
+    }
+}
+
+impl<S, K, Inner: Layer<S, K>, Outer: Layer<Inner::Service, K>> Layer<S, K>
+    for Stack<Inner, Outer>
+{
+    type Service = Outer::Service;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        self.outer.layer(self.inner.layer(inner))
+    }
+}
+
+/// Builds a stack of layers, applied with the first added outermost.
+#[derive(Debug, Clone)]
+pub struct LayerBuilder<L = Identity> {
+    layers: L,
+}
+
+impl Default for LayerBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LayerBuilder {
+    /// Creates an empty stack.
+    #[must_use]
+    pub fn new() -> Self {
+        Self { layers: Identity }
+    }
+}
+
+impl<L> LayerBuilder<L> {
+    /// Adds a layer, inside the layers added so far.
+    pub fn layer<T>(self, layer: T) -> LayerBuilder<Stack<T, L>> {
+        LayerBuilder {
+            layers: Stack::new(layer, self.layers),
+        }
+    }
+
+    /// Returns the stack as a single layer.
+    pub fn into_layer(self) -> L {
+        self.layers
+    }
+
+    /// Wraps a component with the stack.
+    pub fn service<S, K>(&self, inner: S) -> L::Service
+    where
+        L: Layer<S, K>,
+    {
+        self.layers.layer(inner)
+    }
+}
+
+impl<S, K, L: Layer<S, K>> Layer<S, K> for LayerBuilder<L> {
+    type Service = L::Service;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        self.layers.layer(inner)
+    }
+}
+
+/// Applies a timeout to each secret retrieval ([`TimeoutSecret`]) or signing
+/// operation ([`TimeoutSigner`]).
+#[derive(Debug, Clone, Copy)]
+pub struct TimeoutLayer {
+    timeout: Duration,
+}
+
+impl TimeoutLayer {
+    /// Creates a layer with the given timeout.
+    #[must_use]
+    pub fn new(timeout: Duration) -> Self {
+        Self { timeout }
+    }
+}
+
+impl<S: Secret> Layer<S, ForSecret> for TimeoutLayer {
+    type Service = TimeoutSecret<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        TimeoutSecret::new(inner, self.timeout)
+    }
+}
+
+impl<S: JwsSigner> Layer<S, ForSigner> for TimeoutLayer {
+    type Service = TimeoutSigner<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        TimeoutSigner::new(inner, self.timeout)
+    }
+}
+
+/// Reports outcomes and latency to a [`MetricsRecorder`], with
+/// [`MetricsSecret`] or [`MetricsSigner`].
+pub struct MetricsLayer<R> {
+    recorder: Arc<R>,
+    name: Cow<'static, str>,
+}
+
+impl<R> Clone for MetricsLayer<R> {
+    fn clone(&self) -> Self {
+        Self {
+            recorder: Arc::clone(&self.recorder),
+            name: self.name.clone(),
+        }
+    }
+}
+
+impl<R> std::fmt::Debug for MetricsLayer<R> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MetricsLayer")
+            .field("name", &self.name)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<R: MetricsRecorder> MetricsLayer<R> {
+    /// Creates a layer reporting to the given recorder.
+    pub fn new(recorder: R) -> Self {
+        Self {
+            recorder: Arc::new(recorder),
+            name: Cow::Borrowed("secret"),
+        }
+    }
+
+    /// Sets the name used to label secrets' metrics. Defaults to `secret`.
+    ///
+    /// Signers are labelled with their algorithm and key ID instead.
+    #[must_use]
+    pub fn with_name(mut self, name: impl Into<Cow<'static, str>>) -> Self {
+        self.name = name.into();
+        self
+    }
+}
+
+impl<S: Secret, R: MetricsRecorder> Layer<S, ForSecret> for MetricsLayer<R> {
+    type Service = MetricsSecret<S, Arc<R>>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        MetricsSecret::new(inner, self.name.clone(), Arc::clone(&self.recorder))
+    }
+}
+
+impl<S: JwsSigner, R: MetricsRecorder> Layer<S, ForSigner> for MetricsLayer<R> {
+    type Service = MetricsSigner<S, Arc<R>>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        MetricsSigner::new(inner, Arc::clone(&self.recorder))
+    }
+}
+
+/// Caches secret values with [`CachedSecret`].
+///
+/// Each secret wrapped by the layer gets its own cache.
+#[derive(Debug, Clone, Copy)]
+pub struct CacheLayer {
+    ttl: Duration,
+    max_stale: Duration,
+    jitter: Duration,
+}
+
+impl CacheLayer {
+    /// Creates a layer caching values for the given TTL.
+    #[must_use]
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            max_stale: Duration::ZERO,
+            jitter: Duration::ZERO,
+        }
+    }
+
+    /// See [`CachedSecret::with_max_stale`].
+    #[must_use]
+    pub fn with_max_stale(mut self, max_stale: Duration) -> Self {
+        self.max_stale = max_stale;
+        self
+    }
+
+    /// See [`CachedSecret::with_jitter`].
+    #[must_use]
+    pub fn with_jitter(mut self, jitter: Duration) -> Self {
+        self.jitter = jitter;
+        self
+    }
+}
+
+impl<S: Secret> Layer<S, ForSecret> for CacheLayer
+where
+    S::Output: Clone,
+{
+    type Service = CachedSecret<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        CachedSecret::new(inner, self.ttl)
+            .with_max_stale(self.max_stale)
+            .with_jitter(self.jitter)
+    }
+}
+
+/// Retries failed secret retrievals with [`RetrySecret`].
+///
+/// Every error is retried. To retry only some errors, build a
+/// [`RetrySecret`] directly.
+#[derive(Debug, Clone, Copy)]
+pub struct RetryLayer {
+    max_attempts: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+}
+
+impl Default for RetryLayer {
+    /// The defaults of [`RetrySecret::builder`].
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(5),
+        }
+    }
+}
+
+impl RetryLayer {
+    /// Creates a layer making up to `max_attempts` attempts, including the
+    /// first, with the default backoff.
+    #[must_use]
+    pub fn new(max_attempts: u32) -> Self {
+        Self {
+            max_attempts,
+            ..Self::default()
+        }
+    }
+
+    /// Sets the initial and maximum delays between attempts.
+    #[must_use]
+    pub fn with_backoff(mut self, initial: Duration, max: Duration) -> Self {
+        self.initial_backoff = initial;
+        self.max_backoff = max;
+        self
+    }
+}
+
+impl<S: Secret> Layer<S, ForSecret> for RetryLayer {
+    type Service = RetrySecret<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RetrySecret::builder(inner)
+            .max_attempts(self.max_attempts)
+            .initial_backoff(self.initial_backoff)
+            .max_backoff(self.max_backoff)
+            .build()
+    }
+}
+
+/// Instruments secret retrievals with `tracing` spans, with
+/// [`TracedSecret`](crate::secrets::TracedSecret).
+#[cfg(feature = "tracing")]
+#[derive(Debug, Clone)]
+pub struct TracingLayer {
+    name: Cow<'static, str>,
+}
+
+#[cfg(feature = "tracing")]
+impl TracingLayer {
+    /// Creates a layer naming secrets in spans with the given name.
+    pub fn new(name: impl Into<Cow<'static, str>>) -> Self {
+        Self { name: name.into() }
+    }
+}
+
+#[cfg(feature = "tracing")]
+impl<S: Secret> Layer<S, ForSecret> for TracingLayer {
+    type Service = crate::secrets::TracedSecret<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        crate::secrets::TracedSecret::new(inner, self.name.clone())
+    }
+}
+
+/// Reports secret retrievals to a [`SecretAccessAudit`] hook, with
+/// [`AuditedSecret`], or signing operations to a [`SignAuditHook`], with
+/// [`AuditSigner`].
+///
+/// Each component wrapped by the layer gets a clone of the hook.
+#[derive(Debug, Clone)]
+pub struct AuditLayer<H> {
+    hook: H,
+    name: Cow<'static, str>,
+}
+
+impl<H: Clone> AuditLayer<H> {
+    /// Creates a layer reporting to the given hook.
+    pub fn new(hook: H) -> Self {
+        Self {
+            hook,
+            name: Cow::Borrowed("secret"),
+        }
+    }
+
+    /// Sets the name secrets are reported under. Defaults to `secret`.
+    #[must_use]
+    pub fn with_name(mut self, name: impl Into<Cow<'static, str>>) -> Self {
+        self.name = name.into();
+        self
+    }
+}
+
+impl<S: Secret, H: SecretAccessAudit + Clone> Layer<S, ForSecret> for AuditLayer<H> {
+    type Service = AuditedSecret<S, H>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        AuditedSecret::new(inner, self.name.clone(), self.hook.clone())
+    }
+}
+
+impl<S: JwsSigner, H: SignAuditHook + Clone> Layer<S, ForSigner> for AuditLayer<H> {
+    type Service = AuditSigner<S, H>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        AuditSigner::new(inner, self.hook.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use secrecy::ExposeSecret;
+
+    use super::*;
+    use crate::{
+        metrics::tests::MemoryRecorder,
+        secrets::StaticSecret,
+        signer::{MockSigner, SignEvent},
+    };
+
+    #[tokio::test]
+    async fn test_layers_wrap_secrets_and_signers() {
+        let recorder = Arc::new(MemoryRecorder::default());
+        let layers = LayerBuilder::new()
+            .layer(MetricsLayer::new(Arc::clone(&recorder)))
+            .layer(TimeoutLayer::new(Duration::from_secs(5)));
+
+        let secret: MetricsSecret<TimeoutSecret<_>, _> =
+            StaticSecret::string("s3cret").with_layer(&layers);
+        let signer: MetricsSigner<TimeoutSigner<_>, _> =
+            MockSigner::builder().build().with_layer(&layers);
+
+        assert_eq!(
+            secret.get_secret_value().await.unwrap().expose_secret(),
+            "s3cret"
+        );
+        signer.sign_unchecked(b"input").await.unwrap();
+        assert_eq!(recorder.counters.lock().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_audit_layer_clones_hook() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let hook = {
+            let calls = Arc::clone(&calls);
+            move |_: &SignEvent<'_>| {
+                calls.fetch_add(1, Ordering::SeqCst);
+            }
+        };
+        let layer = AuditLayer::new(hook);
+
+        let first = MockSigner::builder().build().with_layer(&layer);
+        let second = MockSigner::builder().build().with_layer(&layer);
+        first.sign_unchecked(b"a").await.unwrap();
+        second.sign_unchecked(b"b").await.unwrap();
+
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+}
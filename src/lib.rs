@@ -9,12 +9,26 @@
 //! Cryptographic trait definitions for Rust applications, optimized for
 //! OAuth 2.0 and `OpenID` Connect.
 
+pub mod clock;
+pub mod cose;
+#[cfg(feature = "dpop")]
+pub mod dpop;
+pub mod http_sig;
+pub mod jwa;
+pub mod jwe;
 pub mod jwk;
+pub mod jws;
+pub mod jwt;
+pub mod layer;
+pub mod metrics;
 mod platform;
-pub use platform::{MaybeSend, MaybeSendSync, MaybeSync};
+pub use platform::{BoxFuture, DynError, MaybeSend, MaybeSendSync, MaybeSync};
 pub mod prelude;
 pub mod secrets;
 pub mod signer;
+mod timeout;
+pub use timeout::TimeoutError;
+pub mod verifier;
 
 // Re-exports
 pub use bytes::Bytes;
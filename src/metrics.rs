@@ -0,0 +1,107 @@
+//! Metrics hooks for signers and secrets.
+//!
+//! Wrap a signer in [`MetricsSigner`](crate::signer::MetricsSigner) or a
+//! secret in [`MetricsSecret`](crate::secrets::MetricsSecret) to report
+//! operation counts and latencies to a [`MetricsRecorder`], which can forward
+//! them to any metrics backend (e.g. Prometheus).
+
+use std::sync::Arc;
+
+use crate::MaybeSendSync;
+
+/// A metric label, as a name and value pair.
+pub type Label<'a> = (&'static str, &'a str);
+
+/// Counter incremented for every successful signing operation.
+///
+/// Labelled with `alg` and `kid` (empty if the signer has no key ID).
+pub const SIGN_SUCCESS_TOTAL: &str = "chewie_crypto_sign_success_total";
+
+/// Counter incremented for every failed signing operation.
+///
+/// Labelled with `alg` and `kid`.
+pub const SIGN_FAILURE_TOTAL: &str = "chewie_crypto_sign_failure_total";
+
+/// Histogram of signing operation latency, in seconds.
+///
+/// Labelled with `alg` and `kid`.
+pub const SIGN_DURATION_SECONDS: &str = "chewie_crypto_sign_duration_seconds";
+
+/// Counter incremented for every successful secret retrieval.
+///
+/// Labelled with `secret`, the name given to the wrapper.
+pub const SECRET_SUCCESS_TOTAL: &str = "chewie_crypto_secret_success_total";
+
+/// Counter incremented for every failed secret retrieval.
+///
+/// Labelled with `secret`.
+pub const SECRET_FAILURE_TOTAL: &str = "chewie_crypto_secret_failure_total";
+
+/// Histogram of secret retrieval latency, in seconds.
+///
+/// Labelled with `secret`.
+pub const SECRET_DURATION_SECONDS: &str = "chewie_crypto_secret_duration_seconds";
+
+/// Trait for receiving metrics emitted by the metrics wrappers.
+pub trait MetricsRecorder: MaybeSendSync {
+    /// Increments the named counter by one.
+    fn increment_counter(&self, name: &'static str, labels: &[Label<'_>]);
+
+    /// Records a value in the named histogram.
+    fn record_histogram(&self, name: &'static str, value: f64, labels: &[Label<'_>]);
+}
+
+impl<R: MetricsRecorder + ?Sized> MetricsRecorder for Arc<R> {
+    fn increment_counter(&self, name: &'static str, labels: &[Label<'_>]) {
+        (**self).increment_counter(name, labels);
+    }
+
+    fn record_histogram(&self, name: &'static str, value: f64, labels: &[Label<'_>]) {
+        (**self).record_histogram(name, value, labels);
+    }
+}
+
+/// Records the outcome and latency of an operation.
+pub(crate) fn record_outcome(
+    recorder: &impl MetricsRecorder,
+    [success, failure, duration]: [&'static str; 3],
+    is_ok: bool,
+    elapsed: std::time::Duration,
+    labels: &[Label<'_>],
+) {
+    recorder.increment_counter(if is_ok { success } else { failure }, labels);
+    recorder.record_histogram(duration, elapsed.as_secs_f64(), labels);
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::sync::{Mutex, PoisonError};
+
+    use super::*;
+
+    type OwnedLabels = Vec<(&'static str, String)>;
+
+    /// A recorder that keeps every metric in memory.
+    #[derive(Debug, Default)]
+    pub(crate) struct MemoryRecorder {
+        pub(crate) counters: Mutex<Vec<(&'static str, OwnedLabels)>>,
+        pub(crate) histograms: Mutex<Vec<&'static str>>,
+    }
+
+    impl MetricsRecorder for MemoryRecorder {
+        fn increment_counter(&self, name: &'static str, labels: &[Label<'_>]) {
+            let labels = labels.iter().map(|(k, v)| (*k, (*v).to_owned())).collect();
+            self.counters
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .push((name, labels));
+        }
+
+        fn record_histogram(&self, name: &'static str, _value: f64, _labels: &[Label<'_>]) {
+            self.histograms
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .push(name);
+        }
+    }
+}
@@ -38,3 +38,49 @@ impl<T: Sync> MaybeSync for T {}
 pub trait MaybeSync {}
 #[cfg(wasm_browser)]
 impl<T> MaybeSync for T {}
+
+/// A boxed future that may be `Send`, depending on platform.
+#[cfg(any(native, wasm_wasi))]
+pub type BoxFuture<'a, T> = std::pin::Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// A boxed future that may be `Send`, depending on platform.
+#[cfg(wasm_browser)]
+pub type BoxFuture<'a, T> = std::pin::Pin<Box<dyn Future<Output = T> + 'a>>;
+
+#[cfg(any(native, wasm_wasi))]
+type BoxedError = Box<dyn std::error::Error + Send + Sync + 'static>;
+
+#[cfg(wasm_browser)]
+type BoxedError = Box<dyn std::error::Error + 'static>;
+
+/// A type-erased error that may be `Send + Sync`, depending on platform.
+///
+/// Used by the object-safe trait variants, where each implementation's
+/// concrete error type can't be named.
+#[derive(Debug)]
+pub struct DynError(BoxedError);
+
+impl DynError {
+    /// Wraps an error.
+    pub fn new<E: std::error::Error + MaybeSendSync + 'static>(error: E) -> Self {
+        Self(Box::new(error))
+    }
+
+    /// Returns the wrapped error.
+    #[must_use]
+    pub fn into_inner(self) -> BoxedError {
+        self.0
+    }
+}
+
+impl std::fmt::Display for DynError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl std::error::Error for DynError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.0.source()
+    }
+}
@@ -2,3 +2,4 @@
 
 pub use crate::secrets::Secret;
 pub use crate::signer::JwsSigner;
+pub use crate::verifier::JwsVerifier;
@@ -0,0 +1,269 @@
+//! Secret wrapper reporting every retrieval to an audit hook.
+
+use std::{borrow::Cow, sync::Arc, time::Duration};
+
+use web_time::{Instant, SystemTime};
+
+use crate::{
+    MaybeSendSync,
+    secrets::{Secret, SecretWithMetadata},
+};
+
+/// The outcome of an audited secret retrieval.
+#[derive(Debug, Clone, Copy)]
+pub enum AccessOutcome<'a> {
+    /// The value was retrieved.
+    Success,
+    /// The retrieval failed.
+    Failure(&'a (dyn std::error::Error + 'static)),
+}
+
+/// A record of a single secret retrieval, passed to a [`SecretAccessAudit`].
+#[derive(Debug, Clone)]
+pub struct SecretAccessEvent<'a> {
+    name: &'a str,
+    requester: Option<&'a str>,
+    version: Option<&'a str>,
+    accessed_at: SystemTime,
+    outcome: AccessOutcome<'a>,
+    latency: Duration,
+}
+
+impl SecretAccessEvent<'_> {
+    /// Returns the name given to the audited secret.
+    #[must_use]
+    pub fn name(&self) -> &str {
+        self.name
+    }
+
+    /// Returns who requested the secret, if set with
+    /// [`AuditedSecret::for_requester`].
+    #[must_use]
+    pub fn requester(&self) -> Option<&str> {
+        self.requester
+    }
+
+    /// Returns the version retrieved, if the retrieval used
+    /// [`Secret::get_secret_with_metadata`] and the store reports versions.
+    #[must_use]
+    pub fn version(&self) -> Option<&str> {
+        self.version
+    }
+
+    /// Returns when the retrieval started.
+    #[must_use]
+    pub fn accessed_at(&self) -> SystemTime {
+        self.accessed_at
+    }
+
+    /// Returns whether the retrieval succeeded.
+    #[must_use]
+    pub fn outcome(&self) -> AccessOutcome<'_> {
+        self.outcome
+    }
+
+    /// Returns how long the retrieval took.
+    #[must_use]
+    pub fn latency(&self) -> Duration {
+        self.latency
+    }
+}
+
+/// Trait for receiving a [`SecretAccessEvent`] for every retrieval of an
+/// [`AuditedSecret`], e.g. to keep a credential access log for compliance.
+///
+/// The hook is called synchronously once the retrieval completes. Hooks that
+/// write to slow sinks should hand the event off (e.g. over a channel) rather
+/// than block. Closures taking a `&SecretAccessEvent` implement this trait.
+pub trait SecretAccessAudit: MaybeSendSync {
+    /// Records a secret retrieval.
+    fn record(&self, event: &SecretAccessEvent<'_>);
+}
+
+impl<F: Fn(&SecretAccessEvent<'_>) + MaybeSendSync> SecretAccessAudit for F {
+    fn record(&self, event: &SecretAccessEvent<'_>) {
+        self(event);
+    }
+}
+
+/// A secret that reports every retrieval to a [`SecretAccessAudit`] hook.
+///
+/// Secrets have no identity of their own, so the wrapper is given a name.
+/// Each component using the secret can get a handle naming it as the
+/// requester with [`AuditedSecret::for_requester`]. Values are never passed
+/// to the hook. Health checks are not reported. Clones share the same hook.
+pub struct AuditedSecret<S, H> {
+    inner: S,
+    name: Cow<'static, str>,
+    requester: Option<Cow<'static, str>>,
+    hook: Arc<H>,
+}
+
+impl<S: Clone, H> Clone for AuditedSecret<S, H> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            name: self.name.clone(),
+            requester: self.requester.clone(),
+            hook: Arc::clone(&self.hook),
+        }
+    }
+}
+
+impl<S: std::fmt::Debug, H> std::fmt::Debug for AuditedSecret<S, H> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AuditedSecret")
+            .field("inner", &self.inner)
+            .field("name", &self.name)
+            .field("requester", &self.requester)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<S: Secret, H: SecretAccessAudit> AuditedSecret<S, H> {
+    /// Wraps a secret, reporting its retrievals to the given hook under the
+    /// given name.
+    pub fn new(inner: S, name: impl Into<Cow<'static, str>>, hook: H) -> Self {
+        Self {
+            inner,
+            name: name.into(),
+            requester: None,
+            hook: Arc::new(hook),
+        }
+    }
+
+    /// Returns a handle reporting retrievals as requested by the given
+    /// component, such as `token-endpoint`, sharing the same hook.
+    #[must_use]
+    pub fn for_requester(&self, requester: impl Into<Cow<'static, str>>) -> Self
+    where
+        S: Clone,
+    {
+        Self {
+            requester: Some(requester.into()),
+            ..self.clone()
+        }
+    }
+
+    /// Returns the wrapped secret.
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    /// Returns the name given to the secret.
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the audit hook.
+    pub fn hook(&self) -> &H {
+        &self.hook
+    }
+
+    fn record<E: std::error::Error + 'static>(
+        &self,
+        accessed_at: SystemTime,
+        started: Instant,
+        version: Option<&str>,
+        error: Option<&E>,
+    ) {
+        let outcome = match error {
+            None => AccessOutcome::Success,
+            Some(e) => AccessOutcome::Failure(e),
+        };
+        self.hook.record(&SecretAccessEvent {
+            name: &self.name,
+            requester: self.requester.as_deref(),
+            version,
+            accessed_at,
+            outcome,
+            latency: started.elapsed(),
+        });
+    }
+}
+
+impl<S: Secret, H: SecretAccessAudit> Secret for AuditedSecret<S, H> {
+    type Error = S::Error;
+    type Output = S::Output;
+
+    async fn get_secret_value(&self) -> Result<Self::Output, Self::Error> {
+        let (accessed_at, started) = (SystemTime::now(), Instant::now());
+        let result = self.inner.get_secret_value().await;
+        self.record(accessed_at, started, None, result.as_ref().err());
+        result
+    }
+
+    async fn get_secret_with_metadata(
+        &self,
+    ) -> Result<SecretWithMetadata<Self::Output>, Self::Error> {
+        let (accessed_at, started) = (SystemTime::now(), Instant::now());
+        let result = self.inner.get_secret_with_metadata().await;
+        let version = result.as_ref().ok().and_then(SecretWithMetadata::version);
+        self.record(accessed_at, started, version, result.as_ref().err());
+        result
+    }
+
+    async fn health_check(&self) -> Result<(), Self::Error> {
+        self.inner.health_check().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+    use crate::secrets::{FileSecret, StaticSecret};
+
+    type Events = Arc<Mutex<Vec<(String, Option<String>, bool)>>>;
+
+    fn recording_hook() -> (impl SecretAccessAudit + Clone, Events) {
+        let events: Events = Arc::default();
+        let recorded = Arc::clone(&events);
+        let hook = move |event: &SecretAccessEvent<'_>| {
+            let success = matches!(event.outcome(), AccessOutcome::Success);
+            recorded.lock().unwrap().push((
+                event.name().to_owned(),
+                event.requester().map(str::to_owned),
+                success,
+            ));
+        };
+        (hook, events)
+    }
+
+    #[tokio::test]
+    async fn test_retrievals_are_recorded_with_requester() {
+        let (hook, events) = recording_hook();
+        let secret = AuditedSecret::new(StaticSecret::string("s3cret"), "client-secret", hook);
+
+        secret.get_secret_value().await.unwrap();
+        secret
+            .for_requester("token-endpoint")
+            .get_secret_value()
+            .await
+            .unwrap();
+
+        assert_eq!(
+            *events.lock().unwrap(),
+            [
+                ("client-secret".to_owned(), None, true),
+                (
+                    "client-secret".to_owned(),
+                    Some("token-endpoint".to_owned()),
+                    true
+                ),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_failed_retrieval_is_recorded() {
+        let (hook, events) = recording_hook();
+        let secret = AuditedSecret::new(FileSecret::string("/nonexistent/key"), "key", hook);
+
+        assert!(secret.get_secret_value().await.is_err());
+
+        assert!(!events.lock().unwrap()[0].2);
+    }
+}
@@ -0,0 +1,219 @@
+//! Byte access to secret values, and conversions between their shapes.
+
+use std::marker::PhantomData;
+
+use secrecy::{ExposeSecret, SecretBox, SecretString};
+use snafu::prelude::*;
+
+use crate::{
+    MaybeSendSync,
+    secrets::{DecodingError, Secret, SecretWithMetadata},
+};
+
+/// Exposes a secret value as bytes, whether it is text or binary.
+///
+/// This lets wrappers that process key material, such as [`DerivedSecret`],
+/// accept either a `SecretString` or `SecretBox<[u8]>` source.
+///
+/// [`DerivedSecret`]: crate::secrets::DerivedSecret
+pub trait ExposeSecretBytes {
+    /// Returns the secret value as bytes.
+    fn expose_secret_bytes(&self) -> &[u8];
+}
+
+impl ExposeSecretBytes for SecretString {
+    fn expose_secret_bytes(&self) -> &[u8] {
+        self.expose_secret().as_bytes()
+    }
+}
+
+impl ExposeSecretBytes for SecretBox<[u8]> {
+    fn expose_secret_bytes(&self) -> &[u8] {
+        self.expose_secret()
+    }
+}
+
+impl ExposeSecretBytes for SecretBox<Vec<u8>> {
+    fn expose_secret_bytes(&self) -> &[u8] {
+        self.expose_secret()
+    }
+}
+
+/// Creates a secret value from bytes, the inverse of [`ExposeSecretBytes`].
+///
+/// Unlike a [`SecretDecoder`](crate::secrets::SecretDecoder), no
+/// transformation (such as trimming) is applied.
+pub trait FromSecretBytes: Sized {
+    /// Creates the secret value from bytes.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the bytes aren't a valid value, e.g. they aren't
+    /// UTF-8 for a `SecretString`.
+    fn from_secret_bytes(bytes: &[u8]) -> Result<Self, DecodingError>;
+}
+
+impl FromSecretBytes for SecretString {
+    fn from_secret_bytes(bytes: &[u8]) -> Result<Self, DecodingError> {
+        let s =
+            std::str::from_utf8(bytes).map_err(|source| DecodingError::InvalidUtf8 { source })?;
+        Ok(SecretString::from(s))
+    }
+}
+
+impl FromSecretBytes for SecretBox<[u8]> {
+    fn from_secret_bytes(bytes: &[u8]) -> Result<Self, DecodingError> {
+        Ok(SecretBox::new(Box::from(bytes)))
+    }
+}
+
+impl FromSecretBytes for SecretBox<Vec<u8>> {
+    fn from_secret_bytes(bytes: &[u8]) -> Result<Self, DecodingError> {
+        Ok(SecretBox::new(Box::new(bytes.to_vec())))
+    }
+}
+
+/// Converts a secret value into another shape, such as a `SecretString` into
+/// a `SecretBox<[u8]>` or back.
+///
+/// The bytes are copied straight into the new secret, without an exposed
+/// intermediate, and the original value is zeroized when dropped as usual.
+/// Implemented for every [`ExposeSecretBytes`] type.
+pub trait ConvertSecret: ExposeSecretBytes {
+    /// Converts the secret value.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the bytes aren't a valid value of the target shape,
+    /// e.g. they aren't UTF-8 for a `SecretString`.
+    fn convert_secret<T: FromSecretBytes>(&self) -> Result<T, DecodingError> {
+        T::from_secret_bytes(self.expose_secret_bytes())
+    }
+}
+
+impl<T: ExposeSecretBytes + ?Sized> ConvertSecret for T {}
+
+/// Errors returned by [`ConvertedSecret`].
+#[derive(Debug, Snafu)]
+pub enum ConvertError<E: std::error::Error + MaybeSendSync + 'static> {
+    /// The secret could not be retrieved.
+    #[snafu(display("Failed to retrieve secret"))]
+    Inner {
+        /// The error from the wrapped secret.
+        source: E,
+    },
+    /// The secret could not be converted.
+    #[snafu(display("Failed to convert secret"))]
+    Convert {
+        /// The underlying conversion error.
+        source: DecodingError,
+    },
+}
+
+/// A secret whose values are converted to another shape with
+/// [`ConvertSecret`], e.g. to pass a `SecretString` source to something
+/// expecting `SecretBox<[u8]>`.
+pub struct ConvertedSecret<S, T> {
+    inner: S,
+    _output: PhantomData<fn() -> T>,
+}
+
+impl<S: Clone, T> Clone for ConvertedSecret<S, T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            _output: PhantomData,
+        }
+    }
+}
+
+impl<S: std::fmt::Debug, T> std::fmt::Debug for ConvertedSecret<S, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ConvertedSecret")
+            .field("inner", &self.inner)
+            .finish()
+    }
+}
+
+impl<S: Secret, T> ConvertedSecret<S, T>
+where
+    S::Output: ExposeSecretBytes,
+    T: FromSecretBytes + MaybeSendSync,
+{
+    /// Wraps a secret, converting its values.
+    pub fn new(inner: S) -> Self {
+        Self {
+            inner,
+            _output: PhantomData,
+        }
+    }
+
+    /// Returns the wrapped secret.
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+}
+
+impl<S: Secret, T> Secret for ConvertedSecret<S, T>
+where
+    S::Output: ExposeSecretBytes,
+    T: FromSecretBytes + MaybeSendSync,
+{
+    type Error = ConvertError<S::Error>;
+    type Output = T;
+
+    async fn get_secret_value(&self) -> Result<Self::Output, Self::Error> {
+        let value = self.inner.get_secret_value().await.context(InnerSnafu)?;
+        value.convert_secret().context(ConvertSnafu)
+    }
+
+    async fn get_secret_with_metadata(
+        &self,
+    ) -> Result<SecretWithMetadata<Self::Output>, Self::Error> {
+        let secret = self
+            .inner
+            .get_secret_with_metadata()
+            .await
+            .context(InnerSnafu)?;
+        let value = secret.value().convert_secret().context(ConvertSnafu)?;
+        Ok(secret.map(|_| value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::secrets::StaticSecret;
+
+    #[test]
+    fn test_convert_round_trip() {
+        let string = SecretString::from("s3cret");
+
+        let bytes: SecretBox<[u8]> = string.convert_secret().unwrap();
+        let back: SecretString = bytes.convert_secret().unwrap();
+
+        assert_eq!(bytes.expose_secret(), b"s3cret");
+        assert_eq!(back.expose_secret(), "s3cret");
+    }
+
+    #[tokio::test]
+    async fn test_converted_secret_rejects_invalid_utf8() {
+        let valid = ConvertedSecret::<_, SecretString>::new(StaticSecret::new(
+            SecretBox::<[u8]>::from(b"ok".to_vec()),
+        ));
+        let invalid = ConvertedSecret::<_, SecretString>::new(StaticSecret::new(
+            SecretBox::<[u8]>::from(vec![0xff, 0xfe]),
+        ));
+
+        assert_eq!(
+            valid.get_secret_value().await.unwrap().expose_secret(),
+            "ok"
+        );
+        assert!(matches!(
+            invalid.get_secret_value().await,
+            Err(ConvertError::Convert {
+                source: DecodingError::InvalidUtf8 { .. }
+            })
+        ));
+    }
+}
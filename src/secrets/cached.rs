@@ -0,0 +1,300 @@
+//! Secret wrapper caching the retrieved value for a TTL.
+
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    sync::{Arc, Mutex, PoisonError},
+    time::Duration,
+};
+
+use web_time::Instant;
+
+use crate::secrets::Secret;
+
+#[derive(Debug, Clone)]
+struct CachedValue<T> {
+    value: T,
+    /// When the value expires, or `None` if it never does.
+    fresh_until: Option<Instant>,
+    /// When the value stops being served stale, or `None` if it never does.
+    stale_until: Option<Instant>,
+}
+
+#[derive(Debug)]
+struct Cache<T> {
+    value: Mutex<Option<CachedValue<T>>>,
+    /// Held while fetching, so only one caller fetches at a time.
+    refresh: tokio::sync::Mutex<()>,
+}
+
+/// A secret that caches the retrieved value, serving it without re-fetching
+/// until the TTL elapses.
+///
+/// Concurrent callers that miss the cache wait for a single fetch, rather than
+/// each fetching from the source. Failed fetches aren't cached. Clones share
+/// the same cache.
+///
+/// With [`CachedSecret::with_max_stale`], an expired value keeps being served
+/// for up to the max-stale duration while it's revalidated: only one caller
+/// fetches, while concurrent callers get the stale value, and if the fetch
+/// fails, the stale value is returned instead of the error. With
+/// [`CachedSecret::with_jitter`], each value's TTL is shortened by a random
+/// amount, so replicas that started together don't refresh in lockstep.
+#[derive(Debug)]
+pub struct CachedSecret<S: Secret> {
+    inner: S,
+    ttl: Duration,
+    max_stale: Duration,
+    jitter: Duration,
+    cache: Arc<Cache<S::Output>>,
+}
+
+impl<S: Secret> Clone for CachedSecret<S> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            ttl: self.ttl,
+            max_stale: self.max_stale,
+            jitter: self.jitter,
+            cache: Arc::clone(&self.cache),
+        }
+    }
+}
+
+/// Returns a TTL shortened by a random duration of up to `jitter`.
+pub(crate) fn jittered(ttl: Duration, jitter: Duration) -> Duration {
+    let jitter = jitter.min(ttl);
+    if jitter.is_zero() {
+        return ttl;
+    }
+    // `RandomState` is randomly seeded, which is sufficient for jitter.
+    let random = RandomState::new().build_hasher().finish();
+    let fraction = u32::try_from(random >> 32).unwrap_or(u32::MAX);
+    let scale = f64::from(fraction) / f64::from(u32::MAX);
+    // Rounding can take the product of a huge jitter past `Duration::MAX`.
+    let jitter = Duration::try_from_secs_f64(jitter.as_secs_f64() * scale)
+        .map_or(jitter, |scaled| scaled.min(jitter));
+    ttl.saturating_sub(jitter)
+}
+
+impl<S: Secret> CachedSecret<S>
+where
+    S::Output: Clone,
+{
+    /// Wraps a secret, caching its value for the given TTL.
+    pub fn new(inner: S, ttl: Duration) -> Self {
+        Self {
+            inner,
+            ttl,
+            max_stale: Duration::ZERO,
+            jitter: Duration::ZERO,
+            cache: Arc::new(Cache {
+                value: Mutex::new(None),
+                refresh: tokio::sync::Mutex::new(()),
+            }),
+        }
+    }
+
+    /// Serves expired values for up to `max_stale` while they're revalidated,
+    /// or while the source is failing.
+    #[must_use]
+    pub fn with_max_stale(mut self, max_stale: Duration) -> Self {
+        self.max_stale = max_stale;
+        self
+    }
+
+    /// Shortens each value's TTL by a random duration of up to `jitter`.
+    #[must_use]
+    pub fn with_jitter(mut self, jitter: Duration) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Returns the wrapped secret.
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    /// Returns the TTL of cached values.
+    #[must_use]
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    /// Returns how long expired values may be served while revalidating.
+    #[must_use]
+    pub fn max_stale(&self) -> Duration {
+        self.max_stale
+    }
+
+    /// Discards the cached value, so the next retrieval fetches from the source.
+    pub fn invalidate(&self) {
+        *self.lock_value() = None;
+    }
+
+    fn lock_value(&self) -> std::sync::MutexGuard<'_, Option<CachedValue<S::Output>>> {
+        self.cache
+            .value
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    async fn fetch(&self) -> Result<S::Output, S::Error> {
+        let value = self.inner.get_secret_value().await?;
+        // Durations too long to add to the current time are treated as
+        // unbounded, rather than overflowing.
+        let fresh_until = Instant::now().checked_add(jittered(self.ttl, self.jitter));
+        *self.lock_value() = Some(CachedValue {
+            value: value.clone(),
+            fresh_until,
+            stale_until: fresh_until
+                .and_then(|fresh_until| fresh_until.checked_add(self.max_stale)),
+        });
+        Ok(value)
+    }
+}
+
+/// Records whether a retrieval was served from the cache on the current span,
+/// e.g. a [`TracedSecret`](crate::secrets::TracedSecret)'s.
+#[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+fn record_cache(outcome: &'static str) {
+    #[cfg(feature = "tracing")]
+    tracing::Span::current().record("secret.cache", outcome);
+}
+
+impl<S: Secret> Secret for CachedSecret<S>
+where
+    S::Output: Clone,
+{
+    type Error = S::Error;
+    type Output = S::Output;
+
+    async fn get_secret_value(&self) -> Result<Self::Output, Self::Error> {
+        let cached = self.lock_value().clone();
+        let now = Instant::now();
+        if let Some(cached) = cached {
+            if cached.fresh_until.is_none_or(|until| now < until) {
+                record_cache("hit");
+                return Ok(cached.value);
+            }
+            if cached.stale_until.is_none_or(|until| now < until) {
+                record_cache("stale");
+                // Stale-while-revalidate: only one caller refreshes.
+                let Ok(_refreshing) = self.cache.refresh.try_lock() else {
+                    return Ok(cached.value);
+                };
+                return Ok(self.fetch().await.unwrap_or(cached.value));
+            }
+        }
+
+        let _refreshing = self.cache.refresh.lock().await;
+        // Another caller may have fetched while we waited.
+        if let Some(cached) = &*self.lock_value()
+            && cached
+                .fresh_until
+                .is_none_or(|until| Instant::now() < until)
+        {
+            record_cache("hit");
+            return Ok(cached.value.clone());
+        }
+        record_cache("miss");
+        self.fetch().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use snafu::Snafu;
+
+    use super::*;
+
+    #[derive(Debug, Snafu)]
+    #[snafu(display("Backend unavailable"))]
+    struct Unavailable;
+
+    /// Returns an incrementing count, failing after `fail_after` successes.
+    #[derive(Debug, Clone)]
+    struct CountingSecret {
+        count: Arc<AtomicUsize>,
+        fail_after: usize,
+    }
+
+    impl CountingSecret {
+        fn new(fail_after: usize) -> Self {
+            Self {
+                count: Arc::default(),
+                fail_after,
+            }
+        }
+    }
+
+    impl Secret for CountingSecret {
+        type Error = Unavailable;
+        type Output = usize;
+
+        async fn get_secret_value(&self) -> Result<Self::Output, Self::Error> {
+            let count = self.count.fetch_add(1, Ordering::SeqCst) + 1;
+            if count > self.fail_after {
+                return Err(Unavailable);
+            }
+            Ok(count)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_value_is_cached_until_invalidated() {
+        let secret = CachedSecret::new(CountingSecret::new(usize::MAX), Duration::from_secs(30));
+
+        assert_eq!(secret.get_secret_value().await.unwrap(), 1);
+        assert_eq!(secret.clone().get_secret_value().await.unwrap(), 1);
+        secret.invalidate();
+        assert_eq!(secret.get_secret_value().await.unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_expired_value_is_refetched() {
+        let secret = CachedSecret::new(CountingSecret::new(usize::MAX), Duration::ZERO);
+
+        assert_eq!(secret.get_secret_value().await.unwrap(), 1);
+        assert_eq!(secret.get_secret_value().await.unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_stale_value_is_served_when_source_fails() {
+        let strict = CachedSecret::new(CountingSecret::new(1), Duration::ZERO);
+        let lenient = CachedSecret::new(CountingSecret::new(1), Duration::ZERO)
+            .with_max_stale(Duration::from_secs(30));
+
+        strict.get_secret_value().await.unwrap();
+        lenient.get_secret_value().await.unwrap();
+
+        assert!(strict.get_secret_value().await.is_err());
+        assert_eq!(lenient.get_secret_value().await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_unbounded_durations_do_not_overflow() {
+        let cached = CachedSecret::new(CountingSecret::new(usize::MAX), Duration::MAX);
+        let stale =
+            CachedSecret::new(CountingSecret::new(1), Duration::ZERO).with_max_stale(Duration::MAX);
+
+        assert_eq!(cached.get_secret_value().await.unwrap(), 1);
+        assert_eq!(cached.get_secret_value().await.unwrap(), 1);
+        assert_eq!(stale.get_secret_value().await.unwrap(), 1);
+        assert_eq!(stale.get_secret_value().await.unwrap(), 1);
+    }
+
+    #[test]
+    fn test_jitter_shortens_ttl_within_bound() {
+        let ttl = Duration::from_secs(30);
+        let jitter = Duration::from_secs(10);
+
+        for _ in 0..100 {
+            let jittered = jittered(ttl, jitter);
+            assert!(jittered <= ttl && jittered >= ttl.saturating_sub(jitter));
+        }
+        assert_eq!(jittered(ttl, Duration::ZERO), ttl);
+    }
+}
@@ -0,0 +1,363 @@
+//! Identity tokens from cloud instance metadata services.
+
+use std::sync::Arc;
+
+use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
+use secrecy::{ExposeSecret, SecretString};
+use serde::Deserialize;
+use snafu::prelude::*;
+use tokio::sync::Mutex;
+use web_time::{Duration, Instant, SystemTime};
+
+use crate::{
+    DynError,
+    secrets::{
+        Secret, SecretAccessError, SecretWithMetadata, providers::RemoteErrorKind,
+        versioned::unix_time,
+    },
+};
+
+/// The link-local address of the AWS and Azure metadata services.
+const LINK_LOCAL_ENDPOINT: &str = "http://169.254.169.254";
+
+/// The address of the GCP metadata server.
+const GCP_ENDPOINT: &str = "http://metadata.google.internal";
+
+/// How long before expiry a token is refreshed, by default.
+const DEFAULT_REFRESH_SKEW: Duration = Duration::from_secs(300);
+
+/// How long tokens without an expiry are cached.
+const DEFAULT_LIFETIME: Duration = Duration::from_secs(300);
+
+/// The lifetime of `IMDSv2` session tokens, in seconds.
+const IMDS_SESSION_TTL: &str = "300";
+
+#[derive(Debug, Snafu)]
+enum MetadataError {
+    #[snafu(display("Request to the metadata service failed"))]
+    Request { source: reqwest::Error },
+    #[snafu(display("The metadata service returned HTTP {status}"))]
+    Status { status: u16 },
+}
+
+impl MetadataError {
+    fn kind(&self) -> RemoteErrorKind {
+        match self {
+            Self::Request { source } if source.is_timeout() || source.is_connect() => {
+                RemoteErrorKind::Unavailable
+            }
+            Self::Status { status: 404 } => RemoteErrorKind::NotFound,
+            Self::Status {
+                status: 401 | 403, ..
+            } => RemoteErrorKind::PermissionDenied,
+            Self::Status {
+                status: 429 | 500..=599,
+            } => RemoteErrorKind::Unavailable,
+            Self::Request { .. } | Self::Status { .. } => RemoteErrorKind::Other,
+        }
+    }
+}
+
+/// The metadata service an identity token is obtained from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum CloudProvider {
+    /// A Google-signed OIDC ID token for the instance's default service
+    /// account, from the GCP metadata server.
+    Gcp {
+        /// The audience (`aud` claim) of the token.
+        audience: String,
+    },
+    /// The PKCS #7 signed instance identity document, from the AWS instance
+    /// metadata service (`IMDSv2`).
+    Aws,
+    /// An OAuth access token for the VM's managed identity, from the Azure
+    /// instance metadata service.
+    Azure {
+        /// The resource the token is for, such as `api://my-app`.
+        resource: String,
+        /// The client ID of a user-assigned managed identity, or `None` for
+        /// the system-assigned identity.
+        client_id: Option<String>,
+    },
+}
+
+impl CloudProvider {
+    fn default_endpoint(&self) -> &'static str {
+        match self {
+            Self::Gcp { .. } => GCP_ENDPOINT,
+            Self::Aws | Self::Azure { .. } => LINK_LOCAL_ENDPOINT,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct AzureToken {
+    access_token: String,
+    /// Seconds since the epoch, as a string.
+    expires_on: String,
+}
+
+#[derive(Debug)]
+struct CachedToken {
+    token: SecretString,
+    expires_at: Option<SystemTime>,
+    refresh_at: Instant,
+}
+
+/// Obtains short-lived identity tokens for the workload's ambient cloud
+/// identity from the instance metadata service, e.g. to use as a client
+/// assertion in an OAuth client credentials flow.
+///
+/// Tokens are cached, and fetched again shortly before they expire (five
+/// minutes by default, or halfway through shorter lifetimes). Tokens without
+/// a known expiry are cached for five minutes. Clones share the same cached
+/// token.
+#[derive(Debug, Clone)]
+pub struct CloudIdentityToken {
+    http: reqwest::Client,
+    provider: CloudProvider,
+    endpoint: String,
+    refresh_skew: Duration,
+    token: Arc<Mutex<Option<CachedToken>>>,
+}
+
+impl CloudIdentityToken {
+    /// Obtains tokens from a provider's metadata service.
+    #[must_use]
+    pub fn new(provider: CloudProvider) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            endpoint: provider.default_endpoint().to_owned(),
+            provider,
+            refresh_skew: DEFAULT_REFRESH_SKEW,
+            token: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Obtains GCP ID tokens for the given audience.
+    pub fn gcp(audience: impl Into<String>) -> Self {
+        Self::new(CloudProvider::Gcp {
+            audience: audience.into(),
+        })
+    }
+
+    /// Obtains AWS signed instance identity documents.
+    #[must_use]
+    pub fn aws() -> Self {
+        Self::new(CloudProvider::Aws)
+    }
+
+    /// Obtains Azure managed identity tokens for the given resource, with the
+    /// system-assigned identity.
+    pub fn azure(resource: impl Into<String>) -> Self {
+        Self::new(CloudProvider::Azure {
+            resource: resource.into(),
+            client_id: None,
+        })
+    }
+
+    /// Uses a different metadata service address, such as an emulator.
+    #[must_use]
+    pub fn with_endpoint(mut self, endpoint: impl Into<String>) -> Self {
+        self.endpoint = endpoint.into();
+        self.endpoint
+            .truncate(self.endpoint.trim_end_matches('/').len());
+        self
+    }
+
+    /// Uses the given HTTP client, e.g. configured with timeouts.
+    #[must_use]
+    pub fn with_http_client(mut self, http: reqwest::Client) -> Self {
+        self.http = http;
+        self
+    }
+
+    /// Refreshes tokens this long before they expire.
+    #[must_use]
+    pub fn with_refresh_skew(mut self, refresh_skew: Duration) -> Self {
+        self.refresh_skew = refresh_skew;
+        self
+    }
+
+    /// Returns the provider tokens are obtained from.
+    #[must_use]
+    pub fn provider(&self) -> &CloudProvider {
+        &self.provider
+    }
+
+    async fn send(request: reqwest::RequestBuilder) -> Result<reqwest::Response, MetadataError> {
+        let response = request.send().await.context(RequestSnafu)?;
+        let status = response.status();
+        ensure!(
+            status.is_success(),
+            StatusSnafu {
+                status: status.as_u16()
+            }
+        );
+        Ok(response)
+    }
+
+    async fn text(request: reqwest::RequestBuilder) -> Result<String, MetadataError> {
+        Self::send(request)
+            .await?
+            .text()
+            .await
+            .context(RequestSnafu)
+    }
+
+    /// Fetches a token, with its expiry if known.
+    async fn fetch(&self) -> Result<(SecretString, Option<SystemTime>), MetadataError> {
+        let http = &self.http;
+        let endpoint = &self.endpoint;
+        match &self.provider {
+            CloudProvider::Gcp { audience } => {
+                let request = http
+                    .get(format!(
+                        "{endpoint}/computeMetadata/v1/instance/service-accounts/default/identity"
+                    ))
+                    .query(&[("audience", audience.as_str()), ("format", "full")])
+                    .header("Metadata-Flavor", "Google");
+                let token = Self::text(request).await?;
+                let expires_at = jwt_expiry(&token);
+                Ok((SecretString::from(token), expires_at))
+            }
+            CloudProvider::Aws => {
+                let session = Self::text(
+                    http.put(format!("{endpoint}/latest/api/token"))
+                        .header("X-aws-ec2-metadata-token-ttl-seconds", IMDS_SESSION_TTL),
+                )
+                .await
+                .map(SecretString::from)?;
+                let request = http
+                    .get(format!("{endpoint}/latest/dynamic/instance-identity/pkcs7"))
+                    .header("X-aws-ec2-metadata-token", session.expose_secret());
+                let document = Self::text(request).await?;
+                Ok((SecretString::from(document), None))
+            }
+            CloudProvider::Azure {
+                resource,
+                client_id,
+            } => {
+                let mut query = vec![("api-version", "2018-02-01"), ("resource", resource)];
+                if let Some(client_id) = client_id {
+                    query.push(("client_id", client_id));
+                }
+                let request = http
+                    .get(format!("{endpoint}/metadata/identity/oauth2/token"))
+                    .query(&query)
+                    .header("Metadata", "true");
+                let token: AzureToken = Self::send(request)
+                    .await?
+                    .json()
+                    .await
+                    .context(RequestSnafu)?;
+                let expires_at = token
+                    .expires_on
+                    .parse()
+                    .ok()
+                    .and_then(|seconds| unix_time(seconds, 0));
+                Ok((SecretString::from(token.access_token), expires_at))
+            }
+        }
+    }
+
+    /// Returns when a token expiring at `expires_at` should be refreshed.
+    fn refresh_at(&self, expires_at: Option<SystemTime>) -> Instant {
+        let lifetime = expires_at.map_or(DEFAULT_LIFETIME, |expires_at| {
+            expires_at
+                .duration_since(SystemTime::now())
+                .unwrap_or_default()
+        });
+        let skew = self.refresh_skew.min(lifetime / 2);
+        Instant::now() + lifetime.saturating_sub(skew)
+    }
+
+    fn remote_error(&self, error: MetadataError) -> SecretAccessError {
+        SecretAccessError::Remote {
+            name: format!("{:?}", self.provider),
+            kind: error.kind(),
+            source: DynError::new(error),
+        }
+    }
+}
+
+/// Returns the expiry (`exp` claim) of a JWT, without verifying it.
+fn jwt_expiry(token: &str) -> Option<SystemTime> {
+    let payload = token.split('.').nth(1)?;
+    let payload = URL_SAFE_NO_PAD.decode(payload).ok()?;
+    let claims: serde_json::Value = serde_json::from_slice(&payload).ok()?;
+    unix_time(claims.get("exp")?.as_i64()?, 0)
+}
+
+impl Secret for CloudIdentityToken {
+    type Output = SecretString;
+    type Error = SecretAccessError;
+
+    async fn get_secret_value(&self) -> Result<SecretString, Self::Error> {
+        self.get_secret_with_metadata()
+            .await
+            .map(SecretWithMetadata::into_value)
+    }
+
+    /// The expiry is the token's, if known.
+    async fn get_secret_with_metadata(
+        &self,
+    ) -> Result<SecretWithMetadata<SecretString>, Self::Error> {
+        let mut cached = self.token.lock().await;
+        let token = match &*cached {
+            Some(token) if Instant::now() < token.refresh_at => token,
+            _ => {
+                let (token, expires_at) = self.fetch().await.map_err(|e| self.remote_error(e))?;
+                cached.insert(CachedToken {
+                    token,
+                    expires_at,
+                    refresh_at: self.refresh_at(expires_at),
+                })
+            }
+        };
+        let secret = SecretWithMetadata::new(token.token.clone());
+        Ok(match token.expires_at {
+            Some(expires_at) => secret.with_expires_at(expires_at),
+            None => secret,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::secrets::test_server::serve;
+
+    #[tokio::test]
+    async fn test_azure_token_is_cached_until_expiry() {
+        let (address, server) = serve(
+            vec![(
+                200,
+                r#"{"access_token":"t1","expires_on":"4102444800","resource":"api://app"}"#,
+            )],
+            &[],
+        );
+        let token = CloudIdentityToken::azure("api://app").with_endpoint(address);
+
+        let first = token.get_secret_with_metadata().await.unwrap();
+        let second = token.get_secret_value().await.unwrap();
+
+        assert_eq!(first.value().expose_secret(), "t1");
+        assert_eq!(first.expires_at(), unix_time(4_102_444_800, 0));
+        assert_eq!(second.expose_secret(), "t1");
+        let requests = server.join().unwrap();
+        assert!(requests[0].starts_with(
+            "GET /metadata/identity/oauth2/token?api-version=2018-02-01&resource=api%3A%2F%2Fapp "
+        ));
+    }
+
+    #[test]
+    fn test_jwt_expiry() {
+        // {"alg":"RS256"}.{"aud":"x","exp":1700000000}
+        let token = "eyJhbGciOiJSUzI1NiJ9.eyJhdWQiOiJ4IiwiZXhwIjoxNzAwMDAwMDAwfQ.c2ln";
+
+        assert_eq!(jwt_expiry(token), unix_time(1_700_000_000, 0));
+        assert_eq!(jwt_expiry("not-a-jwt"), None);
+    }
+}
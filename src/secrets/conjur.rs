@@ -0,0 +1,448 @@
+//! Conjur secret provider.
+
+use std::sync::Arc;
+
+use bon::bon;
+use secrecy::{ExposeSecret, SecretString, zeroize::Zeroizing};
+use snafu::prelude::*;
+use tokio::sync::Mutex;
+use web_time::{Duration, Instant};
+
+use crate::{
+    DynError,
+    secrets::{
+        Secret, SecretAccessError, SecretVersion, SecretWithMetadata, VersionedSecret,
+        encodings::{SecretDecoder, StringEncoding},
+        providers::RemoteErrorKind,
+    },
+};
+
+/// How long a Conjur access token is reused. Tokens are valid for eight
+/// minutes, so this leaves time for the request using it.
+const TOKEN_REUSE: Duration = Duration::from_secs(360);
+
+#[derive(Debug, Snafu)]
+enum ConjurError {
+    #[snafu(display("Request to Conjur failed"))]
+    Request { source: reqwest::Error },
+    #[snafu(display("Conjur returned HTTP {status}"))]
+    Api { status: u16 },
+    #[snafu(display("Invalid Conjur URL"))]
+    InvalidUrl,
+    #[snafu(display("Failed to retrieve the JWT to authenticate with"))]
+    Jwt { source: DynError },
+}
+
+impl ConjurError {
+    fn kind(&self) -> RemoteErrorKind {
+        match self {
+            Self::Request { source } if source.is_timeout() || source.is_connect() => {
+                RemoteErrorKind::Unavailable
+            }
+            Self::Api { status: 404 } => RemoteErrorKind::NotFound,
+            Self::Api {
+                status: 401 | 403, ..
+            } => RemoteErrorKind::PermissionDenied,
+            Self::Api {
+                status: 429 | 500..=599,
+            } => RemoteErrorKind::Unavailable,
+            Self::Request { .. } | Self::Api { .. } | Self::InvalidUrl | Self::Jwt { .. } => {
+                RemoteErrorKind::Other
+            }
+        }
+    }
+}
+
+/// How a [`ConjurClient`] authenticates to Conjur.
+#[derive(Clone)]
+pub enum ConjurAuth {
+    /// The `authn` authenticator, with a host or user's API key.
+    ApiKey {
+        /// The login, such as `host/my-app`.
+        login: String,
+        /// The API key.
+        api_key: SecretString,
+    },
+    /// The `authn-jwt` authenticator, with a JWT from another source, such
+    /// as a Kubernetes service account token or a cloud identity token.
+    Jwt {
+        /// The ID of the authenticator instance.
+        service_id: String,
+        /// The host to authenticate as, or `None` if the authenticator
+        /// identifies it from the token's claims.
+        host_id: Option<String>,
+        /// The source of the JWT, retrieved on every login.
+        jwt: Arc<dyn crate::secrets::DynSecret<Output = SecretString>>,
+    },
+}
+
+impl std::fmt::Debug for ConjurAuth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::ApiKey { login, api_key } => f
+                .debug_struct("ApiKey")
+                .field("login", login)
+                .field("api_key", api_key)
+                .finish(),
+            Self::Jwt {
+                service_id,
+                host_id,
+                ..
+            } => f
+                .debug_struct("Jwt")
+                .field("service_id", service_id)
+                .field("host_id", host_id)
+                .finish_non_exhaustive(),
+        }
+    }
+}
+
+impl ConjurAuth {
+    /// Authenticates with an API key.
+    pub fn api_key(login: impl Into<String>, api_key: impl Into<String>) -> Self {
+        Self::ApiKey {
+            login: login.into(),
+            api_key: SecretString::from(api_key.into()),
+        }
+    }
+
+    /// Authenticates with a JWT from a secret, identifying the host from the
+    /// token's claims.
+    pub fn jwt<S: Secret<Output = SecretString> + 'static>(
+        service_id: impl Into<String>,
+        jwt: S,
+    ) -> Self {
+        Self::Jwt {
+            service_id: service_id.into(),
+            host_id: None,
+            jwt: Arc::new(jwt),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct CachedToken {
+    /// The base64-encoded access token.
+    token: SecretString,
+    renew_at: Instant,
+}
+
+#[derive(Debug)]
+struct ConjurClientInner {
+    http: reqwest::Client,
+    url: reqwest::Url,
+    account: String,
+    auth: ConjurAuth,
+    token: Mutex<Option<CachedToken>>,
+}
+
+/// A client for the Conjur REST API, shared by [`ConjurSecret`] providers.
+///
+/// Clones share the same access token, so a login is reused by every secret
+/// read through the client until the token is about to expire.
+#[derive(Debug, Clone)]
+pub struct ConjurClient {
+    inner: Arc<ConjurClientInner>,
+}
+
+#[bon]
+impl ConjurClient {
+    /// Creates a builder for a client of the Conjur server at the given URL.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the URL isn't a valid base URL.
+    #[builder]
+    pub fn new(
+        /// The URL of the Conjur server, such as `https://conjur.example.com`.
+        #[builder(start_fn)]
+        url: &str,
+        /// The Conjur organization account.
+        #[builder(into)]
+        account: String,
+        /// How to authenticate.
+        auth: ConjurAuth,
+        /// The HTTP client, e.g. configured with a custom CA or timeouts.
+        http_client: Option<reqwest::Client>,
+    ) -> Result<Self, url::ParseError> {
+        let url = reqwest::Url::parse(url.trim_end_matches('/'))?;
+        Ok(Self {
+            inner: Arc::new(ConjurClientInner {
+                http: http_client.unwrap_or_default(),
+                url,
+                account,
+                auth,
+                token: Mutex::new(None),
+            }),
+        })
+    }
+
+    /// Returns the URL of the Conjur server.
+    #[must_use]
+    pub fn url(&self) -> &str {
+        self.inner.url.as_str()
+    }
+
+    /// Returns the Conjur organization account.
+    #[must_use]
+    pub fn account(&self) -> &str {
+        &self.inner.account
+    }
+
+    /// Returns the server URL with the given path segments appended, each
+    /// percent-encoded (so variable IDs may contain `/`).
+    fn url_for<'a>(
+        &self,
+        segments: impl IntoIterator<Item = &'a str>,
+    ) -> Result<reqwest::Url, ConjurError> {
+        let mut url = self.inner.url.clone();
+        url.path_segments_mut()
+            .map_err(|()| ConjurError::InvalidUrl)?
+            .pop_if_empty()
+            .extend(segments);
+        Ok(url)
+    }
+
+    async fn send(request: reqwest::RequestBuilder) -> Result<reqwest::Response, ConjurError> {
+        let response = request.send().await.context(RequestSnafu)?;
+        let status = response.status();
+        ensure!(
+            status.is_success(),
+            ApiSnafu {
+                status: status.as_u16()
+            }
+        );
+        Ok(response)
+    }
+
+    async fn login(&self) -> Result<SecretString, ConjurError> {
+        let account = self.inner.account.as_str();
+        let request = match &self.inner.auth {
+            ConjurAuth::ApiKey { login, api_key } => self
+                .inner
+                .http
+                .post(self.url_for(["authn", account, login, "authenticate"])?)
+                .body(api_key.expose_secret().to_owned()),
+            ConjurAuth::Jwt {
+                service_id,
+                host_id,
+                jwt,
+            } => {
+                let jwt = Secret::get_secret_value(jwt)
+                    .await
+                    .map_err(|source| ConjurError::Jwt { source })?;
+                let url = match host_id {
+                    Some(host_id) => {
+                        self.url_for(["authn-jwt", service_id, account, host_id, "authenticate"])?
+                    }
+                    None => self.url_for(["authn-jwt", service_id, account, "authenticate"])?,
+                };
+                self.inner
+                    .http
+                    .post(url)
+                    .form(&[("jwt", jwt.expose_secret())])
+            }
+        };
+        // Ask for the token pre-encoded, ready for the `Authorization` header.
+        let token = Self::send(request.header(reqwest::header::ACCEPT_ENCODING, "base64"))
+            .await?
+            .text()
+            .await
+            .context(RequestSnafu)?;
+        Ok(SecretString::from(token))
+    }
+
+    async fn token(&self) -> Result<SecretString, ConjurError> {
+        let mut cached = self.inner.token.lock().await;
+        if let Some(token) = &*cached
+            && Instant::now() < token.renew_at
+        {
+            return Ok(token.token.clone());
+        }
+        let token = self.login().await?;
+        *cached = Some(CachedToken {
+            token: token.clone(),
+            renew_at: Instant::now() + TOKEN_REUSE,
+        });
+        Ok(token)
+    }
+
+    /// Discards the cached access token, e.g. after it was rejected.
+    async fn invalidate_token(&self) {
+        *self.inner.token.lock().await = None;
+    }
+
+    async fn read_variable(
+        &self,
+        variable_id: &str,
+        version: Option<&str>,
+    ) -> Result<Zeroizing<Vec<u8>>, ConjurError> {
+        let token = self.token().await?;
+        let url = self.url_for(["secrets", &self.inner.account, "variable", variable_id])?;
+        let mut request = self.inner.http.get(url).header(
+            reqwest::header::AUTHORIZATION,
+            format!("Token token=\"{}\"", token.expose_secret()),
+        );
+        if let Some(version) = version {
+            request = request.query(&[("version", version)]);
+        }
+        let value = Self::send(request)
+            .await?
+            .bytes()
+            .await
+            .context(RequestSnafu)?;
+        Ok(Zeroizing::new(value.to_vec()))
+    }
+}
+
+/// Retrieves a Conjur variable.
+///
+/// If the access token is rejected (e.g. the host's API key was rotated), the
+/// client logs in again and retries once.
+#[derive(Debug, Clone)]
+pub struct ConjurSecret<E: SecretDecoder = StringEncoding> {
+    /// The Conjur client.
+    client: ConjurClient,
+    /// The ID of the variable, such as `prod/db/password`.
+    variable_id: String,
+    /// The encoding of the secret.
+    encoding: E,
+}
+
+impl<E: SecretDecoder> ConjurSecret<E> {
+    /// Creates a new Conjur secret provider with the specified encoding.
+    pub fn new(client: ConjurClient, variable_id: impl Into<String>, encoding: E) -> Self {
+        Self {
+            client,
+            variable_id: variable_id.into(),
+            encoding,
+        }
+    }
+
+    /// Returns the ID of the variable.
+    #[must_use]
+    pub fn variable_id(&self) -> &str {
+        &self.variable_id
+    }
+
+    async fn read(&self, version: Option<&str>) -> Result<E::Output, SecretAccessError> {
+        let result = match self.client.read_variable(&self.variable_id, version).await {
+            Err(ConjurError::Api { status: 401 }) => {
+                self.client.invalidate_token().await;
+                self.client.read_variable(&self.variable_id, version).await
+            }
+            result => result,
+        };
+        let value = result.map_err(|e| self.remote_error(e))?;
+        self.encoding
+            .decode(&value)
+            .map_err(|source| SecretAccessError::Decode { source })
+    }
+
+    fn remote_error(&self, error: ConjurError) -> SecretAccessError {
+        SecretAccessError::Remote {
+            name: self.variable_id.clone(),
+            kind: error.kind(),
+            source: DynError::new(error),
+        }
+    }
+}
+
+impl ConjurSecret<StringEncoding> {
+    /// Creates a new Conjur secret provider returning a `SecretString`.
+    pub fn string(client: ConjurClient, variable_id: impl Into<String>) -> Self {
+        Self::new(client, variable_id, StringEncoding)
+    }
+}
+
+impl<E: SecretDecoder> Secret for ConjurSecret<E> {
+    type Output = E::Output;
+    type Error = SecretAccessError;
+
+    async fn get_secret_value(&self) -> Result<E::Output, Self::Error> {
+        self.read(None).await
+    }
+
+    async fn get_secret_with_metadata(&self) -> Result<SecretWithMetadata<E::Output>, Self::Error> {
+        let value = self.read(None).await?;
+        Ok(SecretWithMetadata::new(value).with_source(&self.variable_id))
+    }
+}
+
+/// Versions are the variable's version numbers, starting at 1. Conjur keeps
+/// the last 20 versions, and has no API to list them, so
+/// [`VersionedSecret::list_versions`] returns an empty list.
+impl<E: SecretDecoder> VersionedSecret for ConjurSecret<E> {
+    async fn get_secret_version(&self, version: &str) -> Result<E::Output, Self::Error> {
+        self.read(Some(version)).await
+    }
+
+    async fn list_versions(&self) -> Result<Vec<SecretVersion>, Self::Error> {
+        Ok(Vec::new())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::secrets::{StaticSecret, test_server::serve};
+
+    #[tokio::test]
+    async fn test_api_key_login_is_reused() {
+        let (address, server) = serve(
+            vec![(200, "dG9rZW4="), (200, "s3cret"), (200, "s3cret")],
+            &[],
+        );
+        let client = ConjurClient::builder(&address)
+            .account("acme")
+            .auth(ConjurAuth::api_key("host/my-app", "key"))
+            .build()
+            .unwrap();
+        let secret = ConjurSecret::string(client, "prod/db/password");
+
+        assert_eq!(
+            secret.get_secret_value().await.unwrap().expose_secret(),
+            "s3cret"
+        );
+        assert_eq!(
+            secret.get_secret_value().await.unwrap().expose_secret(),
+            "s3cret"
+        );
+
+        let requests = server.join().unwrap();
+        assert_eq!(
+            requests,
+            [
+                "POST /authn/acme/host%2Fmy-app/authenticate HTTP/1.1 key",
+                "GET /secrets/acme/variable/prod%2Fdb%2Fpassword HTTP/1.1",
+                "GET /secrets/acme/variable/prod%2Fdb%2Fpassword HTTP/1.1",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_jwt_login_and_missing_variable() {
+        let (address, server) = serve(vec![(200, "dG9rZW4="), (404, "")], &[]);
+        let client = ConjurClient::builder(&address)
+            .account("acme")
+            .auth(ConjurAuth::jwt("k8s", StaticSecret::string("eyJ.x.y")))
+            .build()
+            .unwrap();
+        let secret = ConjurSecret::string(client, "missing");
+
+        let error = secret.get_secret_value().await.unwrap_err();
+
+        assert!(matches!(
+            error,
+            SecretAccessError::Remote {
+                kind: RemoteErrorKind::NotFound,
+                ..
+            }
+        ));
+        let requests = server.join().unwrap();
+        assert_eq!(
+            requests[0],
+            "POST /authn-jwt/k8s/acme/authenticate HTTP/1.1 jwt=eyJ.x.y"
+        );
+    }
+}
@@ -0,0 +1,187 @@
+//! Secret wrapper deriving keys from a master secret with HKDF.
+
+use bon::bon;
+use hkdf::Hkdf;
+use secrecy::{ExposeSecretMut, SecretBox};
+use sha2::Sha256;
+use snafu::prelude::*;
+
+use crate::{
+    MaybeSendSync,
+    secrets::{ExposeSecretBytes, Secret, SecretWithMetadata},
+};
+
+/// The maximum output length of HKDF-SHA256, 255 hash lengths.
+const MAX_LENGTH: usize = 255 * 32;
+
+/// Errors returned by [`DerivedSecret`] and other key-deriving secrets.
+#[derive(Debug, Snafu)]
+#[snafu(visibility(pub(super)))]
+pub enum DeriveError<E: std::error::Error + MaybeSendSync + 'static> {
+    /// The master secret could not be retrieved.
+    #[snafu(display("Failed to retrieve master secret"))]
+    Master {
+        /// The error from the master secret.
+        source: E,
+    },
+    /// The requested key length is too long for the KDF.
+    #[snafu(display("Can't derive a {length}-byte key (maximum {max})"))]
+    InvalidLength {
+        /// The requested length.
+        length: usize,
+        /// The maximum length.
+        max: usize,
+    },
+    /// The KDF parameters are invalid, e.g. the salt is too short.
+    #[snafu(display("Invalid KDF parameters: {message}"))]
+    InvalidParameters {
+        /// The reason the parameters were rejected.
+        message: String,
+    },
+}
+
+/// A secret deriving a purpose-specific key from a master secret, using
+/// HKDF-SHA256 (RFC 5869).
+///
+/// One master secret can then provide independent keys for, e.g., signing
+/// cookies and `state` parameters, by giving each a different `info`. The
+/// master secret is fetched on every retrieval; wrap it in a
+/// [`CachedSecret`](crate::secrets::CachedSecret) to avoid refetching.
+#[derive(Debug, Clone)]
+pub struct DerivedSecret<S> {
+    inner: S,
+    salt: Option<Vec<u8>>,
+    info: Vec<u8>,
+    length: usize,
+}
+
+#[bon]
+impl<S: Secret> DerivedSecret<S>
+where
+    S::Output: ExposeSecretBytes,
+{
+    /// Creates a builder deriving keys from the given master secret.
+    #[builder]
+    pub fn new(
+        #[builder(start_fn)] inner: S,
+        /// The context and purpose of the key, such as `b"cookie-signing"`.
+        #[builder(into)]
+        info: Vec<u8>,
+        /// An optional non-secret salt. Defaults to none (a zero-filled salt).
+        #[builder(into)]
+        salt: Option<Vec<u8>>,
+        /// The length of the derived key, in bytes. Defaults to 32, and must
+        /// be at most 8160.
+        #[builder(default = 32)]
+        length: usize,
+    ) -> Self {
+        Self {
+            inner,
+            salt,
+            info,
+            length,
+        }
+    }
+
+    /// Returns the wrapped master secret.
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    /// Returns the length of the derived key, in bytes.
+    #[must_use]
+    pub fn length(&self) -> usize {
+        self.length
+    }
+
+    fn derive(&self, master: &S::Output) -> Result<SecretBox<[u8]>, DeriveError<S::Error>> {
+        let hkdf = Hkdf::<Sha256>::new(self.salt.as_deref(), master.expose_secret_bytes());
+        let mut key = SecretBox::new(vec![0; self.length].into_boxed_slice());
+        hkdf.expand(&self.info, key.expose_secret_mut())
+            .ok()
+            .context(InvalidLengthSnafu {
+                length: self.length,
+                max: MAX_LENGTH,
+            })?;
+        Ok(key)
+    }
+}
+
+impl<S: Secret> Secret for DerivedSecret<S>
+where
+    S::Output: ExposeSecretBytes,
+{
+    type Error = DeriveError<S::Error>;
+    type Output = SecretBox<[u8]>;
+
+    async fn get_secret_value(&self) -> Result<Self::Output, Self::Error> {
+        let master = self.inner.get_secret_value().await.context(MasterSnafu)?;
+        self.derive(&master)
+    }
+
+    /// The metadata is that of the master secret.
+    async fn get_secret_with_metadata(
+        &self,
+    ) -> Result<SecretWithMetadata<Self::Output>, Self::Error> {
+        let master = self
+            .inner
+            .get_secret_with_metadata()
+            .await
+            .context(MasterSnafu)?;
+        let key = self.derive(master.value())?;
+        Ok(master.map(|_| key))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use secrecy::ExposeSecret;
+
+    use super::*;
+    use crate::secrets::StaticSecret;
+
+    #[tokio::test]
+    async fn test_rfc5869_test_case_1() {
+        let ikm = SecretBox::new(vec![0x0b; 22].into_boxed_slice());
+        let secret = DerivedSecret::builder(StaticSecret::new(ikm))
+            .salt(hex::decode("000102030405060708090a0b0c").unwrap())
+            .info(hex::decode("f0f1f2f3f4f5f6f7f8f9").unwrap())
+            .length(42)
+            .build();
+
+        let key = secret.get_secret_value().await.unwrap();
+
+        assert_eq!(
+            hex::encode(key.expose_secret()),
+            "3cb25f25faacd57a90434f64d0362f2a2d2d0a90cf1a5a4c5db02d56ecc4c5bf34007208d5b887185865"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_info_separates_keys() {
+        let master = StaticSecret::string("master");
+        let cookie = DerivedSecret::builder(master.clone())
+            .info(b"cookie")
+            .build();
+        let state = DerivedSecret::builder(master).info(b"state").build();
+
+        let cookie = cookie.get_secret_value().await.unwrap();
+        let state = state.get_secret_value().await.unwrap();
+
+        assert_eq!(cookie.expose_secret().len(), 32);
+        assert_ne!(cookie.expose_secret(), state.expose_secret());
+    }
+
+    #[tokio::test]
+    async fn test_too_long() {
+        let secret = DerivedSecret::builder(StaticSecret::string("master"))
+            .info(b"too long")
+            .length(MAX_LENGTH + 1)
+            .build();
+
+        assert!(matches!(
+            secret.get_secret_value().await,
+            Err(DeriveError::InvalidLength { .. })
+        ));
+    }
+}
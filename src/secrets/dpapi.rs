@@ -0,0 +1,222 @@
+//! Windows DPAPI-protected file secret provider.
+
+use std::{
+    io::Write as _,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+use base64::Engine as _;
+use secrecy::zeroize::Zeroizing;
+
+use crate::secrets::{
+    Secret, SecretAccessError, SecretWithMetadata,
+    encodings::{SecretDecoder, StringEncoding},
+    providers::{file_metadata, modified_at, read_file},
+};
+
+/// The program used to call DPAPI. Windows PowerShell ships with every
+/// supported Windows version, and includes `ProtectedData`.
+const POWERSHELL: &str = "powershell.exe";
+
+/// The DPAPI scope a blob was protected with.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DpapiScope {
+    /// Only the user who protected the blob can unprotect it.
+    #[default]
+    CurrentUser,
+    /// Any user on the machine that protected the blob can unprotect it.
+    LocalMachine,
+}
+
+impl DpapiScope {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::CurrentUser => "CurrentUser",
+            Self::LocalMachine => "LocalMachine",
+        }
+    }
+}
+
+/// Retrieves secrets from a file holding a blob protected with the Windows
+/// Data Protection API (DPAPI), such as one written by .NET's
+/// `ProtectedData.Protect`.
+///
+/// This lets desktop agents keep refresh tokens and keys encrypted at rest,
+/// bound to the user or machine. The file is read on every call, and the blob
+/// is unprotected by Windows PowerShell, which the blob and entropy are passed
+/// to on standard input (so they don't appear in its command line); this
+/// blocks the current task until it exits.
+#[derive(Debug, Clone)]
+pub struct DpapiSecret<E: SecretDecoder = StringEncoding> {
+    /// The path of the file containing the protected blob.
+    path: PathBuf,
+    /// The scope the blob was protected with.
+    scope: DpapiScope,
+    /// Additional entropy the blob was protected with, if any.
+    entropy: Option<Vec<u8>>,
+    /// The encoding of the secret.
+    encoding: E,
+}
+
+impl<E: SecretDecoder> DpapiSecret<E> {
+    /// Creates a new DPAPI secret provider with the specified encoding.
+    pub fn new(path: impl Into<PathBuf>, encoding: E) -> Self {
+        Self {
+            path: path.into(),
+            scope: DpapiScope::default(),
+            entropy: None,
+            encoding,
+        }
+    }
+
+    /// Sets the scope the blob was protected with. Defaults to
+    /// [`DpapiScope::CurrentUser`].
+    #[must_use]
+    pub fn with_scope(mut self, scope: DpapiScope) -> Self {
+        self.scope = scope;
+        self
+    }
+
+    /// Sets the additional entropy the blob was protected with.
+    #[must_use]
+    pub fn with_entropy(mut self, entropy: impl Into<Vec<u8>>) -> Self {
+        self.entropy = Some(entropy.into());
+        self
+    }
+
+    /// Returns the path of the file containing the protected blob.
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the PowerShell script unprotecting a blob read from standard
+    /// input, and writing the raw value to standard output.
+    ///
+    /// The input is the base64 blob on the first line and, if there is
+    /// entropy, the base64 entropy on the second.
+    fn script(&self) -> String {
+        format!(
+            "$ErrorActionPreference = 'Stop'; \
+             Add-Type -AssemblyName System.Security; \
+             $blob = [Convert]::FromBase64String([Console]::In.ReadLine()); \
+             $entropy = [Console]::In.ReadLine(); \
+             if ($null -ne $entropy) {{ $entropy = [Convert]::FromBase64String($entropy) }}; \
+             $value = [Security.Cryptography.ProtectedData]::Unprotect($blob, $entropy, '{}'); \
+             $out = [Console]::OpenStandardOutput(); \
+             $out.Write($value, 0, $value.Length); \
+             $out.Flush()",
+            self.scope.as_str()
+        )
+    }
+
+    /// Returns the standard input for [`Self::script`].
+    fn input(&self, blob: &[u8]) -> String {
+        let engine = base64::engine::general_purpose::STANDARD;
+        let mut input = engine.encode(blob);
+        input.push('\n');
+        if let Some(entropy) = &self.entropy {
+            input.push_str(&engine.encode(entropy));
+            input.push('\n');
+        }
+        input
+    }
+
+    fn unprotect(&self, blob: &[u8]) -> Result<Zeroizing<Vec<u8>>, SecretAccessError> {
+        let spawn_error = |source| SecretAccessError::CommandSpawn {
+            program: POWERSHELL.into(),
+            source,
+        };
+        let mut child = Command::new(POWERSHELL)
+            .args(["-NoProfile", "-NonInteractive", "-Command", &self.script()])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(spawn_error)?;
+        if let Some(mut stdin) = child.stdin.take()
+            && let Err(source) = stdin.write_all(self.input(blob).as_bytes())
+        {
+            // Don't leave the process running, or unreaped.
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(spawn_error(source));
+        }
+        let output = child.wait_with_output().map_err(spawn_error)?;
+        let stdout = Zeroizing::new(output.stdout);
+        if !output.status.success() {
+            return Err(SecretAccessError::CommandFailed {
+                program: POWERSHELL.into(),
+                status: output.status,
+                stderr: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            });
+        }
+        Ok(stdout)
+    }
+}
+
+impl DpapiSecret<StringEncoding> {
+    /// Creates a new DPAPI secret provider returning a `SecretString`.
+    pub fn string(path: impl Into<PathBuf>) -> Self {
+        Self::new(path, StringEncoding)
+    }
+}
+
+impl<E: SecretDecoder> Secret for DpapiSecret<E> {
+    type Output = E::Output;
+    type Error = SecretAccessError;
+
+    async fn get_secret_value(&self) -> Result<E::Output, Self::Error> {
+        let blob = read_file(&self.path).await?;
+        let value = self.unprotect(&blob)?;
+        self.encoding
+            .decode(&value)
+            .map_err(|source| SecretAccessError::Decode { source })
+    }
+
+    /// The creation time is the file's modification time, and the source is
+    /// its path.
+    async fn get_secret_with_metadata(&self) -> Result<SecretWithMetadata<E::Output>, Self::Error> {
+        let value = self.get_secret_value().await?;
+        let secret = SecretWithMetadata::new(value).with_source(self.path.display().to_string());
+        let metadata = file_metadata(&self.path).await;
+        Ok(match metadata.as_ref().and_then(modified_at) {
+            Some(modified) => secret.with_created_at(modified),
+            None => secret,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_script_embeds_scope() {
+        let secret = DpapiSecret::string("token.bin").with_scope(DpapiScope::LocalMachine);
+
+        assert!(
+            secret
+                .script()
+                .contains("Unprotect($blob, $entropy, 'LocalMachine')")
+        );
+        assert!(
+            DpapiSecret::string("token.bin")
+                .script()
+                .contains("Unprotect($blob, $entropy, 'CurrentUser')")
+        );
+    }
+
+    #[test]
+    fn test_entropy_is_passed_on_standard_input() {
+        let secret = DpapiSecret::string("token.bin").with_entropy(b"app");
+
+        assert_eq!(secret.input(b"blob"), "YmxvYg==\nYXBw\n");
+        assert!(!secret.script().contains("YXBw"));
+        assert_eq!(
+            DpapiSecret::string("token.bin").input(b"blob"),
+            "YmxvYg==\n"
+        );
+    }
+}
@@ -0,0 +1,71 @@
+//! Object-safe secret trait.
+
+use std::sync::Arc;
+
+use crate::{
+    BoxFuture, DynError, MaybeSendSync,
+    secrets::{Secret, SecretWithMetadata},
+};
+
+/// Object-safe variant of [`Secret`].
+///
+/// Every [`Secret`] implements this trait, so secrets of different types (but
+/// the same output) can be stored together, e.g. as a
+/// `Box<dyn DynSecret<Output = SecretString>>` chosen from configuration. An
+/// `Arc<dyn DynSecret<Output = T>>` implements [`Secret`] in turn.
+pub trait DynSecret: MaybeSendSync {
+    /// The type of secret this source provides.
+    type Output: MaybeSendSync;
+
+    /// See [`Secret::get_secret_value`].
+    fn get_secret_value(&self) -> BoxFuture<'_, Result<Self::Output, DynError>>;
+
+    /// See [`Secret::get_secret_with_metadata`].
+    fn get_secret_with_metadata(
+        &self,
+    ) -> BoxFuture<'_, Result<SecretWithMetadata<Self::Output>, DynError>>;
+
+    /// See [`Secret::health_check`].
+    fn health_check(&self) -> BoxFuture<'_, Result<(), DynError>>;
+}
+
+impl<S: Secret> DynSecret for S {
+    type Output = S::Output;
+
+    fn get_secret_value(&self) -> BoxFuture<'_, Result<Self::Output, DynError>> {
+        Box::pin(async move { Secret::get_secret_value(self).await.map_err(DynError::new) })
+    }
+
+    fn get_secret_with_metadata(
+        &self,
+    ) -> BoxFuture<'_, Result<SecretWithMetadata<Self::Output>, DynError>> {
+        Box::pin(async move {
+            Secret::get_secret_with_metadata(self)
+                .await
+                .map_err(DynError::new)
+        })
+    }
+
+    fn health_check(&self) -> BoxFuture<'_, Result<(), DynError>> {
+        Box::pin(async move { Secret::health_check(self).await.map_err(DynError::new) })
+    }
+}
+
+impl<T: MaybeSendSync> Secret for Arc<dyn DynSecret<Output = T>> {
+    type Error = DynError;
+    type Output = T;
+
+    async fn get_secret_value(&self) -> Result<Self::Output, Self::Error> {
+        (**self).get_secret_value().await
+    }
+
+    async fn get_secret_with_metadata(
+        &self,
+    ) -> Result<SecretWithMetadata<Self::Output>, Self::Error> {
+        (**self).get_secret_with_metadata().await
+    }
+
+    async fn health_check(&self) -> Result<(), Self::Error> {
+        (**self).health_check().await
+    }
+}
@@ -0,0 +1,236 @@
+//! Secret cache keeping values encrypted in memory.
+
+use std::{sync::Arc, time::Duration};
+
+use aes_gcm::{
+    Aes256Gcm, KeyInit, Nonce,
+    aead::{Aead, AeadCore, OsRng},
+};
+use secrecy::zeroize::Zeroizing;
+use snafu::prelude::*;
+
+use crate::{
+    MaybeSendSync,
+    secrets::{CachedSecret, DecodingError, ExposeSecretBytes, FromSecretBytes, Secret},
+};
+
+/// Errors returned by [`EncryptedCachedSecret`].
+#[derive(Debug, Snafu)]
+pub enum EncryptedCacheError<E: std::error::Error + MaybeSendSync + 'static> {
+    /// The secret could not be retrieved.
+    #[snafu(display("Failed to retrieve secret"))]
+    Inner {
+        /// The error from the wrapped secret.
+        source: E,
+    },
+    /// The value could not be encrypted or decrypted.
+    #[snafu(display("Failed to encrypt or decrypt the cached value"))]
+    Crypto,
+    /// The decrypted value could not be converted back to the output type.
+    #[snafu(display("Failed to decode the cached value"))]
+    Decode {
+        /// The decoding error.
+        source: DecodingError,
+    },
+}
+
+/// A value encrypted with the process key.
+#[derive(Clone)]
+struct SealedValue {
+    nonce: Nonce<<Aes256Gcm as AeadCore>::NonceSize>,
+    ciphertext: Arc<[u8]>,
+}
+
+/// Encrypts the values of a secret with the process key, for caching.
+struct Sealer<S> {
+    inner: S,
+    cipher: Arc<Aes256Gcm>,
+}
+
+impl<S: Clone> Clone for Sealer<S> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            cipher: Arc::clone(&self.cipher),
+        }
+    }
+}
+
+impl<S: Secret> Secret for Sealer<S>
+where
+    S::Output: ExposeSecretBytes,
+{
+    type Error = EncryptedCacheError<S::Error>;
+    type Output = SealedValue;
+
+    async fn get_secret_value(&self) -> Result<Self::Output, Self::Error> {
+        let value = self.inner.get_secret_value().await.context(InnerSnafu)?;
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher
+            .encrypt(&nonce, value.expose_secret_bytes())
+            .ok()
+            .context(CryptoSnafu)?;
+        Ok(SealedValue {
+            nonce,
+            ciphertext: ciphertext.into(),
+        })
+    }
+}
+
+/// A [`CachedSecret`] that keeps the cached value encrypted in memory, and
+/// only decrypts it when retrieved.
+///
+/// Values are encrypted with AES-256-GCM under a random key generated when the
+/// cache is created, which never leaves the process. This shortens the time
+/// secret material is in plaintext in memory, e.g. for a long TTL, so it is
+/// less likely to appear in a core dump or swap. Each retrieval returns a
+/// freshly decrypted copy, which the caller should drop promptly.
+///
+/// The output type converts to and from bytes, as with `SecretString` and
+/// `SecretBox<[u8]>`. Clones share the same cache and key.
+pub struct EncryptedCachedSecret<S: Secret>
+where
+    S::Output: ExposeSecretBytes,
+{
+    cache: CachedSecret<Sealer<S>>,
+    cipher: Arc<Aes256Gcm>,
+}
+
+impl<S: Secret> Clone for EncryptedCachedSecret<S>
+where
+    S::Output: ExposeSecretBytes,
+{
+    fn clone(&self) -> Self {
+        Self {
+            cache: self.cache.clone(),
+            cipher: Arc::clone(&self.cipher),
+        }
+    }
+}
+
+impl<S: Secret + std::fmt::Debug> std::fmt::Debug for EncryptedCachedSecret<S>
+where
+    S::Output: ExposeSecretBytes,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EncryptedCachedSecret")
+            .field("inner", &self.cache.inner().inner)
+            .field("ttl", &self.cache.ttl())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<S: Secret> EncryptedCachedSecret<S>
+where
+    S::Output: ExposeSecretBytes + FromSecretBytes,
+{
+    /// Wraps a secret, caching its encrypted value for the given TTL.
+    pub fn new(inner: S, ttl: Duration) -> Self {
+        let cipher = Arc::new(Aes256Gcm::new(&Aes256Gcm::generate_key(&mut OsRng)));
+        let sealer = Sealer {
+            inner,
+            cipher: Arc::clone(&cipher),
+        };
+        Self {
+            cache: CachedSecret::new(sealer, ttl),
+            cipher,
+        }
+    }
+
+    /// See [`CachedSecret::with_max_stale`].
+    #[must_use]
+    pub fn with_max_stale(mut self, max_stale: Duration) -> Self {
+        self.cache = self.cache.with_max_stale(max_stale);
+        self
+    }
+
+    /// See [`CachedSecret::with_jitter`].
+    #[must_use]
+    pub fn with_jitter(mut self, jitter: Duration) -> Self {
+        self.cache = self.cache.with_jitter(jitter);
+        self
+    }
+
+    /// Returns the wrapped secret.
+    pub fn inner(&self) -> &S {
+        &self.cache.inner().inner
+    }
+
+    /// Returns the TTL of cached values.
+    #[must_use]
+    pub fn ttl(&self) -> Duration {
+        self.cache.ttl()
+    }
+
+    /// Discards the cached value, so the next retrieval fetches from the source.
+    pub fn invalidate(&self) {
+        self.cache.invalidate();
+    }
+}
+
+impl<S: Secret> Secret for EncryptedCachedSecret<S>
+where
+    S::Output: ExposeSecretBytes + FromSecretBytes,
+{
+    type Error = EncryptedCacheError<S::Error>;
+    type Output = S::Output;
+
+    async fn get_secret_value(&self) -> Result<Self::Output, Self::Error> {
+        let sealed = self.cache.get_secret_value().await?;
+        let plaintext = self
+            .cipher
+            .decrypt(&sealed.nonce, &*sealed.ciphertext)
+            .map(Zeroizing::new)
+            .ok()
+            .context(CryptoSnafu)?;
+        S::Output::from_secret_bytes(&plaintext).context(DecodeSnafu)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use secrecy::{ExposeSecret, SecretString};
+
+    use super::*;
+
+    #[derive(Debug, Clone, Default)]
+    struct CountingSecret {
+        count: Arc<AtomicUsize>,
+    }
+
+    impl Secret for CountingSecret {
+        type Error = std::convert::Infallible;
+        type Output = SecretString;
+
+        async fn get_secret_value(&self) -> Result<Self::Output, Self::Error> {
+            let count = self.count.fetch_add(1, Ordering::SeqCst) + 1;
+            Ok(SecretString::from(format!(" secret-{count} ")))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_value_is_cached_encrypted_and_round_trips() {
+        let secret = EncryptedCachedSecret::new(CountingSecret::default(), Duration::from_secs(30));
+
+        let first = secret.get_secret_value().await.unwrap();
+        let second = secret.clone().get_secret_value().await.unwrap();
+        let sealed = secret.cache.get_secret_value().await.unwrap();
+
+        assert_eq!(first.expose_secret(), " secret-1 ");
+        assert_eq!(second.expose_secret(), " secret-1 ");
+        assert!(
+            !sealed
+                .ciphertext
+                .windows(b"secret-1".len())
+                .any(|window| window == b"secret-1")
+        );
+        secret.invalidate();
+        assert_eq!(
+            secret.get_secret_value().await.unwrap().expose_secret(),
+            " secret-2 "
+        );
+    }
+}
@@ -0,0 +1,516 @@
+//! etcd secret provider, using the etcd v3 JSON gateway.
+
+use std::sync::Arc;
+
+use base64::{Engine as _, engine::general_purpose::STANDARD};
+use bon::bon;
+use futures_timer::Delay;
+use futures_util::{Stream, stream};
+use secrecy::zeroize::{Zeroize, Zeroizing};
+use serde::Deserialize;
+use snafu::prelude::*;
+use web_time::Duration;
+
+use crate::{
+    DynError, MaybeSend,
+    secrets::{
+        Secret, SecretAccessError, SecretSink, SecretWatcher, SecretWithMetadata,
+        encodings::{SecretDecoder, StringEncoding},
+        providers::RemoteErrorKind,
+    },
+};
+
+/// How long to wait before reconnecting a failed watch.
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Snafu)]
+enum EtcdError {
+    #[snafu(display("Request to etcd failed"))]
+    Request { source: reqwest::Error },
+    #[snafu(display("etcd returned HTTP {status}"))]
+    Api { status: u16 },
+    #[snafu(display("Key not found"))]
+    MissingKey,
+    #[snafu(display("Invalid response from etcd"))]
+    InvalidResponse,
+    #[snafu(display("Watch was canceled by etcd"))]
+    WatchCanceled,
+}
+
+impl EtcdError {
+    fn kind(&self) -> RemoteErrorKind {
+        match self {
+            Self::Request { source } if source.is_timeout() || source.is_connect() => {
+                RemoteErrorKind::Unavailable
+            }
+            Self::Api { status: 404 } | Self::MissingKey => RemoteErrorKind::NotFound,
+            Self::Api {
+                status: 401 | 403, ..
+            } => RemoteErrorKind::PermissionDenied,
+            Self::Api {
+                status: 429 | 500..=599,
+            }
+            | Self::WatchCanceled => RemoteErrorKind::Unavailable,
+            Self::Request { .. } | Self::Api { .. } | Self::InvalidResponse => {
+                RemoteErrorKind::Other
+            }
+        }
+    }
+}
+
+/// A key-value pair, with base64-encoded fields and 64-bit integers as
+/// strings, as returned by the gateway.
+#[derive(Deserialize)]
+struct KeyValue {
+    #[serde(default)]
+    value: String,
+    #[serde(default)]
+    mod_revision: String,
+}
+
+impl Drop for KeyValue {
+    fn drop(&mut self) {
+        self.value.zeroize();
+    }
+}
+
+#[derive(Deserialize)]
+struct RangeResponse {
+    #[serde(default)]
+    kvs: Vec<KeyValue>,
+}
+
+#[derive(Deserialize)]
+struct WatchMessage {
+    result: Option<WatchResult>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct WatchResult {
+    events: Vec<WatchEvent>,
+    canceled: bool,
+}
+
+#[derive(Deserialize)]
+struct WatchEvent {
+    /// `DELETE`, or absent for a put.
+    #[serde(rename = "type", default)]
+    kind: Option<String>,
+    kv: Option<KeyValue>,
+}
+
+#[derive(Debug)]
+struct EtcdClientInner {
+    http: reqwest::Client,
+    endpoint: String,
+    timeout: Option<Duration>,
+}
+
+/// A client for the etcd v3 JSON gateway, shared by [`EtcdSecret`]
+/// providers.
+#[derive(Debug, Clone)]
+pub struct EtcdClient {
+    inner: Arc<EtcdClientInner>,
+}
+
+#[bon]
+impl EtcdClient {
+    /// Creates a builder for a client of the etcd server at the given
+    /// endpoint.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the HTTP client can't be built, e.g. the TLS
+    /// configuration is invalid.
+    #[builder]
+    pub fn new(
+        /// The client URL of an etcd member, such as `https://etcd:2379`.
+        #[builder(start_fn, into)]
+        endpoint: String,
+        /// The CA certificate to verify the server with, if not a public CA.
+        ca_certificate: Option<reqwest::Certificate>,
+        /// The client certificate and key, for TLS client authentication.
+        client_identity: Option<reqwest::Identity>,
+        /// The timeout for requests, other than watches.
+        timeout: Option<Duration>,
+    ) -> Result<Self, reqwest::Error> {
+        let mut http = reqwest::Client::builder();
+        if let Some(certificate) = ca_certificate {
+            http = http.add_root_certificate(certificate);
+        }
+        if let Some(identity) = client_identity {
+            http = http.identity(identity);
+        }
+        let mut endpoint = endpoint;
+        endpoint.truncate(endpoint.trim_end_matches('/').len());
+        Ok(Self {
+            inner: Arc::new(EtcdClientInner {
+                http: http.build()?,
+                endpoint,
+                timeout,
+            }),
+        })
+    }
+
+    /// Returns the endpoint of the etcd server.
+    #[must_use]
+    pub fn endpoint(&self) -> &str {
+        &self.inner.endpoint
+    }
+
+    async fn post(
+        &self,
+        path: &str,
+        body: &serde_json::Value,
+        timeout: bool,
+    ) -> Result<reqwest::Response, EtcdError> {
+        let mut request = self
+            .inner
+            .http
+            .post(format!("{}/v3/{path}", self.inner.endpoint))
+            .json(body);
+        if let Some(duration) = self.inner.timeout.filter(|_| timeout) {
+            request = request.timeout(duration);
+        }
+        let response = request.send().await.context(RequestSnafu)?;
+        let status = response.status();
+        ensure!(
+            status.is_success(),
+            ApiSnafu {
+                status: status.as_u16()
+            }
+        );
+        Ok(response)
+    }
+
+    /// Reads a key, returning its value and modification revision.
+    async fn range(&self, key: &str) -> Result<(Zeroizing<Vec<u8>>, i64), EtcdError> {
+        let body = serde_json::json!({ "key": STANDARD.encode(key) });
+        let response: RangeResponse = self
+            .post("kv/range", &body, true)
+            .await?
+            .json()
+            .await
+            .context(RequestSnafu)?;
+        let kv = response.kvs.first().context(MissingKeySnafu)?;
+        decode_kv(kv)
+    }
+
+    async fn put(&self, key: &str, value: &[u8]) -> Result<(), EtcdError> {
+        let mut body = serde_json::json!({
+            "key": STANDARD.encode(key),
+            "value": STANDARD.encode(value),
+        });
+        let result = self.post("kv/put", &body, true).await;
+        crate::secrets::encodings::zeroize_json(&mut body);
+        result.map(drop)
+    }
+
+    /// Starts watching a key for changes after a revision.
+    async fn watch(&self, key: &str, after_revision: i64) -> Result<reqwest::Response, EtcdError> {
+        let body = serde_json::json!({
+            "create_request": {
+                "key": STANDARD.encode(key),
+                "start_revision": (after_revision + 1).to_string(),
+            }
+        });
+        self.post("watch", &body, false).await
+    }
+}
+
+/// Decodes a key-value pair's value and modification revision.
+fn decode_kv(kv: &KeyValue) -> Result<(Zeroizing<Vec<u8>>, i64), EtcdError> {
+    let value = STANDARD
+        .decode(&kv.value)
+        .map(Zeroizing::new)
+        .map_err(|_| EtcdError::InvalidResponse)?;
+    let revision = kv
+        .mod_revision
+        .parse()
+        .map_err(|_| EtcdError::InvalidResponse)?;
+    Ok((value, revision))
+}
+
+/// Retrieves the value of an etcd key.
+///
+/// Requests go through the etcd v3 JSON gateway (enabled by default on etcd's
+/// client URLs). As a [`SecretWatcher`], the key is watched for changes,
+/// rather than polled.
+#[derive(Debug, Clone)]
+pub struct EtcdSecret<E: SecretDecoder = StringEncoding> {
+    /// The etcd client.
+    client: EtcdClient,
+    /// The key, such as `/services/api/client-secret`.
+    key: String,
+    /// The encoding of the secret.
+    encoding: E,
+}
+
+impl<E: SecretDecoder> EtcdSecret<E> {
+    /// Creates a new etcd secret provider with the specified encoding.
+    pub fn new(client: EtcdClient, key: impl Into<String>, encoding: E) -> Self {
+        Self {
+            client,
+            key: key.into(),
+            encoding,
+        }
+    }
+
+    /// Returns the key.
+    #[must_use]
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    async fn read(&self) -> Result<(E::Output, i64), SecretAccessError> {
+        let (value, revision) = self
+            .client
+            .range(&self.key)
+            .await
+            .map_err(|e| self.remote_error(e))?;
+        Ok((self.decode(&value)?, revision))
+    }
+
+    fn decode(&self, value: &[u8]) -> Result<E::Output, SecretAccessError> {
+        self.encoding
+            .decode(value)
+            .map_err(|source| SecretAccessError::Decode { source })
+    }
+
+    fn remote_error(&self, error: EtcdError) -> SecretAccessError {
+        SecretAccessError::Remote {
+            name: self.key.clone(),
+            kind: error.kind(),
+            source: DynError::new(error),
+        }
+    }
+}
+
+impl EtcdSecret<StringEncoding> {
+    /// Creates a new etcd secret provider returning a `SecretString`.
+    pub fn string(client: EtcdClient, key: impl Into<String>) -> Self {
+        Self::new(client, key, StringEncoding)
+    }
+}
+
+impl<E: SecretDecoder> Secret for EtcdSecret<E> {
+    type Output = E::Output;
+    type Error = SecretAccessError;
+
+    async fn get_secret_value(&self) -> Result<E::Output, Self::Error> {
+        self.read().await.map(|(value, _)| value)
+    }
+
+    /// The version is the key's modification revision.
+    async fn get_secret_with_metadata(&self) -> Result<SecretWithMetadata<E::Output>, Self::Error> {
+        let (value, revision) = self.read().await?;
+        Ok(SecretWithMetadata::new(value)
+            .with_version(revision.to_string())
+            .with_source(&self.key))
+    }
+}
+
+/// Puts the value to the key, creating a new revision.
+impl<E: SecretDecoder> SecretSink for EtcdSecret<E> {
+    type Error = SecretAccessError;
+
+    async fn put_secret(&self, value: &[u8]) -> Result<(), Self::Error> {
+        self.client
+            .put(&self.key, value)
+            .await
+            .map_err(|e| self.remote_error(e))
+    }
+}
+
+/// The state of a watch on an etcd key.
+struct WatchState<E: SecretDecoder> {
+    secret: EtcdSecret<E>,
+    /// The revision of the last value yielded, or `None` to read the key.
+    revision: Option<i64>,
+    /// The open watch stream.
+    response: Option<reqwest::Response>,
+    /// Bytes of the watch stream not yet parsed into messages.
+    buffer: Zeroizing<Vec<u8>>,
+    /// Whether to wait before the next attempt, after an error.
+    backoff: bool,
+}
+
+impl<E: SecretDecoder> WatchState<E> {
+    /// Waits for the next value or error.
+    async fn next(&mut self) -> Result<E::Output, SecretAccessError> {
+        loop {
+            if std::mem::take(&mut self.backoff) {
+                Delay::new(RECONNECT_DELAY).await;
+            }
+            let Some(revision) = self.revision else {
+                let (value, revision) = self.secret.read().await.inspect_err(|_| {
+                    self.backoff = true;
+                })?;
+                self.revision = Some(revision);
+                return Ok(value);
+            };
+            if self.response.is_none() {
+                let response = self
+                    .secret
+                    .client
+                    .watch(&self.secret.key, revision)
+                    .await
+                    .map_err(|e| {
+                        self.backoff = true;
+                        self.secret.remote_error(e)
+                    })?;
+                self.buffer.clear();
+                self.response = Some(response);
+            }
+            if let Some(end) = self.buffer.iter().position(|&byte| byte == b'\n') {
+                let line = Zeroizing::new(self.buffer.drain(..=end).collect::<Vec<_>>());
+                if let Some(item) = self.handle_message(&line) {
+                    return item;
+                }
+                continue;
+            }
+            let Some(response) = &mut self.response else {
+                continue;
+            };
+            match response.chunk().await {
+                Ok(Some(chunk)) => self.buffer.extend_from_slice(&chunk),
+                // The server closed the watch, so reconnect.
+                Ok(None) => self.response = None,
+                Err(e) => {
+                    self.response = None;
+                    self.backoff = true;
+                    return Err(self.secret.remote_error(EtcdError::Request { source: e }));
+                }
+            }
+        }
+    }
+
+    /// Handles a message from the watch stream, returning the item to yield,
+    /// if any.
+    fn handle_message(&mut self, line: &[u8]) -> Option<Result<E::Output, SecretAccessError>> {
+        let Ok(message) = serde_json::from_slice::<WatchMessage>(line) else {
+            self.response = None;
+            self.backoff = true;
+            return Some(Err(self.secret.remote_error(EtcdError::InvalidResponse)));
+        };
+        let result = message.result.unwrap_or_default();
+        if result.canceled {
+            // E.g. the revision was compacted, so read the key again.
+            self.response = None;
+            self.revision = None;
+            self.backoff = true;
+            return Some(Err(self.secret.remote_error(EtcdError::WatchCanceled)));
+        }
+        // Only the latest event for the key matters.
+        let event = result.events.last()?;
+        let kv = event.kv.as_ref()?;
+        if event.kind.as_deref() == Some("DELETE") {
+            self.revision = kv.mod_revision.parse().ok().or(self.revision);
+            return Some(Err(self.secret.remote_error(EtcdError::MissingKey)));
+        }
+        Some(
+            decode_kv(kv)
+                .map_err(|e| self.secret.remote_error(e))
+                .and_then(|(value, revision)| {
+                    self.revision = Some(revision);
+                    self.secret.decode(&value)
+                }),
+        )
+    }
+}
+
+impl<E: SecretDecoder + 'static> SecretWatcher for EtcdSecret<E> {
+    type Error = SecretAccessError;
+    type Output = E::Output;
+
+    fn watch(&self) -> impl Stream<Item = Result<Self::Output, Self::Error>> + MaybeSend + 'static {
+        let state = WatchState {
+            secret: self.clone(),
+            revision: None,
+            response: None,
+            buffer: Zeroizing::new(Vec::new()),
+            backoff: false,
+        };
+        stream::unfold(state, |mut state| async move {
+            let item = state.next().await;
+            Some((item, state))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_util::StreamExt;
+    use secrecy::ExposeSecret;
+
+    use super::*;
+    use crate::secrets::test_server::serve;
+
+    #[tokio::test]
+    async fn test_read_key() {
+        let (address, server) = serve(
+            vec![
+                (
+                    200,
+                    r#"{"header":{"revision":"8"},"kvs":[{"key":"L2FwaS9zZWNyZXQ=","value":"czNjcmV0","mod_revision":"7"}],"count":"1"}"#,
+                ),
+                (200, r#"{"header":{"revision":"8"}}"#),
+            ],
+            &[],
+        );
+        let client = EtcdClient::builder(address).build().unwrap();
+        let secret = EtcdSecret::string(client.clone(), "/api/secret");
+        let missing = EtcdSecret::string(client, "/api/missing");
+
+        let value = secret.get_secret_with_metadata().await.unwrap();
+        let error = missing.get_secret_value().await.unwrap_err();
+
+        assert_eq!(value.value().expose_secret(), "s3cret");
+        assert_eq!(value.version(), Some("7"));
+        assert!(matches!(
+            error,
+            SecretAccessError::Remote {
+                kind: RemoteErrorKind::NotFound,
+                ..
+            }
+        ));
+        let requests = server.join().unwrap();
+        assert_eq!(
+            requests[0],
+            r#"POST /v3/kv/range HTTP/1.1 {"key":"L2FwaS9zZWNyZXQ="}"#
+        );
+    }
+
+    #[tokio::test]
+    async fn test_watch_yields_put_values() {
+        let (address, server) = serve(
+            vec![
+                (200, r#"{"kvs":[{"value":"Zmlyc3Q=","mod_revision":"7"}]}"#),
+                (
+                    200,
+                    concat!(
+                        r#"{"result":{"header":{"revision":"7"},"created":true}}"#,
+                        "\n",
+                        r#"{"result":{"events":[{"kv":{"value":"c2Vjb25k","mod_revision":"9"}}]}}"#,
+                        "\n",
+                    ),
+                ),
+            ],
+            &[],
+        );
+        let client = EtcdClient::builder(address).build().unwrap();
+        let secret = EtcdSecret::string(client, "/api/secret");
+
+        let values: Vec<_> = secret
+            .watch()
+            .take(2)
+            .map(|value| value.unwrap().expose_secret().to_owned())
+            .collect()
+            .await;
+
+        assert_eq!(values, ["first", "second"]);
+        let requests = server.join().unwrap();
+        assert!(requests[1].contains(r#""start_revision":"8""#));
+    }
+}
@@ -0,0 +1,172 @@
+//! Secret values that can't be used past their expiry.
+
+use std::time::Duration;
+
+use snafu::prelude::*;
+use web_time::SystemTime;
+
+use crate::{
+    clock::{Clock, SystemClock},
+    secrets::SecretWithMetadata,
+};
+
+/// The error returned when exposing an [`ExpiringSecret`] past its expiry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Snafu)]
+#[snafu(display("Secret expired at {expires_at:?}"))]
+pub struct ExpiredError {
+    /// When the value expired.
+    pub expires_at: SystemTime,
+}
+
+/// A short-lived secret value, such as an STS token, that refuses to expose
+/// the value once its recorded expiry has passed.
+///
+/// Holding an expired value is not an error, so it can still be cached or
+/// passed around; exposing it is. Callers that hold on to values should
+/// refetch on [`ExpiredError`]. The current time is read from the system
+/// clock, unless another [`Clock`] is set with [`ExpiringSecret::with_clock`].
+#[derive(Debug, Clone)]
+pub struct ExpiringSecret<T, C = SystemClock> {
+    value: T,
+    expires_at: SystemTime,
+    clock: C,
+}
+
+impl<T> ExpiringSecret<T> {
+    /// Wraps a value that expires at the given time.
+    pub fn new(value: T, expires_at: SystemTime) -> Self {
+        Self {
+            value,
+            expires_at,
+            clock: SystemClock,
+        }
+    }
+
+    /// Wraps a value with the expiry from its metadata, or returns `None` if
+    /// the source didn't report one.
+    pub fn from_metadata(secret: SecretWithMetadata<T>) -> Option<Self> {
+        let expires_at = secret.expires_at()?;
+        Some(Self::new(secret.into_value(), expires_at))
+    }
+}
+
+impl<T, C: Clock> ExpiringSecret<T, C> {
+    /// Reads the current time from the given clock.
+    pub fn with_clock<C2: Clock>(self, clock: C2) -> ExpiringSecret<T, C2> {
+        ExpiringSecret {
+            value: self.value,
+            expires_at: self.expires_at,
+            clock,
+        }
+    }
+
+    /// Returns when the value expires.
+    #[must_use]
+    pub fn expires_at(&self) -> SystemTime {
+        self.expires_at
+    }
+
+    /// Returns `true` if the value has expired.
+    #[must_use]
+    pub fn is_expired(&self) -> bool {
+        self.remaining().is_none()
+    }
+
+    /// Returns how long the value remains valid, or `None` if it has expired.
+    #[must_use]
+    pub fn remaining(&self) -> Option<Duration> {
+        self.expires_at
+            .duration_since(self.clock.now())
+            .ok()
+            .filter(|remaining| !remaining.is_zero())
+    }
+
+    /// Returns the value, if it hasn't expired.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the value has expired.
+    pub fn expose(&self) -> Result<&T, ExpiredError> {
+        self.expose_for(Duration::ZERO)
+    }
+
+    /// Returns the value, if it remains valid for at least `min_validity`,
+    /// e.g. long enough for a request using it to complete.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the value expires within `min_validity`.
+    pub fn expose_for(&self, min_validity: Duration) -> Result<&T, ExpiredError> {
+        self.check(min_validity)?;
+        Ok(&self.value)
+    }
+
+    /// Returns the value, if it hasn't expired.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the value has expired.
+    pub fn into_value(self) -> Result<T, ExpiredError> {
+        self.check(Duration::ZERO)?;
+        Ok(self.value)
+    }
+
+    fn check(&self, min_validity: Duration) -> Result<(), ExpiredError> {
+        let valid = self
+            .remaining()
+            .is_some_and(|remaining| remaining > min_validity);
+        ensure!(
+            valid,
+            ExpiredSnafu {
+                expires_at: self.expires_at
+            }
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use secrecy::{ExposeSecret, SecretString};
+    use web_time::UNIX_EPOCH;
+
+    use super::*;
+    use crate::clock::FixedClock;
+
+    #[test]
+    fn test_value_is_exposed_until_expiry() {
+        let expires_at = SystemTime::now() + Duration::from_secs(300);
+        let token = ExpiringSecret::new(SecretString::from("token"), expires_at);
+
+        assert_eq!(token.expose().unwrap().expose_secret(), "token");
+        assert!(token.expose_for(Duration::from_secs(60)).is_ok());
+        assert_eq!(
+            token.expose_for(Duration::from_secs(600)).unwrap_err(),
+            ExpiredError { expires_at }
+        );
+    }
+
+    #[test]
+    fn test_expired_value_is_refused() {
+        let clock = FixedClock::new(UNIX_EPOCH);
+        let expires_at = UNIX_EPOCH + Duration::from_secs(300);
+        let token =
+            ExpiringSecret::new(SecretString::from("token"), expires_at).with_clock(clock.clone());
+
+        clock.advance(Duration::from_secs(300));
+
+        assert!(token.is_expired());
+        assert!(token.expose().is_err());
+        assert!(token.into_value().is_err());
+    }
+
+    #[test]
+    fn test_from_metadata_requires_expiry() {
+        let secret = SecretWithMetadata::new("token");
+
+        assert!(ExpiringSecret::from_metadata(secret.clone()).is_none());
+        let expires_at = SystemTime::now() + Duration::from_secs(300);
+        let token = ExpiringSecret::from_metadata(secret.with_expires_at(expires_at)).unwrap();
+        assert_eq!(token.expires_at(), expires_at);
+    }
+}
@@ -0,0 +1,213 @@
+//! Google Cloud Secret Manager secret provider.
+
+use google_cloud_secretmanager_v1::{
+    Error as GcpError,
+    client::SecretManagerService,
+    model::{SecretPayload, secret_version::State},
+};
+use snafu::prelude::*;
+
+use crate::{
+    DynError,
+    secrets::{
+        Secret, SecretAccessError, SecretSink, SecretVersion, SecretWithMetadata, VersionedSecret,
+        encodings::{SecretDecoder, StringEncoding},
+        providers::RemoteErrorKind,
+        versioned::unix_time,
+    },
+};
+
+#[derive(Debug, Snafu)]
+#[snafu(display("The secret version has no payload"))]
+struct MissingPayload;
+
+/// Retrieves secrets from Google Cloud Secret Manager.
+///
+/// The client is configured by the caller; the default
+/// (`SecretManagerService::builder().build().await`) authenticates with
+/// Application Default Credentials. The caller needs the
+/// `secretmanager.versions.access` permission on the secret.
+#[derive(Debug, Clone)]
+pub struct GcpSecret<E: SecretDecoder = StringEncoding> {
+    /// The Secret Manager client.
+    client: SecretManagerService,
+    /// The resource name of the secret version.
+    name: String,
+    /// The encoding of the secret.
+    encoding: E,
+}
+
+impl<E: SecretDecoder> GcpSecret<E> {
+    /// Creates a new Secret Manager secret provider with the specified encoding.
+    ///
+    /// The name is the full resource name of the secret version, such as
+    /// `projects/my-project/secrets/client-secret/versions/3`.
+    pub fn new(client: SecretManagerService, name: impl Into<String>, encoding: E) -> Self {
+        Self {
+            client,
+            name: name.into(),
+            encoding,
+        }
+    }
+
+    /// Creates a new Secret Manager secret provider for the latest version of
+    /// a secret, with the specified encoding.
+    pub fn latest(client: SecretManagerService, project: &str, secret: &str, encoding: E) -> Self {
+        Self::new(
+            client,
+            format!("projects/{project}/secrets/{secret}/versions/latest"),
+            encoding,
+        )
+    }
+
+    /// Returns the resource name of the secret version.
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the resource name of the secret, without the version.
+    fn secret_name(&self) -> &str {
+        self.name
+            .split_once("/versions/")
+            .map_or(self.name.as_str(), |(secret, _)| secret)
+    }
+
+    async fn access(&self, name: &str) -> Result<SecretWithMetadata<E::Output>, SecretAccessError> {
+        let response = self
+            .client
+            .access_secret_version()
+            .set_name(name)
+            .send()
+            .await
+            .map_err(|e| self.remote_error(error_kind(&e), DynError::new(e)))?;
+        let payload = response.payload.ok_or_else(|| {
+            self.remote_error(RemoteErrorKind::NotFound, DynError::new(MissingPayload))
+        })?;
+        let value = self
+            .encoding
+            .decode(&payload.data)
+            .map_err(|source| SecretAccessError::Decode { source })?;
+
+        let secret = SecretWithMetadata::new(value).with_source(self.secret_name());
+        // The response names the version accessed, even for aliases like `latest`.
+        Ok(match response.name.rsplit_once("/versions/") {
+            Some((_, version)) => secret.with_version(version),
+            None => secret,
+        })
+    }
+
+    fn remote_error(&self, kind: RemoteErrorKind, source: DynError) -> SecretAccessError {
+        SecretAccessError::Remote {
+            name: self.name.clone(),
+            kind,
+            source,
+        }
+    }
+}
+
+impl GcpSecret<StringEncoding> {
+    /// Creates a new Secret Manager secret provider returning a `SecretString`.
+    pub fn string(client: SecretManagerService, name: impl Into<String>) -> Self {
+        Self::new(client, name, StringEncoding)
+    }
+}
+
+fn error_kind(error: &GcpError) -> RemoteErrorKind {
+    if let Some(status) = error.status() {
+        return match status.code.name() {
+            "NOT_FOUND" => RemoteErrorKind::NotFound,
+            "PERMISSION_DENIED" | "UNAUTHENTICATED" => RemoteErrorKind::PermissionDenied,
+            "UNAVAILABLE" | "DEADLINE_EXCEEDED" | "RESOURCE_EXHAUSTED" => {
+                RemoteErrorKind::Unavailable
+            }
+            _ => RemoteErrorKind::Other,
+        };
+    }
+    if error.is_authentication() {
+        RemoteErrorKind::PermissionDenied
+    } else if error.is_timeout() || error.is_exhausted() || error.is_connect() || error.is_io() {
+        RemoteErrorKind::Unavailable
+    } else {
+        RemoteErrorKind::Other
+    }
+}
+
+impl<E: SecretDecoder> Secret for GcpSecret<E> {
+    type Output = E::Output;
+    type Error = SecretAccessError;
+
+    async fn get_secret_value(&self) -> Result<E::Output, Self::Error> {
+        self.get_secret_with_metadata()
+            .await
+            .map(SecretWithMetadata::into_value)
+    }
+
+    /// The version is the version number accessed, even when the name uses
+    /// an alias such as `latest`.
+    async fn get_secret_with_metadata(&self) -> Result<SecretWithMetadata<E::Output>, Self::Error> {
+        self.access(&self.name).await
+    }
+}
+
+/// Versions are version numbers, or `latest`. Listing versions needs the
+/// `secretmanager.versions.list` permission.
+impl<E: SecretDecoder> VersionedSecret for GcpSecret<E> {
+    async fn get_secret_version(&self, version: &str) -> Result<E::Output, Self::Error> {
+        self.access(&format!("{}/versions/{version}", self.secret_name()))
+            .await
+            .map(SecretWithMetadata::into_value)
+    }
+
+    async fn list_versions(&self) -> Result<Vec<SecretVersion>, Self::Error> {
+        let mut versions = Vec::new();
+        let mut page_token = String::new();
+        loop {
+            let response = self
+                .client
+                .list_secret_versions()
+                .set_parent(self.secret_name())
+                .set_page_token(page_token)
+                .send()
+                .await
+                .map_err(|e| self.remote_error(error_kind(&e), DynError::new(e)))?;
+            versions.extend(response.versions.into_iter().map(|version| {
+                let id = version
+                    .name
+                    .rsplit_once('/')
+                    .map_or(version.name.as_str(), |(_, id)| id);
+                SecretVersion::builder(id)
+                    .maybe_created_at(
+                        version.create_time.and_then(|time| {
+                            unix_time(time.seconds(), time.nanos().unsigned_abs())
+                        }),
+                    )
+                    .enabled(version.state == State::Enabled)
+                    .build()
+            }));
+            page_token = response.next_page_token;
+            if page_token.is_empty() {
+                break;
+            }
+        }
+        Ok(versions)
+    }
+}
+
+/// Adds a new version to the secret, which becomes its `latest` version.
+///
+/// The caller needs the `secretmanager.versions.add` permission on the secret.
+impl<E: SecretDecoder> SecretSink for GcpSecret<E> {
+    type Error = SecretAccessError;
+
+    async fn put_secret(&self, value: &[u8]) -> Result<(), Self::Error> {
+        self.client
+            .add_secret_version()
+            .set_parent(self.secret_name())
+            .set_payload(SecretPayload::new().set_data(value.to_vec()))
+            .send()
+            .await
+            .map_err(|e| self.remote_error(error_kind(&e), DynError::new(e)))?;
+        Ok(())
+    }
+}
@@ -0,0 +1,129 @@
+//! Aggregated health checks across secrets.
+
+use std::{borrow::Cow, sync::Arc};
+
+use futures_util::future::join_all;
+
+use crate::{
+    BoxFuture, DynError, MaybeSendSync,
+    secrets::{DynSecret, Secret},
+};
+
+/// Type-erased [`Secret::health_check`], whatever the secret's output.
+trait HealthProbe: MaybeSendSync {
+    fn probe(&self) -> BoxFuture<'_, Result<(), DynError>>;
+}
+
+impl<S: Secret> HealthProbe for S {
+    fn probe(&self) -> BoxFuture<'_, Result<(), DynError>> {
+        DynSecret::health_check(self)
+    }
+}
+
+/// Checks the health of a set of named secrets concurrently, e.g. from a
+/// readiness probe, so an instance doesn't serve traffic before it can reach
+/// its secret stores.
+///
+/// Each secret is checked with [`Secret::health_check`], which by default
+/// retrieves (and immediately drops) the value. Wrap slow sources in a
+/// [`TimeoutSecret`](crate::secrets::TimeoutSecret) to bound the check.
+#[derive(Clone, Default)]
+pub struct SecretHealthCheck {
+    secrets: Vec<(Cow<'static, str>, Arc<dyn HealthProbe>)>,
+}
+
+impl std::fmt::Debug for SecretHealthCheck {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list()
+            .entries(self.secrets.iter().map(|(name, _)| name))
+            .finish()
+    }
+}
+
+impl SecretHealthCheck {
+    /// Creates an empty health check.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a secret to check, under a name used in the report.
+    #[must_use]
+    pub fn with_secret<S: Secret + 'static>(
+        mut self,
+        name: impl Into<Cow<'static, str>>,
+        secret: S,
+    ) -> Self {
+        self.secrets.push((name.into(), Arc::new(secret)));
+        self
+    }
+
+    /// Checks every secret concurrently.
+    pub async fn check(&self) -> HealthReport {
+        let results = join_all(self.secrets.iter().map(|(_, secret)| secret.probe())).await;
+        HealthReport {
+            results: self
+                .secrets
+                .iter()
+                .map(|(name, _)| name.clone())
+                .zip(results)
+                .collect(),
+        }
+    }
+}
+
+/// The outcome of a [`SecretHealthCheck`], for each secret in the order they
+/// were added.
+#[derive(Debug)]
+pub struct HealthReport {
+    results: Vec<(Cow<'static, str>, Result<(), DynError>)>,
+}
+
+impl HealthReport {
+    /// Returns `true` if every secret is healthy.
+    #[must_use]
+    pub fn is_healthy(&self) -> bool {
+        self.results.iter().all(|(_, result)| result.is_ok())
+    }
+
+    /// Returns the names of the unhealthy secrets, with their errors.
+    pub fn failures(&self) -> impl Iterator<Item = (&str, &DynError)> {
+        self.results
+            .iter()
+            .filter_map(|(name, result)| Some((name.as_ref(), result.as_ref().err()?)))
+    }
+
+    /// Returns the result for each secret.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, Result<(), &DynError>)> {
+        self.results
+            .iter()
+            .map(|(name, result)| (name.as_ref(), result.as_ref().copied()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::secrets::{FileSecret, StaticSecret};
+
+    #[tokio::test]
+    async fn test_report_lists_failures() {
+        let health = SecretHealthCheck::new()
+            .with_secret("client-secret", StaticSecret::string("s3cret"))
+            .with_secret("signing-key", FileSecret::string("/nonexistent/key.pem"));
+
+        let report = health.check().await;
+
+        assert!(!report.is_healthy());
+        assert_eq!(
+            report.failures().map(|(name, _)| name).collect::<Vec<_>>(),
+            ["signing-key"]
+        );
+        assert_eq!(report.iter().count(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_empty_check_is_healthy() {
+        assert!(SecretHealthCheck::new().check().await.is_healthy());
+    }
+}
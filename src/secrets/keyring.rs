@@ -0,0 +1,125 @@
+//! Platform credential store secret provider.
+
+use secrecy::zeroize::Zeroizing;
+
+use crate::{
+    DynError,
+    secrets::{
+        Secret, SecretAccessError, SecretSink,
+        encodings::{SecretDecoder, StringEncoding},
+        providers::RemoteErrorKind,
+    },
+};
+
+/// Retrieves secrets from the platform credential store: the macOS Keychain,
+/// the Secret Service on Linux (e.g. GNOME Keyring or `KWallet`), or the
+/// Windows Credential Manager.
+///
+/// This suits desktop tools, such as CLIs that store a refresh token after an
+/// OAuth device flow. The store's APIs are blocking, and may prompt the user
+/// to unlock it, so reads block the current task.
+#[derive(Debug, Clone)]
+pub struct KeyringSecret<E: SecretDecoder = StringEncoding> {
+    /// The service the credential belongs to.
+    service: String,
+    /// The user (account) of the credential.
+    user: String,
+    /// The encoding of the secret.
+    encoding: E,
+}
+
+impl<E: SecretDecoder> KeyringSecret<E> {
+    /// Creates a new credential store secret provider with the specified encoding.
+    pub fn new(service: impl Into<String>, user: impl Into<String>, encoding: E) -> Self {
+        Self {
+            service: service.into(),
+            user: user.into(),
+            encoding,
+        }
+    }
+
+    /// Returns the service the credential belongs to.
+    #[must_use]
+    pub fn service(&self) -> &str {
+        &self.service
+    }
+
+    /// Returns the user (account) of the credential.
+    #[must_use]
+    pub fn user(&self) -> &str {
+        &self.user
+    }
+
+    fn remote_error(&self, error: ::keyring::Error) -> SecretAccessError {
+        SecretAccessError::Remote {
+            name: format!("{}/{}", self.service, self.user),
+            kind: error_kind(&error),
+            source: DynError::new(error),
+        }
+    }
+}
+
+impl KeyringSecret<StringEncoding> {
+    /// Creates a new credential store secret provider returning a `SecretString`.
+    pub fn string(service: impl Into<String>, user: impl Into<String>) -> Self {
+        Self::new(service, user, StringEncoding)
+    }
+}
+
+fn error_kind(error: &::keyring::Error) -> RemoteErrorKind {
+    match error {
+        ::keyring::Error::NoEntry => RemoteErrorKind::NotFound,
+        ::keyring::Error::NoStorageAccess(_) => RemoteErrorKind::Unavailable,
+        _ => RemoteErrorKind::Other,
+    }
+}
+
+impl<E: SecretDecoder> Secret for KeyringSecret<E> {
+    type Output = E::Output;
+    type Error = SecretAccessError;
+
+    #[allow(clippy::unused_async)]
+    async fn get_secret_value(&self) -> Result<E::Output, Self::Error> {
+        let value = ::keyring::Entry::new(&self.service, &self.user)
+            .and_then(|entry| entry.get_secret())
+            .map(Zeroizing::new)
+            .map_err(|e| self.remote_error(e))?;
+        self.encoding
+            .decode(&value)
+            .map_err(|source| SecretAccessError::Decode { source })
+    }
+}
+
+/// Creates or replaces the credential.
+impl<E: SecretDecoder> SecretSink for KeyringSecret<E> {
+    type Error = SecretAccessError;
+
+    #[allow(clippy::unused_async)]
+    async fn put_secret(&self, value: &[u8]) -> Result<(), Self::Error> {
+        ::keyring::Entry::new(&self.service, &self.user)
+            .and_then(|entry| entry.set_secret(value))
+            .map_err(|e| self.remote_error(e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_missing_entry_is_not_found() {
+        ::keyring::set_default_credential_builder(::keyring::mock::default_credential_builder());
+
+        let result = KeyringSecret::string("chewie-crypto", "missing")
+            .get_secret_value()
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(SecretAccessError::Remote {
+                kind: RemoteErrorKind::NotFound,
+                ..
+            })
+        ));
+    }
+}
@@ -0,0 +1,245 @@
+//! Kubernetes API secret provider.
+
+use futures_util::{Stream, StreamExt};
+use k8s_openapi::api::core::v1::Secret as K8sSecret;
+use kube::{Api, Client, runtime::watcher};
+use snafu::prelude::*;
+
+use crate::{
+    DynError, MaybeSend,
+    secrets::{
+        Secret, SecretAccessError, SecretWatcher, SecretWithMetadata,
+        encodings::{SecretDecoder, StringEncoding},
+        providers::RemoteErrorKind,
+    },
+};
+
+#[derive(Debug, Snafu)]
+enum KubeError {
+    #[snafu(display("Secret not found"))]
+    MissingSecret,
+    #[snafu(display("Key '{key}' not found in secret"))]
+    MissingKey { key: String },
+}
+
+/// Retrieves a key of a Kubernetes Secret object through the Kubernetes API.
+///
+/// This suits workloads that can't mount secrets as volumes; otherwise, a
+/// [`KubernetesSecret`](crate::secrets::KubernetesSecret) avoids the API
+/// round-trip. The client is configured by the caller; the default
+/// (`Client::try_default().await`) uses the pod's service account. The
+/// service account needs the `get` verb on the secret, and `list` and `watch`
+/// to use it as a [`SecretWatcher`].
+#[derive(Clone)]
+pub struct KubeApiSecret<E: SecretDecoder = StringEncoding> {
+    /// The Secret objects in the namespace.
+    api: Api<K8sSecret>,
+    /// The namespace of the Secret object.
+    namespace: String,
+    /// The name of the Secret object.
+    name: String,
+    /// The key within the Secret object's data.
+    key: String,
+    /// The encoding of the secret.
+    encoding: E,
+}
+
+impl<E: SecretDecoder + std::fmt::Debug> std::fmt::Debug for KubeApiSecret<E> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("KubeApiSecret")
+            .field("namespace", &self.namespace)
+            .field("name", &self.name)
+            .field("key", &self.key)
+            .field("encoding", &self.encoding)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<E: SecretDecoder> KubeApiSecret<E> {
+    /// Creates a new Kubernetes API secret provider with the specified
+    /// encoding, reading `key` from the Secret object `name` in `namespace`.
+    pub fn new(
+        client: Client,
+        namespace: impl Into<String>,
+        name: impl Into<String>,
+        key: impl Into<String>,
+        encoding: E,
+    ) -> Self {
+        let namespace = namespace.into();
+        Self {
+            api: Api::namespaced(client, &namespace),
+            namespace,
+            name: name.into(),
+            key: key.into(),
+            encoding,
+        }
+    }
+
+    /// Returns the namespace of the Secret object.
+    #[must_use]
+    pub fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    /// Returns the name of the Secret object.
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the key within the Secret object.
+    #[must_use]
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Extracts and decodes the key from a Secret object.
+    fn extract(
+        &self,
+        secret: &K8sSecret,
+    ) -> Result<SecretWithMetadata<E::Output>, SecretAccessError> {
+        let data = secret
+            .data
+            .as_ref()
+            .and_then(|data| data.get(&self.key))
+            .ok_or_else(|| {
+                self.remote_error(
+                    RemoteErrorKind::NotFound,
+                    DynError::new(KubeError::MissingKey {
+                        key: self.key.clone(),
+                    }),
+                )
+            })?;
+        let value = self
+            .encoding
+            .decode(&data.0)
+            .map_err(|source| SecretAccessError::Decode { source })?;
+
+        let secret_meta =
+            SecretWithMetadata::new(value).with_source(format!("{}/{}", self.namespace, self.name));
+        Ok(match &secret.metadata.resource_version {
+            Some(version) => secret_meta.with_version(version),
+            None => secret_meta,
+        })
+    }
+
+    fn remote_error(&self, kind: RemoteErrorKind, source: DynError) -> SecretAccessError {
+        SecretAccessError::Remote {
+            name: format!("{}/{}/{}", self.namespace, self.name, self.key),
+            kind,
+            source,
+        }
+    }
+}
+
+impl KubeApiSecret<StringEncoding> {
+    /// Creates a new Kubernetes API secret provider returning a `SecretString`.
+    pub fn string(
+        client: Client,
+        namespace: impl Into<String>,
+        name: impl Into<String>,
+        key: impl Into<String>,
+    ) -> Self {
+        Self::new(client, namespace, name, key, StringEncoding)
+    }
+}
+
+fn error_kind(error: &kube::Error) -> RemoteErrorKind {
+    match error {
+        kube::Error::Api(response) => match response.code {
+            404 => RemoteErrorKind::NotFound,
+            401 | 403 => RemoteErrorKind::PermissionDenied,
+            429 | 500..=599 => RemoteErrorKind::Unavailable,
+            _ => RemoteErrorKind::Other,
+        },
+        kube::Error::HyperError(_) | kube::Error::Service(_) => RemoteErrorKind::Unavailable,
+        _ => RemoteErrorKind::Other,
+    }
+}
+
+impl<E: SecretDecoder> Secret for KubeApiSecret<E> {
+    type Output = E::Output;
+    type Error = SecretAccessError;
+
+    async fn get_secret_value(&self) -> Result<E::Output, Self::Error> {
+        self.get_secret_with_metadata()
+            .await
+            .map(SecretWithMetadata::into_value)
+    }
+
+    /// The version is the Secret object's `resourceVersion`.
+    async fn get_secret_with_metadata(&self) -> Result<SecretWithMetadata<E::Output>, Self::Error> {
+        let secret = self
+            .api
+            .get(&self.name)
+            .await
+            .map_err(|e| self.remote_error(error_kind(&e), DynError::new(e)))?;
+        self.extract(&secret)
+    }
+}
+
+/// Watches the Secret object through the API, yielding a value when its
+/// `resourceVersion` changes, rather than polling.
+///
+/// A deleted Secret object is reported as a not found error, and watching
+/// continues.
+impl<E: SecretDecoder + 'static> SecretWatcher for KubeApiSecret<E> {
+    type Error = SecretAccessError;
+    type Output = E::Output;
+
+    fn watch(&self) -> impl Stream<Item = Result<Self::Output, Self::Error>> + MaybeSend + 'static {
+        let this = self.clone();
+        let mut last_version = None;
+        watcher::watch_object(self.api.clone(), &self.name).filter_map(move |event| {
+            let item = match event {
+                Ok(Some(secret)) => {
+                    let version = secret.metadata.resource_version.clone();
+                    if version.is_some() && version == last_version {
+                        None
+                    } else {
+                        last_version = version;
+                        Some(this.extract(&secret).map(SecretWithMetadata::into_value))
+                    }
+                }
+                Ok(None) => {
+                    last_version = None;
+                    Some(Err(this.remote_error(
+                        RemoteErrorKind::NotFound,
+                        DynError::new(KubeError::MissingSecret),
+                    )))
+                }
+                Err(e) => Some(Err(
+                    this.remote_error(RemoteErrorKind::Unavailable, DynError::new(e))
+                )),
+            };
+            std::future::ready(item)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use kube::core::ErrorResponse;
+
+    use super::*;
+
+    fn api_error(code: u16) -> kube::Error {
+        kube::Error::Api(ErrorResponse {
+            status: "Failure".into(),
+            message: String::new(),
+            reason: String::new(),
+            code,
+        })
+    }
+
+    #[test]
+    fn test_error_kind() {
+        assert_eq!(error_kind(&api_error(404)), RemoteErrorKind::NotFound);
+        assert_eq!(
+            error_kind(&api_error(403)),
+            RemoteErrorKind::PermissionDenied
+        );
+        assert_eq!(error_kind(&api_error(503)), RemoteErrorKind::Unavailable);
+        assert_eq!(error_kind(&api_error(422)), RemoteErrorKind::Other);
+    }
+}
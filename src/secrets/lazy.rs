@@ -0,0 +1,120 @@
+//! Secret wrapper fetching the value once.
+
+use std::sync::Arc;
+
+use tokio::sync::OnceCell;
+
+use crate::secrets::Secret;
+
+/// A secret that fetches the value on first use, then serves it forever.
+///
+/// This suits values that never change for the life of the process, such as
+/// an OIDC client ID. Concurrent callers during the first fetch wait for a
+/// single call to the source. Failed fetches aren't cached, so the next
+/// caller tries again. Clones share the same value.
+///
+/// For values that may rotate, use a [`CachedSecret`](crate::secrets::CachedSecret)
+/// instead.
+#[derive(Debug)]
+pub struct LazySecret<S: Secret> {
+    inner: S,
+    value: Arc<OnceCell<S::Output>>,
+}
+
+impl<S: Secret> Clone for LazySecret<S> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            value: Arc::clone(&self.value),
+        }
+    }
+}
+
+impl<S: Secret> LazySecret<S>
+where
+    S::Output: Clone,
+{
+    /// Wraps a secret, fetching its value on first use.
+    pub fn new(inner: S) -> Self {
+        Self {
+            inner,
+            value: Arc::new(OnceCell::new()),
+        }
+    }
+
+    /// Returns the wrapped secret.
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    /// Returns `true` if the value has been fetched.
+    #[must_use]
+    pub fn is_initialized(&self) -> bool {
+        self.value.initialized()
+    }
+}
+
+impl<S: Secret> Secret for LazySecret<S>
+where
+    S::Output: Clone,
+{
+    type Error = S::Error;
+    type Output = S::Output;
+
+    async fn get_secret_value(&self) -> Result<Self::Output, Self::Error> {
+        self.value
+            .get_or_try_init(|| self.inner.get_secret_value())
+            .await
+            .cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::atomic::{AtomicUsize, Ordering},
+        time::Duration,
+    };
+
+    use snafu::Snafu;
+
+    use super::*;
+
+    #[derive(Debug, Snafu)]
+    #[snafu(display("Backend unavailable"))]
+    struct Unavailable;
+
+    /// Returns an incrementing count after a delay, failing the first call.
+    #[derive(Debug, Clone, Default)]
+    struct SlowSecret {
+        count: Arc<AtomicUsize>,
+    }
+
+    impl Secret for SlowSecret {
+        type Error = Unavailable;
+        type Output = usize;
+
+        async fn get_secret_value(&self) -> Result<Self::Output, Self::Error> {
+            let count = self.count.fetch_add(1, Ordering::SeqCst);
+            if count == 0 {
+                return Err(Unavailable);
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            Ok(count)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_first_fetches_are_coalesced() {
+        let secret = LazySecret::new(SlowSecret::default());
+
+        assert!(secret.get_secret_value().await.is_err());
+        assert!(!secret.is_initialized());
+        let clone = secret.clone();
+        let (a, b) = tokio::join!(secret.get_secret_value(), clone.get_secret_value());
+
+        assert_eq!((a.unwrap(), b.unwrap()), (1, 1));
+        assert_eq!(secret.get_secret_value().await.unwrap(), 1);
+        assert_eq!(secret.inner().count.load(Ordering::SeqCst), 2);
+    }
+}
@@ -0,0 +1,173 @@
+//! Secret values held in memory locked into RAM.
+
+use secrecy::{ExposeSecret, SecretBox};
+use snafu::prelude::*;
+
+use crate::{
+    MaybeSendSync,
+    secrets::{ExposeSecretBytes, Secret, SecretWithMetadata},
+};
+
+/// The error returned when secret memory can't be locked.
+///
+/// On Unix, this usually means the process exceeded `RLIMIT_MEMLOCK`.
+#[derive(Debug, Snafu)]
+#[snafu(display("Failed to lock secret memory"), context(name(MlockSnafu)))]
+pub struct LockError {
+    source: region::Error,
+}
+
+/// Secret bytes whose memory is locked into RAM, so it's never written to
+/// swap.
+///
+/// The pages holding the value are locked with `mlock` (`VirtualLock` on
+/// Windows) for as long as the value lives. The value is zeroized on drop,
+/// before the pages are unlocked. Locking is per page and not reference
+/// counted, so dropping a value unlocks any other value sharing its pages;
+/// this is a hardening measure, not a guarantee.
+///
+/// Bytes are copied into a new allocation when locked, so lock values as soon
+/// as they're retrieved, e.g. with [`LockedSecret`].
+pub struct LockedSecretBox {
+    // Declared before the guard, so the value is zeroized while still locked.
+    value: SecretBox<[u8]>,
+    _guard: Option<region::LockGuard>,
+}
+
+impl LockedSecretBox {
+    /// Copies the bytes into locked memory.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the memory can't be locked.
+    pub fn new(bytes: &[u8]) -> Result<Self, LockError> {
+        let value = SecretBox::new(Box::from(bytes));
+        let guard = if bytes.is_empty() {
+            None
+        } else {
+            let exposed: &[u8] = value.expose_secret();
+            Some(region::lock(exposed.as_ptr(), exposed.len()).context(MlockSnafu)?)
+        };
+        Ok(Self {
+            value,
+            _guard: guard,
+        })
+    }
+}
+
+impl ExposeSecret<[u8]> for LockedSecretBox {
+    fn expose_secret(&self) -> &[u8] {
+        self.value.expose_secret()
+    }
+}
+
+impl ExposeSecretBytes for LockedSecretBox {
+    fn expose_secret_bytes(&self) -> &[u8] {
+        self.value.expose_secret()
+    }
+}
+
+impl std::fmt::Debug for LockedSecretBox {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("LockedSecretBox([REDACTED])")
+    }
+}
+
+/// Errors returned by [`LockedSecret`].
+#[derive(Debug, Snafu)]
+pub enum LockedSecretError<E: std::error::Error + MaybeSendSync + 'static> {
+    /// The secret could not be retrieved.
+    #[snafu(display("Failed to retrieve secret"))]
+    Inner {
+        /// The error from the wrapped secret.
+        source: E,
+    },
+    /// The value could not be locked into memory.
+    #[snafu(display("Failed to lock secret"))]
+    Lock {
+        /// The underlying locking error.
+        source: LockError,
+    },
+}
+
+/// A secret whose values are copied into a [`LockedSecretBox`] as soon as
+/// they're retrieved, for deployments that must keep key material out of
+/// swap.
+///
+/// Retrieval fails if the memory can't be locked, rather than silently
+/// returning an unlocked value.
+#[derive(Debug, Clone)]
+pub struct LockedSecret<S> {
+    inner: S,
+}
+
+impl<S: Secret> LockedSecret<S>
+where
+    S::Output: ExposeSecretBytes,
+{
+    /// Wraps a secret, locking its values.
+    pub fn new(inner: S) -> Self {
+        Self { inner }
+    }
+
+    /// Returns the wrapped secret.
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+}
+
+impl<S: Secret> Secret for LockedSecret<S>
+where
+    S::Output: ExposeSecretBytes,
+{
+    type Error = LockedSecretError<S::Error>;
+    type Output = LockedSecretBox;
+
+    async fn get_secret_value(&self) -> Result<Self::Output, Self::Error> {
+        let value = self.inner.get_secret_value().await.context(InnerSnafu)?;
+        LockedSecretBox::new(value.expose_secret_bytes()).context(LockSnafu)
+    }
+
+    async fn get_secret_with_metadata(
+        &self,
+    ) -> Result<SecretWithMetadata<Self::Output>, Self::Error> {
+        let secret = self
+            .inner
+            .get_secret_with_metadata()
+            .await
+            .context(InnerSnafu)?;
+        let value =
+            LockedSecretBox::new(secret.value().expose_secret_bytes()).context(LockSnafu)?;
+        Ok(secret.map(|_| value))
+    }
+
+    async fn health_check(&self) -> Result<(), Self::Error> {
+        self.inner.health_check().await.context(InnerSnafu)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::secrets::StaticSecret;
+
+    #[tokio::test]
+    async fn test_locked_secret_copies_value() {
+        let secret = LockedSecret::new(StaticSecret::string("s3cret"));
+
+        let value = secret.get_secret_value().await.unwrap();
+
+        assert_eq!(value.expose_secret(), b"s3cret");
+        assert_eq!(format!("{value:?}"), "LockedSecretBox([REDACTED])");
+    }
+
+    #[test]
+    fn test_empty_value_needs_no_lock() {
+        assert!(
+            LockedSecretBox::new(&[])
+                .unwrap()
+                .expose_secret()
+                .is_empty()
+        );
+    }
+}
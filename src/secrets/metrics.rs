@@ -0,0 +1,136 @@
+//! Secret wrapper emitting metrics for secret retrieval.
+
+use std::{borrow::Cow, sync::Arc};
+
+use web_time::Instant;
+
+use crate::{
+    metrics::{
+        self, MetricsRecorder, SECRET_DURATION_SECONDS, SECRET_FAILURE_TOTAL, SECRET_SUCCESS_TOTAL,
+    },
+    secrets::{Secret, SecretWithMetadata},
+};
+
+/// A secret that reports retrieval outcomes and latency to a [`MetricsRecorder`].
+///
+/// Secrets have no identity of their own, so the wrapper is given a name,
+/// which is used as the `secret` label. See the [`metrics`](crate::metrics)
+/// module for the emitted metrics.
+pub struct MetricsSecret<S, R> {
+    inner: S,
+    name: Cow<'static, str>,
+    recorder: Arc<R>,
+}
+
+impl<S: Clone, R> Clone for MetricsSecret<S, R> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            name: self.name.clone(),
+            recorder: Arc::clone(&self.recorder),
+        }
+    }
+}
+
+impl<S: std::fmt::Debug, R> std::fmt::Debug for MetricsSecret<S, R> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MetricsSecret")
+            .field("inner", &self.inner)
+            .field("name", &self.name)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<S: Secret, R: MetricsRecorder> MetricsSecret<S, R> {
+    /// Wraps a secret, reporting to the given recorder under the given name.
+    pub fn new(inner: S, name: impl Into<Cow<'static, str>>, recorder: R) -> Self {
+        Self {
+            inner,
+            name: name.into(),
+            recorder: Arc::new(recorder),
+        }
+    }
+
+    /// Returns the wrapped secret.
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    /// Returns the name used to label this secret's metrics.
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn record<T>(
+        &self,
+        retrieval: impl Future<Output = Result<T, S::Error>>,
+    ) -> Result<T, S::Error> {
+        let started = Instant::now();
+        let result = retrieval.await;
+        metrics::record_outcome(
+            &self.recorder,
+            [
+                SECRET_SUCCESS_TOTAL,
+                SECRET_FAILURE_TOTAL,
+                SECRET_DURATION_SECONDS,
+            ],
+            result.is_ok(),
+            started.elapsed(),
+            &[("secret", &self.name)],
+        );
+        result
+    }
+}
+
+impl<S: Secret, R: MetricsRecorder> Secret for MetricsSecret<S, R> {
+    type Error = S::Error;
+    type Output = S::Output;
+
+    async fn get_secret_value(&self) -> Result<Self::Output, Self::Error> {
+        self.record(self.inner.get_secret_value()).await
+    }
+
+    async fn get_secret_with_metadata(
+        &self,
+    ) -> Result<SecretWithMetadata<Self::Output>, Self::Error> {
+        self.record(self.inner.get_secret_with_metadata()).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+
+    use super::*;
+    use crate::metrics::tests::MemoryRecorder;
+
+    #[derive(Debug, Clone)]
+    struct StaticSecret;
+
+    impl Secret for StaticSecret {
+        type Error = Infallible;
+        type Output = &'static str;
+
+        async fn get_secret_value(&self) -> Result<Self::Output, Self::Error> {
+            Ok("value")
+        }
+    }
+
+    #[tokio::test]
+    async fn test_retrieval_is_counted() {
+        let recorder = Arc::new(MemoryRecorder::default());
+        let secret = MetricsSecret::new(StaticSecret, "api-key", Arc::clone(&recorder));
+
+        assert_eq!(secret.get_secret_value().await.unwrap(), "value");
+
+        assert_eq!(
+            *recorder.counters.lock().unwrap(),
+            [(SECRET_SUCCESS_TOTAL, vec![("secret", "api-key".to_owned())])]
+        );
+        assert_eq!(
+            *recorder.histograms.lock().unwrap(),
+            [SECRET_DURATION_SECONDS]
+        );
+    }
+}
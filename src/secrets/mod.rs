@@ -1,11 +1,123 @@
 //! Secret management traits and providers.
 
+mod audit;
+mod bytes;
+mod cached;
+#[cfg(feature = "cloud-identity")]
+mod cloud;
+#[cfg(feature = "conjur")]
+mod conjur;
+mod derived;
+#[cfg(windows)]
+mod dpapi;
+mod dynamic;
 mod encodings;
+#[cfg(feature = "encrypted-cache")]
+mod encrypted_cache;
+#[cfg(feature = "etcd")]
+mod etcd;
+mod expiring;
+#[cfg(feature = "gcp-secret-manager")]
+mod gcp;
+mod health;
+#[cfg(feature = "keyring")]
+mod keyring;
+#[cfg(feature = "kube")]
+mod kube;
+mod lazy;
+#[cfg(all(feature = "mlock", native))]
+mod locked;
+mod metrics;
+#[cfg(feature = "argon2")]
+mod passphrase;
+#[cfg(feature = "cli")]
+mod prompt;
 mod providers;
+mod refreshing;
+mod retry;
 mod secret;
+mod set;
+mod sink;
+mod sops;
+mod spec;
+#[cfg(feature = "aws-ssm")]
+mod ssm;
+#[cfg(test)]
+#[cfg(any(
+    feature = "cloud-identity",
+    feature = "conjur",
+    feature = "etcd",
+    feature = "vault"
+))]
+mod test_server;
+mod timeout;
+#[cfg(feature = "tracing")]
+mod traced;
+mod validated;
+#[cfg(feature = "vault")]
+mod vault;
+mod versioned;
+mod watcher;
+mod zip;
 
+pub use audit::{AccessOutcome, AuditedSecret, SecretAccessAudit, SecretAccessEvent};
+pub use bytes::{ConvertError, ConvertSecret, ConvertedSecret, ExposeSecretBytes, FromSecretBytes};
+pub use cached::CachedSecret;
+#[cfg(feature = "cloud-identity")]
+pub use cloud::{CloudIdentityToken, CloudProvider};
+#[cfg(feature = "conjur")]
+pub use conjur::{ConjurAuth, ConjurClient, ConjurSecret};
+pub use derived::{DeriveError, DerivedSecret};
+#[cfg(windows)]
+pub use dpapi::{DpapiScope, DpapiSecret};
+pub use dynamic::DynSecret;
 pub use encodings::{
-    Base64Encoding, BinaryEncoding, DecodingError, HexEncoding, SecretDecoder, StringEncoding,
+    Base64Encoding, Base64UrlEncoding, BinaryEncoding, DecodingError, DelimitedEncoding,
+    HexEncoding, JsonFieldEncoding, JwkEncoding, LenientBase64Encoding, MapEncoding, PemEncoding,
+    SecretDecoder, SecretMap, StringEncoding,
 };
-pub use providers::EnvVarSecret;
-pub use secret::Secret;
+#[cfg(feature = "encrypted-cache")]
+pub use encrypted_cache::{EncryptedCacheError, EncryptedCachedSecret};
+#[cfg(feature = "etcd")]
+pub use etcd::{EtcdClient, EtcdSecret};
+pub use expiring::{ExpiredError, ExpiringSecret};
+#[cfg(feature = "gcp-secret-manager")]
+pub use gcp::GcpSecret;
+pub use health::{HealthReport, SecretHealthCheck};
+#[cfg(feature = "keyring")]
+pub use keyring::KeyringSecret;
+#[cfg(feature = "kube")]
+pub use kube::KubeApiSecret;
+pub use lazy::LazySecret;
+#[cfg(all(feature = "mlock", native))]
+pub use locked::{LockError, LockedSecret, LockedSecretBox, LockedSecretError};
+pub use metrics::MetricsSecret;
+#[cfg(feature = "argon2")]
+pub use passphrase::PassphraseSecret;
+#[cfg(feature = "cli")]
+pub use prompt::{PromptError, PromptSecret};
+pub use providers::{
+    CommandSecret, DockerSecret, EnvVarSecret, Environment, FileSecret, KubernetesSecret,
+    ProcessEnv, RemoteErrorKind, SecretAccessError, StaticSecret, SystemdCredential,
+};
+pub use refreshing::{RefreshingSecret, SecretWatch};
+pub use retry::RetrySecret;
+pub use secret::{Secret, SecretWithMetadata};
+pub use set::{PrefetchedSecrets, SecretSet};
+pub use sink::SecretSink;
+pub use sops::SopsSecret;
+pub use spec::{RawSecret, SchemeHandler, SecretFactory, SecretSpec, SpecError};
+#[cfg(feature = "aws-ssm")]
+pub use ssm::SsmParameter;
+pub use timeout::TimeoutSecret;
+#[cfg(feature = "tracing")]
+pub use traced::TracedSecret;
+pub use validated::{
+    MinEntropy, MinLength, RequiredPrefix, SecretCheck, ValidatedSecret, ValidationError,
+    WellFormedPem,
+};
+#[cfg(feature = "vault")]
+pub use vault::{VaultAuth, VaultClient, VaultKvSecret};
+pub use versioned::{SecretVersion, VersionedSecret};
+pub use watcher::{FileWatcher, PollingWatcher, SecretWatcher};
+pub use zip::{ZipError, ZipSecret};
@@ -0,0 +1,162 @@
+//! Interactive terminal prompt secret provider.
+
+use std::{io::IsTerminal, sync::Arc};
+
+use secrecy::zeroize::Zeroizing;
+use snafu::prelude::*;
+
+use crate::{
+    DynError,
+    secrets::{
+        DecodingError, DynSecret, Secret,
+        encodings::{SecretDecoder, StringEncoding},
+    },
+};
+
+/// Errors returned by [`PromptSecret`].
+#[derive(Debug, Snafu)]
+pub enum PromptError {
+    /// There is no terminal to prompt on, and no fallback was configured.
+    #[snafu(display("Can't prompt for a secret without a terminal"))]
+    NotInteractive,
+    /// Reading from the terminal failed.
+    #[snafu(display("Failed to read secret from the terminal"))]
+    Prompt {
+        /// The underlying I/O error.
+        source: std::io::Error,
+    },
+    /// Failed to decode the secret.
+    #[snafu(display("Failed to decode secret"))]
+    Decode {
+        /// The encoding error.
+        source: DecodingError,
+    },
+    /// The fallback secret failed.
+    #[snafu(display("Fallback secret failed"))]
+    Fallback {
+        /// The error from the fallback secret.
+        source: DynError,
+    },
+}
+
+/// Prompts for a secret on the terminal, with echo disabled.
+///
+/// When standard input isn't a terminal (e.g. in CI, or when piped), the
+/// fallback secret is used instead, if configured. The prompt blocks the
+/// current task until the user presses enter, and is shown on every call, so
+/// wrap it in a [`CachedSecret`](super::CachedSecret) to prompt once.
+#[derive(Clone)]
+pub struct PromptSecret<E: SecretDecoder = StringEncoding> {
+    /// The prompt to show.
+    prompt: String,
+    /// The encoding of the secret.
+    encoding: E,
+    /// The secret used when not running interactively.
+    fallback: Option<Arc<dyn DynSecret<Output = E::Output>>>,
+}
+
+impl<E: SecretDecoder + std::fmt::Debug> std::fmt::Debug for PromptSecret<E> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PromptSecret")
+            .field("prompt", &self.prompt)
+            .field("encoding", &self.encoding)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<E: SecretDecoder> PromptSecret<E> {
+    /// Creates a new prompt secret provider with the specified encoding.
+    pub fn new(prompt: impl Into<String>, encoding: E) -> Self {
+        Self {
+            prompt: prompt.into(),
+            encoding,
+            fallback: None,
+        }
+    }
+
+    /// Uses the given secret when standard input isn't a terminal.
+    #[must_use]
+    pub fn with_fallback<F>(mut self, fallback: F) -> Self
+    where
+        F: Secret<Output = E::Output> + 'static,
+    {
+        self.fallback = Some(Arc::new(fallback));
+        self
+    }
+
+    /// Returns the prompt shown to the user.
+    #[must_use]
+    pub fn prompt(&self) -> &str {
+        &self.prompt
+    }
+}
+
+impl PromptSecret<StringEncoding> {
+    /// Creates a new prompt secret provider returning a `SecretString`.
+    pub fn string(prompt: impl Into<String>) -> Self {
+        Self::new(prompt, StringEncoding)
+    }
+}
+
+impl<E: SecretDecoder> Secret for PromptSecret<E> {
+    type Output = E::Output;
+    type Error = PromptError;
+
+    async fn get_secret_value(&self) -> Result<E::Output, Self::Error> {
+        if !std::io::stdin().is_terminal() {
+            let fallback = self.fallback.as_ref().context(NotInteractiveSnafu)?;
+            return DynSecret::get_secret_value(&**fallback)
+                .await
+                .context(FallbackSnafu);
+        }
+        let value = rpassword::prompt_password(&self.prompt)
+            .map(Zeroizing::new)
+            .context(PromptSnafu)?;
+        self.encoding.decode(value.as_bytes()).context(DecodeSnafu)
+    }
+
+    /// Succeeds without prompting if there is a terminal, and otherwise checks
+    /// the fallback.
+    async fn health_check(&self) -> Result<(), Self::Error> {
+        if std::io::stdin().is_terminal() {
+            return Ok(());
+        }
+        let fallback = self.fallback.as_ref().context(NotInteractiveSnafu)?;
+        DynSecret::health_check(&**fallback)
+            .await
+            .context(FallbackSnafu)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::IsTerminal;
+
+    use secrecy::ExposeSecret;
+
+    use super::{PromptError, PromptSecret};
+    use crate::secrets::{Secret, StaticSecret};
+
+    #[tokio::test]
+    async fn test_non_interactive_uses_fallback() {
+        if std::io::stdin().is_terminal() {
+            return;
+        }
+        let without_fallback = PromptSecret::string("Password: ");
+        let with_fallback =
+            PromptSecret::string("Password: ").with_fallback(StaticSecret::string("s3cret"));
+
+        assert!(matches!(
+            without_fallback.get_secret_value().await,
+            Err(PromptError::NotInteractive)
+        ));
+        assert_eq!(
+            with_fallback
+                .get_secret_value()
+                .await
+                .unwrap()
+                .expose_secret(),
+            "s3cret"
+        );
+    }
+}
@@ -1,12 +1,22 @@
 //! Built-in secret source providers.
 
-use std::ffi::OsString;
+use std::{
+    collections::{BTreeMap, HashMap},
+    convert::Infallible,
+    ffi::{OsStr, OsString},
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
+};
 
+use secrecy::{SecretString, zeroize::Zeroizing};
 use snafu::prelude::*;
 
-use crate::secrets::{
-    DecodingError, Secret,
-    encodings::{SecretDecoder, StringEncoding},
+use crate::{
+    DynError, MaybeSendSync,
+    secrets::{
+        DecodingError, Secret, SecretSink, SecretWithMetadata,
+        encodings::{SecretDecoder, StringEncoding},
+    },
 };
 
 /// Errors that can occur when using built-in secret implementations.
@@ -20,6 +30,63 @@ pub enum SecretAccessError {
         /// The underlying error from the environment variable lookup.
         source: std::env::VarError,
     },
+    /// The file could not be read.
+    #[snafu(display("Failed to read secret file '{}'", path.display()))]
+    FileAccess {
+        /// The path of the file that could not be read.
+        path: PathBuf,
+        /// The underlying I/O error.
+        source: std::io::Error,
+    },
+    /// The file could not be written.
+    #[snafu(display("Failed to write secret file '{}'", path.display()))]
+    FileWrite {
+        /// The path of the file that could not be written.
+        path: PathBuf,
+        /// The underlying I/O error.
+        source: std::io::Error,
+    },
+    /// The secret name can't be used as a file name within its directory,
+    /// e.g. because it contains a path separator or is `..`.
+    #[snafu(display("Invalid secret name '{name}'"))]
+    InvalidName {
+        /// The rejected name.
+        name: String,
+    },
+    /// The secret store only holds text, and the value is not valid UTF-8.
+    #[snafu(display("Secret value is not valid UTF-8"))]
+    NotUtf8 {
+        /// The UTF-8 error.
+        source: std::str::Utf8Error,
+    },
+    /// The command could not be run.
+    #[snafu(display("Failed to run '{}'", program.to_string_lossy()))]
+    CommandSpawn {
+        /// The program that could not be run.
+        program: OsString,
+        /// The underlying I/O error.
+        source: std::io::Error,
+    },
+    /// The command exited unsuccessfully.
+    #[snafu(display("'{}' failed with {status}: {stderr}", program.to_string_lossy()))]
+    CommandFailed {
+        /// The program that failed.
+        program: OsString,
+        /// The exit status of the command.
+        status: std::process::ExitStatus,
+        /// The command's standard error, lossily decoded and trimmed.
+        stderr: String,
+    },
+    /// The secret could not be retrieved from a remote secret store.
+    #[snafu(display("Failed to access secret '{name}' ({kind})"))]
+    Remote {
+        /// The name of the secret in the secret store.
+        name: String,
+        /// The category of the failure.
+        kind: RemoteErrorKind,
+        /// The error from the secret store's client.
+        source: DynError,
+    },
     /// Failed to decode the secret.
     #[snafu(display("Failed to decode secret"))]
     Decode {
@@ -28,13 +95,100 @@ pub enum SecretAccessError {
     },
 }
 
+impl SecretAccessError {
+    /// Returns `true` if retrying later may succeed, i.e. the remote store was
+    /// [unavailable](RemoteErrorKind::Unavailable).
+    #[must_use]
+    pub fn is_transient(&self) -> bool {
+        matches!(
+            self,
+            Self::Remote {
+                kind: RemoteErrorKind::Unavailable,
+                ..
+            }
+        )
+    }
+}
+
+/// The category of a failure to access a remote secret store.
+///
+/// Each store's client errors are mapped to these categories, so callers can
+/// handle failures (e.g. retrying when unavailable) independently of the store.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum RemoteErrorKind {
+    /// The secret (or the requested version) does not exist.
+    NotFound,
+    /// The caller is not authenticated, or not authorized to read the secret.
+    PermissionDenied,
+    /// The store could not be reached, timed out or throttled the request.
+    /// Retrying later may succeed.
+    Unavailable,
+    /// Any other failure.
+    Other,
+}
+
+impl std::fmt::Display for RemoteErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::NotFound => "not found",
+            Self::PermissionDenied => "permission denied",
+            Self::Unavailable => "unavailable",
+            Self::Other => "other error",
+        })
+    }
+}
+
+/// A source of environment variables for [`EnvVarSecret`].
+///
+/// [`ProcessEnv`] reads the process environment. Maps of names to values
+/// provide a synthetic environment, e.g. so tests can run in parallel without
+/// mutating the process environment, or on WASM targets without one.
+pub trait Environment: MaybeSendSync + Clone {
+    /// Returns the value of a variable, if it is set.
+    fn var_os(&self, name: &OsStr) -> Option<OsString>;
+}
+
+/// The environment of the current process, read with [`std::env::var_os`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ProcessEnv;
+
+impl Environment for ProcessEnv {
+    fn var_os(&self, name: &OsStr) -> Option<OsString> {
+        std::env::var_os(name)
+    }
+}
+
+impl<S: std::hash::BuildHasher + Clone + MaybeSendSync> Environment for HashMap<String, String, S> {
+    fn var_os(&self, name: &OsStr) -> Option<OsString> {
+        self.get(name.to_str()?).map(OsString::from)
+    }
+}
+
+impl Environment for BTreeMap<String, String> {
+    fn var_os(&self, name: &OsStr) -> Option<OsString> {
+        self.get(name.to_str()?).map(OsString::from)
+    }
+}
+
+impl<V: Environment> Environment for std::sync::Arc<V> {
+    fn var_os(&self, name: &OsStr) -> Option<OsString> {
+        (**self).var_os(name)
+    }
+}
+
 /// Retrieves secrets from environment variables with configurable encoding.
+///
+/// Variables are read from the process environment, unless another
+/// [`Environment`] is set with [`EnvVarSecret::with_env`].
 #[derive(Debug, Clone)]
-pub struct EnvVarSecret<E: SecretDecoder = StringEncoding> {
+pub struct EnvVarSecret<E: SecretDecoder = StringEncoding, V: Environment = ProcessEnv> {
     /// The name of the environment variable containing the secret.
     var_name: OsString,
     /// The encoding of the secret.
     encoding: E,
+    /// The environment the variable is read from.
+    env: V,
 }
 
 impl<E: SecretDecoder> EnvVarSecret<E> {
@@ -43,8 +197,27 @@ impl<E: SecretDecoder> EnvVarSecret<E> {
         Self {
             var_name: var_name.into(),
             encoding,
+            env: ProcessEnv,
+        }
+    }
+}
+
+impl<E: SecretDecoder, V: Environment> EnvVarSecret<E, V> {
+    /// Reads the variable from the given environment, rather than the process
+    /// environment.
+    pub fn with_env<W: Environment>(self, env: W) -> EnvVarSecret<E, W> {
+        EnvVarSecret {
+            var_name: self.var_name,
+            encoding: self.encoding,
+            env,
         }
     }
+
+    /// Returns the name of the environment variable.
+    #[must_use]
+    pub fn var_name(&self) -> &OsStr {
+        &self.var_name
+    }
 }
 
 impl EnvVarSecret<StringEncoding> {
@@ -54,13 +227,902 @@ impl EnvVarSecret<StringEncoding> {
     }
 }
 
-impl<E: SecretDecoder> Secret for EnvVarSecret<E> {
+impl<E: SecretDecoder, V: Environment> Secret for EnvVarSecret<E, V> {
     type Output = E::Output;
     type Error = SecretAccessError;
 
     async fn get_secret_value(&self) -> Result<E::Output, Self::Error> {
         let var_name = self.var_name.clone();
-        let value = std::env::var(&self.var_name).context(EnvAccessSnafu { var_name })?;
+        let value = self
+            .env
+            .var_os(&self.var_name)
+            .ok_or(std::env::VarError::NotPresent)
+            .and_then(|value| value.into_string().map_err(std::env::VarError::NotUnicode))
+            .map(Zeroizing::new)
+            .context(EnvAccessSnafu { var_name })?;
         self.encoding.decode(value.as_bytes()).context(DecodeSnafu)
     }
 }
+
+/// A secret with a fixed value, for tests and local development.
+///
+/// The value is never included in the `Debug` output.
+#[derive(Clone)]
+pub struct StaticSecret<T> {
+    value: T,
+}
+
+impl<T> StaticSecret<T> {
+    /// Creates a secret that always returns the given value.
+    pub fn new(value: T) -> Self {
+        Self { value }
+    }
+}
+
+impl StaticSecret<SecretString> {
+    /// Creates a secret that always returns the given string.
+    pub fn string(value: impl Into<String>) -> Self {
+        Self::new(SecretString::from(value.into()))
+    }
+}
+
+impl<T> std::fmt::Debug for StaticSecret<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StaticSecret").finish_non_exhaustive()
+    }
+}
+
+impl<T: Clone + MaybeSendSync> Secret for StaticSecret<T> {
+    type Output = T;
+    type Error = Infallible;
+
+    async fn get_secret_value(&self) -> Result<T, Self::Error> {
+        Ok(self.value.clone())
+    }
+}
+
+/// Retrieves secrets from the standard output of a command, such as a password
+/// manager's CLI (`pass show`, `op read`).
+///
+/// The command is run on every call, without a shell, and blocks the current
+/// task until it exits. Trailing newlines are trimmed, as with [`FileSecret`].
+#[derive(Debug, Clone)]
+pub struct CommandSecret<E: SecretDecoder = StringEncoding> {
+    /// The program to run.
+    program: OsString,
+    /// The arguments to pass to the program.
+    args: Vec<OsString>,
+    /// The encoding of the secret.
+    encoding: E,
+}
+
+impl<E: SecretDecoder> CommandSecret<E> {
+    /// Creates a new command secret provider with the specified encoding.
+    pub fn new<I>(program: impl Into<OsString>, args: I, encoding: E) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<OsString>,
+    {
+        Self {
+            program: program.into(),
+            args: args.into_iter().map(Into::into).collect(),
+            encoding,
+        }
+    }
+}
+
+impl CommandSecret<StringEncoding> {
+    /// Creates a new command secret provider returning a `SecretString`.
+    pub fn string<I>(program: impl Into<OsString>, args: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<OsString>,
+    {
+        Self::new(program, args, StringEncoding)
+    }
+}
+
+impl<E: SecretDecoder> Secret for CommandSecret<E> {
+    type Output = E::Output;
+    type Error = SecretAccessError;
+
+    #[allow(clippy::unused_async)]
+    async fn get_secret_value(&self) -> Result<E::Output, Self::Error> {
+        let output = std::process::Command::new(&self.program)
+            .args(&self.args)
+            .stdin(std::process::Stdio::null())
+            .output()
+            .context(CommandSpawnSnafu {
+                program: &self.program,
+            })?;
+        ensure!(
+            output.status.success(),
+            CommandFailedSnafu {
+                program: &self.program,
+                status: output.status,
+                stderr: String::from_utf8_lossy(&output.stderr).trim(),
+            }
+        );
+        let stdout = Zeroizing::new(output.stdout);
+        self.encoding
+            .decode(trim_trailing_newlines(&stdout))
+            .context(DecodeSnafu)
+    }
+}
+
+/// Reads a file, asynchronously with the `tokio-fs` feature.
+///
+/// The contents are zeroized once decoded.
+#[cfg_attr(not(feature = "tokio-fs"), allow(clippy::unused_async))]
+pub(crate) async fn read_file(path: &Path) -> Result<Zeroizing<Vec<u8>>, SecretAccessError> {
+    #[cfg(feature = "tokio-fs")]
+    let result = tokio::fs::read(path).await;
+    #[cfg(not(feature = "tokio-fs"))]
+    let result = std::fs::read(path);
+    result.map(Zeroizing::new).context(FileAccessSnafu { path })
+}
+
+/// Returns a file's metadata, asynchronously with the `tokio-fs` feature.
+#[cfg_attr(not(feature = "tokio-fs"), allow(clippy::unused_async))]
+pub(crate) async fn file_metadata(path: &Path) -> Option<std::fs::Metadata> {
+    #[cfg(feature = "tokio-fs")]
+    let metadata = tokio::fs::metadata(path).await;
+    #[cfg(not(feature = "tokio-fs"))]
+    let metadata = std::fs::metadata(path);
+    metadata.ok()
+}
+
+/// Returns when a file was last modified, if the platform reports it.
+pub(crate) fn modified_at(metadata: &std::fs::Metadata) -> Option<web_time::SystemTime> {
+    let since_epoch = metadata
+        .modified()
+        .ok()?
+        .duration_since(std::time::UNIX_EPOCH)
+        .ok()?;
+    Some(web_time::UNIX_EPOCH + since_epoch)
+}
+
+/// Makes temporary file names unique within the process.
+static TEMP_FILE_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Replaces a file atomically, by writing a sibling temporary file and
+/// renaming it over the original.
+///
+/// Each write uses its own temporary file, so concurrent writes can't publish
+/// each other's partial contents. On Unix, the file is only readable and
+/// writable by its owner, and the directory is synced after the rename.
+#[cfg_attr(not(feature = "tokio-fs"), allow(clippy::unused_async))]
+async fn write_file(path: &Path, contents: &[u8]) -> Result<(), SecretAccessError> {
+    let mut temp_name = OsString::from(".");
+    temp_name.push(path.file_name().unwrap_or_default());
+    temp_name.push(format!(
+        ".{}.{}.tmp",
+        std::process::id(),
+        TEMP_FILE_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    let temp = path.with_file_name(temp_name);
+    #[cfg(unix)]
+    let dir = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+
+    #[cfg(feature = "tokio-fs")]
+    let result = async {
+        use tokio::io::AsyncWriteExt as _;
+
+        let mut options = tokio::fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        options.mode(0o600);
+        let mut file = options.open(&temp).await?;
+        let written = async {
+            #[cfg(unix)]
+            file.set_permissions(std::os::unix::fs::PermissionsExt::from_mode(0o600))
+                .await?;
+            file.write_all(contents).await?;
+            file.sync_all().await?;
+            tokio::fs::rename(&temp, path).await
+        }
+        .await;
+        if written.is_err() {
+            let _ = tokio::fs::remove_file(&temp).await;
+        }
+        written?;
+        #[cfg(unix)]
+        tokio::fs::File::open(dir).await?.sync_all().await?;
+        Ok(())
+    }
+    .await;
+    #[cfg(not(feature = "tokio-fs"))]
+    let result = (|| {
+        use std::io::Write as _;
+
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        let mut file = options.open(&temp)?;
+        let written = (|| {
+            #[cfg(unix)]
+            file.set_permissions(std::os::unix::fs::PermissionsExt::from_mode(0o600))?;
+            file.write_all(contents)?;
+            file.sync_all()?;
+            std::fs::rename(&temp, path)
+        })();
+        if written.is_err() {
+            let _ = std::fs::remove_file(&temp);
+        }
+        written?;
+        #[cfg(unix)]
+        std::fs::File::open(dir)?.sync_all()?;
+        Ok(())
+    })();
+    result.context(FileWriteSnafu { path })
+}
+
+/// Retrieves secrets from a file with configurable encoding.
+///
+/// The file is read on every call, so the latest value is returned after the
+/// file is replaced. Trailing newlines are trimmed before decoding, as most
+/// tools write one when creating secret files; use
+/// [`FileSecret::keep_trailing_newlines`] for binary secrets.
+///
+/// Reads are blocking unless the `tokio-fs` feature is enabled, in which case
+/// they run on the Tokio blocking pool.
+#[derive(Debug, Clone)]
+pub struct FileSecret<E: SecretDecoder = StringEncoding> {
+    /// The path of the file containing the secret.
+    path: PathBuf,
+    /// The encoding of the secret.
+    encoding: E,
+    /// Whether to trim trailing newlines before decoding.
+    trim_newlines: bool,
+}
+
+impl<E: SecretDecoder> FileSecret<E> {
+    /// Creates a new file secret provider with the specified encoding.
+    pub fn new(path: impl Into<PathBuf>, encoding: E) -> Self {
+        Self {
+            path: path.into(),
+            encoding,
+            trim_newlines: true,
+        }
+    }
+
+    /// Decodes the file contents as-is, without trimming trailing newlines.
+    #[must_use]
+    pub fn keep_trailing_newlines(mut self) -> Self {
+        self.trim_newlines = false;
+        self
+    }
+
+    /// Returns the path of the file containing the secret.
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl FileSecret<StringEncoding> {
+    /// Creates a new file secret provider returning a `SecretString`.
+    pub fn string(path: impl Into<PathBuf>) -> Self {
+        Self::new(path, StringEncoding)
+    }
+}
+
+impl<E: SecretDecoder> Secret for FileSecret<E> {
+    type Output = E::Output;
+    type Error = SecretAccessError;
+
+    async fn get_secret_value(&self) -> Result<E::Output, Self::Error> {
+        let contents = read_file(&self.path).await?;
+        let contents = if self.trim_newlines {
+            trim_trailing_newlines(&contents)
+        } else {
+            &contents
+        };
+        self.encoding.decode(contents).context(DecodeSnafu)
+    }
+
+    /// The creation time is the file's modification time, and the source is
+    /// its path.
+    async fn get_secret_with_metadata(&self) -> Result<SecretWithMetadata<E::Output>, Self::Error> {
+        let value = self.get_secret_value().await?;
+        let secret = SecretWithMetadata::new(value).with_source(self.path.display().to_string());
+        let metadata = file_metadata(&self.path).await;
+        Ok(match metadata.as_ref().and_then(modified_at) {
+            Some(modified) => secret.with_created_at(modified),
+            None => secret,
+        })
+    }
+}
+
+/// Replaces the file atomically, so concurrent readers never see a partial
+/// value.
+impl<E: SecretDecoder> SecretSink for FileSecret<E> {
+    type Error = SecretAccessError;
+
+    async fn put_secret(&self, value: &[u8]) -> Result<(), Self::Error> {
+        write_file(&self.path, value).await
+    }
+}
+
+fn trim_trailing_newlines(mut contents: &[u8]) -> &[u8] {
+    while let [rest @ .., b'\n' | b'\r'] = contents {
+        contents = rest;
+    }
+    contents
+}
+
+/// Returns `true` if `name` is a single, normal file name, so joining it onto
+/// a directory can't escape that directory.
+fn is_file_name(name: &str) -> bool {
+    let mut components = Path::new(name).components();
+    !name.contains(['/', '\\'])
+        && matches!(
+            (components.next(), components.next()),
+            (Some(std::path::Component::Normal(_)), None)
+        )
+}
+
+/// The directory Docker Swarm and Compose mount secrets in.
+const DOCKER_SECRETS_DIR: &str = "/run/secrets";
+
+/// Retrieves Docker Swarm or Compose secrets, mounted at `/run/secrets/<name>`.
+///
+/// Names that aren't a single file name (e.g. containing `/`, or `..`) are
+/// rejected when reading, so a secret can't be read from outside
+/// `/run/secrets`.
+///
+/// Trailing newlines are trimmed, as with [`FileSecret`]; use
+/// [`DockerSecret::keep_trailing_newlines`] for binary secrets.
+#[derive(Debug, Clone)]
+pub struct DockerSecret<E: SecretDecoder = StringEncoding> {
+    /// The name of the secret.
+    name: String,
+    /// The file the secret is read from.
+    file: FileSecret<E>,
+}
+
+impl<E: SecretDecoder> DockerSecret<E> {
+    /// Creates a new Docker secret provider with the specified encoding.
+    pub fn new(name: impl Into<String>, encoding: E) -> Self {
+        let name = name.into();
+        let file = FileSecret::new(Path::new(DOCKER_SECRETS_DIR).join(&name), encoding);
+        Self { name, file }
+    }
+
+    /// Decodes the secret as-is, without trimming trailing newlines.
+    #[must_use]
+    pub fn keep_trailing_newlines(mut self) -> Self {
+        self.file = self.file.keep_trailing_newlines();
+        self
+    }
+
+    /// Returns the name of the secret.
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the path the secret is read from.
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        self.file.path()
+    }
+}
+
+impl DockerSecret<StringEncoding> {
+    /// Creates a new Docker secret provider returning a `SecretString`.
+    pub fn string(name: impl Into<String>) -> Self {
+        Self::new(name, StringEncoding)
+    }
+}
+
+impl<E: SecretDecoder> Secret for DockerSecret<E> {
+    type Output = E::Output;
+    type Error = SecretAccessError;
+
+    async fn get_secret_value(&self) -> Result<E::Output, Self::Error> {
+        ensure!(
+            is_file_name(&self.name),
+            InvalidNameSnafu { name: &self.name }
+        );
+        self.file.get_secret_value().await
+    }
+}
+
+/// The environment variable systemd sets to the service's credentials directory.
+const SYSTEMD_CREDENTIALS_DIRECTORY: &str = "CREDENTIALS_DIRECTORY";
+
+/// Retrieves systemd service credentials, from `$CREDENTIALS_DIRECTORY/<name>`.
+///
+/// Credentials are passed with `LoadCredential=`, `SetCredential=` or their
+/// encrypted variants, and systemd decrypts them before the service starts, so
+/// they never appear in the environment. The directory is looked up on every
+/// read.
+///
+/// Names that aren't a single file name (e.g. containing `/`, or `..`) are
+/// rejected when reading, so a credential can't be read from outside the
+/// credentials directory.
+///
+/// Trailing newlines are trimmed, as with [`FileSecret`]; use
+/// [`SystemdCredential::keep_trailing_newlines`] for binary credentials.
+#[derive(Debug, Clone)]
+pub struct SystemdCredential<E: SecretDecoder = StringEncoding> {
+    /// The name of the credential.
+    name: String,
+    /// The encoding of the credential.
+    encoding: E,
+    /// Whether to trim trailing newlines before decoding.
+    trim_newlines: bool,
+}
+
+impl<E: SecretDecoder> SystemdCredential<E> {
+    /// Creates a new systemd credential provider with the specified encoding.
+    pub fn new(name: impl Into<String>, encoding: E) -> Self {
+        Self {
+            name: name.into(),
+            encoding,
+            trim_newlines: true,
+        }
+    }
+
+    /// Decodes the credential as-is, without trimming trailing newlines.
+    #[must_use]
+    pub fn keep_trailing_newlines(mut self) -> Self {
+        self.trim_newlines = false;
+        self
+    }
+
+    /// Returns the name of the credential.
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl SystemdCredential<StringEncoding> {
+    /// Creates a new systemd credential provider returning a `SecretString`.
+    pub fn string(name: impl Into<String>) -> Self {
+        Self::new(name, StringEncoding)
+    }
+}
+
+impl<E: SecretDecoder> Secret for SystemdCredential<E> {
+    type Output = E::Output;
+    type Error = SecretAccessError;
+
+    async fn get_secret_value(&self) -> Result<E::Output, Self::Error> {
+        ensure!(
+            is_file_name(&self.name),
+            InvalidNameSnafu { name: &self.name }
+        );
+        let dir = std::env::var_os(SYSTEMD_CREDENTIALS_DIRECTORY)
+            .ok_or(std::env::VarError::NotPresent)
+            .context(EnvAccessSnafu {
+                var_name: SYSTEMD_CREDENTIALS_DIRECTORY,
+            })?;
+        let contents = read_file(&Path::new(&dir).join(&self.name)).await?;
+        let contents = if self.trim_newlines {
+            trim_trailing_newlines(&contents)
+        } else {
+            &contents
+        };
+        self.encoding.decode(contents).context(DecodeSnafu)
+    }
+}
+
+/// Reads a symlink, asynchronously with the `tokio-fs` feature.
+#[cfg_attr(not(feature = "tokio-fs"), allow(clippy::unused_async))]
+async fn read_link(path: &Path) -> std::io::Result<PathBuf> {
+    #[cfg(feature = "tokio-fs")]
+    return tokio::fs::read_link(path).await;
+    #[cfg(not(feature = "tokio-fs"))]
+    std::fs::read_link(path)
+}
+
+/// Returns `true` if `key` is a valid Kubernetes `Secret` or `ConfigMap` data
+/// key: `[-._a-zA-Z0-9]+`, other than `.` and `..`.
+fn is_kubernetes_key(key: &str) -> bool {
+    !key.is_empty()
+        && key != "."
+        && key != ".."
+        && key
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'-' | b'.' | b'_'))
+}
+
+/// The symlink the kubelet atomically swaps to publish a new version.
+const KUBERNETES_DATA_LINK: &str = "..data";
+
+/// Retrieves secrets from a Kubernetes volume-mounted `Secret` or `ConfigMap`.
+///
+/// The kubelet writes each version of the volume to a new timestamped
+/// directory, then atomically swaps the `..data` symlink to point at it. This
+/// source resolves `..data` once per read, so each value comes from a single,
+/// complete version even while an update is in progress. Volumes mounted
+/// without `..data` (e.g. with `subPath`) are read directly.
+///
+/// Keys are validated with the rules Kubernetes applies to data keys when
+/// reading, so a key can't name a file outside the volume.
+///
+/// Trailing newlines are trimmed, as with [`FileSecret`]; use
+/// [`KubernetesSecret::keep_trailing_newlines`] for binary secrets.
+#[derive(Debug, Clone)]
+pub struct KubernetesSecret<E: SecretDecoder = StringEncoding> {
+    /// The directory the volume is mounted at.
+    mount_dir: PathBuf,
+    /// The key of the secret within the volume.
+    key: String,
+    /// The encoding of the secret.
+    encoding: E,
+    /// Whether to trim trailing newlines before decoding.
+    trim_newlines: bool,
+}
+
+impl<E: SecretDecoder> KubernetesSecret<E> {
+    /// Creates a new Kubernetes secret provider with the specified encoding.
+    pub fn new(mount_dir: impl Into<PathBuf>, key: impl Into<String>, encoding: E) -> Self {
+        Self {
+            mount_dir: mount_dir.into(),
+            key: key.into(),
+            encoding,
+            trim_newlines: true,
+        }
+    }
+
+    /// Decodes the secret as-is, without trimming trailing newlines.
+    #[must_use]
+    pub fn keep_trailing_newlines(mut self) -> Self {
+        self.trim_newlines = false;
+        self
+    }
+
+    /// Returns the directory the volume is mounted at.
+    #[must_use]
+    pub fn mount_dir(&self) -> &Path {
+        &self.mount_dir
+    }
+
+    /// Returns the key of the secret within the volume.
+    #[must_use]
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Returns the currently-mounted version of the volume (the target of the
+    /// `..data` symlink), or `None` if the volume has no `..data` symlink.
+    ///
+    /// The version changes whenever the kubelet publishes an update, so
+    /// comparing it with a previously-seen version detects rotation.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the symlink exists but can't be read.
+    pub async fn version(&self) -> Result<Option<PathBuf>, SecretAccessError> {
+        let link = self.mount_dir.join(KUBERNETES_DATA_LINK);
+        match read_link(&link).await {
+            Ok(target) => Ok(Some(target)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(source) => Err(SecretAccessError::FileAccess { path: link, source }),
+        }
+    }
+
+    /// Retrieves the secret value, together with the version it was read from.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the secret can't be read or decoded.
+    pub async fn get_versioned_secret_value(
+        &self,
+    ) -> Result<(E::Output, Option<PathBuf>), SecretAccessError> {
+        ensure!(
+            is_kubernetes_key(&self.key),
+            InvalidNameSnafu { name: &self.key }
+        );
+        // The kubelet removes the previous version shortly after the swap, so
+        // a read racing an update can fail; re-resolving once picks up the new
+        // version.
+        let mut retried = false;
+        loop {
+            let version = self.version().await?;
+            let path = match &version {
+                Some(target) => self.mount_dir.join(target).join(&self.key),
+                None => self.mount_dir.join(&self.key),
+            };
+            match read_file(&path).await {
+                Ok(contents) => {
+                    let contents = if self.trim_newlines {
+                        trim_trailing_newlines(&contents)
+                    } else {
+                        &contents
+                    };
+                    let value = self.encoding.decode(contents).context(DecodeSnafu)?;
+                    return Ok((value, version));
+                }
+                Err(SecretAccessError::FileAccess { source, .. })
+                    if !retried
+                        && version.is_some()
+                        && source.kind() == std::io::ErrorKind::NotFound =>
+                {
+                    retried = true;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+impl KubernetesSecret<StringEncoding> {
+    /// Creates a new Kubernetes secret provider returning a `SecretString`.
+    pub fn string(mount_dir: impl Into<PathBuf>, key: impl Into<String>) -> Self {
+        Self::new(mount_dir, key, StringEncoding)
+    }
+}
+
+impl<E: SecretDecoder> Secret for KubernetesSecret<E> {
+    type Output = E::Output;
+    type Error = SecretAccessError;
+
+    async fn get_secret_value(&self) -> Result<E::Output, Self::Error> {
+        self.get_versioned_secret_value()
+            .await
+            .map(|(value, _)| value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use secrecy::ExposeSecret;
+
+    use super::*;
+    use crate::secrets::BinaryEncoding;
+
+    fn write_temp_file(name: &str, contents: &[u8]) -> PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("chewie-crypto-{}-{name}", std::process::id()));
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[tokio::test]
+    async fn test_file_secret_trims_trailing_newline() {
+        let path = write_temp_file("file-secret", b"s3cret\r\n");
+
+        let secret = FileSecret::string(&path).get_secret_value().await.unwrap();
+        let raw = FileSecret::new(&path, BinaryEncoding)
+            .keep_trailing_newlines()
+            .get_secret_value()
+            .await
+            .unwrap();
+
+        assert_eq!(secret.expose_secret(), "s3cret");
+        assert_eq!(raw.expose_secret(), b"s3cret\r\n");
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_file_secret_put_replaces_contents() {
+        let path = write_temp_file("file-sink", b"old");
+        let secret = FileSecret::string(&path);
+
+        secret.put_secret(b"new").await.unwrap();
+
+        let written = secret.get_secret_with_metadata().await.unwrap();
+        assert_eq!(written.value().expose_secret(), "new");
+        assert_eq!(written.source(), Some(path.display().to_string().as_str()));
+        assert!(written.created_at().is_some());
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_file_secret_concurrent_puts_publish_whole_values() {
+        let dir = std::env::temp_dir().join(format!(
+            "chewie-crypto-{}-file-sink-concurrent",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let secret = FileSecret::new(dir.join("secret"), BinaryEncoding);
+        let values: Vec<Vec<u8>> = (0..8u8).map(|i| vec![i; 64 * 1024]).collect();
+
+        futures_util::future::try_join_all(values.iter().map(|value| secret.put_secret(value)))
+            .await
+            .unwrap();
+
+        let written = secret.get_secret_value().await.unwrap();
+        assert!(values.iter().any(|value| value == written.expose_secret()));
+        // Only the secret itself is left, without temporary files.
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_kubernetes_secret_follows_data_symlink() {
+        use std::os::unix::fs::symlink;
+
+        let dir = std::env::temp_dir().join(format!("chewie-crypto-{}-k8s", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("..v1")).unwrap();
+        std::fs::create_dir_all(dir.join("..v2")).unwrap();
+        std::fs::write(dir.join("..v1/password"), b"first\n").unwrap();
+        std::fs::write(dir.join("..v2/password"), b"second\n").unwrap();
+        symlink("..v1", dir.join("..data")).unwrap();
+        symlink("..data/password", dir.join("password")).unwrap();
+        let secret = KubernetesSecret::string(&dir, "password");
+
+        let (first, v1) = secret.get_versioned_secret_value().await.unwrap();
+        // Swap the symlink the way the kubelet does: create, then rename over.
+        symlink("..v2", dir.join("..data_tmp")).unwrap();
+        std::fs::rename(dir.join("..data_tmp"), dir.join("..data")).unwrap();
+        let (second, v2) = secret.get_versioned_secret_value().await.unwrap();
+
+        assert_eq!(first.expose_secret(), "first");
+        assert_eq!(second.expose_secret(), "second");
+        assert_eq!(v1.as_deref(), Some(Path::new("..v1")));
+        assert_ne!(v1, v2);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_kubernetes_secret_keeps_trailing_newlines() {
+        let dir =
+            std::env::temp_dir().join(format!("chewie-crypto-{}-k8s-raw", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("key"), b"raw\n").unwrap();
+
+        let trimmed = KubernetesSecret::new(&dir, "key", BinaryEncoding)
+            .get_secret_value()
+            .await
+            .unwrap();
+        let raw = KubernetesSecret::new(&dir, "key", BinaryEncoding)
+            .keep_trailing_newlines()
+            .get_secret_value()
+            .await
+            .unwrap();
+
+        assert_eq!(trimmed.expose_secret(), b"raw");
+        assert_eq!(raw.expose_secret(), b"raw\n");
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_kubernetes_secret_rejects_invalid_keys() {
+        for key in [
+            "../../etc/shadow",
+            "nested/key",
+            "..",
+            ".",
+            "",
+            "key with spaces",
+        ] {
+            let result = KubernetesSecret::string("/var/run/secrets/app", key)
+                .get_secret_value()
+                .await;
+
+            assert!(
+                matches!(&result, Err(SecretAccessError::InvalidName { name }) if name == key),
+                "{key:?} was not rejected"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_static_secret() {
+        let secret = StaticSecret::string("s3cret");
+
+        assert_eq!(
+            secret.get_secret_value().await.unwrap().expose_secret(),
+            "s3cret"
+        );
+        assert_eq!(format!("{secret:?}"), "StaticSecret { .. }");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_command_secret() {
+        let secret = CommandSecret::string("echo", ["s3cret"]);
+        let failing = CommandSecret::string("sh", ["-c", "echo locked >&2; exit 3"]);
+
+        assert_eq!(
+            secret.get_secret_value().await.unwrap().expose_secret(),
+            "s3cret"
+        );
+        assert!(matches!(
+            failing.get_secret_value().await,
+            Err(SecretAccessError::CommandFailed { stderr, .. }) if stderr == "locked"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_env_var_secret_with_synthetic_env() {
+        let env: HashMap<String, String> = [("CLIENT_SECRET".into(), "s3cret".into())].into();
+
+        let secret = EnvVarSecret::string("CLIENT_SECRET").with_env(env.clone());
+        let missing = EnvVarSecret::string("MISSING").with_env(env);
+
+        assert_eq!(
+            secret.get_secret_value().await.unwrap().expose_secret(),
+            "s3cret"
+        );
+        assert!(matches!(
+            missing.get_secret_value().await,
+            Err(SecretAccessError::EnvAccess { .. })
+        ));
+    }
+
+    #[test]
+    fn test_docker_secret_path() {
+        let secret = DockerSecret::string("db_password");
+        let raw = DockerSecret::new("tls_key", BinaryEncoding).keep_trailing_newlines();
+
+        assert_eq!(secret.path(), Path::new("/run/secrets/db_password"));
+        assert!(secret.file.trim_newlines);
+        assert!(!raw.file.trim_newlines);
+    }
+
+    #[tokio::test]
+    async fn test_docker_secret_rejects_path_traversal() {
+        for name in [
+            "../../etc/shadow",
+            "nested/name",
+            "..",
+            ".",
+            "",
+            "/etc/shadow",
+        ] {
+            let result = DockerSecret::string(name).get_secret_value().await;
+
+            assert!(
+                matches!(&result, Err(SecretAccessError::InvalidName { name: n }) if n == name),
+                "{name:?} was not rejected"
+            );
+        }
+    }
+
+    #[test]
+    fn test_systemd_credential_keep_trailing_newlines() {
+        let secret = SystemdCredential::string("db_password");
+        let raw = SystemdCredential::new("tls_key", BinaryEncoding).keep_trailing_newlines();
+
+        assert!(secret.trim_newlines);
+        assert!(!raw.trim_newlines);
+    }
+
+    #[tokio::test]
+    async fn test_systemd_credential_rejects_path_traversal() {
+        for name in [
+            "../../etc/shadow",
+            "nested/name",
+            "..",
+            ".",
+            "",
+            "/etc/shadow",
+        ] {
+            let result = SystemdCredential::string(name).get_secret_value().await;
+
+            assert!(
+                matches!(&result, Err(SecretAccessError::InvalidName { name: n }) if n == name),
+                "{name:?} was not rejected"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_file_secret_missing_file() {
+        let result = FileSecret::string("/nonexistent/secret")
+            .get_secret_value()
+            .await;
+
+        assert!(matches!(result, Err(SecretAccessError::FileAccess { .. })));
+    }
+}
@@ -0,0 +1,181 @@
+//! Secret wrapper refreshing the value in the background.
+
+use std::{sync::Arc, time::Duration};
+
+use futures_timer::Delay;
+use tokio::sync::watch;
+
+use crate::{MaybeSend, secrets::Secret};
+
+/// A secret whose value is refreshed by a background task, and pushed to
+/// subscribers.
+///
+/// The crate doesn't spawn tasks itself: the caller spawns the future returned
+/// by [`RefreshingSecret::run`] on their runtime. Consumers that need to react
+/// to rotation (e.g. rebuilding a signer) hold a [`SecretWatch`] from
+/// [`RefreshingSecret::subscribe`]; others use it as a [`Secret`], which
+/// returns the latest value without fetching. Clones share the same value.
+pub struct RefreshingSecret<S: Secret> {
+    inner: S,
+    sender: Arc<watch::Sender<Option<S::Output>>>,
+}
+
+impl<S: Secret> Clone for RefreshingSecret<S> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            sender: Arc::clone(&self.sender),
+        }
+    }
+}
+
+impl<S: Secret + std::fmt::Debug> std::fmt::Debug for RefreshingSecret<S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RefreshingSecret")
+            .field("inner", &self.inner)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<S: Secret> RefreshingSecret<S>
+where
+    S::Output: Clone,
+{
+    /// Wraps a secret. No value is fetched until the first refresh or retrieval.
+    pub fn new(inner: S) -> Self {
+        Self {
+            inner,
+            sender: Arc::new(watch::Sender::new(None)),
+        }
+    }
+
+    /// Returns the wrapped secret.
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    /// Returns a handle yielding the latest value and change notifications.
+    #[must_use]
+    pub fn subscribe(&self) -> SecretWatch<S::Output> {
+        SecretWatch {
+            receiver: self.sender.subscribe(),
+        }
+    }
+
+    /// Fetches the value from the wrapped secret and publishes it to
+    /// subscribers.
+    ///
+    /// # Errors
+    ///
+    /// Returns the wrapped secret's error. The previous value is kept.
+    pub async fn refresh(&self) -> Result<S::Output, S::Error> {
+        let value = self.inner.get_secret_value().await?;
+        self.sender.send_replace(Some(value.clone()));
+        Ok(value)
+    }
+
+    /// Returns a future that refreshes the value every `interval`, starting
+    /// immediately. It never completes, so drop or abort it to stop refreshing.
+    ///
+    /// Failed refreshes keep the previous value, and are retried at the next
+    /// interval. Use [`RefreshingSecret::refresh`] in a custom loop to observe
+    /// errors or back off.
+    pub fn run(&self, interval: Duration) -> impl Future<Output = ()> + MaybeSend + 'static
+    where
+        S: 'static,
+    {
+        let secret = self.clone();
+        async move {
+            loop {
+                let _ = secret.refresh().await;
+                Delay::new(interval).await;
+            }
+        }
+    }
+}
+
+impl<S: Secret> Secret for RefreshingSecret<S>
+where
+    S::Output: Clone,
+{
+    type Error = S::Error;
+    type Output = S::Output;
+
+    /// Returns the latest value, fetching it only if no value has been
+    /// published yet.
+    async fn get_secret_value(&self) -> Result<Self::Output, Self::Error> {
+        let latest = self.sender.borrow().clone();
+        match latest {
+            Some(value) => Ok(value),
+            None => self.refresh().await,
+        }
+    }
+}
+
+/// A handle to the latest value of a [`RefreshingSecret`].
+#[derive(Debug, Clone)]
+pub struct SecretWatch<T> {
+    receiver: watch::Receiver<Option<T>>,
+}
+
+impl<T: Clone> SecretWatch<T> {
+    /// Returns the latest value, or `None` if no value has been published yet.
+    #[must_use]
+    pub fn latest(&self) -> Option<T> {
+        self.receiver.borrow().clone()
+    }
+
+    /// Waits for the next published value, and returns it.
+    ///
+    /// Every successful refresh is published, even if the value is unchanged.
+    /// Returns `None` once every clone of the [`RefreshingSecret`] is dropped.
+    pub async fn changed(&mut self) -> Option<T> {
+        self.receiver.changed().await.ok()?;
+        self.receiver.borrow_and_update().clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        convert::Infallible,
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+
+    use super::*;
+
+    #[derive(Debug, Clone, Default)]
+    struct CountingSecret(Arc<AtomicUsize>);
+
+    impl Secret for CountingSecret {
+        type Error = Infallible;
+        type Output = usize;
+
+        async fn get_secret_value(&self) -> Result<Self::Output, Self::Error> {
+            Ok(self.0.fetch_add(1, Ordering::SeqCst) + 1)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_run_publishes_refreshed_values() {
+        let secret = RefreshingSecret::new(CountingSecret::default());
+        let mut watch = secret.subscribe();
+        assert_eq!(watch.latest(), None);
+
+        let task = tokio::spawn(secret.run(Duration::from_millis(10)));
+
+        assert_eq!(watch.changed().await, Some(1));
+        assert_eq!(watch.changed().await, Some(2));
+        task.abort();
+        assert!(secret.get_secret_value().await.unwrap() >= 2);
+    }
+
+    #[tokio::test]
+    async fn test_get_secret_value_fetches_once() {
+        let secret = RefreshingSecret::new(CountingSecret::default());
+
+        assert_eq!(secret.get_secret_value().await.unwrap(), 1);
+        assert_eq!(secret.get_secret_value().await.unwrap(), 1);
+        assert_eq!(secret.subscribe().latest(), Some(1));
+    }
+}
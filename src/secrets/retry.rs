@@ -0,0 +1,199 @@
+//! Secret wrapper retrying failed retrievals with exponential backoff.
+
+use std::time::Duration;
+
+use bon::bon;
+use futures_timer::Delay;
+
+use crate::secrets::{Secret, SecretWithMetadata, cached::jittered};
+
+/// A secret that retries failed retrievals, with exponential backoff and
+/// jitter.
+///
+/// This keeps transient secret store errors (throttling, brief outages) from
+/// failing the operation that needs the secret. Each delay is twice the
+/// previous one, up to a maximum, and randomly shortened by up to half so
+/// that clients don't retry in lockstep. Once the attempts are exhausted, or
+/// an error isn't retryable, the last error is returned.
+#[derive(Debug)]
+pub struct RetrySecret<S: Secret> {
+    inner: S,
+    max_attempts: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    is_retryable: fn(&S::Error) -> bool,
+}
+
+impl<S: Secret> Clone for RetrySecret<S> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            max_attempts: self.max_attempts,
+            initial_backoff: self.initial_backoff,
+            max_backoff: self.max_backoff,
+            is_retryable: self.is_retryable,
+        }
+    }
+}
+
+#[bon]
+impl<S: Secret> RetrySecret<S> {
+    /// Creates a builder wrapping the given secret.
+    #[builder]
+    pub fn new(
+        #[builder(start_fn)] inner: S,
+        /// The maximum number of attempts, including the first. Defaults to 3.
+        #[builder(default = 3)]
+        max_attempts: u32,
+        /// The delay before the first retry. Defaults to 100 milliseconds.
+        #[builder(default = Duration::from_millis(100))]
+        initial_backoff: Duration,
+        /// The maximum delay between attempts. Defaults to 5 seconds.
+        #[builder(default = Duration::from_secs(5))]
+        max_backoff: Duration,
+        /// Returns `true` for errors worth retrying. Defaults to retrying every
+        /// error; for the built-in secrets, use
+        /// [`SecretAccessError::is_transient`](crate::secrets::SecretAccessError::is_transient).
+        #[builder(default = |_| true)]
+        is_retryable: fn(&S::Error) -> bool,
+    ) -> Self {
+        Self {
+            inner,
+            max_attempts: max_attempts.max(1),
+            initial_backoff,
+            max_backoff,
+            is_retryable,
+        }
+    }
+
+    /// Returns the wrapped secret.
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    /// Returns the maximum number of attempts, including the first.
+    #[must_use]
+    pub fn max_attempts(&self) -> u32 {
+        self.max_attempts
+    }
+
+    async fn retry<T, F, Fut>(&self, retrieve: F) -> Result<T, S::Error>
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = Result<T, S::Error>>,
+    {
+        let mut backoff = self.initial_backoff.min(self.max_backoff);
+        let mut attempt = 1;
+        loop {
+            match retrieve().await {
+                Err(error) if attempt < self.max_attempts && (self.is_retryable)(&error) => {
+                    Delay::new(jittered(backoff, backoff / 2)).await;
+                    backoff = backoff.saturating_mul(2).min(self.max_backoff);
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+impl<S: Secret> Secret for RetrySecret<S> {
+    type Error = S::Error;
+    type Output = S::Output;
+
+    async fn get_secret_value(&self) -> Result<Self::Output, Self::Error> {
+        self.retry(|| self.inner.get_secret_value()).await
+    }
+
+    async fn get_secret_with_metadata(
+        &self,
+    ) -> Result<SecretWithMetadata<Self::Output>, Self::Error> {
+        self.retry(|| self.inner.get_secret_with_metadata()).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        Arc,
+        atomic::{AtomicU32, Ordering},
+    };
+
+    use snafu::Snafu;
+
+    use super::*;
+
+    #[derive(Debug, Snafu)]
+    enum MockError {
+        Transient,
+        Permanent,
+    }
+
+    /// Fails with the given error until the given attempt.
+    #[derive(Debug, Clone)]
+    struct FlakySecret {
+        attempts: Arc<AtomicU32>,
+        succeed_on: u32,
+        error: fn() -> MockError,
+    }
+
+    impl FlakySecret {
+        fn new(succeed_on: u32, error: fn() -> MockError) -> Self {
+            Self {
+                attempts: Arc::default(),
+                succeed_on,
+                error,
+            }
+        }
+    }
+
+    impl Secret for FlakySecret {
+        type Error = MockError;
+        type Output = u32;
+
+        async fn get_secret_value(&self) -> Result<Self::Output, Self::Error> {
+            let attempt = self.attempts.fetch_add(1, Ordering::SeqCst) + 1;
+            if attempt < self.succeed_on {
+                Err((self.error)())
+            } else {
+                Ok(attempt)
+            }
+        }
+    }
+
+    fn retry(inner: FlakySecret) -> RetrySecret<FlakySecret> {
+        RetrySecret::builder(inner)
+            .initial_backoff(Duration::from_millis(1))
+            .is_retryable(|e| matches!(e, MockError::Transient))
+            .build()
+    }
+
+    #[tokio::test]
+    async fn test_transient_errors_are_retried() {
+        let secret = retry(FlakySecret::new(3, || MockError::Transient));
+
+        assert_eq!(secret.get_secret_value().await.unwrap(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_gives_up_after_max_attempts() {
+        let inner = FlakySecret::new(4, || MockError::Transient);
+        let secret = retry(inner.clone());
+
+        let result = secret.get_secret_value().await;
+
+        assert!(matches!(result, Err(MockError::Transient)));
+        assert_eq!(inner.attempts.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_permanent_errors_are_not_retried() {
+        let inner = FlakySecret::new(2, || MockError::Permanent);
+        let secret = retry(inner.clone());
+
+        let result = secret.get_secret_value().await;
+
+        assert!(matches!(result, Err(MockError::Permanent)));
+        assert_eq!(inner.attempts.load(Ordering::SeqCst), 1);
+    }
+}
@@ -1,3 +1,7 @@
+use std::sync::Arc;
+
+use web_time::SystemTime;
+
 use crate::{MaybeSend, MaybeSendSync};
 
 /// Trait for async secret retrieval.
@@ -12,4 +16,174 @@ pub trait Secret: MaybeSendSync + Clone {
     fn get_secret_value(
         &self,
     ) -> impl Future<Output = Result<Self::Output, Self::Error>> + MaybeSend;
+
+    /// Retrieves the secret value, together with what the source knows about
+    /// it, such as its version.
+    ///
+    /// The default implementation returns the value without metadata.
+    fn get_secret_with_metadata(
+        &self,
+    ) -> impl Future<Output = Result<SecretWithMetadata<Self::Output>, Self::Error>> + MaybeSend
+    {
+        async move { Ok(SecretWithMetadata::new(self.get_secret_value().await?)) }
+    }
+
+    /// Asynchronously checks that the secret can be retrieved, e.g. for a
+    /// readiness probe.
+    ///
+    /// The default implementation retrieves the value and drops it
+    /// immediately. Sources with a cheaper way to check connectivity and
+    /// permissions can override this.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the secret can't be retrieved.
+    fn health_check(&self) -> impl Future<Output = Result<(), Self::Error>> + MaybeSend {
+        async move { self.get_secret_value().await.map(drop) }
+    }
+}
+
+impl<S: Secret> Secret for Arc<S> {
+    type Error = S::Error;
+    type Output = S::Output;
+
+    fn get_secret_value(
+        &self,
+    ) -> impl Future<Output = Result<Self::Output, Self::Error>> + MaybeSend {
+        (**self).get_secret_value()
+    }
+
+    fn get_secret_with_metadata(
+        &self,
+    ) -> impl Future<Output = Result<SecretWithMetadata<Self::Output>, Self::Error>> + MaybeSend
+    {
+        (**self).get_secret_with_metadata()
+    }
+
+    fn health_check(&self) -> impl Future<Output = Result<(), Self::Error>> + MaybeSend {
+        (**self).health_check()
+    }
+}
+
+impl<S: Secret> Secret for &S {
+    type Error = S::Error;
+    type Output = S::Output;
+
+    fn get_secret_value(
+        &self,
+    ) -> impl Future<Output = Result<Self::Output, Self::Error>> + MaybeSend {
+        (**self).get_secret_value()
+    }
+
+    fn get_secret_with_metadata(
+        &self,
+    ) -> impl Future<Output = Result<SecretWithMetadata<Self::Output>, Self::Error>> + MaybeSend
+    {
+        (**self).get_secret_with_metadata()
+    }
+
+    fn health_check(&self) -> impl Future<Output = Result<(), Self::Error>> + MaybeSend {
+        (**self).health_check()
+    }
+}
+
+/// A secret value with metadata from its source, as returned by
+/// [`Secret::get_secret_with_metadata`].
+///
+/// Rotation logic can use the metadata to decide whether a value changed, or
+/// is due to be replaced, without comparing the values themselves.
+#[derive(Debug, Clone)]
+pub struct SecretWithMetadata<T> {
+    value: T,
+    version: Option<String>,
+    created_at: Option<SystemTime>,
+    expires_at: Option<SystemTime>,
+    source: Option<String>,
+}
+
+impl<T> SecretWithMetadata<T> {
+    /// Wraps a value without metadata.
+    pub fn new(value: T) -> Self {
+        Self {
+            value,
+            version: None,
+            created_at: None,
+            expires_at: None,
+            source: None,
+        }
+    }
+
+    /// Sets the version identifier of the value.
+    #[must_use]
+    pub fn with_version(mut self, version: impl Into<String>) -> Self {
+        self.version = Some(version.into());
+        self
+    }
+
+    /// Sets when the value was created.
+    #[must_use]
+    pub fn with_created_at(mut self, created_at: SystemTime) -> Self {
+        self.created_at = Some(created_at);
+        self
+    }
+
+    /// Sets when the value expires.
+    #[must_use]
+    pub fn with_expires_at(mut self, expires_at: SystemTime) -> Self {
+        self.expires_at = Some(expires_at);
+        self
+    }
+
+    /// Sets a label describing where the value came from, such as a file path
+    /// or secret name.
+    #[must_use]
+    pub fn with_source(mut self, source: impl Into<String>) -> Self {
+        self.source = Some(source.into());
+        self
+    }
+
+    /// Returns the value.
+    pub fn value(&self) -> &T {
+        &self.value
+    }
+
+    /// Returns the value, discarding the metadata.
+    pub fn into_value(self) -> T {
+        self.value
+    }
+
+    /// Returns the version identifier of the value, if known.
+    #[must_use]
+    pub fn version(&self) -> Option<&str> {
+        self.version.as_deref()
+    }
+
+    /// Returns when the value was created, if known.
+    #[must_use]
+    pub fn created_at(&self) -> Option<SystemTime> {
+        self.created_at
+    }
+
+    /// Returns when the value expires, if known.
+    #[must_use]
+    pub fn expires_at(&self) -> Option<SystemTime> {
+        self.expires_at
+    }
+
+    /// Returns the label describing where the value came from, if known.
+    #[must_use]
+    pub fn source(&self) -> Option<&str> {
+        self.source.as_deref()
+    }
+
+    /// Transforms the value, keeping the metadata.
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> SecretWithMetadata<U> {
+        SecretWithMetadata {
+            value: f(self.value),
+            version: self.version,
+            created_at: self.created_at,
+            expires_at: self.expires_at,
+            source: self.source,
+        }
+    }
 }
@@ -0,0 +1,182 @@
+//! Concurrent warm-up of a named collection of secrets.
+
+use std::{borrow::Cow, collections::BTreeMap, sync::Arc};
+
+use futures_util::future::join_all;
+
+use crate::{
+    DynError, MaybeSendSync,
+    secrets::{DynSecret, Secret},
+};
+
+/// A named collection of secrets with the same output, resolved together at
+/// startup.
+///
+/// [`SecretSet::prefetch`] fetches every secret concurrently, so the first
+/// request doesn't pay for a round-trip to each secret store, and reports
+/// which secrets couldn't be fetched.
+#[derive(Clone)]
+pub struct SecretSet<T> {
+    secrets: Vec<(Cow<'static, str>, Arc<dyn DynSecret<Output = T>>)>,
+}
+
+impl<T> Default for SecretSet<T> {
+    fn default() -> Self {
+        Self {
+            secrets: Vec::new(),
+        }
+    }
+}
+
+impl<T> std::fmt::Debug for SecretSet<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list()
+            .entries(self.secrets.iter().map(|(name, _)| name))
+            .finish()
+    }
+}
+
+impl<T: MaybeSendSync + 'static> SecretSet<T> {
+    /// Creates an empty set.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a secret under a name. A later secret with the same name replaces
+    /// the earlier one.
+    #[must_use]
+    pub fn with_secret<S: Secret<Output = T> + 'static>(
+        mut self,
+        name: impl Into<Cow<'static, str>>,
+        secret: S,
+    ) -> Self {
+        let name = name.into();
+        self.secrets.retain(|(existing, _)| *existing != name);
+        self.secrets.push((name, Arc::new(secret)));
+        self
+    }
+
+    /// Returns the names of the secrets in the set.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.secrets.iter().map(|(name, _)| name.as_ref())
+    }
+
+    /// Fetches every secret concurrently.
+    pub async fn prefetch(&self) -> PrefetchedSecrets<T> {
+        let results = join_all(
+            self.secrets
+                .iter()
+                .map(|(_, secret)| Secret::get_secret_value(secret)),
+        )
+        .await;
+
+        let mut prefetched = PrefetchedSecrets {
+            values: BTreeMap::new(),
+            failures: BTreeMap::new(),
+        };
+        for ((name, _), result) in self.secrets.iter().zip(results) {
+            match result {
+                Ok(value) => {
+                    prefetched.values.insert(name.clone(), value);
+                }
+                Err(error) => {
+                    prefetched.failures.insert(name.clone(), error);
+                }
+            }
+        }
+        prefetched
+    }
+}
+
+/// The values fetched by [`SecretSet::prefetch`], by name.
+pub struct PrefetchedSecrets<T> {
+    values: BTreeMap<Cow<'static, str>, T>,
+    failures: BTreeMap<Cow<'static, str>, DynError>,
+}
+
+impl<T> std::fmt::Debug for PrefetchedSecrets<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PrefetchedSecrets")
+            .field("values", &self.values.keys().collect::<Vec<_>>())
+            .field("failures", &self.failures)
+            .finish()
+    }
+}
+
+impl<T> PrefetchedSecrets<T> {
+    /// Returns the value of a secret, if it was fetched.
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<&T> {
+        self.values.get(name)
+    }
+
+    /// Removes and returns the value of a secret, if it was fetched.
+    pub fn take(&mut self, name: &str) -> Option<T> {
+        self.values.remove(name)
+    }
+
+    /// Returns `true` if every secret was fetched.
+    #[must_use]
+    pub fn is_complete(&self) -> bool {
+        self.failures.is_empty()
+    }
+
+    /// Returns the names of the secrets that couldn't be fetched, with their
+    /// errors.
+    pub fn failures(&self) -> impl Iterator<Item = (&str, &DynError)> {
+        self.failures
+            .iter()
+            .map(|(name, error)| (name.as_ref(), error))
+    }
+
+    /// Returns the fetched values, by name.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &T)> {
+        self.values
+            .iter()
+            .map(|(name, value)| (name.as_ref(), value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use secrecy::{ExposeSecret, SecretString};
+
+    use super::*;
+    use crate::secrets::{FileSecret, StaticSecret};
+
+    #[tokio::test]
+    async fn test_prefetch_reports_failures() {
+        let set = SecretSet::<SecretString>::new()
+            .with_secret("client-secret", StaticSecret::string("s3cret"))
+            .with_secret("signing-key", FileSecret::string("/nonexistent/key.pem"));
+
+        let prefetched = set.prefetch().await;
+
+        assert!(!prefetched.is_complete());
+        assert_eq!(
+            prefetched
+                .get("client-secret")
+                .map(ExposeSecret::expose_secret),
+            Some("s3cret")
+        );
+        assert!(prefetched.get("signing-key").is_none());
+        assert_eq!(
+            prefetched
+                .failures()
+                .map(|(name, _)| name)
+                .collect::<Vec<_>>(),
+            ["signing-key"]
+        );
+    }
+
+    #[test]
+    fn test_later_secret_replaces_earlier() {
+        let set = SecretSet::<SecretString>::new()
+            .with_secret("a", StaticSecret::string("1"))
+            .with_secret("b", StaticSecret::string("2"))
+            .with_secret("a", StaticSecret::string("3"));
+
+        assert_eq!(set.names().collect::<Vec<_>>(), ["b", "a"]);
+    }
+}
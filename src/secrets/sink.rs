@@ -0,0 +1,41 @@
+use std::sync::Arc;
+
+use crate::{MaybeSend, MaybeSendSync};
+
+/// Trait for async secret write-back, the counterpart of
+/// [`Secret`](crate::secrets::Secret).
+///
+/// Sinks persist newly generated secrets, such as during key rotation. The
+/// value is stored as-is, so it should be in the form the corresponding
+/// source's encoding decodes (e.g. base64 text for
+/// [`Base64Encoding`](crate::secrets::Base64Encoding)).
+pub trait SecretSink: MaybeSendSync + Clone {
+    /// The error type returned by this secret sink's operations.
+    type Error: std::error::Error + MaybeSendSync + 'static;
+
+    /// Stores a new secret value, replacing the current one.
+    fn put_secret(&self, value: &[u8])
+    -> impl Future<Output = Result<(), Self::Error>> + MaybeSend;
+}
+
+impl<S: SecretSink> SecretSink for Arc<S> {
+    type Error = S::Error;
+
+    fn put_secret(
+        &self,
+        value: &[u8],
+    ) -> impl Future<Output = Result<(), Self::Error>> + MaybeSend {
+        (**self).put_secret(value)
+    }
+}
+
+impl<S: SecretSink> SecretSink for &S {
+    type Error = S::Error;
+
+    fn put_secret(
+        &self,
+        value: &[u8],
+    ) -> impl Future<Output = Result<(), Self::Error>> + MaybeSend {
+        (**self).put_secret(value)
+    }
+}
@@ -0,0 +1,141 @@
+//! SOPS-encrypted file secret provider.
+
+use std::{ffi::OsString, path::PathBuf};
+
+use crate::secrets::{
+    CommandSecret, JsonFieldEncoding, Secret, SecretAccessError, SecretWithMetadata,
+    encodings::{SecretDecoder, StringEncoding},
+};
+
+/// Retrieves secrets from a [SOPS](https://getsops.io)-encrypted YAML, JSON,
+/// dotenv or INI file.
+///
+/// The file is decrypted by running `sops --decrypt --output-type json`, so
+/// every key backend supported by SOPS (age, PGP, AWS/GCP/Azure KMS, Vault
+/// Transit) works as configured for the `sops` binary, e.g. with
+/// `SOPS_AGE_KEY_FILE`. The decrypted JSON is then decoded with the given
+/// encoding; use [`SopsSecret::field`] or a [`JsonFieldEncoding`] to extract a
+/// single value.
+///
+/// The command is run on every call, as with [`CommandSecret`].
+#[derive(Debug, Clone)]
+pub struct SopsSecret<E: SecretDecoder = StringEncoding> {
+    /// The path of the encrypted file.
+    path: PathBuf,
+    /// The `sops` program to run.
+    program: OsString,
+    /// The encoding of the decrypted document.
+    encoding: E,
+}
+
+impl<E: SecretDecoder> SopsSecret<E> {
+    /// Creates a new SOPS secret provider, decoding the whole decrypted
+    /// document (as JSON) with the specified encoding.
+    pub fn new(path: impl Into<PathBuf>, encoding: E) -> Self {
+        Self {
+            path: path.into(),
+            program: OsString::from("sops"),
+            encoding,
+        }
+    }
+
+    /// Runs the given `sops` binary, rather than `sops` from the `PATH`.
+    #[must_use]
+    pub fn with_program(mut self, program: impl Into<OsString>) -> Self {
+        self.program = program.into();
+        self
+    }
+
+    /// Returns the path of the encrypted file.
+    #[must_use]
+    pub fn path(&self) -> &std::path::Path {
+        &self.path
+    }
+
+    fn command(&self) -> CommandSecret<E> {
+        CommandSecret::new(
+            &self.program,
+            [
+                OsString::from("--decrypt"),
+                OsString::from("--output-type"),
+                OsString::from("json"),
+                self.path.clone().into_os_string(),
+            ],
+            self.encoding.clone(),
+        )
+    }
+}
+
+impl<E: SecretDecoder> SopsSecret<JsonFieldEncoding<E>> {
+    /// Creates a new SOPS secret provider extracting the value at a key path,
+    /// such as `["database", "password"]`, decoded with the specified encoding.
+    pub fn field<I>(path: impl Into<PathBuf>, keys: I, encoding: E) -> Self
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        Self::new(path, JsonFieldEncoding::path(keys, encoding))
+    }
+}
+
+impl<E: SecretDecoder> Secret for SopsSecret<E> {
+    type Output = E::Output;
+    type Error = SecretAccessError;
+
+    async fn get_secret_value(&self) -> Result<E::Output, Self::Error> {
+        self.command().get_secret_value().await
+    }
+
+    /// The source is the path of the encrypted file.
+    async fn get_secret_with_metadata(&self) -> Result<SecretWithMetadata<E::Output>, Self::Error> {
+        let value = self.get_secret_value().await?;
+        Ok(SecretWithMetadata::new(value).with_source(self.path.display().to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_sops_secret_extracts_field() {
+        use std::os::unix::fs::PermissionsExt;
+
+        use secrecy::ExposeSecret;
+
+        let script =
+            std::env::temp_dir().join(format!("chewie-crypto-{}-fake-sops", std::process::id()));
+        // Checks the arguments, then prints the "decrypted" document.
+        std::fs::write(
+            &script,
+            "#!/bin/sh\n\
+             [ \"$*\" = '--decrypt --output-type json secrets.enc.yaml' ] || exit 1\n\
+             echo '{\"database\":{\"password\":\"s3cret\"}}'\n",
+        )
+        .unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o700)).unwrap();
+        let secret =
+            SopsSecret::field("secrets.enc.yaml", ["database", "password"], StringEncoding)
+                .with_program(&script);
+
+        let value = secret.get_secret_with_metadata().await.unwrap();
+
+        assert_eq!(value.value().expose_secret(), "s3cret");
+        assert_eq!(value.source(), Some("secrets.enc.yaml"));
+        std::fs::remove_file(script).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_missing_sops_binary() {
+        let secret =
+            SopsSecret::new("secrets.enc.yaml", StringEncoding).with_program("/nonexistent/sops");
+
+        let result = secret.get_secret_value().await;
+
+        assert!(matches!(
+            result,
+            Err(SecretAccessError::CommandSpawn { .. })
+        ));
+    }
+}
@@ -0,0 +1,560 @@
+//! Config-driven secret construction from URI-like specifications.
+
+use std::{collections::HashMap, sync::Arc};
+
+use secrecy::{ExposeSecret, SecretBox, SecretString};
+use snafu::prelude::*;
+
+use crate::{
+    DynError, MaybeSendSync,
+    secrets::{
+        Base64Encoding, Base64UrlEncoding, BinaryEncoding, DecodingError, DockerSecret, DynSecret,
+        EnvVarSecret, FileSecret, HexEncoding, JsonFieldEncoding, PemEncoding, Secret,
+        SecretAccessError, SecretDecoder, SecretWithMetadata, SopsSecret, StringEncoding,
+        SystemdCredential,
+    },
+};
+
+/// A type-erased secret returning the raw bytes of a value, before decoding.
+pub type RawSecret = Arc<dyn DynSecret<Output = SecretBox<[u8]>>>;
+
+/// Errors that can occur when parsing a [`SecretSpec`] or building its secret.
+#[derive(Debug, Snafu)]
+#[non_exhaustive]
+pub enum SpecError {
+    /// The specification has no `scheme:` prefix.
+    #[snafu(display("Secret specification '{spec}' has no scheme"))]
+    MissingScheme {
+        /// The specification.
+        spec: String,
+    },
+    /// The specification has nothing after the scheme.
+    #[snafu(display("Secret specification '{spec}' has no location"))]
+    MissingLocation {
+        /// The specification.
+        spec: String,
+    },
+    /// No handler is registered for the scheme.
+    #[snafu(display("Unknown secret scheme '{scheme}'"))]
+    UnknownScheme {
+        /// The scheme.
+        scheme: String,
+    },
+    /// The encoding is unknown, or can't produce the requested output.
+    #[snafu(display("Unsupported secret encoding '{encoding}'"))]
+    UnsupportedEncoding {
+        /// The encoding.
+        encoding: String,
+    },
+    /// The scheme's handler failed to build the secret.
+    #[snafu(display("Failed to build '{scheme}' secret"))]
+    Build {
+        /// The scheme.
+        scheme: String,
+        /// The handler's error.
+        source: DynError,
+    },
+}
+
+/// A secret source described by a URI-like string, such as
+/// `env:CLIENT_SECRET`, `file:/etc/app/key.pem?encoding=pem` or
+/// `sops:secrets.enc.yaml#/database/password`.
+///
+/// The specification has the form `scheme:location[?params][#field]`:
+///
+/// - the scheme selects the source, via the handlers of a [`SecretFactory`].
+///   Schemes for sources needing a client (e.g. `ssm:`) must be registered
+///   with [`SecretFactory::with_scheme`];
+/// - the location is interpreted by the handler, e.g. as a variable name or path;
+/// - `params` are `&`-separated `key=value` pairs. `encoding` selects how the
+///   value is decoded (`string`, `binary`, `hex`, `base64`, `base64url` or
+///   `pem`, with an optional `label`), and handlers may read others;
+/// - the field extracts a value from a JSON secret, as with
+///   [`JsonFieldEncoding`]: a key, or a JSON pointer if it starts with `/`.
+///
+/// No percent-decoding is applied.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SecretSpec {
+    scheme: String,
+    location: String,
+    params: Vec<(String, String)>,
+    field: Option<String>,
+}
+
+impl SecretSpec {
+    /// Parses a specification.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the specification has no scheme or location.
+    pub fn parse(spec: &str) -> Result<Self, SpecError> {
+        let (scheme, rest) = spec
+            .split_once(':')
+            .filter(|(scheme, _)| is_valid_scheme(scheme))
+            .context(MissingSchemeSnafu { spec })?;
+        let (rest, field) = match rest.split_once('#') {
+            Some((rest, field)) => (rest, Some(field.to_string())),
+            None => (rest, None),
+        };
+        let (location, query) = rest.split_once('?').unwrap_or((rest, ""));
+        ensure!(!location.is_empty(), MissingLocationSnafu { spec });
+
+        let params = query
+            .split('&')
+            .filter(|param| !param.is_empty())
+            .map(|param| {
+                let (key, value) = param.split_once('=').unwrap_or((param, ""));
+                (key.to_string(), value.to_string())
+            })
+            .collect();
+        Ok(Self {
+            scheme: scheme.to_ascii_lowercase(),
+            location: location.to_string(),
+            params,
+            field,
+        })
+    }
+
+    /// Returns the scheme, in lowercase.
+    #[must_use]
+    pub fn scheme(&self) -> &str {
+        &self.scheme
+    }
+
+    /// Returns the location, such as a variable name or path.
+    #[must_use]
+    pub fn location(&self) -> &str {
+        &self.location
+    }
+
+    /// Returns the value of a parameter, if present.
+    #[must_use]
+    pub fn param(&self, key: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Returns the JSON field to extract, if any.
+    #[must_use]
+    pub fn field(&self) -> Option<&str> {
+        self.field.as_deref()
+    }
+
+    /// Returns the `encoding` parameter, if present.
+    #[must_use]
+    pub fn encoding(&self) -> Option<&str> {
+        self.param("encoding")
+    }
+
+    fn wrap_field<E: SecretDecoder>(&self, encoding: E) -> SpecEncoding<E> {
+        match &self.field {
+            Some(pointer) if pointer.starts_with('/') => {
+                SpecEncoding::Field(JsonFieldEncoding::pointer(pointer.clone(), encoding))
+            }
+            Some(key) => SpecEncoding::Field(JsonFieldEncoding::path([key], encoding)),
+            None => SpecEncoding::Plain(encoding),
+        }
+    }
+}
+
+impl std::str::FromStr for SecretSpec {
+    type Err = SpecError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl std::fmt::Display for SecretSpec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.scheme, self.location)?;
+        for (index, (key, value)) in self.params.iter().enumerate() {
+            let separator = if index == 0 { '?' } else { '&' };
+            write!(f, "{separator}{key}={value}")?;
+        }
+        if let Some(field) = &self.field {
+            write!(f, "#{field}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Schemes are a letter followed by letters, digits, `+`, `-` or `.`, as in
+/// RFC 3986. Single letters are rejected, so Windows paths aren't mistaken for
+/// specifications.
+fn is_valid_scheme(scheme: &str) -> bool {
+    let mut chars = scheme.chars();
+    chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+        && scheme.len() > 1
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+}
+
+/// Builds the raw secret for a [`SecretSpec`] with a given scheme.
+///
+/// Implemented for closures taking a `&SecretSpec`. Handlers for remote
+/// stores typically capture a configured client.
+pub trait SchemeHandler: MaybeSendSync {
+    /// Builds the secret, returning the raw bytes of its value.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the specification is invalid for the scheme.
+    fn build(&self, spec: &SecretSpec) -> Result<RawSecret, DynError>;
+}
+
+impl<F> SchemeHandler for F
+where
+    F: Fn(&SecretSpec) -> Result<RawSecret, DynError> + MaybeSendSync,
+{
+    fn build(&self, spec: &SecretSpec) -> Result<RawSecret, DynError> {
+        self(spec)
+    }
+}
+
+/// Builds secrets from [`SecretSpec`]s, for config-driven secret wiring.
+///
+/// Each scheme is handled by a [`SchemeHandler`], which builds a secret
+/// returning the raw value; the factory then applies the specification's
+/// encoding and field. [`SecretFactory::new`] registers the built-in schemes:
+///
+/// - `env:NAME` for [`EnvVarSecret`];
+/// - `file:PATH` for [`FileSecret`];
+/// - `docker:NAME` for [`DockerSecret`];
+/// - `systemd:NAME` for [`SystemdCredential`];
+/// - `sops:PATH` for [`SopsSecret`], usually with a field, as in
+///   `sops:secrets.enc.yaml#/database/password`.
+///
+/// Sources needing a client, such as `SsmParameter` or `VaultKvSecret`, are
+/// added with [`SecretFactory::with_scheme`].
+#[derive(Clone)]
+pub struct SecretFactory {
+    handlers: HashMap<String, Arc<dyn SchemeHandler>>,
+}
+
+impl Default for SecretFactory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl std::fmt::Debug for SecretFactory {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SecretFactory")
+            .field("schemes", &self.handlers.keys())
+            .finish()
+    }
+}
+
+impl SecretFactory {
+    /// Creates a factory with the built-in schemes.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::empty()
+            .with_scheme("env", |spec: &SecretSpec| -> Result<RawSecret, DynError> {
+                Ok(Arc::new(EnvVarSecret::new(spec.location(), BinaryEncoding)))
+            })
+            .with_scheme("file", |spec: &SecretSpec| -> Result<RawSecret, DynError> {
+                Ok(Arc::new(FileSecret::new(spec.location(), BinaryEncoding)))
+            })
+            .with_scheme(
+                "docker",
+                |spec: &SecretSpec| -> Result<RawSecret, DynError> {
+                    Ok(Arc::new(DockerSecret::new(spec.location(), BinaryEncoding)))
+                },
+            )
+            .with_scheme(
+                "systemd",
+                |spec: &SecretSpec| -> Result<RawSecret, DynError> {
+                    Ok(Arc::new(SystemdCredential::new(
+                        spec.location(),
+                        BinaryEncoding,
+                    )))
+                },
+            )
+            .with_scheme("sops", |spec: &SecretSpec| -> Result<RawSecret, DynError> {
+                Ok(Arc::new(SopsSecret::new(spec.location(), BinaryEncoding)))
+            })
+    }
+
+    /// Creates a factory without any schemes.
+    #[must_use]
+    pub fn empty() -> Self {
+        Self {
+            handlers: HashMap::new(),
+        }
+    }
+
+    /// Registers the handler for a scheme, replacing any existing one.
+    #[must_use]
+    pub fn with_scheme(
+        mut self,
+        scheme: impl Into<String>,
+        handler: impl SchemeHandler + 'static,
+    ) -> Self {
+        let mut scheme = scheme.into();
+        scheme.make_ascii_lowercase();
+        self.handlers.insert(scheme, Arc::new(handler));
+        self
+    }
+
+    /// Returns `true` if a handler is registered for the scheme.
+    #[must_use]
+    pub fn supports_scheme(&self, scheme: &str) -> bool {
+        self.handlers.contains_key(&scheme.to_ascii_lowercase())
+    }
+
+    /// Builds the raw secret for a specification, ignoring its encoding and
+    /// field.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the scheme is unknown, or its handler fails.
+    pub fn raw(&self, spec: &SecretSpec) -> Result<RawSecret, SpecError> {
+        let handler = self
+            .handlers
+            .get(spec.scheme())
+            .context(UnknownSchemeSnafu {
+                scheme: spec.scheme(),
+            })?;
+        handler.build(spec).context(BuildSnafu {
+            scheme: spec.scheme(),
+        })
+    }
+
+    /// Builds a secret returning a `SecretString`.
+    ///
+    /// The only supported encoding is `string` (the default).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the specification can't be parsed, the scheme is
+    /// unknown or its handler fails, or the encoding isn't `string`.
+    // Type-erased secrets aren't `Send + Sync` on browser WASM (see
+    // `MaybeSendSync`); the `Arc` only shares them within the thread.
+    #[cfg_attr(wasm_browser, allow(clippy::arc_with_non_send_sync))]
+    pub fn string(
+        &self,
+        spec: &str,
+    ) -> Result<Arc<dyn DynSecret<Output = SecretString>>, SpecError> {
+        let spec = SecretSpec::parse(spec)?;
+        match spec.encoding() {
+            None | Some("string") => {}
+            Some(encoding) => return UnsupportedEncodingSnafu { encoding }.fail(),
+        }
+        Ok(Arc::new(DecodedSecret {
+            inner: self.raw(&spec)?,
+            encoding: spec.wrap_field(StringEncoding),
+        }))
+    }
+
+    /// Builds a secret returning `SecretBox<[u8]>`.
+    ///
+    /// The default encoding is `binary`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the specification can't be parsed, the scheme is
+    /// unknown or its handler fails, or the encoding is unsupported.
+    // Type-erased secrets aren't `Send + Sync` on browser WASM (see
+    // `MaybeSendSync`); the `Arc` only shares them within the thread.
+    #[cfg_attr(wasm_browser, allow(clippy::arc_with_non_send_sync))]
+    pub fn bytes(
+        &self,
+        spec: &str,
+    ) -> Result<Arc<dyn DynSecret<Output = SecretBox<[u8]>>>, SpecError> {
+        let spec = SecretSpec::parse(spec)?;
+        let encoding = match spec.encoding() {
+            None | Some("binary") => BytesEncoding::Binary,
+            Some("hex") => BytesEncoding::Hex,
+            Some("base64") => BytesEncoding::Base64,
+            Some("base64url") => BytesEncoding::Base64Url,
+            Some("pem") => BytesEncoding::Pem(match spec.param("label") {
+                Some(label) => PemEncoding::label(label),
+                None => PemEncoding::new(),
+            }),
+            Some(encoding) => return UnsupportedEncodingSnafu { encoding }.fail(),
+        };
+        Ok(Arc::new(DecodedSecret {
+            inner: self.raw(&spec)?,
+            encoding: spec.wrap_field(encoding),
+        }))
+    }
+}
+
+/// The byte encodings selectable by the `encoding` parameter.
+#[derive(Debug, Clone)]
+enum BytesEncoding {
+    Binary,
+    Hex,
+    Base64,
+    Base64Url,
+    Pem(PemEncoding),
+}
+
+impl SecretDecoder for BytesEncoding {
+    type Output = SecretBox<[u8]>;
+
+    fn decode(&self, bytes: &[u8]) -> Result<Self::Output, DecodingError> {
+        match self {
+            Self::Binary => BinaryEncoding.decode(bytes),
+            Self::Hex => HexEncoding.decode(bytes),
+            Self::Base64 => Base64Encoding.decode(bytes),
+            Self::Base64Url => Base64UrlEncoding.decode(bytes),
+            Self::Pem(pem) => pem.decode(bytes),
+        }
+    }
+}
+
+/// An encoding, optionally applied to a field of a JSON secret.
+#[derive(Debug, Clone)]
+enum SpecEncoding<E: SecretDecoder> {
+    Plain(E),
+    Field(JsonFieldEncoding<E>),
+}
+
+impl<E: SecretDecoder> SecretDecoder for SpecEncoding<E> {
+    type Output = E::Output;
+
+    fn decode(&self, bytes: &[u8]) -> Result<Self::Output, DecodingError> {
+        match self {
+            Self::Plain(encoding) => encoding.decode(bytes),
+            Self::Field(encoding) => encoding.decode(bytes),
+        }
+    }
+}
+
+/// Decodes the value of a raw secret.
+#[derive(Clone)]
+struct DecodedSecret<E> {
+    inner: RawSecret,
+    encoding: E,
+}
+
+impl<E: SecretDecoder> DecodedSecret<E> {
+    fn decode(&self, raw: &SecretBox<[u8]>) -> Result<E::Output, DynError> {
+        self.encoding
+            .decode(raw.expose_secret())
+            .map_err(|source| DynError::new(SecretAccessError::Decode { source }))
+    }
+}
+
+impl<E: SecretDecoder> Secret for DecodedSecret<E> {
+    type Error = DynError;
+    type Output = E::Output;
+
+    async fn get_secret_value(&self) -> Result<Self::Output, Self::Error> {
+        let raw = Secret::get_secret_value(&self.inner).await?;
+        self.decode(&raw)
+    }
+
+    async fn get_secret_with_metadata(
+        &self,
+    ) -> Result<SecretWithMetadata<Self::Output>, Self::Error> {
+        let raw = Secret::get_secret_with_metadata(&self.inner).await?;
+        let value = self.decode(raw.value())?;
+        Ok(raw.map(|_| value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_full_spec() {
+        let spec: SecretSpec = "File:/etc/app/creds.json?encoding=base64&label=KEY#client_secret"
+            .parse()
+            .unwrap();
+
+        assert_eq!(spec.scheme(), "file");
+        assert_eq!(spec.location(), "/etc/app/creds.json");
+        assert_eq!(spec.encoding(), Some("base64"));
+        assert_eq!(spec.param("label"), Some("KEY"));
+        assert_eq!(spec.field(), Some("client_secret"));
+        assert_eq!(
+            spec.to_string(),
+            "file:/etc/app/creds.json?encoding=base64&label=KEY#client_secret"
+        );
+    }
+
+    #[test]
+    fn test_parse_rejects_invalid_specs() {
+        for spec in ["CLIENT_SECRET", r"C:\secret.txt", "1env:NAME"] {
+            assert!(
+                matches!(
+                    SecretSpec::parse(spec),
+                    Err(SpecError::MissingScheme { .. })
+                ),
+                "{spec}"
+            );
+        }
+        assert!(matches!(
+            SecretSpec::parse("env:?encoding=hex"),
+            Err(SpecError::MissingLocation { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_factory_builds_file_secret_with_field() {
+        let path =
+            std::env::temp_dir().join(format!("chewie-crypto-{}-spec.json", std::process::id()));
+        std::fs::write(&path, br#"{"client_secret":"s3cret","key":"deadbeef"}"#).unwrap();
+        let factory = SecretFactory::new();
+
+        let secret = factory
+            .string(&format!("file:{}#client_secret", path.display()))
+            .unwrap();
+        let key = factory
+            .bytes(&format!("file:{}?encoding=hex#/key", path.display()))
+            .unwrap();
+
+        assert_eq!(
+            Secret::get_secret_value(&secret)
+                .await
+                .unwrap()
+                .expose_secret(),
+            "s3cret"
+        );
+        assert_eq!(
+            Secret::get_secret_value(&key)
+                .await
+                .unwrap()
+                .expose_secret(),
+            &[0xde, 0xad, 0xbe, 0xef]
+        );
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_factory_custom_scheme() {
+        let factory = SecretFactory::empty().with_scheme(
+            "static",
+            |spec: &SecretSpec| -> Result<RawSecret, DynError> {
+                let value = spec.location().as_bytes().to_vec().into_boxed_slice();
+                Ok(Arc::new(crate::secrets::StaticSecret::new(SecretBox::new(
+                    value,
+                ))))
+            },
+        );
+
+        let secret = factory.bytes("static:aGk=?encoding=base64").unwrap();
+
+        assert_eq!(
+            Secret::get_secret_value(&secret)
+                .await
+                .unwrap()
+                .expose_secret(),
+            b"hi"
+        );
+        assert!(matches!(
+            factory.string("env:NAME"),
+            Err(SpecError::UnknownScheme { .. })
+        ));
+        assert!(matches!(
+            factory.string("static:x?encoding=hex"),
+            Err(SpecError::UnsupportedEncoding { .. })
+        ));
+    }
+}
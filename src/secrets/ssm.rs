@@ -0,0 +1,219 @@
+//! AWS Systems Manager Parameter Store secret provider.
+
+use aws_sdk_ssm::{
+    Client,
+    error::{ProvideErrorMetadata, SdkError},
+};
+use snafu::prelude::*;
+
+use crate::{
+    DynError,
+    secrets::{
+        Secret, SecretAccessError, SecretSink, SecretVersion, SecretWithMetadata, VersionedSecret,
+        encodings::{SecretDecoder, StringEncoding},
+        providers::RemoteErrorKind,
+        versioned::unix_time,
+    },
+};
+
+#[derive(Debug, Snafu)]
+#[snafu(display("The parameter has no value"))]
+struct MissingValue;
+
+/// Retrieves secrets from AWS Systems Manager Parameter Store.
+///
+/// `SecureString` parameters are decrypted with their KMS key, so the caller
+/// needs `kms:Decrypt` as well as `ssm:GetParameter`. `String` parameters are
+/// returned as-is. The name may include a version or label selector, such as
+/// `/app/client-secret:3`.
+///
+/// The client is configured by the caller (region, credentials, retries), so
+/// this crate doesn't depend on `aws-config`.
+#[derive(Debug, Clone)]
+pub struct SsmParameter<E: SecretDecoder = StringEncoding> {
+    /// The Parameter Store client.
+    client: Client,
+    /// The name or ARN of the parameter.
+    name: String,
+    /// The encoding of the secret.
+    encoding: E,
+}
+
+impl<E: SecretDecoder> SsmParameter<E> {
+    /// Creates a new Parameter Store secret provider with the specified encoding.
+    pub fn new(client: Client, name: impl Into<String>, encoding: E) -> Self {
+        Self {
+            client,
+            name: name.into(),
+            encoding,
+        }
+    }
+
+    /// Returns the name of the parameter.
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the parameter name without any version or label selector.
+    fn unversioned_name(&self) -> &str {
+        let start = self.name.rfind('/').map_or(0, |i| i + 1);
+        match self.name[start..].find(':') {
+            Some(selector) => &self.name[..start + selector],
+            None => &self.name,
+        }
+    }
+
+    fn remote_error(&self, kind: RemoteErrorKind, source: DynError) -> SecretAccessError {
+        SecretAccessError::Remote {
+            name: self.name.clone(),
+            kind,
+            source,
+        }
+    }
+}
+
+impl SsmParameter<StringEncoding> {
+    /// Creates a new Parameter Store secret provider returning a `SecretString`.
+    pub fn string(client: Client, name: impl Into<String>) -> Self {
+        Self::new(client, name, StringEncoding)
+    }
+}
+
+fn error_kind<E: ProvideErrorMetadata, R>(error: &SdkError<E, R>) -> RemoteErrorKind {
+    match error {
+        SdkError::TimeoutError(_) | SdkError::DispatchFailure(_) => RemoteErrorKind::Unavailable,
+        SdkError::ServiceError(e) => match e.err().code() {
+            Some("ParameterNotFound" | "ParameterVersionNotFound") => RemoteErrorKind::NotFound,
+            Some("AccessDeniedException" | "UnrecognizedClientException") => {
+                RemoteErrorKind::PermissionDenied
+            }
+            Some("InternalServerError" | "ThrottlingException" | "TooManyUpdates") => {
+                RemoteErrorKind::Unavailable
+            }
+            _ => RemoteErrorKind::Other,
+        },
+        _ => RemoteErrorKind::Other,
+    }
+}
+
+impl<E: SecretDecoder> SsmParameter<E> {
+    async fn get_parameter(
+        &self,
+        name: &str,
+    ) -> Result<SecretWithMetadata<E::Output>, SecretAccessError> {
+        let output = self
+            .client
+            .get_parameter()
+            .name(name)
+            .with_decryption(true)
+            .send()
+            .await
+            .map_err(|e| self.remote_error(error_kind(&e), DynError::new(e)))?;
+        let parameter = output.parameter();
+        let value = parameter
+            .and_then(|parameter| parameter.value())
+            .ok_or_else(|| {
+                self.remote_error(RemoteErrorKind::NotFound, DynError::new(MissingValue))
+            })?;
+        let value = self
+            .encoding
+            .decode(value.as_bytes())
+            .map_err(|source| SecretAccessError::Decode { source })?;
+
+        let mut secret = SecretWithMetadata::new(value).with_source(self.unversioned_name());
+        if let Some(parameter) = parameter {
+            secret = secret.with_version(parameter.version().to_string());
+            if let Some(modified) = parameter
+                .last_modified_date()
+                .and_then(|date| unix_time(date.secs(), date.subsec_nanos()))
+            {
+                secret = secret.with_created_at(modified);
+            }
+        }
+        Ok(secret)
+    }
+}
+
+impl<E: SecretDecoder> Secret for SsmParameter<E> {
+    type Output = E::Output;
+    type Error = SecretAccessError;
+
+    async fn get_secret_value(&self) -> Result<E::Output, Self::Error> {
+        self.get_secret_with_metadata()
+            .await
+            .map(SecretWithMetadata::into_value)
+    }
+
+    /// The version is the parameter version number, and the creation time is
+    /// when that version was written.
+    async fn get_secret_with_metadata(&self) -> Result<SecretWithMetadata<E::Output>, Self::Error> {
+        self.get_parameter(&self.name).await
+    }
+}
+
+/// Versions are parameter version numbers or labels. Listing versions needs
+/// `ssm:GetParameterHistory`.
+impl<E: SecretDecoder> VersionedSecret for SsmParameter<E> {
+    async fn get_secret_version(&self, version: &str) -> Result<E::Output, Self::Error> {
+        self.get_parameter(&format!("{}:{version}", self.unversioned_name()))
+            .await
+            .map(SecretWithMetadata::into_value)
+    }
+
+    async fn list_versions(&self) -> Result<Vec<SecretVersion>, Self::Error> {
+        let mut versions = Vec::new();
+        let mut next_token = None;
+        loop {
+            let output = self
+                .client
+                .get_parameter_history()
+                .name(self.unversioned_name())
+                .set_next_token(next_token)
+                .send()
+                .await
+                .map_err(|e| self.remote_error(error_kind(&e), DynError::new(e)))?;
+            versions.extend(output.parameters().iter().map(|parameter| {
+                SecretVersion::builder(parameter.version().to_string())
+                    .labels(parameter.labels().iter().cloned())
+                    .maybe_created_at(
+                        parameter
+                            .last_modified_date()
+                            .and_then(|date| unix_time(date.secs(), date.subsec_nanos())),
+                    )
+                    .build()
+            }));
+            next_token = output.next_token().map(str::to_owned);
+            if next_token.is_none() {
+                break;
+            }
+        }
+        // The history is oldest first.
+        versions.reverse();
+        Ok(versions)
+    }
+}
+
+/// Overwrites the parameter with a new version, keeping its type and KMS key.
+///
+/// The parameter must already exist, and the caller needs
+/// `ssm:PutParameter` (and `kms:Encrypt` for `SecureString` parameters). Any
+/// version or label selector in the name is ignored. Parameter values are
+/// text, so the value must be valid UTF-8.
+impl<E: SecretDecoder> SecretSink for SsmParameter<E> {
+    type Error = SecretAccessError;
+
+    async fn put_secret(&self, value: &[u8]) -> Result<(), Self::Error> {
+        let value =
+            std::str::from_utf8(value).map_err(|source| SecretAccessError::NotUtf8 { source })?;
+        self.client
+            .put_parameter()
+            .name(self.unversioned_name())
+            .value(value)
+            .overwrite(true)
+            .send()
+            .await
+            .map_err(|e| self.remote_error(error_kind(&e), DynError::new(e)))?;
+        Ok(())
+    }
+}
@@ -0,0 +1,73 @@
+//! Loopback HTTP server for testing the remote secret providers.
+
+use std::{
+    io::{BufRead, BufReader, Read, Write},
+    net::TcpListener,
+    thread::JoinHandle,
+};
+
+/// Serves the given `(status, body)` responses, one per connection, and
+/// returns the requests received.
+///
+/// Each request is recorded as its request line, followed by ` name=value`
+/// for each of `headers` it has (matched case-insensitively, in the order
+/// received), and its body, if any.
+pub(crate) fn serve(
+    responses: Vec<(u16, &'static str)>,
+    headers: &'static [&'static str],
+) -> (String, JoinHandle<Vec<String>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = format!("http://{}", listener.local_addr().unwrap());
+    let handle = std::thread::spawn(move || {
+        let mut requests = Vec::new();
+        for (status, body) in responses {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut request = String::new();
+            let mut content_length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                let line = line.trim_end();
+                if line.is_empty() {
+                    break;
+                }
+                if request.is_empty() {
+                    request.push_str(line);
+                    continue;
+                }
+                let Some((name, value)) = line.split_once(':') else {
+                    continue;
+                };
+                let name = name.to_ascii_lowercase();
+                if name == "content-length" {
+                    content_length = value.trim().parse().unwrap();
+                } else if headers.contains(&name.as_str()) {
+                    request.push(' ');
+                    request.push_str(&name);
+                    request.push('=');
+                    request.push_str(value.trim());
+                }
+            }
+            let mut request_body = Vec::new();
+            reader
+                .by_ref()
+                .take(content_length)
+                .read_to_end(&mut request_body)
+                .unwrap();
+            if !request_body.is_empty() {
+                request.push(' ');
+                request.push_str(&String::from_utf8(request_body).unwrap());
+            }
+            write!(
+                reader.get_mut(),
+                "HTTP/1.1 {status} X\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            )
+            .unwrap();
+            requests.push(request);
+        }
+        requests
+    });
+    (address, handle)
+}
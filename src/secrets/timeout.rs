@@ -0,0 +1,106 @@
+//! Secret wrapper imposing a deadline on retrievals.
+
+use std::time::Duration;
+
+use crate::{
+    TimeoutError,
+    secrets::{Secret, SecretWithMetadata},
+    timeout::with_timeout,
+};
+
+/// A secret that fails retrievals that don't complete within a deadline.
+///
+/// Remote secret stores (e.g. a cloud metadata service) can otherwise hang
+/// indefinitely on network issues, stalling application startup. On expiry,
+/// the in-flight retrieval is dropped and [`TimeoutError::Elapsed`] is
+/// returned.
+#[derive(Debug, Clone)]
+pub struct TimeoutSecret<S> {
+    inner: S,
+    timeout: Duration,
+}
+
+impl<S: Secret> TimeoutSecret<S> {
+    /// Wraps a secret with the given timeout per retrieval.
+    pub fn new(inner: S, timeout: Duration) -> Self {
+        Self { inner, timeout }
+    }
+
+    /// Returns the wrapped secret.
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    /// Returns the timeout applied to each retrieval.
+    #[must_use]
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    fn elapsed(&self) -> TimeoutError<S::Error> {
+        TimeoutError::Elapsed {
+            duration: self.timeout,
+        }
+    }
+}
+
+impl<S: Secret> Secret for TimeoutSecret<S> {
+    type Error = TimeoutError<S::Error>;
+    type Output = S::Output;
+
+    async fn get_secret_value(&self) -> Result<Self::Output, Self::Error> {
+        with_timeout(self.timeout, self.inner.get_secret_value())
+            .await
+            .ok_or_else(|| self.elapsed())?
+            .map_err(|source| TimeoutError::Inner { source })
+    }
+
+    async fn get_secret_with_metadata(
+        &self,
+    ) -> Result<SecretWithMetadata<Self::Output>, Self::Error> {
+        with_timeout(self.timeout, self.inner.get_secret_with_metadata())
+            .await
+            .ok_or_else(|| self.elapsed())?
+            .map_err(|source| TimeoutError::Inner { source })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+
+    use super::*;
+
+    #[derive(Debug, Clone)]
+    struct MockSecret {
+        hang: bool,
+    }
+
+    impl Secret for MockSecret {
+        type Error = Infallible;
+        type Output = &'static str;
+
+        async fn get_secret_value(&self) -> Result<Self::Output, Self::Error> {
+            if self.hang {
+                std::future::pending::<()>().await;
+            }
+            Ok("value")
+        }
+    }
+
+    #[tokio::test]
+    async fn test_completes_within_timeout() {
+        let secret = TimeoutSecret::new(MockSecret { hang: false }, Duration::from_secs(5));
+
+        assert_eq!(secret.get_secret_value().await.unwrap(), "value");
+    }
+
+    #[tokio::test]
+    async fn test_hanging_secret_times_out() {
+        let secret = TimeoutSecret::new(MockSecret { hang: true }, Duration::from_millis(10));
+
+        let result = secret.get_secret_with_metadata().await;
+
+        assert!(matches!(result, Err(TimeoutError::Elapsed { .. })));
+    }
+}
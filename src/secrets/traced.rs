@@ -0,0 +1,154 @@
+//! Secret wrapper instrumenting retrieval with `tracing` spans.
+
+use std::borrow::Cow;
+
+use tracing::{Instrument, Span, field};
+use web_time::Instant;
+
+use crate::secrets::{RemoteErrorKind, Secret, SecretAccessError, SecretWithMetadata};
+
+/// Classifies a retrieval error for the `error.class` field, from the first
+/// [`SecretAccessError`] in its source chain.
+fn error_class(error: &(dyn std::error::Error + 'static)) -> &'static str {
+    let mut current = Some(error);
+    while let Some(error) = current {
+        if let Some(error) = error.downcast_ref::<SecretAccessError>() {
+            return match error {
+                SecretAccessError::EnvAccess { .. } => "env",
+                SecretAccessError::FileAccess { .. }
+                | SecretAccessError::FileWrite { .. }
+                | SecretAccessError::InvalidName { .. } => "file",
+                SecretAccessError::NotUtf8 { .. } | SecretAccessError::Decode { .. } => "decode",
+                SecretAccessError::CommandSpawn { .. }
+                | SecretAccessError::CommandFailed { .. } => "command",
+                SecretAccessError::Remote { kind, .. } => match kind {
+                    RemoteErrorKind::NotFound => "not_found",
+                    RemoteErrorKind::PermissionDenied => "permission_denied",
+                    RemoteErrorKind::Unavailable => "unavailable",
+                    _ => "remote",
+                },
+            };
+        }
+        current = error.source();
+    }
+    "other"
+}
+
+/// A secret whose retrievals are instrumented with a `secret.get` span.
+///
+/// The span has these fields, for subscribers to record or export:
+///
+/// - `secret.name`: the name given to the wrapper.
+/// - `secret.source` and `secret.version`: from the value's metadata, when
+///   retrieved with [`Secret::get_secret_with_metadata`].
+/// - `secret.cache`: `hit`, `stale` or `miss`, when a
+///   [`CachedSecret`](crate::secrets::CachedSecret) is wrapped.
+/// - `elapsed_ms`: the retrieval latency.
+/// - `error.class`: on failure, a category such as `not_found`,
+///   `unavailable` or `decode`.
+///
+/// Failures are also reported with a `WARN` event. Values are never recorded.
+#[derive(Debug, Clone)]
+pub struct TracedSecret<S> {
+    inner: S,
+    name: Cow<'static, str>,
+}
+
+impl<S: Secret> TracedSecret<S> {
+    /// Wraps a secret, naming it in spans with the given name.
+    pub fn new(inner: S, name: impl Into<Cow<'static, str>>) -> Self {
+        Self {
+            inner,
+            name: name.into(),
+        }
+    }
+
+    /// Returns the wrapped secret.
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    /// Returns the name used in spans.
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn span(&self) -> Span {
+        tracing::info_span!(
+            "secret.get",
+            secret.name = %self.name,
+            secret.source = field::Empty,
+            secret.version = field::Empty,
+            secret.cache = field::Empty,
+            elapsed_ms = field::Empty,
+            error.class = field::Empty,
+        )
+    }
+
+    async fn trace<T>(
+        &self,
+        span: &Span,
+        retrieval: impl Future<Output = Result<T, S::Error>>,
+    ) -> Result<T, S::Error> {
+        let started = Instant::now();
+        let result = retrieval.instrument(span.clone()).await;
+        span.record("elapsed_ms", started.elapsed().as_secs_f64() * 1000.0);
+        if let Err(error) = &result {
+            let class = error_class(error);
+            span.record("error.class", class);
+            tracing::warn!(parent: span, error.class = class, error = %error, "Failed to retrieve secret");
+        }
+        result
+    }
+}
+
+impl<S: Secret> Secret for TracedSecret<S> {
+    type Error = S::Error;
+    type Output = S::Output;
+
+    async fn get_secret_value(&self) -> Result<Self::Output, Self::Error> {
+        let span = self.span();
+        self.trace(&span, self.inner.get_secret_value()).await
+    }
+
+    async fn get_secret_with_metadata(
+        &self,
+    ) -> Result<SecretWithMetadata<Self::Output>, Self::Error> {
+        let span = self.span();
+        let secret = self
+            .trace(&span, self.inner.get_secret_with_metadata())
+            .await?;
+        if let Some(source) = secret.source() {
+            span.record("secret.source", source);
+        }
+        if let Some(version) = secret.version() {
+            span.record("secret.version", version);
+        }
+        Ok(secret)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DynError, TimeoutError, secrets::FileSecret};
+
+    #[tokio::test]
+    async fn test_error_class_follows_source_chain() {
+        let error = FileSecret::string("/nonexistent/secret")
+            .get_secret_value()
+            .await
+            .unwrap_err();
+        let wrapped: TimeoutError<SecretAccessError> = TimeoutError::Inner { source: error };
+        let remote = SecretAccessError::Remote {
+            name: "db".into(),
+            kind: RemoteErrorKind::Unavailable,
+            source: DynError::new(std::io::Error::other("down")),
+        };
+
+        assert_eq!(error_class(&wrapped), "file");
+        assert_eq!(error_class(&remote), "unavailable");
+        assert_eq!(error_class(&std::io::Error::other("x")), "other");
+    }
+}
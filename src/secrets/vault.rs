@@ -0,0 +1,699 @@
+//! HashiCorp Vault KV v2 secret provider.
+
+use std::{cmp::Reverse, sync::Arc};
+
+use bon::bon;
+use secrecy::{ExposeSecret, SecretString, zeroize::Zeroizing};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use snafu::prelude::*;
+use tokio::sync::Mutex;
+use web_time::{Duration, Instant, SystemTime};
+
+use crate::{
+    DynError,
+    secrets::{
+        Secret, SecretAccessError, SecretSink, SecretVersion, SecretWithMetadata, VersionedSecret,
+        encodings::{SecretDecoder, StringEncoding, zeroize_json},
+        providers::RemoteErrorKind,
+        versioned::unix_time,
+    },
+};
+
+/// The default mount path of the AppRole auth method.
+const DEFAULT_APPROLE_MOUNT: &str = "approle";
+
+#[derive(Debug, Snafu)]
+enum VaultError {
+    #[snafu(display("Request to Vault failed"))]
+    Request { source: reqwest::Error },
+    #[snafu(display("Vault returned HTTP {status}: {}", errors.join("; ")))]
+    Api { status: u16, errors: Vec<String> },
+    #[snafu(display("Field '{field}' not found in secret"))]
+    MissingField { field: String },
+}
+
+impl VaultError {
+    fn kind(&self) -> RemoteErrorKind {
+        match self {
+            Self::Request { source } if source.is_timeout() || source.is_connect() => {
+                RemoteErrorKind::Unavailable
+            }
+            Self::Api { status: 404, .. } | Self::MissingField { .. } => RemoteErrorKind::NotFound,
+            Self::Api {
+                status: 401 | 403, ..
+            } => RemoteErrorKind::PermissionDenied,
+            // Vault returns 503 while sealed or in standby.
+            Self::Api {
+                status: 429 | 500..=599,
+                ..
+            } => RemoteErrorKind::Unavailable,
+            Self::Request { .. } | Self::Api { .. } => RemoteErrorKind::Other,
+        }
+    }
+}
+
+/// How a [`VaultClient`] authenticates to Vault.
+#[derive(Debug, Clone)]
+pub enum VaultAuth {
+    /// A static token, such as one written by the Vault agent.
+    Token(SecretString),
+    /// The AppRole auth method. Tokens are cached for their lease, and the
+    /// client logs in again before a lease expires.
+    AppRole {
+        /// The mount path of the auth method.
+        mount: String,
+        /// The role ID.
+        role_id: String,
+        /// The secret ID.
+        secret_id: SecretString,
+    },
+}
+
+impl VaultAuth {
+    /// Authenticates with a static token.
+    pub fn token(token: impl Into<String>) -> Self {
+        Self::Token(SecretString::from(token.into()))
+    }
+
+    /// Authenticates with AppRole, mounted at the default `approle` path.
+    pub fn app_role(role_id: impl Into<String>, secret_id: impl Into<String>) -> Self {
+        Self::AppRole {
+            mount: DEFAULT_APPROLE_MOUNT.to_owned(),
+            role_id: role_id.into(),
+            secret_id: SecretString::from(secret_id.into()),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct LeasedToken {
+    token: SecretString,
+    /// When to log in again, or `None` if the token doesn't expire.
+    renew_at: Option<Instant>,
+}
+
+#[derive(Debug)]
+struct VaultClientInner {
+    http: reqwest::Client,
+    address: String,
+    namespace: Option<String>,
+    auth: VaultAuth,
+    token: Mutex<Option<LeasedToken>>,
+}
+
+/// A client for the Vault HTTP API, shared by [`VaultKvSecret`] providers.
+///
+/// Clones share the same token, so an AppRole login is reused by every
+/// secret read through the client.
+#[derive(Debug, Clone)]
+pub struct VaultClient {
+    inner: Arc<VaultClientInner>,
+}
+
+#[derive(Deserialize)]
+struct LoginResponse {
+    auth: LoginAuth,
+}
+
+#[derive(Deserialize)]
+struct LoginAuth {
+    client_token: String,
+    lease_duration: u64,
+}
+
+#[derive(Deserialize)]
+struct KvResponse {
+    data: KvData,
+}
+
+#[derive(Deserialize)]
+struct KvData {
+    data: Map<String, Value>,
+    metadata: Option<KvVersion>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct KvVersion {
+    version: Option<u64>,
+    created_time: String,
+    /// When the version is (or was) deleted, e.g. due to the mount's
+    /// `delete_version_after`, or empty.
+    deletion_time: String,
+}
+
+#[derive(Deserialize)]
+struct MetadataResponse {
+    data: Metadata,
+}
+
+#[derive(Deserialize)]
+struct Metadata {
+    versions: Map<String, Value>,
+}
+
+#[derive(Deserialize)]
+struct VersionMetadata {
+    created_time: String,
+    #[serde(default)]
+    deletion_time: String,
+    #[serde(default)]
+    destroyed: bool,
+}
+
+/// Parses an RFC 3339 UTC timestamp, as returned by Vault, such as
+/// `2018-03-22T02:24:06.945319214Z`.
+fn parse_timestamp(timestamp: &str) -> Option<SystemTime> {
+    let (date, time) = timestamp.strip_suffix('Z')?.split_once('T')?;
+    let mut date = date.splitn(3, '-').map(str::parse::<i64>);
+    let (year, month, day) = (date.next()?.ok()?, date.next()?.ok()?, date.next()?.ok()?);
+    let (time, fraction) = time.split_once('.').unwrap_or((time, ""));
+    let mut time = time.splitn(3, ':').map(str::parse::<i64>);
+    let (hour, minute, second) = (time.next()?.ok()?, time.next()?.ok()?, time.next()?.ok()?);
+    let nanos = if fraction.is_empty() {
+        0
+    } else {
+        format!("{fraction:0<9}").get(..9)?.parse().ok()?
+    };
+
+    // Days since the epoch, using the `days_from_civil` algorithm.
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146_097 + day_of_era - 719_468;
+    unix_time(days * 86_400 + hour * 3_600 + minute * 60 + second, nanos)
+}
+
+#[derive(Deserialize, Default)]
+struct ErrorResponse {
+    #[serde(default)]
+    errors: Vec<String>,
+}
+
+#[bon]
+impl VaultClient {
+    /// Creates a builder for a client of the Vault server at the given address.
+    #[builder]
+    pub fn new(
+        /// The address of the Vault server, such as `https://vault:8200`.
+        #[builder(start_fn, into)]
+        address: String,
+        /// How to authenticate.
+        auth: VaultAuth,
+        /// The Vault Enterprise namespace.
+        #[builder(into)]
+        namespace: Option<String>,
+        /// The HTTP client, e.g. configured with a custom CA or timeouts.
+        http_client: Option<reqwest::Client>,
+    ) -> Self {
+        let mut address = address;
+        address.truncate(address.trim_end_matches('/').len());
+        Self {
+            inner: Arc::new(VaultClientInner {
+                http: http_client.unwrap_or_default(),
+                address,
+                namespace,
+                auth,
+                token: Mutex::new(None),
+            }),
+        }
+    }
+
+    /// Returns the address of the Vault server.
+    #[must_use]
+    pub fn address(&self) -> &str {
+        &self.inner.address
+    }
+
+    fn request(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        let request = self
+            .inner
+            .http
+            .request(method, format!("{}/v1/{path}", self.inner.address));
+        match &self.inner.namespace {
+            Some(namespace) => request.header("X-Vault-Namespace", namespace),
+            None => request,
+        }
+    }
+
+    async fn send(request: reqwest::RequestBuilder) -> Result<reqwest::Response, VaultError> {
+        let response = request.send().await.context(RequestSnafu)?;
+        let status = response.status();
+        if status.is_success() {
+            return Ok(response);
+        }
+        let errors = response
+            .json::<ErrorResponse>()
+            .await
+            .unwrap_or_default()
+            .errors;
+        ApiSnafu {
+            status: status.as_u16(),
+            errors,
+        }
+        .fail()
+    }
+
+    async fn token(&self) -> Result<SecretString, VaultError> {
+        let (mount, role_id, secret_id) = match &self.inner.auth {
+            VaultAuth::Token(token) => return Ok(token.clone()),
+            VaultAuth::AppRole {
+                mount,
+                role_id,
+                secret_id,
+            } => (mount, role_id, secret_id),
+        };
+
+        let mut cached = self.inner.token.lock().await;
+        if let Some(leased) = &*cached
+            && leased.renew_at.is_none_or(|at| Instant::now() < at)
+        {
+            return Ok(leased.token.clone());
+        }
+
+        let body = serde_json::json!({
+            "role_id": role_id,
+            "secret_id": secret_id.expose_secret(),
+        });
+        let request = self
+            .request(reqwest::Method::POST, &format!("auth/{mount}/login"))
+            .json(&body);
+        let login: LoginResponse = Self::send(request)
+            .await?
+            .json()
+            .await
+            .context(RequestSnafu)?;
+        let token = SecretString::from(login.auth.client_token);
+        // Log in again once two thirds of the lease has elapsed, leaving time
+        // for the login itself before the token expires.
+        let renew_at = (login.auth.lease_duration > 0)
+            .then(|| Instant::now() + Duration::from_secs(login.auth.lease_duration) * 2 / 3);
+        *cached = Some(LeasedToken {
+            token: token.clone(),
+            renew_at,
+        });
+        Ok(token)
+    }
+
+    /// Discards a cached AppRole token, e.g. after it was revoked.
+    async fn invalidate_token(&self) {
+        *self.inner.token.lock().await = None;
+    }
+
+    async fn read_kv(
+        &self,
+        mount: &str,
+        path: &str,
+        version: Option<&str>,
+    ) -> Result<KvData, VaultError> {
+        let token = self.token().await?;
+        let mut request = self
+            .request(reqwest::Method::GET, &format!("{mount}/data/{path}"))
+            .header("X-Vault-Token", token.expose_secret());
+        if let Some(version) = version {
+            request = request.query(&[("version", version)]);
+        }
+        let response: KvResponse = Self::send(request)
+            .await?
+            .json()
+            .await
+            .context(RequestSnafu)?;
+        Ok(response.data)
+    }
+
+    async fn read_metadata(&self, mount: &str, path: &str) -> Result<Metadata, VaultError> {
+        let token = self.token().await?;
+        let request = self
+            .request(reqwest::Method::GET, &format!("{mount}/metadata/{path}"))
+            .header("X-Vault-Token", token.expose_secret());
+        let response: MetadataResponse = Self::send(request)
+            .await?
+            .json()
+            .await
+            .context(RequestSnafu)?;
+        Ok(response.data)
+    }
+
+    /// Merges fields into the latest version of a secret, creating a new version.
+    async fn patch_kv(
+        &self,
+        mount: &str,
+        path: &str,
+        data: Map<String, Value>,
+    ) -> Result<(), VaultError> {
+        let token = self.token().await?;
+        let request = self
+            .request(reqwest::Method::PATCH, &format!("{mount}/data/{path}"))
+            .header("X-Vault-Token", token.expose_secret())
+            .header(
+                reqwest::header::CONTENT_TYPE,
+                "application/merge-patch+json",
+            )
+            .body(serde_json::json!({ "data": data }).to_string());
+        Self::send(request).await?;
+        Ok(())
+    }
+}
+
+/// Retrieves a field of a HashiCorp Vault KV v2 secret.
+///
+/// String fields are decoded from their value; other JSON values are decoded
+/// from their JSON serialization. If an AppRole token is rejected (e.g. it was
+/// revoked before its lease expired), the client logs in again and retries
+/// once.
+#[derive(Debug, Clone)]
+pub struct VaultKvSecret<E: SecretDecoder = StringEncoding> {
+    /// The Vault client.
+    client: VaultClient,
+    /// The mount path of the KV v2 secrets engine.
+    mount: String,
+    /// The path of the secret within the mount.
+    path: String,
+    /// The field of the secret to read.
+    field: String,
+    /// The encoding of the secret.
+    encoding: E,
+}
+
+impl<E: SecretDecoder> VaultKvSecret<E> {
+    /// Creates a new Vault KV v2 secret provider with the specified encoding.
+    pub fn new(
+        client: VaultClient,
+        mount: impl Into<String>,
+        path: impl Into<String>,
+        field: impl Into<String>,
+        encoding: E,
+    ) -> Self {
+        Self {
+            client,
+            mount: mount.into(),
+            path: path.into(),
+            field: field.into(),
+            encoding,
+        }
+    }
+
+    /// Returns the mount path of the KV v2 secrets engine.
+    #[must_use]
+    pub fn mount(&self) -> &str {
+        &self.mount
+    }
+
+    /// Returns the path of the secret within the mount.
+    #[must_use]
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Returns the field of the secret to read.
+    #[must_use]
+    pub fn field(&self) -> &str {
+        &self.field
+    }
+
+    async fn read_field(
+        &self,
+        version: Option<&str>,
+    ) -> Result<(Zeroizing<Vec<u8>>, Option<KvVersion>), VaultError> {
+        let KvData { mut data, metadata } = self
+            .client
+            .read_kv(&self.mount, &self.path, version)
+            .await?;
+        let field = data.remove(&self.field);
+        // The other fields of the secret are discarded, so zeroize them too.
+        data.values_mut().for_each(zeroize_json);
+        let value = match field {
+            Some(Value::String(value)) => value.into_bytes(),
+            Some(mut value) => {
+                let serialized = value.to_string();
+                zeroize_json(&mut value);
+                serialized.into_bytes()
+            }
+            None => {
+                return MissingFieldSnafu {
+                    field: self.field.clone(),
+                }
+                .fail();
+            }
+        };
+        Ok((Zeroizing::new(value), metadata))
+    }
+
+    async fn read(
+        &self,
+        version: Option<&str>,
+    ) -> Result<SecretWithMetadata<E::Output>, SecretAccessError> {
+        let (value, metadata) = self.with_retry(|| self.read_field(version)).await?;
+        let value = self
+            .encoding
+            .decode(&value)
+            .map_err(|source| SecretAccessError::Decode { source })?;
+
+        let mut secret =
+            SecretWithMetadata::new(value).with_source(format!("{}/{}", self.mount, self.path));
+        if let Some(metadata) = metadata {
+            if let Some(version) = metadata.version {
+                secret = secret.with_version(version.to_string());
+            }
+            if let Some(created) = parse_timestamp(&metadata.created_time) {
+                secret = secret.with_created_at(created);
+            }
+            if let Some(deleted) = parse_timestamp(&metadata.deletion_time) {
+                secret = secret.with_expires_at(deleted);
+            }
+        }
+        Ok(secret)
+    }
+
+    async fn write_field(&self, value: &str) -> Result<(), VaultError> {
+        let data = Map::from_iter([(self.field.clone(), Value::from(value))]);
+        self.client.patch_kv(&self.mount, &self.path, data).await
+    }
+
+    /// Runs a request, logging in again and retrying once if an AppRole
+    /// token is rejected.
+    async fn with_retry<T, F, Fut>(&self, request: F) -> Result<T, SecretAccessError>
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = Result<T, VaultError>>,
+    {
+        let result = match request().await {
+            Err(VaultError::Api { status: 403, .. })
+                if matches!(self.client.inner.auth, VaultAuth::AppRole { .. }) =>
+            {
+                self.client.invalidate_token().await;
+                request().await
+            }
+            result => result,
+        };
+        result.map_err(|e| self.remote_error(e))
+    }
+
+    fn remote_error(&self, error: VaultError) -> SecretAccessError {
+        SecretAccessError::Remote {
+            name: format!("{}/{}", self.mount, self.path),
+            kind: error.kind(),
+            source: DynError::new(error),
+        }
+    }
+}
+
+impl VaultKvSecret<StringEncoding> {
+    /// Creates a new Vault KV v2 secret provider returning a `SecretString`.
+    pub fn string(
+        client: VaultClient,
+        mount: impl Into<String>,
+        path: impl Into<String>,
+        field: impl Into<String>,
+    ) -> Self {
+        Self::new(client, mount, path, field, StringEncoding)
+    }
+}
+
+impl<E: SecretDecoder> Secret for VaultKvSecret<E> {
+    type Output = E::Output;
+    type Error = SecretAccessError;
+
+    async fn get_secret_value(&self) -> Result<E::Output, Self::Error> {
+        self.read(None).await.map(SecretWithMetadata::into_value)
+    }
+
+    /// The version is the KV version number. If the mount deletes versions
+    /// after a period (`delete_version_after`), the expiry is when this
+    /// version will be deleted.
+    async fn get_secret_with_metadata(&self) -> Result<SecretWithMetadata<E::Output>, Self::Error> {
+        self.read(None).await
+    }
+}
+
+/// Versions are KV version numbers. Listing versions needs the `read`
+/// capability on the secret's metadata path.
+impl<E: SecretDecoder> VersionedSecret for VaultKvSecret<E> {
+    async fn get_secret_version(&self, version: &str) -> Result<E::Output, Self::Error> {
+        self.read(Some(version))
+            .await
+            .map(SecretWithMetadata::into_value)
+    }
+
+    async fn list_versions(&self) -> Result<Vec<SecretVersion>, Self::Error> {
+        let metadata = self
+            .with_retry(|| self.client.read_metadata(&self.mount, &self.path))
+            .await?;
+        let mut versions: Vec<_> = metadata
+            .versions
+            .into_iter()
+            .filter_map(|(id, metadata)| {
+                let metadata: VersionMetadata = serde_json::from_value(metadata).ok()?;
+                Some(
+                    SecretVersion::builder(id)
+                        .maybe_created_at(parse_timestamp(&metadata.created_time))
+                        .enabled(metadata.deletion_time.is_empty() && !metadata.destroyed)
+                        .build(),
+                )
+            })
+            .collect();
+        versions.sort_by_key(|version| Reverse(version.id().parse::<u64>().unwrap_or_default()));
+        Ok(versions)
+    }
+}
+
+/// Sets the field in a new version of the secret, keeping its other fields.
+///
+/// The secret must already exist, and the token needs the `patch` capability
+/// on its data path. KV fields are text, so the value must be valid UTF-8.
+impl<E: SecretDecoder> SecretSink for VaultKvSecret<E> {
+    type Error = SecretAccessError;
+
+    async fn put_secret(&self, value: &[u8]) -> Result<(), Self::Error> {
+        let value =
+            std::str::from_utf8(value).map_err(|source| SecretAccessError::NotUtf8 { source })?;
+        self.with_retry(|| self.write_field(value)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use secrecy::ExposeSecret;
+
+    use super::*;
+    use crate::secrets::test_server::serve;
+
+    #[tokio::test]
+    async fn test_app_role_token_is_reused() {
+        let (address, server) = serve(
+            vec![
+                (
+                    200,
+                    r#"{"auth":{"client_token":"t1","lease_duration":3600}}"#,
+                ),
+                (
+                    200,
+                    r#"{"data":{"data":{"password":"s3cret"},"metadata":{}}}"#,
+                ),
+                (
+                    200,
+                    r#"{"data":{"data":{"password":"s3cret"},"metadata":{"version":3,"created_time":"2018-03-22T02:24:06.945319214Z","deletion_time":""}}}"#,
+                ),
+            ],
+            &["x-vault-token"],
+        );
+        let client = VaultClient::builder(address)
+            .auth(VaultAuth::app_role("role", "secret"))
+            .build();
+        let secret = VaultKvSecret::string(client, "secret", "app", "password");
+
+        let first = secret.get_secret_value().await.unwrap();
+        let second = secret.get_secret_with_metadata().await.unwrap();
+
+        assert_eq!(first.expose_secret(), "s3cret");
+        assert_eq!(second.value().expose_secret(), "s3cret");
+        assert_eq!(second.version(), Some("3"));
+        assert_eq!(second.source(), Some("secret/app"));
+        assert!(second.created_at().is_some());
+        assert!(second.expires_at().is_none());
+        assert_eq!(
+            server.join().unwrap(),
+            [
+                r#"POST /v1/auth/approle/login HTTP/1.1 {"role_id":"role","secret_id":"secret"}"#,
+                "GET /v1/secret/data/app HTTP/1.1 x-vault-token=t1",
+                "GET /v1/secret/data/app HTTP/1.1 x-vault-token=t1",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_put_secret_patches_field() {
+        let (address, server) = serve(vec![(200, r#"{"data":{"version":2}}"#)], &["x-vault-token"]);
+        let client = VaultClient::builder(address)
+            .auth(VaultAuth::token("t"))
+            .build();
+        let secret = VaultKvSecret::string(client, "secret", "app", "password");
+
+        secret.put_secret(b"n3w").await.unwrap();
+
+        assert_eq!(
+            server.join().unwrap(),
+            [r#"PATCH /v1/secret/data/app HTTP/1.1 x-vault-token=t {"data":{"password":"n3w"}}"#]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_list_versions_newest_first() {
+        let (address, server) = serve(
+            vec![(
+                200,
+                r#"{"data":{"current_version":10,"versions":{
+                "9":{"created_time":"2018-03-22T02:24:06.945319214Z","deletion_time":"","destroyed":false},
+                "10":{"created_time":"2018-03-22T02:36:33.954880664Z","deletion_time":"2018-03-22T02:40:00Z","destroyed":false}
+            }}}"#,
+            )],
+            &["x-vault-token"],
+        );
+        let client = VaultClient::builder(address)
+            .auth(VaultAuth::token("t"))
+            .build();
+        let secret = VaultKvSecret::string(client, "secret", "app", "password");
+
+        let versions = secret.list_versions().await.unwrap();
+
+        assert_eq!(
+            versions,
+            [
+                SecretVersion::builder("10")
+                    .created_at(SystemTime::UNIX_EPOCH + Duration::new(1_521_686_193, 954_880_664))
+                    .enabled(false)
+                    .build(),
+                SecretVersion::builder("9")
+                    .created_at(SystemTime::UNIX_EPOCH + Duration::new(1_521_685_446, 945_319_214))
+                    .build(),
+            ]
+        );
+        assert_eq!(
+            server.join().unwrap(),
+            ["GET /v1/secret/metadata/app HTTP/1.1 x-vault-token=t"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_missing_secret_is_not_found() {
+        let (address, server) = serve(vec![(404, r#"{"errors":[]}"#)], &["x-vault-token"]);
+        let client = VaultClient::builder(address)
+            .auth(VaultAuth::token("t"))
+            .build();
+        let secret = VaultKvSecret::string(client, "secret", "missing", "password");
+
+        let result = secret.get_secret_value().await;
+
+        assert!(matches!(
+            result,
+            Err(SecretAccessError::Remote {
+                kind: RemoteErrorKind::NotFound,
+                ..
+            })
+        ));
+        server.join().unwrap();
+    }
+}
@@ -0,0 +1,83 @@
+use bon::Builder;
+use web_time::SystemTime;
+
+use crate::{MaybeSend, secrets::Secret};
+
+/// Metadata of a version of a secret, as returned by
+/// [`VersionedSecret::list_versions`].
+#[derive(Debug, Clone, PartialEq, Eq, Builder)]
+#[builder(on(String, into))]
+pub struct SecretVersion {
+    /// The version identifier, as accepted by
+    /// [`VersionedSecret::get_secret_version`].
+    #[builder(start_fn)]
+    id: String,
+    /// The labels or stages attached to the version.
+    #[builder(default, with = <_>::from_iter)]
+    labels: Vec<String>,
+    /// When the version was created.
+    created_at: Option<SystemTime>,
+    /// Whether the version can be retrieved, rather than being disabled or
+    /// destroyed.
+    #[builder(default = true)]
+    enabled: bool,
+}
+
+impl SecretVersion {
+    /// Returns the version identifier.
+    #[must_use]
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Returns the labels or stages attached to the version, such as
+    /// `AWSCURRENT`.
+    #[must_use]
+    pub fn labels(&self) -> &[String] {
+        &self.labels
+    }
+
+    /// Returns when the version was created, if known.
+    #[must_use]
+    pub fn created_at(&self) -> Option<SystemTime> {
+        self.created_at
+    }
+
+    /// Returns `true` if the version can be retrieved.
+    #[must_use]
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+}
+
+/// Converts a Unix timestamp to a `SystemTime`, or `None` if it's before the epoch.
+#[cfg(any(
+    feature = "aws-ssm",
+    feature = "gcp-secret-manager",
+    feature = "vault",
+    feature = "cloud-identity"
+))]
+pub(crate) fn unix_time(seconds: i64, nanos: u32) -> Option<SystemTime> {
+    let seconds = u64::try_from(seconds).ok()?;
+    Some(web_time::UNIX_EPOCH + web_time::Duration::new(seconds, nanos))
+}
+
+/// Capability trait for secrets whose store keeps previous versions.
+///
+/// This makes rollbacks and staged rotations expressible, e.g. verifying with
+/// both the current and previous version of a key while a new one rolls out.
+pub trait VersionedSecret: Secret {
+    /// Retrieves a specific version of the secret.
+    ///
+    /// The version is store-specific: a version number, or a label or stage
+    /// where the store supports them.
+    fn get_secret_version(
+        &self,
+        version: &str,
+    ) -> impl Future<Output = Result<Self::Output, Self::Error>> + MaybeSend;
+
+    /// Lists the versions of the secret, newest first.
+    fn list_versions(
+        &self,
+    ) -> impl Future<Output = Result<Vec<SecretVersion>, Self::Error>> + MaybeSend;
+}
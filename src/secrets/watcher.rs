@@ -0,0 +1,208 @@
+//! Streams of secret values, for reacting to rotation as it happens.
+
+use std::time::Duration;
+
+use futures_timer::Delay;
+use futures_util::{Stream, stream};
+
+use crate::{
+    MaybeSend, MaybeSendSync,
+    secrets::{
+        FileSecret, Secret, SecretAccessError, SecretDecoder,
+        providers::{file_metadata, modified_at},
+    },
+};
+
+/// Trait for secrets that can be watched for new values.
+///
+/// Unlike [`RefreshingSecret`](crate::secrets::RefreshingSecret), which
+/// publishes every refresh, watchers only yield a value when it changes, so
+/// consumers (e.g. a signer backed by a rotating key) can rebuild immediately
+/// and only when needed.
+pub trait SecretWatcher: MaybeSendSync + Clone {
+    /// The error type returned by this watcher's operations.
+    type Error: std::error::Error + MaybeSendSync + 'static;
+
+    /// The type of secret this watcher provides.
+    type Output: MaybeSendSync;
+
+    /// Returns a stream yielding the current value, then each new value.
+    ///
+    /// Errors are yielded as they occur, and watching continues. The stream
+    /// never ends, so drop it to stop watching.
+    fn watch(&self) -> impl Stream<Item = Result<Self::Output, Self::Error>> + MaybeSend + 'static;
+}
+
+/// Watches a secret by polling it at an interval.
+///
+/// New values are detected by the version reported by
+/// [`Secret::get_secret_with_metadata`]. Secrets that don't report a version
+/// yield a value on every poll.
+#[derive(Debug, Clone)]
+pub struct PollingWatcher<S> {
+    inner: S,
+    interval: Duration,
+}
+
+impl<S: Secret> PollingWatcher<S> {
+    /// Watches a secret, polling it every `interval`.
+    pub fn new(inner: S, interval: Duration) -> Self {
+        Self { inner, interval }
+    }
+
+    /// Returns the watched secret.
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    /// Returns the polling interval.
+    #[must_use]
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+}
+
+impl<S: Secret + 'static> SecretWatcher for PollingWatcher<S> {
+    type Error = S::Error;
+    type Output = S::Output;
+
+    fn watch(&self) -> impl Stream<Item = Result<Self::Output, Self::Error>> + MaybeSend + 'static {
+        let state = (self.clone(), None::<String>, true);
+        stream::unfold(state, |(watcher, mut last_version, first)| async move {
+            if !first {
+                Delay::new(watcher.interval).await;
+            }
+            loop {
+                match watcher.inner.get_secret_with_metadata().await {
+                    Ok(secret) => {
+                        let version = secret.version().map(str::to_owned);
+                        if first || version.is_none() || version != last_version {
+                            last_version = version;
+                            let item = Ok(secret.into_value());
+                            return Some((item, (watcher, last_version, false)));
+                        }
+                    }
+                    Err(error) => return Some((Err(error), (watcher, last_version, false))),
+                }
+                Delay::new(watcher.interval).await;
+            }
+        })
+    }
+}
+
+/// Watches a [`FileSecret`] by checking the file's modification time and size
+/// at an interval, reading it only when they change.
+///
+/// Checking the modification time is cheap, so short intervals (e.g. a second)
+/// are reasonable. Replacing the file, as Kubernetes and most deployment tools
+/// do, is detected as a change.
+#[derive(Debug, Clone)]
+pub struct FileWatcher<E: SecretDecoder> {
+    file: FileSecret<E>,
+    interval: Duration,
+}
+
+impl<E: SecretDecoder> FileWatcher<E> {
+    /// Watches a file secret, checking it every `interval`.
+    pub fn new(file: FileSecret<E>, interval: Duration) -> Self {
+        Self { file, interval }
+    }
+
+    /// Returns the watched file secret.
+    pub fn file(&self) -> &FileSecret<E> {
+        &self.file
+    }
+}
+
+impl<E: SecretDecoder + 'static> SecretWatcher for FileWatcher<E> {
+    type Error = SecretAccessError;
+    type Output = E::Output;
+
+    fn watch(&self) -> impl Stream<Item = Result<Self::Output, Self::Error>> + MaybeSend + 'static {
+        let state = (self.clone(), None, true);
+        stream::unfold(state, |(watcher, mut last_seen, first)| async move {
+            if !first {
+                Delay::new(watcher.interval).await;
+            }
+            loop {
+                // Timestamps can be coarser than the interval between writes,
+                // so the size is compared too.
+                let seen = file_metadata(watcher.file.path())
+                    .await
+                    .and_then(|metadata| Some((modified_at(&metadata)?, metadata.len())));
+                // Without a modification time (e.g. the file is missing), read
+                // the file to report the error.
+                if first || seen.is_none() || seen != last_seen {
+                    last_seen = seen;
+                    let item = watcher.file.get_secret_value().await;
+                    return Some((item, (watcher, last_seen, false)));
+                }
+                Delay::new(watcher.interval).await;
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        convert::Infallible,
+        sync::{
+            Arc,
+            atomic::{AtomicUsize, Ordering},
+        },
+    };
+
+    use futures_util::StreamExt;
+    use secrecy::ExposeSecret;
+
+    use super::*;
+    use crate::secrets::SecretWithMetadata;
+
+    /// A secret whose version changes every other retrieval.
+    #[derive(Debug, Clone, Default)]
+    struct SlowlyRotatingSecret(Arc<AtomicUsize>);
+
+    impl Secret for SlowlyRotatingSecret {
+        type Error = Infallible;
+        type Output = usize;
+
+        async fn get_secret_value(&self) -> Result<Self::Output, Self::Error> {
+            Ok(self.0.fetch_add(1, Ordering::SeqCst) / 2)
+        }
+
+        async fn get_secret_with_metadata(
+            &self,
+        ) -> Result<SecretWithMetadata<Self::Output>, Self::Error> {
+            let value = self.get_secret_value().await?;
+            Ok(SecretWithMetadata::new(value).with_version(value.to_string()))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_polling_watcher_skips_unchanged_versions() {
+        let secret = SlowlyRotatingSecret::default();
+        let watcher = PollingWatcher::new(secret.clone(), Duration::from_millis(5));
+
+        let values: Vec<_> = watcher.watch().take(3).map(Result::unwrap).collect().await;
+
+        assert_eq!(values, [0, 1, 2]);
+        assert!(secret.0.load(Ordering::SeqCst) >= 5);
+    }
+
+    #[tokio::test]
+    async fn test_file_watcher_yields_replaced_contents() {
+        let path = std::env::temp_dir().join(format!("chewie-crypto-{}-watch", std::process::id()));
+        std::fs::write(&path, "first").unwrap();
+        let watcher = FileWatcher::new(FileSecret::string(&path), Duration::from_millis(5));
+        let mut stream = Box::pin(watcher.watch());
+
+        let first = stream.next().await.unwrap().unwrap();
+        std::fs::write(&path, "second value").unwrap();
+        let second = stream.next().await.unwrap().unwrap();
+
+        assert_eq!(first.expose_secret(), "first");
+        assert_eq!(second.expose_secret(), "second value");
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,215 @@
+//! Secret combinator fetching two secrets concurrently.
+
+use std::{borrow::Cow, future::poll_fn, pin::pin, task::Poll};
+
+use snafu::prelude::*;
+
+use crate::{MaybeSendSync, secrets::Secret};
+
+/// Errors returned by [`ZipSecret`], naming the part that failed.
+#[derive(Debug, Snafu)]
+pub enum ZipError<A, B>
+where
+    A: std::error::Error + MaybeSendSync + 'static,
+    B: std::error::Error + MaybeSendSync + 'static,
+{
+    /// The first secret could not be retrieved.
+    #[snafu(display("Failed to retrieve secret part '{part}'"))]
+    First {
+        /// The name of the part.
+        part: Cow<'static, str>,
+        /// The error from the first secret.
+        source: A,
+    },
+    /// The second secret could not be retrieved.
+    #[snafu(display("Failed to retrieve secret part '{part}'"))]
+    Second {
+        /// The name of the part.
+        part: Cow<'static, str>,
+        /// The error from the second secret.
+        source: B,
+    },
+}
+
+/// A secret combining two secrets, fetched concurrently, into one value.
+///
+/// This assembles structured credentials from separately stored parts, such
+/// as a `TlsIdentity { cert, key }` from a certificate and its private key,
+/// using [`ZipSecret::map`].
+///
+/// Each part is given a name, which is reported if it fails. Retrieval fails
+/// as soon as either part does. For more than two parts, nest `ZipSecret`s.
+pub struct ZipSecret<A: Secret, B: Secret, T = (<A as Secret>::Output, <B as Secret>::Output)> {
+    first: A,
+    first_part: Cow<'static, str>,
+    second: B,
+    second_part: Cow<'static, str>,
+    combine: fn(A::Output, B::Output) -> T,
+}
+
+impl<A: Secret, B: Secret, T> Clone for ZipSecret<A, B, T> {
+    fn clone(&self) -> Self {
+        Self {
+            first: self.first.clone(),
+            first_part: self.first_part.clone(),
+            second: self.second.clone(),
+            second_part: self.second_part.clone(),
+            combine: self.combine,
+        }
+    }
+}
+
+impl<A, B, T> std::fmt::Debug for ZipSecret<A, B, T>
+where
+    A: Secret + std::fmt::Debug,
+    B: Secret + std::fmt::Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ZipSecret")
+            .field("first", &self.first)
+            .field("first_part", &self.first_part)
+            .field("second", &self.second)
+            .field("second_part", &self.second_part)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<A: Secret, B: Secret> ZipSecret<A, B> {
+    /// Combines two named secrets, producing a tuple of their values.
+    pub fn new(
+        first_part: impl Into<Cow<'static, str>>,
+        first: A,
+        second_part: impl Into<Cow<'static, str>>,
+        second: B,
+    ) -> Self {
+        Self {
+            first,
+            first_part: first_part.into(),
+            second,
+            second_part: second_part.into(),
+            combine: |first, second| (first, second),
+        }
+    }
+}
+
+impl<A: Secret, B: Secret, T> ZipSecret<A, B, T> {
+    /// Combines the two values with the given function, instead of into a tuple.
+    pub fn map<U>(self, combine: fn(A::Output, B::Output) -> U) -> ZipSecret<A, B, U> {
+        ZipSecret {
+            first: self.first,
+            first_part: self.first_part,
+            second: self.second,
+            second_part: self.second_part,
+            combine,
+        }
+    }
+
+    /// Returns the first secret.
+    pub fn first(&self) -> &A {
+        &self.first
+    }
+
+    /// Returns the second secret.
+    pub fn second(&self) -> &B {
+        &self.second
+    }
+}
+
+impl<A: Secret, B: Secret, T: MaybeSendSync> Secret for ZipSecret<A, B, T> {
+    type Error = ZipError<A::Error, B::Error>;
+    type Output = T;
+
+    async fn get_secret_value(&self) -> Result<Self::Output, Self::Error> {
+        let mut first = pin!(self.first.get_secret_value());
+        let mut second = pin!(self.second.get_secret_value());
+        let mut first_value = None;
+        let mut second_value = None;
+
+        poll_fn(|cx| {
+            if first_value.is_none()
+                && let Poll::Ready(result) = first.as_mut().poll(cx)
+            {
+                first_value = Some(result.context(FirstSnafu {
+                    part: self.first_part.clone(),
+                })?);
+            }
+            if second_value.is_none()
+                && let Poll::Ready(result) = second.as_mut().poll(cx)
+            {
+                second_value = Some(result.context(SecondSnafu {
+                    part: self.second_part.clone(),
+                })?);
+            }
+            match (first_value.take(), second_value.take()) {
+                (Some(first), Some(second)) => Poll::Ready(Ok((self.combine)(first, second))),
+                (first, second) => {
+                    first_value = first;
+                    second_value = second;
+                    Poll::Pending
+                }
+            }
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use snafu::Snafu;
+
+    use super::*;
+
+    #[derive(Debug, Snafu)]
+    #[snafu(display("Unavailable"))]
+    struct MockError;
+
+    #[derive(Debug, Clone)]
+    struct DelayedSecret {
+        value: Option<&'static str>,
+        delay: Duration,
+    }
+
+    impl Secret for DelayedSecret {
+        type Error = MockError;
+        type Output = &'static str;
+
+        async fn get_secret_value(&self) -> Result<Self::Output, Self::Error> {
+            tokio::time::sleep(self.delay).await;
+            self.value.context(MockSnafu)
+        }
+    }
+
+    fn secret(value: Option<&'static str>, delay_secs: u64) -> DelayedSecret {
+        DelayedSecret {
+            value,
+            delay: Duration::from_secs(delay_secs),
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_parts_are_fetched_concurrently() {
+        let started = tokio::time::Instant::now();
+        let zipped = ZipSecret::new(
+            "id",
+            secret(Some("app"), 2),
+            "secret",
+            secret(Some("s3cret"), 3),
+        )
+        .map(|id, secret| format!("{id}:{secret}"));
+
+        assert_eq!(zipped.get_secret_value().await.unwrap(), "app:s3cret");
+        assert_eq!(started.elapsed(), Duration::from_secs(3));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_failure_names_part() {
+        let zipped = ZipSecret::new("id", secret(Some("app"), 2), "secret", secret(None, 1));
+
+        let error = zipped.get_secret_value().await.unwrap_err();
+
+        assert!(matches!(&error, ZipError::Second { part, .. } if part == "secret"));
+        assert_eq!(error.to_string(), "Failed to retrieve secret part 'secret'");
+    }
+}
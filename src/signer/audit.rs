@@ -0,0 +1,392 @@
+//! Signer wrapper reporting every signing operation to an audit hook.
+
+use std::{borrow::Cow, sync::Arc, time::Duration};
+
+use bytes::Bytes;
+use sha2::{Digest, Sha256};
+use web_time::Instant;
+
+use crate::{
+    MaybeSendSync,
+    jws::{self, Header},
+    signer::{Error, JwsSigner, SignedBytes},
+};
+
+/// The outcome of an audited signing operation.
+#[derive(Debug, Clone, Copy)]
+pub enum SignOutcome<'a> {
+    /// A signature was produced.
+    Success,
+    /// The signing operation failed.
+    Failure(&'a (dyn std::error::Error + 'static)),
+}
+
+/// A record of a single signing operation, passed to a [`SignAuditHook`].
+#[derive(Debug, Clone)]
+pub struct SignEvent<'a> {
+    key_id: Option<Cow<'a, str>>,
+    jws_algorithm: Cow<'a, str>,
+    input_digest: [u8; 32],
+    outcome: SignOutcome<'a>,
+    latency: Duration,
+}
+
+impl SignEvent<'_> {
+    /// Returns the key ID used to sign, if any.
+    #[must_use]
+    pub fn key_id(&self) -> Option<&str> {
+        self.key_id.as_deref()
+    }
+
+    /// Returns the JWS algorithm identifier used to sign.
+    #[must_use]
+    pub fn jws_algorithm(&self) -> &str {
+        &self.jws_algorithm
+    }
+
+    /// Returns the SHA-256 digest of the signing input.
+    ///
+    /// The input itself is not exposed, so that hooks don't log token contents.
+    #[must_use]
+    pub fn input_digest(&self) -> &[u8; 32] {
+        &self.input_digest
+    }
+
+    /// Returns whether the signing operation succeeded.
+    #[must_use]
+    pub fn outcome(&self) -> SignOutcome<'_> {
+        self.outcome
+    }
+
+    /// Returns how long the signing operation took.
+    #[must_use]
+    pub fn latency(&self) -> Duration {
+        self.latency
+    }
+}
+
+/// Trait for receiving a [`SignEvent`] for every signing operation of an [`AuditSigner`].
+///
+/// The hook is called synchronously once the operation completes. Hooks that
+/// write to slow sinks should hand the event off (e.g. over a channel) rather
+/// than block. Closures taking a `&SignEvent` implement this trait.
+pub trait SignAuditHook: MaybeSendSync {
+    /// Records a signing operation.
+    fn record(&self, event: &SignEvent<'_>);
+}
+
+impl<F: Fn(&SignEvent<'_>) + MaybeSendSync> SignAuditHook for F {
+    fn record(&self, event: &SignEvent<'_>) {
+        self(event);
+    }
+}
+
+/// A signer that reports every signing operation to a [`SignAuditHook`].
+///
+/// Events name the key and algorithm the operation actually used: the
+/// metadata returned by the wrapped signer, or the values requested from
+/// [`JwsSigner::sign`]. This stays accurate when the wrapped signer switches
+/// keys, as [`FallbackSigner`](super::FallbackSigner) and
+/// [`RotatingSigner`](super::RotatingSigner) do. Failed operations that don't
+/// report metadata are recorded with the wrapped signer's current key.
+///
+/// Health checks are not reported. Errors are passed through unchanged.
+/// Clones share the same hook.
+pub struct AuditSigner<S, H> {
+    inner: S,
+    hook: Arc<H>,
+}
+
+impl<S: Clone, H> Clone for AuditSigner<S, H> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            hook: Arc::clone(&self.hook),
+        }
+    }
+}
+
+impl<S: std::fmt::Debug, H> std::fmt::Debug for AuditSigner<S, H> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AuditSigner")
+            .field("inner", &self.inner)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<S: JwsSigner, H: SignAuditHook> AuditSigner<S, H> {
+    /// Wraps a signer, reporting its signing operations to the given hook.
+    pub fn new(inner: S, hook: H) -> Self {
+        Self {
+            inner,
+            hook: Arc::new(hook),
+        }
+    }
+
+    /// Returns the wrapped signer.
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    /// Returns the audit hook.
+    pub fn hook(&self) -> &H {
+        &self.hook
+    }
+
+    fn record<T, E: std::error::Error + 'static>(
+        &self,
+        key_id: Option<Cow<'_, str>>,
+        jws_algorithm: Cow<'_, str>,
+        input: &[u8],
+        started: Instant,
+        result: &Result<T, E>,
+    ) {
+        let outcome = match result {
+            Ok(_) => SignOutcome::Success,
+            Err(e) => SignOutcome::Failure(e),
+        };
+        self.hook.record(&SignEvent {
+            key_id,
+            jws_algorithm,
+            input_digest: Sha256::digest(input).into(),
+            outcome,
+            latency: started.elapsed(),
+        });
+    }
+
+    /// Records an operation returning [`SignedBytes`], with its metadata if
+    /// it succeeded.
+    fn record_signed<E: std::error::Error + 'static>(
+        &self,
+        input: &[u8],
+        started: Instant,
+        result: &Result<SignedBytes, E>,
+    ) {
+        match result {
+            Ok(signed) => self.record(
+                signed.key_id().map(Cow::Borrowed),
+                Cow::Borrowed(signed.jws_algorithm()),
+                input,
+                started,
+                result,
+            ),
+            Err(_) => self.record(
+                self.inner.key_id(),
+                self.inner.jws_algorithm(),
+                input,
+                started,
+                result,
+            ),
+        }
+    }
+}
+
+impl<S: JwsSigner, H: SignAuditHook> JwsSigner for AuditSigner<S, H> {
+    type Error = S::Error;
+
+    fn algorithm(&self) -> Cow<'_, str> {
+        self.inner.algorithm()
+    }
+
+    fn jws_algorithm(&self) -> Cow<'_, str> {
+        self.inner.jws_algorithm()
+    }
+
+    fn key_id(&self) -> Option<Cow<'_, str>> {
+        self.inner.key_id()
+    }
+
+    fn critical_headers(&self) -> Cow<'_, [String]> {
+        self.inner.critical_headers()
+    }
+
+    fn supported_algorithms(&self) -> Vec<Cow<'_, str>> {
+        self.inner.supported_algorithms()
+    }
+
+    fn allows_precomputed_header(&self) -> bool {
+        self.inner.allows_precomputed_header()
+    }
+
+    async fn health_check(&self) -> Result<(), Self::Error> {
+        self.inner.health_check().await
+    }
+
+    async fn sign_unchecked(&self, input: &[u8]) -> Result<Bytes, Self::Error> {
+        let started = Instant::now();
+        let result = self.inner.sign_unchecked(input).await;
+        self.record(
+            self.inner.key_id(),
+            self.inner.jws_algorithm(),
+            input,
+            started,
+            &result,
+        );
+        result
+    }
+
+    async fn sign(
+        &self,
+        input: &[u8],
+        jws_algorithm: &str,
+        key_id: Option<&str>,
+    ) -> Result<Bytes, Error<Self::Error>> {
+        let started = Instant::now();
+        let result = self.inner.sign(input, jws_algorithm, key_id).await;
+        self.record(
+            key_id.map(Cow::Borrowed),
+            Cow::Borrowed(jws_algorithm),
+            input,
+            started,
+            &result,
+        );
+        result
+    }
+
+    async fn sign_with_metadata(&self, input: &[u8]) -> Result<SignedBytes, Self::Error> {
+        let started = Instant::now();
+        let result = self.inner.sign_with_metadata(input).await;
+        self.record_signed(input, started, &result);
+        result
+    }
+
+    /// The input digest is of the signing input built from the header the
+    /// wrapped signer produced, or would have produced with its current key if
+    /// it failed (or of the payload, if the header is invalid).
+    async fn sign_with_header(
+        &self,
+        header: &Header,
+        payload: &[u8],
+    ) -> Result<SignedBytes, Error<Self::Error>> {
+        let started = Instant::now();
+        let result = self.inner.sign_with_header(header, payload).await;
+        let encoded_header = match &result {
+            Ok(signed) => signed.protected_header().map(str::to_owned),
+            Err(_) => jws::encode_protected_header(
+                &self.inner.jws_algorithm(),
+                self.inner.key_id().as_deref(),
+                &self.inner.critical_headers(),
+                header,
+            )
+            .ok(),
+        };
+        let input = encoded_header.map_or_else(
+            || payload.to_vec(),
+            |encoded| jws::signing_input(&encoded, payload).into_bytes(),
+        );
+        self.record_signed(&input, started, &result);
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        borrow::Cow,
+        convert::Infallible,
+        sync::{Arc, Mutex},
+    };
+
+    use bytes::Bytes;
+
+    use super::*;
+
+    #[derive(Debug, Clone)]
+    struct MockSigner;
+
+    impl JwsSigner for MockSigner {
+        type Error = Infallible;
+
+        fn algorithm(&self) -> Cow<'_, str> {
+            "ALG".into()
+        }
+
+        fn jws_algorithm(&self) -> Cow<'_, str> {
+            "JWS-ALG".into()
+        }
+
+        fn key_id(&self) -> Option<Cow<'_, str>> {
+            Some("key-id".into())
+        }
+
+        async fn sign_unchecked(&self, _input: &[u8]) -> Result<Bytes, Self::Error> {
+            Ok(Bytes::new())
+        }
+    }
+
+    type Events = Arc<Mutex<Vec<(bool, String, [u8; 32])>>>;
+
+    fn recording_signer() -> (AuditSigner<MockSigner, impl SignAuditHook>, Events) {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let recorded = Arc::clone(&events);
+        let signer = AuditSigner::new(MockSigner, move |event: &SignEvent<'_>| {
+            assert_eq!(event.key_id(), Some("key-id"));
+            let success = matches!(event.outcome(), SignOutcome::Success);
+            recorded.lock().unwrap().push((
+                success,
+                event.jws_algorithm().to_owned(),
+                *event.input_digest(),
+            ));
+        });
+        (signer, events)
+    }
+
+    #[tokio::test]
+    async fn test_successful_sign_is_recorded() {
+        let (signer, events) = recording_signer();
+
+        signer
+            .sign(b"input", "JWS-ALG", Some("key-id"))
+            .await
+            .unwrap();
+
+        let expected: [u8; 32] = Sha256::digest(b"input").into();
+        assert_eq!(
+            *events.lock().unwrap(),
+            [(true, "JWS-ALG".to_owned(), expected)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_failed_sign_is_recorded() {
+        let (signer, events) = recording_signer();
+
+        let _ = signer.sign(b"input", "OTHER", Some("key-id")).await;
+
+        // The requested algorithm is recorded, not the signer's.
+        assert!(matches!(
+            events.lock().unwrap().as_slice(),
+            [(false, alg, _)] if alg == "OTHER"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_records_key_that_signed() {
+        let primary = crate::signer::MockSigner::builder()
+            .key_id("primary")
+            .error("unavailable")
+            .build();
+        let secondary = crate::signer::MockSigner::builder()
+            .key_id("secondary")
+            .build();
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let recorded = Arc::clone(&events);
+        let signer = AuditSigner::new(
+            crate::signer::FallbackSigner::new(primary, secondary),
+            move |event: &SignEvent<'_>| {
+                recorded
+                    .lock()
+                    .unwrap()
+                    .push(event.key_id().map(str::to_owned));
+            },
+        );
+
+        let result = signer
+            .sign_with_header(&Header::default(), b"payload")
+            .await
+            .unwrap();
+
+        assert_eq!(result.key_id(), Some("secondary"));
+        assert_eq!(*events.lock().unwrap(), [Some("secondary".to_owned())]);
+    }
+}
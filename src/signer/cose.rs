@@ -0,0 +1,192 @@
+//! COSE signing traits.
+
+use std::borrow::Cow;
+
+use bytes::Bytes;
+use snafu::prelude::*;
+
+use crate::{
+    MaybeSend, MaybeSendSync,
+    cose::{self, Sign1},
+    signer::{
+        Error, JwsSigner,
+        error::{MismatchedKeyInfoSnafu, UnderlyingSnafu},
+    },
+};
+
+/// Trait for signers that produce RFC 9052 (COSE) compatible signatures.
+pub trait CoseSigner: MaybeSendSync + Clone {
+    /// The error type returned by this signer's operations.
+    type Error: std::error::Error + MaybeSendSync + 'static;
+
+    /// Returns the COSE algorithm identifier.
+    fn cose_algorithm(&self) -> i64;
+
+    /// Returns the key ID of the signer, for use in the `kid` header parameter.
+    fn key_id(&self) -> Option<Cow<'_, [u8]>>;
+
+    /// Asynchronously signs the given input data and returns the signature.
+    ///
+    /// This should not be called directly, as it does not verify that the algorithm
+    /// and key ID match the values signed (which could happen due to key updates).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the signing operation fails.
+    fn sign_unchecked(
+        &self,
+        input: &[u8],
+    ) -> impl Future<Output = Result<Bytes, Self::Error>> + MaybeSend;
+
+    /// Asynchronously signs the given input data, checking the algorithm and key ID.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the key metadata is mismatched, or the signing operation fails.
+    fn sign(
+        &self,
+        input: &[u8],
+        cose_algorithm: i64,
+        key_id: Option<&[u8]>,
+    ) -> impl Future<Output = Result<Bytes, Error<Self::Error>>> + MaybeSend {
+        async move {
+            ensure!(
+                cose_algorithm == self.cose_algorithm() && key_id == self.key_id().as_deref(),
+                MismatchedKeyInfoSnafu
+            );
+            self.sign_unchecked(input).await.context(UnderlyingSnafu)
+        }
+    }
+
+    /// Asynchronously signs a payload as a `COSE_Sign1`, with the algorithm and
+    /// key ID in the protected header filled in from this signer.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the key metadata changed while signing, or the
+    /// signing operation fails.
+    fn sign1(
+        &self,
+        payload: &[u8],
+        external_aad: &[u8],
+    ) -> impl Future<Output = Result<Sign1, Error<Self::Error>>> + MaybeSend {
+        async move {
+            let cose_algorithm = self.cose_algorithm();
+            let key_id = self.key_id().map(Cow::into_owned);
+            let protected_header = cose::encode_protected_header(cose_algorithm, key_id.as_deref());
+            let input = cose::sig_structure1(&protected_header, external_aad, payload);
+            let signature = self.sign(&input, cose_algorithm, key_id.as_deref()).await?;
+            Ok(Sign1::new(protected_header.into(), signature))
+        }
+    }
+}
+
+/// Errors returned by [`JwsCoseSigner`].
+#[derive(Debug, Snafu)]
+pub enum CoseBridgeError<E: std::error::Error + MaybeSendSync + 'static> {
+    /// The JWS algorithm has no COSE equivalent.
+    #[snafu(display("JWS algorithm '{algorithm}' has no COSE equivalent"))]
+    UnsupportedAlgorithm {
+        /// The JWS algorithm.
+        algorithm: String,
+    },
+    /// The error from the wrapped signer.
+    #[snafu(display("Signing failed"))]
+    Inner {
+        /// The source error.
+        source: E,
+    },
+}
+
+/// A [`CoseSigner`] backed by a [`JwsSigner`], for algorithms whose JWS and
+/// COSE signatures have the same format (see [`cose::algorithm_for_jws`]).
+///
+/// The key ID is the UTF-8 encoding of the JWS key ID.
+#[derive(Debug, Clone)]
+pub struct JwsCoseSigner<S> {
+    inner: S,
+}
+
+impl<S: JwsSigner> JwsCoseSigner<S> {
+    /// Wraps a JWS signer.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the signer's algorithm has no COSE equivalent.
+    pub fn new(inner: S) -> Result<Self, CoseBridgeError<S::Error>> {
+        let algorithm = inner.jws_algorithm();
+        ensure!(
+            cose::algorithm_for_jws(&algorithm).is_some(),
+            UnsupportedAlgorithmSnafu {
+                algorithm: algorithm.as_ref()
+            }
+        );
+        Ok(Self { inner })
+    }
+
+    /// Returns the wrapped signer.
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+}
+
+impl<S: JwsSigner> CoseSigner for JwsCoseSigner<S> {
+    type Error = CoseBridgeError<S::Error>;
+
+    /// Returns the COSE algorithm, or `0` (reserved) if the wrapped signer
+    /// changed to an algorithm with no COSE equivalent.
+    fn cose_algorithm(&self) -> i64 {
+        cose::algorithm_for_jws(&self.inner.jws_algorithm()).unwrap_or(0)
+    }
+
+    fn key_id(&self) -> Option<Cow<'_, [u8]>> {
+        self.inner.key_id().map(|kid| match kid {
+            Cow::Borrowed(kid) => Cow::Borrowed(kid.as_bytes()),
+            Cow::Owned(kid) => Cow::Owned(kid.into_bytes()),
+        })
+    }
+
+    async fn sign_unchecked(&self, input: &[u8]) -> Result<Bytes, Self::Error> {
+        let algorithm = self.inner.jws_algorithm().into_owned();
+        ensure!(
+            cose::algorithm_for_jws(&algorithm).is_some(),
+            UnsupportedAlgorithmSnafu { algorithm }
+        );
+        self.inner.sign_unchecked(input).await.context(InnerSnafu)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signer::MockSigner;
+
+    #[tokio::test]
+    async fn test_bridge_signs_sign1() {
+        let jws_signer = MockSigner::builder()
+            .key_id("k")
+            .signature(Bytes::from_static(b"sig"))
+            .build();
+        let signer = JwsCoseSigner::new(jws_signer.clone()).unwrap();
+
+        let sign1 = signer.sign1(b"hi", b"").await.unwrap();
+
+        assert_eq!(signer.cose_algorithm(), -7);
+        assert_eq!(
+            sign1.protected_header(),
+            &cose::encode_protected_header(-7, Some(b"k"))
+        );
+        jws_signer.assert_signed(&cose::sig_structure1(sign1.protected_header(), b"", b"hi"));
+        assert_eq!(sign1.to_cbor(b"hi")[..2], [0xd2, 0x84]);
+    }
+
+    #[test]
+    fn test_bridge_rejects_unsupported_algorithm() {
+        let result = JwsCoseSigner::new(MockSigner::builder().jws_algorithm("HS256").build());
+
+        assert!(matches!(
+            result,
+            Err(CoseBridgeError::UnsupportedAlgorithm { .. })
+        ));
+    }
+}
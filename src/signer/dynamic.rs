@@ -0,0 +1,251 @@
+//! Object-safe signer trait.
+
+use std::{borrow::Cow, sync::Arc};
+
+use bytes::Bytes;
+
+use crate::{
+    BoxFuture, DynError, MaybeSendSync,
+    jws::Header,
+    signer::{Error, JwsSigner, SignedBytes},
+};
+
+/// Object-safe variant of [`JwsSigner`].
+///
+/// Every [`JwsSigner`] implements this trait, so signers of different types can
+/// be stored together (e.g. in a `Vec<Box<dyn DynJwsSigner>>`). An
+/// `Arc<dyn DynJwsSigner>` implements [`JwsSigner`] in turn, so it can be used
+/// anywhere a concrete signer is expected.
+pub trait DynJwsSigner: MaybeSendSync {
+    /// See [`JwsSigner::algorithm`].
+    fn algorithm(&self) -> Cow<'_, str>;
+
+    /// See [`JwsSigner::jws_algorithm`].
+    fn jws_algorithm(&self) -> Cow<'_, str>;
+
+    /// See [`JwsSigner::key_id`].
+    fn key_id(&self) -> Option<Cow<'_, str>>;
+
+    /// See [`JwsSigner::critical_headers`].
+    fn critical_headers(&self) -> Cow<'_, [String]>;
+
+    /// See [`JwsSigner::supported_algorithms`].
+    fn supported_algorithms(&self) -> Vec<Cow<'_, str>>;
+
+    /// See [`JwsSigner::allows_precomputed_header`].
+    fn allows_precomputed_header(&self) -> bool;
+
+    /// See [`JwsSigner::health_check`].
+    fn health_check(&self) -> BoxFuture<'_, Result<(), DynError>>;
+
+    /// See [`JwsSigner::sign_unchecked`].
+    fn sign_unchecked<'a>(&'a self, input: &'a [u8]) -> BoxFuture<'a, Result<Bytes, DynError>>;
+
+    /// See [`JwsSigner::sign`].
+    fn sign<'a>(
+        &'a self,
+        input: &'a [u8],
+        jws_algorithm: &'a str,
+        key_id: Option<&'a str>,
+    ) -> BoxFuture<'a, Result<Bytes, Error<DynError>>>;
+
+    /// See [`JwsSigner::sign_with_metadata`].
+    fn sign_with_metadata<'a>(
+        &'a self,
+        input: &'a [u8],
+    ) -> BoxFuture<'a, Result<SignedBytes, DynError>>;
+
+    /// See [`JwsSigner::sign_with_header`].
+    fn sign_with_header<'a>(
+        &'a self,
+        header: &'a Header,
+        payload: &'a [u8],
+    ) -> BoxFuture<'a, Result<SignedBytes, Error<DynError>>>;
+}
+
+impl<S: JwsSigner> DynJwsSigner for S {
+    fn algorithm(&self) -> Cow<'_, str> {
+        JwsSigner::algorithm(self)
+    }
+
+    fn jws_algorithm(&self) -> Cow<'_, str> {
+        JwsSigner::jws_algorithm(self)
+    }
+
+    fn key_id(&self) -> Option<Cow<'_, str>> {
+        JwsSigner::key_id(self)
+    }
+
+    fn critical_headers(&self) -> Cow<'_, [String]> {
+        JwsSigner::critical_headers(self)
+    }
+
+    fn supported_algorithms(&self) -> Vec<Cow<'_, str>> {
+        JwsSigner::supported_algorithms(self)
+    }
+
+    fn allows_precomputed_header(&self) -> bool {
+        JwsSigner::allows_precomputed_header(self)
+    }
+
+    fn health_check(&self) -> BoxFuture<'_, Result<(), DynError>> {
+        Box::pin(async move { JwsSigner::health_check(self).await.map_err(DynError::new) })
+    }
+
+    fn sign_unchecked<'a>(&'a self, input: &'a [u8]) -> BoxFuture<'a, Result<Bytes, DynError>> {
+        Box::pin(async move {
+            JwsSigner::sign_unchecked(self, input)
+                .await
+                .map_err(DynError::new)
+        })
+    }
+
+    fn sign<'a>(
+        &'a self,
+        input: &'a [u8],
+        jws_algorithm: &'a str,
+        key_id: Option<&'a str>,
+    ) -> BoxFuture<'a, Result<Bytes, Error<DynError>>> {
+        Box::pin(async move {
+            JwsSigner::sign(self, input, jws_algorithm, key_id)
+                .await
+                .map_err(|e| e.map_underlying(DynError::new))
+        })
+    }
+
+    fn sign_with_metadata<'a>(
+        &'a self,
+        input: &'a [u8],
+    ) -> BoxFuture<'a, Result<SignedBytes, DynError>> {
+        Box::pin(async move {
+            JwsSigner::sign_with_metadata(self, input)
+                .await
+                .map_err(DynError::new)
+        })
+    }
+
+    fn sign_with_header<'a>(
+        &'a self,
+        header: &'a Header,
+        payload: &'a [u8],
+    ) -> BoxFuture<'a, Result<SignedBytes, Error<DynError>>> {
+        Box::pin(async move {
+            JwsSigner::sign_with_header(self, header, payload)
+                .await
+                .map_err(|e| e.map_underlying(DynError::new))
+        })
+    }
+}
+
+impl JwsSigner for Arc<dyn DynJwsSigner> {
+    type Error = DynError;
+
+    fn algorithm(&self) -> Cow<'_, str> {
+        (**self).algorithm()
+    }
+
+    fn jws_algorithm(&self) -> Cow<'_, str> {
+        (**self).jws_algorithm()
+    }
+
+    fn key_id(&self) -> Option<Cow<'_, str>> {
+        (**self).key_id()
+    }
+
+    fn critical_headers(&self) -> Cow<'_, [String]> {
+        (**self).critical_headers()
+    }
+
+    fn supported_algorithms(&self) -> Vec<Cow<'_, str>> {
+        (**self).supported_algorithms()
+    }
+
+    fn allows_precomputed_header(&self) -> bool {
+        (**self).allows_precomputed_header()
+    }
+
+    async fn health_check(&self) -> Result<(), Self::Error> {
+        (**self).health_check().await
+    }
+
+    async fn sign_unchecked(&self, input: &[u8]) -> Result<Bytes, Self::Error> {
+        (**self).sign_unchecked(input).await
+    }
+
+    async fn sign(
+        &self,
+        input: &[u8],
+        jws_algorithm: &str,
+        key_id: Option<&str>,
+    ) -> Result<Bytes, Error<Self::Error>> {
+        (**self).sign(input, jws_algorithm, key_id).await
+    }
+
+    async fn sign_with_metadata(&self, input: &[u8]) -> Result<SignedBytes, Self::Error> {
+        (**self).sign_with_metadata(input).await
+    }
+
+    async fn sign_with_header(
+        &self,
+        header: &Header,
+        payload: &[u8],
+    ) -> Result<SignedBytes, Error<Self::Error>> {
+        (**self).sign_with_header(header, payload).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{borrow::Cow, convert::Infallible, sync::Arc};
+
+    use bytes::Bytes;
+
+    use crate::signer::{DynJwsSigner, JwsSigner};
+
+    #[derive(Debug, Clone)]
+    struct MockSigner;
+
+    impl JwsSigner for MockSigner {
+        type Error = Infallible;
+
+        fn algorithm(&self) -> Cow<'_, str> {
+            "ALG".into()
+        }
+
+        fn jws_algorithm(&self) -> Cow<'_, str> {
+            "JWS-ALG".into()
+        }
+
+        fn key_id(&self) -> Option<Cow<'_, str>> {
+            Some("key-id".into())
+        }
+
+        async fn sign_unchecked(&self, _input: &[u8]) -> Result<Bytes, Self::Error> {
+            Ok(Bytes::from_static(b"signature"))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_boxed_signers_sign() {
+        let signers: Vec<Box<dyn DynJwsSigner>> = vec![Box::new(MockSigner)];
+
+        let signature = signers[0]
+            .sign(b"input", "JWS-ALG", Some("key-id"))
+            .await
+            .unwrap();
+
+        assert_eq!(signature, "signature");
+    }
+
+    #[tokio::test]
+    async fn test_arc_dyn_signer_is_jws_signer() {
+        let signer: Arc<dyn DynJwsSigner> = Arc::new(MockSigner);
+
+        let result = JwsSigner::sign(&signer, b"input", "OTHER", Some("key-id")).await;
+
+        assert!(matches!(
+            result,
+            Err(crate::signer::Error::MismatchedKeyInfo)
+        ));
+    }
+}
@@ -0,0 +1,242 @@
+//! ECDSA signature format conversion.
+//!
+//! KMS and HSM backends usually return ECDSA signatures as an ASN.1 DER
+//! `Ecdsa-Sig-Value` (RFC 3279 §2.2.3), while JWS requires the fixed-width
+//! `R || S` form (RFC 7518 §3.4). COSE uses the same fixed-width form.
+
+use snafu::prelude::*;
+
+/// Errors that can occur when converting ECDSA signature formats.
+#[derive(Debug, Snafu)]
+#[non_exhaustive]
+pub enum SignatureFormatError {
+    /// The DER encoding is malformed.
+    #[snafu(display("Malformed DER ECDSA signature"))]
+    MalformedDer,
+    /// An integer in the signature is larger than the curve's field size.
+    #[snafu(display("Signature integer exceeds {field_size} bytes"))]
+    IntegerTooLarge {
+        /// The field size in bytes.
+        field_size: usize,
+    },
+    /// The raw signature has an invalid length.
+    #[snafu(display("Invalid raw signature length {length}"))]
+    InvalidRawLength {
+        /// The length of the raw signature.
+        length: usize,
+    },
+    /// The JWS algorithm is not an ECDSA algorithm.
+    #[snafu(display("'{algorithm}' is not an ECDSA algorithm"))]
+    NotEcdsa {
+        /// The JWS algorithm.
+        algorithm: String,
+    },
+}
+
+/// Returns the size in bytes of each of `R` and `S` for an ECDSA JWS algorithm.
+#[must_use]
+pub fn field_size(jws_algorithm: &str) -> Option<usize> {
+    match jws_algorithm {
+        "ES256" | "ES256K" => Some(32),
+        "ES384" => Some(48),
+        "ES512" => Some(66),
+        _ => None,
+    }
+}
+
+/// Converts a DER-encoded ECDSA signature to the JWS `R || S` form for the given algorithm.
+///
+/// # Errors
+///
+/// Returns an error if the algorithm isn't ECDSA, or the signature is malformed.
+pub fn der_to_jose(der: &[u8], jws_algorithm: &str) -> Result<Vec<u8>, SignatureFormatError> {
+    let size = field_size(jws_algorithm).context(NotEcdsaSnafu {
+        algorithm: jws_algorithm,
+    })?;
+    der_to_raw(der, size)
+}
+
+/// Converts a DER-encoded ECDSA signature to `R || S`, each left-padded to `field_size` bytes.
+///
+/// # Errors
+///
+/// Returns an error if the signature is malformed, or an integer exceeds the field size.
+pub fn der_to_raw(der: &[u8], field_size: usize) -> Result<Vec<u8>, SignatureFormatError> {
+    let (body, rest) = read_tlv(der, 0x30)?;
+    ensure!(rest.is_empty(), MalformedDerSnafu);
+    let (r, rest) = read_tlv(body, 0x02)?;
+    let (s, rest) = read_tlv(rest, 0x02)?;
+    ensure!(rest.is_empty(), MalformedDerSnafu);
+
+    let mut raw = vec![0; field_size * 2];
+    write_padded(r, &mut raw[..field_size])?;
+    write_padded(s, &mut raw[field_size..])?;
+    Ok(raw)
+}
+
+/// Converts a JWS `R || S` ECDSA signature to DER.
+///
+/// # Errors
+///
+/// Returns an error if the raw signature is empty or has an odd length.
+pub fn raw_to_der(raw: &[u8]) -> Result<Vec<u8>, SignatureFormatError> {
+    ensure!(
+        !raw.is_empty() && raw.len().is_multiple_of(2),
+        InvalidRawLengthSnafu { length: raw.len() }
+    );
+    let (r, s) = raw.split_at(raw.len() / 2);
+
+    let mut body = Vec::with_capacity(raw.len() + 6);
+    write_integer(r, &mut body);
+    write_integer(s, &mut body);
+
+    let mut der = Vec::with_capacity(body.len() + 3);
+    der.push(0x30);
+    write_length(body.len(), &mut der);
+    der.extend_from_slice(&body);
+    Ok(der)
+}
+
+fn read_tlv(input: &[u8], tag: u8) -> Result<(&[u8], &[u8]), SignatureFormatError> {
+    let (&actual, input) = input.split_first().context(MalformedDerSnafu)?;
+    ensure!(actual == tag, MalformedDerSnafu);
+    let (&first, mut input) = input.split_first().context(MalformedDerSnafu)?;
+    let length = if first < 0x80 {
+        usize::from(first)
+    } else {
+        // Long form; signatures never need more than two length bytes.
+        let count = usize::from(first & 0x7f);
+        ensure!(
+            (1..=2).contains(&count) && input.len() >= count,
+            MalformedDerSnafu
+        );
+        let (bytes, rest) = input.split_at(count);
+        input = rest;
+        bytes.iter().fold(0, |acc, &b| (acc << 8) | usize::from(b))
+    };
+    ensure!(input.len() >= length, MalformedDerSnafu);
+    Ok(input.split_at(length))
+}
+
+fn write_padded(integer: &[u8], out: &mut [u8]) -> Result<(), SignatureFormatError> {
+    let start = integer
+        .iter()
+        .position(|&b| b != 0)
+        .unwrap_or(integer.len());
+    let trimmed = &integer[start..];
+    ensure!(
+        trimmed.len() <= out.len(),
+        IntegerTooLargeSnafu {
+            field_size: out.len()
+        }
+    );
+    let offset = out.len() - trimmed.len();
+    out[offset..].copy_from_slice(trimmed);
+    Ok(())
+}
+
+fn write_integer(integer: &[u8], out: &mut Vec<u8>) {
+    let start = integer
+        .iter()
+        .position(|&b| b != 0)
+        .unwrap_or(integer.len());
+    let trimmed = &integer[start..];
+    out.push(0x02);
+    match trimmed.first() {
+        None => {
+            out.push(1);
+            out.push(0);
+        }
+        Some(&first) if first & 0x80 != 0 => {
+            write_length(trimmed.len() + 1, out);
+            out.push(0);
+            out.extend_from_slice(trimmed);
+        }
+        Some(_) => {
+            write_length(trimmed.len(), out);
+            out.extend_from_slice(trimmed);
+        }
+    }
+}
+
+fn write_length(length: usize, out: &mut Vec<u8>) {
+    match u8::try_from(length) {
+        Ok(short) if short < 0x80 => out.push(short),
+        Ok(short) => out.extend([0x81, short]),
+        Err(_) => {
+            // Signatures are never anywhere near 64 KiB.
+            let [high, low] = u16::try_from(length).unwrap_or(u16::MAX).to_be_bytes();
+            out.extend([0x82, high, low]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip_with_high_bit_and_leading_zero() {
+        let mut raw = vec![0u8; 64];
+        raw[0] = 0x80; // R needs a leading zero in DER
+        raw[31] = 0x01;
+        raw[33] = 0x7f; // S has a leading zero byte that DER strips
+        raw[63] = 0x02;
+
+        let der = raw_to_der(&raw).unwrap();
+        assert_eq!(der[..5], [0x30, 0x44, 0x02, 0x21, 0x00]);
+
+        assert_eq!(der_to_jose(&der, "ES256").unwrap(), raw);
+    }
+
+    #[test]
+    fn test_round_trip_p521_long_form_length() {
+        let raw: Vec<u8> = (0..132u8).map(|i| 0x80 | i).collect();
+
+        let der = raw_to_der(&raw).unwrap();
+        assert_eq!(der[..2], [0x30, 0x81]);
+
+        assert_eq!(der_to_jose(&der, "ES512").unwrap(), raw);
+    }
+
+    #[test]
+    fn test_der_to_raw_pads_short_integers() {
+        let der = [0x30, 0x06, 0x02, 0x01, 0x01, 0x02, 0x01, 0x02];
+
+        let raw = der_to_raw(&der, 32).unwrap();
+
+        assert_eq!(raw.len(), 64);
+        assert_eq!(raw[31], 0x01);
+        assert_eq!(raw[63], 0x02);
+    }
+
+    #[test]
+    fn test_der_to_raw_rejects_oversized_integer() {
+        let mut der = vec![0x30, 0x26, 0x02, 0x21];
+        der.extend([0x01; 33]);
+        der.extend([0x02, 0x01, 0x01]);
+
+        assert!(matches!(
+            der_to_raw(&der, 32),
+            Err(SignatureFormatError::IntegerTooLarge { field_size: 32 })
+        ));
+    }
+
+    #[test]
+    fn test_der_to_raw_rejects_trailing_data() {
+        let der = [0x30, 0x06, 0x02, 0x01, 0x01, 0x02, 0x01, 0x02, 0x00];
+
+        assert!(matches!(
+            der_to_raw(&der, 32),
+            Err(SignatureFormatError::MalformedDer)
+        ));
+    }
+
+    #[test]
+    fn test_der_to_jose_rejects_non_ecdsa() {
+        assert!(matches!(
+            der_to_jose(&[], "RS256"),
+            Err(SignatureFormatError::NotEcdsa { .. })
+        ));
+    }
+}
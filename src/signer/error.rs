@@ -10,9 +10,44 @@ pub enum Error<E: std::error::Error + MaybeSendSync + 'static> {
     ///
     /// Callers should usually retry once if this is received.
     MismatchedKeyInfo,
+    /// The protected header could not be built.
+    #[snafu(display("Invalid protected header"))]
+    InvalidHeader {
+        /// The header error.
+        source: crate::jws::HeaderError,
+    },
+    /// The HTTP message signature base could not be built.
+    #[snafu(display("Invalid HTTP message signature"))]
+    InvalidHttpSignature {
+        /// The signature base error.
+        source: crate::http_sig::HttpSigError,
+    },
+    /// The claims could not be serialized to JSON.
+    #[snafu(display("Failed to serialize claims"))]
+    InvalidClaims {
+        /// The serialization error.
+        source: serde_json::Error,
+    },
     /// The error from the underlying implementation.
     UnderlyingError {
         /// The source error.
         source: E,
     },
 }
+
+impl<E: std::error::Error + MaybeSendSync + 'static> Error<E> {
+    /// Maps the underlying error, keeping other variants as-is.
+    pub(crate) fn map_underlying<F, O>(self, f: F) -> Error<O>
+    where
+        F: FnOnce(E) -> O,
+        O: std::error::Error + MaybeSendSync + 'static,
+    {
+        match self {
+            Self::MismatchedKeyInfo => Error::MismatchedKeyInfo,
+            Self::InvalidHeader { source } => Error::InvalidHeader { source },
+            Self::InvalidHttpSignature { source } => Error::InvalidHttpSignature { source },
+            Self::InvalidClaims { source } => Error::InvalidClaims { source },
+            Self::UnderlyingError { source } => Error::UnderlyingError { source: f(source) },
+        }
+    }
+}
@@ -0,0 +1,97 @@
+//! Typed extension map for signature metadata.
+
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+    sync::Arc,
+};
+
+use crate::MaybeSendSync;
+
+trait Extension: Any + MaybeSendSync {
+    fn as_any(&self) -> &dyn Any;
+}
+
+impl<T: Any + MaybeSendSync> Extension for T {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// A map of values keyed by their type.
+///
+/// Signers use this to attach provenance information to a [`SignedBytes`](super::SignedBytes),
+/// such as an HSM attestation or the identifier of a KMS key, using types
+/// of their choosing. Values are shared between clones.
+#[derive(Clone, Default)]
+pub struct Extensions {
+    map: HashMap<TypeId, Arc<dyn Extension>>,
+}
+
+impl Extensions {
+    /// Creates an empty map.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Inserts a value, replacing any existing value of the same type.
+    pub fn insert<T: Any + MaybeSendSync>(&mut self, value: T) {
+        self.map.insert(TypeId::of::<T>(), Arc::new(value));
+    }
+
+    /// Returns the value of type `T`, if present.
+    #[must_use]
+    pub fn get<T: Any + MaybeSendSync>(&self) -> Option<&T> {
+        self.map
+            .get(&TypeId::of::<T>())
+            .and_then(|value| (**value).as_any().downcast_ref())
+    }
+
+    /// Removes the value of type `T`, returning `true` if it was present.
+    pub fn remove<T: Any + MaybeSendSync>(&mut self) -> bool {
+        self.map.remove(&TypeId::of::<T>()).is_some()
+    }
+
+    /// Returns the number of values in the map.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns `true` if the map is empty.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+}
+
+impl std::fmt::Debug for Extensions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Extensions")
+            .field("len", &self.map.len())
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    struct KmsKeyArn(&'static str);
+
+    #[test]
+    fn test_insert_and_get_by_type() {
+        let mut extensions = Extensions::new();
+        extensions.insert(KmsKeyArn("arn:aws:kms:key/1"));
+        extensions.insert(KmsKeyArn("arn:aws:kms:key/2"));
+
+        assert_eq!(extensions.len(), 1);
+        assert_eq!(
+            extensions.get::<KmsKeyArn>(),
+            Some(&KmsKeyArn("arn:aws:kms:key/2"))
+        );
+        assert_eq!(extensions.clone().get::<u32>(), None);
+    }
+}
@@ -0,0 +1,386 @@
+//! Signer wrapper falling back to a secondary signer on failure.
+
+use std::borrow::Cow;
+
+use bytes::Bytes;
+use snafu::prelude::*;
+
+use crate::{
+    MaybeSendSync,
+    jws::Header,
+    signer::{Error, JwsSigner, SignedBytes},
+};
+
+/// Errors returned by [`FallbackSigner`].
+#[derive(Debug, Snafu)]
+pub enum FallbackError<A, B>
+where
+    A: std::error::Error + MaybeSendSync + 'static,
+    B: std::error::Error + MaybeSendSync + 'static,
+{
+    /// The primary signer failed, and the error was not eligible for fallback.
+    #[snafu(display("Primary signer failed"))]
+    Primary {
+        /// The error from the primary signer.
+        source: A,
+    },
+    /// Both the primary and the secondary signer failed.
+    #[snafu(display("Primary and secondary signers failed"))]
+    Secondary {
+        /// The error from the primary signer.
+        primary: A,
+        /// The error from the secondary signer.
+        source: B,
+    },
+}
+
+/// A signer that falls back to a secondary signer when the primary fails.
+///
+/// The two signers will usually have different key IDs (and possibly
+/// algorithms), so fallback only happens through
+/// [`JwsSigner::sign_with_header`], which builds the protected header with
+/// the `alg` and `kid` of the signer actually used. The other methods take a
+/// signing input whose header already names the primary's key, so the
+/// metadata accessors, [`JwsSigner::sign`], [`JwsSigner::sign_unchecked`] and
+/// [`JwsSigner::sign_with_metadata`] always use the primary signer.
+///
+/// By default every primary error triggers a fallback; use
+/// [`FallbackSigner::with_transient_check`] to only fall back on errors that
+/// are known to be transient (e.g. KMS outages, rather than permission errors).
+#[derive(Debug)]
+pub struct FallbackSigner<A: JwsSigner, B> {
+    primary: A,
+    secondary: B,
+    is_transient: fn(&A::Error) -> bool,
+}
+
+impl<A: JwsSigner, B: Clone> Clone for FallbackSigner<A, B> {
+    fn clone(&self) -> Self {
+        Self {
+            primary: self.primary.clone(),
+            secondary: self.secondary.clone(),
+            is_transient: self.is_transient,
+        }
+    }
+}
+
+impl<A: JwsSigner, B: JwsSigner> FallbackSigner<A, B> {
+    /// Creates a signer using `primary`, falling back to `secondary` on any error.
+    pub fn new(primary: A, secondary: B) -> Self {
+        Self {
+            primary,
+            secondary,
+            is_transient: |_| true,
+        }
+    }
+
+    /// Only falls back when `is_transient` returns `true` for the primary error.
+    #[must_use]
+    pub fn with_transient_check(mut self, is_transient: fn(&A::Error) -> bool) -> Self {
+        self.is_transient = is_transient;
+        self
+    }
+
+    /// Returns the primary signer.
+    pub fn primary(&self) -> &A {
+        &self.primary
+    }
+
+    /// Returns the secondary signer.
+    pub fn secondary(&self) -> &B {
+        &self.secondary
+    }
+}
+
+impl<A: JwsSigner, B: JwsSigner> JwsSigner for FallbackSigner<A, B> {
+    type Error = FallbackError<A::Error, B::Error>;
+
+    fn algorithm(&self) -> Cow<'_, str> {
+        self.primary.algorithm()
+    }
+
+    fn jws_algorithm(&self) -> Cow<'_, str> {
+        self.primary.jws_algorithm()
+    }
+
+    fn key_id(&self) -> Option<Cow<'_, str>> {
+        self.primary.key_id()
+    }
+
+    fn critical_headers(&self) -> Cow<'_, [String]> {
+        self.primary.critical_headers()
+    }
+
+    fn supported_algorithms(&self) -> Vec<Cow<'_, str>> {
+        self.primary.supported_algorithms()
+    }
+
+    /// The secondary signs with its own header on fallback.
+    fn allows_precomputed_header(&self) -> bool {
+        false
+    }
+
+    /// Succeeds if the primary is healthy, or the secondary is healthy and the
+    /// primary's error is eligible for fallback.
+    async fn health_check(&self) -> Result<(), Self::Error> {
+        match self.primary.health_check().await {
+            Ok(()) => Ok(()),
+            Err(primary) if (self.is_transient)(&primary) => self
+                .secondary
+                .health_check()
+                .await
+                .context(SecondarySnafu { primary }),
+            Err(source) => Err(FallbackError::Primary { source }),
+        }
+    }
+
+    async fn sign_unchecked(&self, input: &[u8]) -> Result<Bytes, Self::Error> {
+        self.primary
+            .sign_unchecked(input)
+            .await
+            .context(PrimarySnafu)
+    }
+
+    async fn sign(
+        &self,
+        input: &[u8],
+        jws_algorithm: &str,
+        key_id: Option<&str>,
+    ) -> Result<Bytes, Error<Self::Error>> {
+        // Delegated as a whole, so the primary checks and signs with the same key.
+        self.primary
+            .sign(input, jws_algorithm, key_id)
+            .await
+            .map_err(|e| e.map_underlying(|source| FallbackError::Primary { source }))
+    }
+
+    async fn sign_with_metadata(&self, input: &[u8]) -> Result<SignedBytes, Self::Error> {
+        // The input's header names the primary's key, so a signature by the
+        // secondary couldn't be verified.
+        self.primary
+            .sign_with_metadata(input)
+            .await
+            .context(PrimarySnafu)
+    }
+
+    async fn sign_with_header(
+        &self,
+        header: &Header,
+        payload: &[u8],
+    ) -> Result<SignedBytes, Error<Self::Error>> {
+        match self.primary.sign_with_header(header, payload).await {
+            Err(Error::UnderlyingError { source: primary }) if (self.is_transient)(&primary) => {
+                // The secondary builds its own header, so it carries its own `alg`/`kid`.
+                self.secondary
+                    .sign_with_header(header, payload)
+                    .await
+                    .map_err(|e| {
+                        e.map_underlying(|source| FallbackError::Secondary { primary, source })
+                    })
+            }
+            result => {
+                result.map_err(|e| e.map_underlying(|source| FallbackError::Primary { source }))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        borrow::Cow,
+        convert::Infallible,
+        sync::{Arc, OnceLock},
+        time::Duration,
+    };
+
+    use bytes::Bytes;
+    use snafu::Snafu;
+
+    use super::*;
+    use crate::{
+        metrics::tests::MemoryRecorder,
+        signer::{LimitSigner, MetricsSigner, RotatingSigner, TimeoutSigner},
+    };
+
+    #[derive(Debug, Snafu)]
+    enum MockError {
+        Transient,
+        Permanent,
+    }
+
+    #[derive(Debug, Clone)]
+    struct MockSigner {
+        kid: &'static str,
+        fail_with: Option<fn() -> MockError>,
+    }
+
+    impl JwsSigner for MockSigner {
+        type Error = MockError;
+
+        fn algorithm(&self) -> Cow<'_, str> {
+            "ALG".into()
+        }
+
+        fn jws_algorithm(&self) -> Cow<'_, str> {
+            "JWS-ALG".into()
+        }
+
+        fn key_id(&self) -> Option<Cow<'_, str>> {
+            Some(self.kid.into())
+        }
+
+        async fn sign_unchecked(&self, _input: &[u8]) -> Result<Bytes, Self::Error> {
+            match self.fail_with {
+                Some(error) => Err(error()),
+                None => Ok(Bytes::from_static(self.kid.as_bytes())),
+            }
+        }
+    }
+
+    fn signer(fail_with: Option<fn() -> MockError>) -> FallbackSigner<MockSigner, MockSigner> {
+        FallbackSigner::new(
+            MockSigner {
+                kid: "primary",
+                fail_with,
+            },
+            MockSigner {
+                kid: "secondary",
+                fail_with: None,
+            },
+        )
+        .with_transient_check(|e| matches!(e, MockError::Transient))
+    }
+
+    #[tokio::test]
+    async fn test_primary_used_when_healthy() {
+        let signed = signer(None).sign_with_metadata(b"input").await.unwrap();
+
+        assert_eq!(signed.key_id(), Some("primary"));
+    }
+
+    #[tokio::test]
+    async fn test_signing_input_does_not_fall_back() {
+        let result = signer(Some(|| MockError::Transient))
+            .sign_with_metadata(b"input")
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(FallbackError::Primary {
+                source: MockError::Transient
+            })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_transient_error_falls_back_with_secondary_header() {
+        let signed = signer(Some(|| MockError::Transient))
+            .sign_with_header(&Header::default(), b"payload")
+            .await
+            .unwrap();
+
+        // {"alg":"JWS-ALG","kid":"secondary"}
+        assert_eq!(
+            signed.protected_header(),
+            Some("eyJhbGciOiJKV1MtQUxHIiwia2lkIjoic2Vjb25kYXJ5In0")
+        );
+        assert_eq!(signed.key_id(), Some("secondary"));
+        assert_eq!(signed.signature(), "secondary");
+    }
+
+    #[tokio::test]
+    async fn test_permanent_error_does_not_fall_back() {
+        let result = signer(Some(|| MockError::Permanent))
+            .sign_with_header(&Header::default(), b"payload")
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(Error::UnderlyingError {
+                source: FallbackError::Primary {
+                    source: MockError::Permanent
+                }
+            })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_wrapped_transient_error_falls_back() {
+        let wrapped = TimeoutSigner::new(
+            LimitSigner::builder(MetricsSigner::new(
+                signer(Some(|| MockError::Transient)),
+                MemoryRecorder::default(),
+            ))
+            .build(),
+            Duration::from_secs(5),
+        );
+
+        assert!(!wrapped.allows_precomputed_header());
+        let signed = wrapped
+            .sign_with_header(&Header::default(), b"payload")
+            .await
+            .unwrap();
+
+        assert_eq!(signed.key_id(), Some("secondary"));
+        assert_eq!(signed.signature(), "secondary");
+    }
+
+    /// Rotates the slot it was loaded from when its key ID is read, simulating
+    /// a rotation racing with a signing call.
+    #[derive(Clone)]
+    struct RotatesOnKeyId {
+        kid: &'static str,
+        slot: Arc<OnceLock<RotatingSigner<RotatesOnKeyId>>>,
+    }
+
+    impl JwsSigner for RotatesOnKeyId {
+        type Error = Infallible;
+
+        fn algorithm(&self) -> Cow<'_, str> {
+            "ALG".into()
+        }
+
+        fn jws_algorithm(&self) -> Cow<'_, str> {
+            "JWS-ALG".into()
+        }
+
+        fn key_id(&self) -> Option<Cow<'_, str>> {
+            if let Some(slot) = self.slot.get() {
+                slot.rotate(Self {
+                    kid: "key-2",
+                    slot: Arc::default(),
+                });
+            }
+            Some(self.kid.into())
+        }
+
+        async fn sign_unchecked(&self, _input: &[u8]) -> Result<Bytes, Self::Error> {
+            Ok(Bytes::from_static(self.kid.as_bytes()))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sign_checks_and_signs_with_one_primary_snapshot() {
+        let slot = Arc::new(OnceLock::new());
+        let primary = RotatingSigner::new(RotatesOnKeyId {
+            kid: "key-1",
+            slot: Arc::clone(&slot),
+        });
+        let _ = slot.set(primary.clone());
+        let signer = FallbackSigner::new(
+            primary,
+            MockSigner {
+                kid: "secondary",
+                fail_with: None,
+            },
+        );
+
+        let signature = signer
+            .sign(b"input", "JWS-ALG", Some("key-1"))
+            .await
+            .unwrap();
+
+        assert_eq!(signature, "key-1");
+    }
+}
@@ -0,0 +1,230 @@
+//! Signer wrapper caching encoded protected headers.
+
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    sync::{Arc, Mutex, PoisonError},
+};
+
+use bytes::Bytes;
+use snafu::prelude::*;
+
+use crate::{
+    jws::{self, Header},
+    signer::{Error, JwsSigner, SignedBytes, error::InvalidHeaderSnafu},
+};
+
+#[derive(Debug, Default)]
+struct HeaderCache {
+    jws_algorithm: String,
+    key_id: Option<String>,
+    /// Encoded headers, keyed on `typ`.
+    encoded: HashMap<Option<String>, String>,
+}
+
+impl HeaderCache {
+    fn get_or_encode(
+        &mut self,
+        jws_algorithm: &str,
+        key_id: Option<&str>,
+        header: &Header,
+    ) -> Result<String, jws::HeaderError> {
+        if self.jws_algorithm != jws_algorithm || self.key_id.as_deref() != key_id {
+            // The key was rotated, so every cached header is stale.
+            jws_algorithm.clone_into(&mut self.jws_algorithm);
+            self.key_id = key_id.map(str::to_owned);
+            self.encoded.clear();
+        }
+        let typ = header.typ().map(str::to_owned);
+        if let Some(encoded) = self.encoded.get(&typ) {
+            return Ok(encoded.clone());
+        }
+        let encoded = jws::encode_protected_header(jws_algorithm, key_id, &[], header)?;
+        self.encoded.insert(typ, encoded.clone());
+        Ok(encoded)
+    }
+}
+
+/// A signer that caches encoded protected headers, skipping the JSON and
+/// base64url encoding on repeated [`JwsSigner::sign_with_header`] calls.
+///
+/// Headers are cached by `alg`, `kid` and `typ`, and the cache is cleared
+/// whenever the inner signer's algorithm or key ID changes (e.g. on rotation).
+/// Clones share the same cache.
+///
+/// Cached headers are signed through the inner signer's [`JwsSigner::sign`].
+/// Headers with other parameters, signers with critical headers, and signers
+/// that pick the key while signing (see
+/// [`JwsSigner::allows_precomputed_header`]) aren't cached; those are signed
+/// through the inner signer's own `sign_with_header`.
+#[derive(Debug, Clone)]
+pub struct HeaderCacheSigner<S> {
+    inner: S,
+    cache: Arc<Mutex<HeaderCache>>,
+}
+
+impl<S: JwsSigner> HeaderCacheSigner<S> {
+    /// Wraps a signer with an empty header cache.
+    pub fn new(inner: S) -> Self {
+        Self {
+            inner,
+            cache: Arc::default(),
+        }
+    }
+
+    /// Returns the wrapped signer.
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    /// Returns `true` if the encoded header can be cached.
+    fn is_cacheable(&self, header: &Header) -> bool {
+        self.inner.allows_precomputed_header()
+            && self.inner.critical_headers().is_empty()
+            && header.cty().is_none()
+            && header.params().next().is_none()
+    }
+}
+
+impl<S: JwsSigner> JwsSigner for HeaderCacheSigner<S> {
+    type Error = S::Error;
+
+    fn algorithm(&self) -> Cow<'_, str> {
+        self.inner.algorithm()
+    }
+
+    fn jws_algorithm(&self) -> Cow<'_, str> {
+        self.inner.jws_algorithm()
+    }
+
+    fn key_id(&self) -> Option<Cow<'_, str>> {
+        self.inner.key_id()
+    }
+
+    fn critical_headers(&self) -> Cow<'_, [String]> {
+        self.inner.critical_headers()
+    }
+
+    fn supported_algorithms(&self) -> Vec<Cow<'_, str>> {
+        self.inner.supported_algorithms()
+    }
+
+    fn allows_precomputed_header(&self) -> bool {
+        self.inner.allows_precomputed_header()
+    }
+
+    async fn health_check(&self) -> Result<(), Self::Error> {
+        self.inner.health_check().await
+    }
+
+    async fn sign_unchecked(&self, input: &[u8]) -> Result<Bytes, Self::Error> {
+        self.inner.sign_unchecked(input).await
+    }
+
+    async fn sign(
+        &self,
+        input: &[u8],
+        jws_algorithm: &str,
+        key_id: Option<&str>,
+    ) -> Result<Bytes, Error<Self::Error>> {
+        self.inner.sign(input, jws_algorithm, key_id).await
+    }
+
+    async fn sign_with_metadata(&self, input: &[u8]) -> Result<SignedBytes, Self::Error> {
+        self.inner.sign_with_metadata(input).await
+    }
+
+    async fn sign_with_header(
+        &self,
+        header: &Header,
+        payload: &[u8],
+    ) -> Result<SignedBytes, Error<Self::Error>> {
+        if !self.is_cacheable(header) {
+            return self.inner.sign_with_header(header, payload).await;
+        }
+        let jws_algorithm = self.inner.jws_algorithm().into_owned();
+        let key_id = self.inner.key_id().map(Cow::into_owned);
+        let encoded_header = self
+            .cache
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get_or_encode(&jws_algorithm, key_id.as_deref(), header)
+            .context(InvalidHeaderSnafu)?;
+        let input = jws::signing_input(&encoded_header, payload);
+        // The inner signer checks the metadata is unchanged, so a rotation
+        // between encoding and signing can't produce a mislabelled signature.
+        let signature = self
+            .inner
+            .sign(input.as_bytes(), &jws_algorithm, key_id.as_deref())
+            .await?;
+        Ok(SignedBytes::builder()
+            .signature(signature)
+            .jws_algorithm(jws_algorithm)
+            .maybe_key_id(key_id)
+            .protected_header(encoded_header)
+            .build())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signer::{FallbackSigner, MockSigner, PolicySigner, RotatingSigner};
+
+    fn jwt_header() -> Header {
+        Header::builder().typ("JWT").build()
+    }
+
+    #[tokio::test]
+    async fn test_cached_header_matches_uncached() {
+        let signer = HeaderCacheSigner::new(MockSigner::builder().key_id("key-1").build());
+
+        let first = signer.sign_with_header(&jwt_header(), b"{}").await.unwrap();
+        let second = signer.sign_with_header(&jwt_header(), b"{}").await.unwrap();
+        let uncached = signer
+            .inner()
+            .sign_with_header(&jwt_header(), b"{}")
+            .await
+            .unwrap();
+
+        assert_eq!(first, uncached);
+        assert_eq!(second, uncached);
+    }
+
+    #[tokio::test]
+    async fn test_rotation_invalidates_cache() {
+        let rotating = RotatingSigner::new(MockSigner::builder().key_id("key-1").build());
+        let signer = HeaderCacheSigner::new(rotating.clone());
+        signer.sign_with_header(&jwt_header(), b"{}").await.unwrap();
+
+        rotating.rotate(MockSigner::builder().key_id("key-2").build());
+        let rotated = signer.sign_with_header(&jwt_header(), b"{}").await.unwrap();
+
+        let expected =
+            jws::encode_protected_header("ES256", Some("key-2"), &[], &jwt_header()).unwrap();
+        assert_eq!(rotated.protected_header(), Some(expected.as_str()));
+        assert_eq!(rotated.key_id(), Some("key-2"));
+    }
+
+    #[tokio::test]
+    async fn test_fallback_signer_still_falls_back() {
+        let primary = MockSigner::builder()
+            .key_id("primary")
+            .error("unavailable")
+            .build();
+        let secondary = MockSigner::builder().key_id("secondary").build();
+        // The intermediate wrapper must not hide that the fallback signer's
+        // header can't be precomputed.
+        let signer = HeaderCacheSigner::new(PolicySigner::new(
+            FallbackSigner::new(primary, secondary),
+            ["ES256"],
+        ));
+
+        let fallback = signer.sign_with_header(&jwt_header(), b"{}").await.unwrap();
+
+        let expected =
+            jws::encode_protected_header("ES256", Some("secondary"), &[], &jwt_header()).unwrap();
+        assert_eq!(fallback.protected_header(), Some(expected.as_str()));
+        assert_eq!(fallback.key_id(), Some("secondary"));
+    }
+}
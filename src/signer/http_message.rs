@@ -0,0 +1,126 @@
+//! HTTP Message Signatures (RFC 9421) signing.
+
+use std::borrow::Cow;
+
+use base64::{Engine, prelude::BASE64_STANDARD};
+use snafu::prelude::*;
+
+use crate::{
+    MaybeSend,
+    http_sig::{self, Components, HttpSignature, SignatureParams},
+    jws::HeaderError,
+    signer::{
+        Error, JwsSigner,
+        error::{InvalidHeaderSnafu, InvalidHttpSignatureSnafu},
+    },
+};
+
+/// Trait for signing HTTP messages per RFC 9421.
+///
+/// This is implemented for every [`JwsSigner`] whose algorithm has an RFC 9421
+/// equivalent (see [`http_sig::algorithm_for_jws`]), so the same keys can
+/// sign tokens and HTTP requests. The `keyid` parameter is the signer's key ID.
+pub trait HttpMessageSigner: JwsSigner {
+    /// Asynchronously signs the covered components, returning the
+    /// `Signature-Input` and `Signature` header field values for the label.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the label isn't a structured field key, a signature
+    /// parameter can't be serialized, the algorithm has no RFC 9421
+    /// equivalent, the key metadata changed while signing, or the signing
+    /// operation fails.
+    fn sign_http_message(
+        &self,
+        label: &str,
+        components: &Components,
+        params: &SignatureParams,
+    ) -> impl Future<Output = Result<HttpSignature, Error<Self::Error>>> + MaybeSend {
+        async move {
+            http_sig::check_label(label).context(InvalidHttpSignatureSnafu)?;
+            let jws_algorithm = self.jws_algorithm().into_owned();
+            let key_id = self.key_id().map(Cow::into_owned);
+            let algorithm = http_sig::algorithm_for_jws(&jws_algorithm)
+                .ok_or(HeaderError::UnsupportedAlgorithm {
+                    algorithm: jws_algorithm.clone(),
+                })
+                .context(InvalidHeaderSnafu)?;
+            let signature_params = http_sig::signature_params(
+                components,
+                params,
+                key_id.as_deref(),
+                params.include_alg().then_some(algorithm),
+            )
+            .context(InvalidHttpSignatureSnafu)?;
+            let base = http_sig::signature_base(components, &signature_params)
+                .context(InvalidHttpSignatureSnafu)?;
+            let signature = self
+                .sign(base.as_bytes(), &jws_algorithm, key_id.as_deref())
+                .await?;
+            Ok(HttpSignature::new(
+                format!("{label}={signature_params}"),
+                format!("{label}=:{}:", BASE64_STANDARD.encode(signature)),
+            ))
+        }
+    }
+}
+
+impl<S: JwsSigner> HttpMessageSigner for S {}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use super::*;
+    use crate::signer::MockSigner;
+
+    #[tokio::test]
+    async fn test_sign_http_message() {
+        let signer = MockSigner::builder()
+            .jws_algorithm("EdDSA")
+            .key_id("test-key-ed25519")
+            .signature(Bytes::from_static(b"sig"))
+            .build();
+        let components = Components::new().with("@method", "POST").unwrap();
+        let params = SignatureParams::builder()
+            .created(1_618_884_473)
+            .include_alg(true)
+            .build();
+
+        let signature = signer
+            .sign_http_message("sig1", &components, &params)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            signature.signature_input(),
+            r#"sig1=("@method");created=1618884473;keyid="test-key-ed25519";alg="ed25519""#
+        );
+        assert_eq!(signature.signature(), "sig1=:c2ln:");
+        signer.assert_signed(
+            concat!(
+                "\"@method\": POST\n",
+                "\"@signature-params\": (\"@method\");created=1618884473;",
+                "keyid=\"test-key-ed25519\";alg=\"ed25519\"",
+            )
+            .as_bytes(),
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sign_http_message_rejects_invalid_label() {
+        let signer = MockSigner::builder().jws_algorithm("EdDSA").build();
+        let components = Components::new().with("@method", "POST").unwrap();
+
+        let result = signer
+            .sign_http_message("sig1=();x", &components, &SignatureParams::default())
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(Error::InvalidHttpSignature {
+                source: http_sig::HttpSigError::InvalidLabel { .. }
+            })
+        ));
+    }
+}
@@ -0,0 +1,315 @@
+//! Signer wrapper limiting concurrency and request rate.
+
+use std::{
+    borrow::Cow,
+    num::NonZeroUsize,
+    sync::{Arc, Mutex, PoisonError},
+    time::Duration,
+};
+
+use bon::bon;
+use bytes::Bytes;
+use snafu::prelude::*;
+use tokio::sync::{Semaphore, SemaphorePermit};
+use web_time::Instant;
+
+use crate::{
+    MaybeSendSync,
+    jws::Header,
+    signer::{Error, JwsSigner, SignedBytes},
+};
+
+/// Errors returned by [`LimitSigner`].
+#[derive(Debug, Snafu)]
+pub enum LimitError<E: std::error::Error + MaybeSendSync + 'static> {
+    /// The rate limit was exceeded.
+    #[snafu(display("Signing rate limit exceeded"))]
+    RateLimited,
+    /// The error from the wrapped signer.
+    #[snafu(display("Signing failed"))]
+    Inner {
+        /// The source error.
+        source: E,
+    },
+}
+
+/// A token-bucket rate limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimit {
+    capacity: u32,
+    refill_interval: Duration,
+}
+
+impl RateLimit {
+    /// Allows bursts of up to `capacity` operations, regaining one every `refill_interval`.
+    #[must_use]
+    pub fn new(capacity: u32, refill_interval: Duration) -> Self {
+        Self {
+            capacity,
+            refill_interval,
+        }
+    }
+
+    /// Allows `count` operations per second, with bursts of up to `count`.
+    #[must_use]
+    pub fn per_second(count: u32) -> Self {
+        Self::new(count, Duration::from_secs(1) / count.max(1))
+    }
+}
+
+#[derive(Debug)]
+struct TokenBucket {
+    limit: RateLimit,
+    tokens: u32,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    fn new(limit: RateLimit) -> Self {
+        Self {
+            limit,
+            tokens: limit.capacity,
+            last_refill: Instant::now(),
+        }
+    }
+
+    fn try_take(&mut self) -> bool {
+        let now = Instant::now();
+        if self.limit.refill_interval.is_zero() {
+            self.tokens = self.limit.capacity;
+        } else {
+            let elapsed = now.duration_since(self.last_refill);
+            let refilled = elapsed.as_nanos() / self.limit.refill_interval.as_nanos();
+            if refilled > 0 {
+                let refilled = u32::try_from(refilled).unwrap_or(u32::MAX);
+                self.tokens = self
+                    .tokens
+                    .saturating_add(refilled)
+                    .min(self.limit.capacity);
+                self.last_refill += self.limit.refill_interval * refilled;
+            }
+        }
+        if self.tokens == 0 {
+            return false;
+        }
+        self.tokens -= 1;
+        true
+    }
+}
+
+/// A signer that limits the number of in-flight signing operations, and
+/// optionally the rate at which they're started.
+///
+/// This protects HSMs and KMS quotas from request storms. Operations beyond
+/// the in-flight limit wait for a slot; operations beyond the rate limit fail
+/// immediately with [`LimitError::RateLimited`]. Clones share the same limits.
+#[derive(Debug, Clone)]
+pub struct LimitSigner<S> {
+    inner: S,
+    in_flight: Option<Arc<Semaphore>>,
+    rate: Option<Arc<Mutex<TokenBucket>>>,
+}
+
+#[bon]
+impl<S: JwsSigner> LimitSigner<S> {
+    /// Creates a builder wrapping the given signer.
+    #[builder]
+    pub fn new(
+        #[builder(start_fn)] inner: S,
+        /// The maximum number of concurrent signing operations, capped at
+        /// [`Semaphore::MAX_PERMITS`].
+        max_in_flight: Option<NonZeroUsize>,
+        /// The rate limit for starting signing operations.
+        rate_limit: Option<RateLimit>,
+    ) -> Self {
+        Self {
+            inner,
+            in_flight: max_in_flight
+                .map(|n| Arc::new(Semaphore::new(n.get().min(Semaphore::MAX_PERMITS)))),
+            rate: rate_limit.map(|limit| Arc::new(Mutex::new(TokenBucket::new(limit)))),
+        }
+    }
+
+    /// Returns the wrapped signer.
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    async fn acquire(&self) -> Result<Option<SemaphorePermit<'_>>, LimitError<S::Error>> {
+        if let Some(rate) = &self.rate {
+            let allowed = rate
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .try_take();
+            ensure!(allowed, RateLimitedSnafu);
+        }
+        match &self.in_flight {
+            // The semaphore is never closed, so acquiring can't fail.
+            Some(semaphore) => Ok(semaphore.acquire().await.ok()),
+            None => Ok(None),
+        }
+    }
+}
+
+impl<S: JwsSigner> JwsSigner for LimitSigner<S> {
+    type Error = LimitError<S::Error>;
+
+    fn algorithm(&self) -> Cow<'_, str> {
+        self.inner.algorithm()
+    }
+
+    fn jws_algorithm(&self) -> Cow<'_, str> {
+        self.inner.jws_algorithm()
+    }
+
+    fn key_id(&self) -> Option<Cow<'_, str>> {
+        self.inner.key_id()
+    }
+
+    fn critical_headers(&self) -> Cow<'_, [String]> {
+        self.inner.critical_headers()
+    }
+
+    fn supported_algorithms(&self) -> Vec<Cow<'_, str>> {
+        self.inner.supported_algorithms()
+    }
+
+    fn allows_precomputed_header(&self) -> bool {
+        self.inner.allows_precomputed_header()
+    }
+
+    async fn health_check(&self) -> Result<(), Self::Error> {
+        // Health checks bypass the limits, so probes can't starve real traffic
+        // of rate-limit tokens.
+        self.inner.health_check().await.context(InnerSnafu)
+    }
+
+    async fn sign_unchecked(&self, input: &[u8]) -> Result<Bytes, Self::Error> {
+        let _permit = self.acquire().await?;
+        self.inner.sign_unchecked(input).await.context(InnerSnafu)
+    }
+
+    async fn sign(
+        &self,
+        input: &[u8],
+        jws_algorithm: &str,
+        key_id: Option<&str>,
+    ) -> Result<Bytes, Error<Self::Error>> {
+        let _permit = self
+            .acquire()
+            .await
+            .map_err(|source| Error::UnderlyingError { source })?;
+        self.inner
+            .sign(input, jws_algorithm, key_id)
+            .await
+            .map_err(|e| e.map_underlying(|source| LimitError::Inner { source }))
+    }
+
+    async fn sign_with_metadata(&self, input: &[u8]) -> Result<SignedBytes, Self::Error> {
+        let _permit = self.acquire().await?;
+        self.inner
+            .sign_with_metadata(input)
+            .await
+            .context(InnerSnafu)
+    }
+
+    async fn sign_with_header(
+        &self,
+        header: &Header,
+        payload: &[u8],
+    ) -> Result<SignedBytes, Error<Self::Error>> {
+        let _permit = self
+            .acquire()
+            .await
+            .map_err(|source| Error::UnderlyingError { source })?;
+        self.inner
+            .sign_with_header(header, payload)
+            .await
+            .map_err(|e| e.map_underlying(|source| LimitError::Inner { source }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        borrow::Cow,
+        convert::Infallible,
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+
+    use bytes::Bytes;
+
+    use super::*;
+
+    #[derive(Debug, Clone, Default)]
+    struct MockSigner {
+        in_flight: Arc<AtomicUsize>,
+        max_seen: Arc<AtomicUsize>,
+    }
+
+    impl JwsSigner for MockSigner {
+        type Error = Infallible;
+
+        fn algorithm(&self) -> Cow<'_, str> {
+            "ALG".into()
+        }
+
+        fn jws_algorithm(&self) -> Cow<'_, str> {
+            "JWS-ALG".into()
+        }
+
+        fn key_id(&self) -> Option<Cow<'_, str>> {
+            None
+        }
+
+        async fn sign_unchecked(&self, _input: &[u8]) -> Result<Bytes, Self::Error> {
+            let current = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max_seen.fetch_max(current, Ordering::SeqCst);
+            tokio::task::yield_now().await;
+            self.in_flight.fetch_sub(1, Ordering::SeqCst);
+            Ok(Bytes::new())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_max_in_flight_is_enforced() {
+        let mock = MockSigner::default();
+        let signer = LimitSigner::builder(mock.clone())
+            .max_in_flight(NonZeroUsize::new(2).unwrap())
+            .build();
+
+        let tasks: Vec<_> = (0..8)
+            .map(|_| {
+                let signer = signer.clone();
+                tokio::spawn(async move { signer.sign_unchecked(b"input").await })
+            })
+            .collect();
+        for task in tasks {
+            task.await.unwrap().unwrap();
+        }
+
+        assert!(mock.max_seen.load(Ordering::SeqCst) <= 2);
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_rejects_excess() {
+        let signer = LimitSigner::builder(MockSigner::default())
+            .rate_limit(RateLimit::new(2, Duration::from_secs(30)))
+            .build();
+
+        signer.sign_unchecked(b"input").await.unwrap();
+        signer.sign_unchecked(b"input").await.unwrap();
+        let result = signer.sign_unchecked(b"input").await;
+
+        assert!(matches!(result, Err(LimitError::RateLimited)));
+    }
+
+    #[tokio::test]
+    async fn test_max_in_flight_is_capped() {
+        let signer = LimitSigner::builder(MockSigner::default())
+            .max_in_flight(NonZeroUsize::MAX)
+            .build();
+
+        signer.sign_unchecked(b"input").await.unwrap();
+    }
+}
@@ -0,0 +1,195 @@
+//! Signer wrapper emitting metrics for signing operations.
+
+use std::{borrow::Cow, sync::Arc};
+
+use bytes::Bytes;
+use web_time::Instant;
+
+use crate::{
+    jws::Header,
+    metrics::{
+        self, MetricsRecorder, SIGN_DURATION_SECONDS, SIGN_FAILURE_TOTAL, SIGN_SUCCESS_TOTAL,
+    },
+    signer::{Error, JwsSigner, SignedBytes},
+};
+
+/// A signer that reports signing outcomes and latency to a [`MetricsRecorder`].
+///
+/// See the [`metrics`](crate::metrics) module for the emitted metrics. Health
+/// checks are not measured. Errors are passed through unchanged.
+pub struct MetricsSigner<S, R> {
+    inner: S,
+    recorder: Arc<R>,
+}
+
+impl<S: Clone, R> Clone for MetricsSigner<S, R> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            recorder: Arc::clone(&self.recorder),
+        }
+    }
+}
+
+impl<S: std::fmt::Debug, R> std::fmt::Debug for MetricsSigner<S, R> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MetricsSigner")
+            .field("inner", &self.inner)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<S: JwsSigner, R: MetricsRecorder> MetricsSigner<S, R> {
+    /// Wraps a signer, reporting to the given recorder.
+    pub fn new(inner: S, recorder: R) -> Self {
+        Self {
+            inner,
+            recorder: Arc::new(recorder),
+        }
+    }
+
+    /// Returns the wrapped signer.
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    fn record(&self, started: Instant, is_ok: bool) {
+        let alg = self.inner.jws_algorithm();
+        let kid = self.inner.key_id();
+        metrics::record_outcome(
+            &self.recorder,
+            [
+                SIGN_SUCCESS_TOTAL,
+                SIGN_FAILURE_TOTAL,
+                SIGN_DURATION_SECONDS,
+            ],
+            is_ok,
+            started.elapsed(),
+            &[("alg", &alg), ("kid", kid.as_deref().unwrap_or_default())],
+        );
+    }
+}
+
+impl<S: JwsSigner, R: MetricsRecorder> JwsSigner for MetricsSigner<S, R> {
+    type Error = S::Error;
+
+    fn algorithm(&self) -> Cow<'_, str> {
+        self.inner.algorithm()
+    }
+
+    fn jws_algorithm(&self) -> Cow<'_, str> {
+        self.inner.jws_algorithm()
+    }
+
+    fn key_id(&self) -> Option<Cow<'_, str>> {
+        self.inner.key_id()
+    }
+
+    fn critical_headers(&self) -> Cow<'_, [String]> {
+        self.inner.critical_headers()
+    }
+
+    fn supported_algorithms(&self) -> Vec<Cow<'_, str>> {
+        self.inner.supported_algorithms()
+    }
+
+    fn allows_precomputed_header(&self) -> bool {
+        self.inner.allows_precomputed_header()
+    }
+
+    async fn health_check(&self) -> Result<(), Self::Error> {
+        self.inner.health_check().await
+    }
+
+    async fn sign_unchecked(&self, input: &[u8]) -> Result<Bytes, Self::Error> {
+        let started = Instant::now();
+        let result = self.inner.sign_unchecked(input).await;
+        self.record(started, result.is_ok());
+        result
+    }
+
+    async fn sign(
+        &self,
+        input: &[u8],
+        jws_algorithm: &str,
+        key_id: Option<&str>,
+    ) -> Result<Bytes, Error<Self::Error>> {
+        let started = Instant::now();
+        let result = self.inner.sign(input, jws_algorithm, key_id).await;
+        self.record(started, result.is_ok());
+        result
+    }
+
+    async fn sign_with_metadata(&self, input: &[u8]) -> Result<SignedBytes, Self::Error> {
+        let started = Instant::now();
+        let result = self.inner.sign_with_metadata(input).await;
+        self.record(started, result.is_ok());
+        result
+    }
+
+    async fn sign_with_header(
+        &self,
+        header: &Header,
+        payload: &[u8],
+    ) -> Result<SignedBytes, Error<Self::Error>> {
+        let started = Instant::now();
+        let result = self.inner.sign_with_header(header, payload).await;
+        self.record(started, result.is_ok());
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{borrow::Cow, convert::Infallible};
+
+    use bytes::Bytes;
+
+    use super::*;
+    use crate::metrics::tests::MemoryRecorder;
+
+    #[derive(Debug, Clone)]
+    struct MockSigner;
+
+    impl JwsSigner for MockSigner {
+        type Error = Infallible;
+
+        fn algorithm(&self) -> Cow<'_, str> {
+            "ALG".into()
+        }
+
+        fn jws_algorithm(&self) -> Cow<'_, str> {
+            "JWS-ALG".into()
+        }
+
+        fn key_id(&self) -> Option<Cow<'_, str>> {
+            Some("key-id".into())
+        }
+
+        async fn sign_unchecked(&self, _input: &[u8]) -> Result<Bytes, Self::Error> {
+            Ok(Bytes::new())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sign_outcomes_are_counted() {
+        let recorder = Arc::new(MemoryRecorder::default());
+        let signer = MetricsSigner::new(MockSigner, Arc::clone(&recorder));
+
+        signer
+            .sign(b"input", "JWS-ALG", Some("key-id"))
+            .await
+            .unwrap();
+        let _ = signer.sign(b"input", "OTHER", Some("key-id")).await;
+
+        let labels = vec![("alg", "JWS-ALG".to_owned()), ("kid", "key-id".to_owned())];
+        assert_eq!(
+            *recorder.counters.lock().unwrap(),
+            [
+                (SIGN_SUCCESS_TOTAL, labels.clone()),
+                (SIGN_FAILURE_TOTAL, labels)
+            ]
+        );
+        assert_eq!(recorder.histograms.lock().unwrap().len(), 2);
+    }
+}
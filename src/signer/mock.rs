@@ -0,0 +1,194 @@
+//! Deterministic mock signer for tests.
+
+use std::{
+    borrow::Cow,
+    sync::{Arc, Mutex, PoisonError},
+};
+
+use bon::bon;
+use bytes::Bytes;
+use snafu::prelude::*;
+
+use crate::signer::JwsSigner;
+
+/// The error returned by a failing [`MockSigner`].
+#[derive(Debug, Clone, PartialEq, Eq, Snafu)]
+#[snafu(display("Mock signer failure: {message}"))]
+pub struct MockError {
+    /// The configured failure message.
+    pub message: String,
+}
+
+#[derive(Debug, Default)]
+struct MockState {
+    inputs: Vec<Bytes>,
+    error: Option<String>,
+}
+
+/// A signer for tests, which returns a fixed signature and records every input.
+///
+/// Clones share the recorded inputs and the failure setting, so a clone can
+/// be handed to the code under test while the original is used for assertions.
+/// Available with the `test-util` feature.
+#[derive(Debug, Clone)]
+pub struct MockSigner {
+    algorithm: String,
+    jws_algorithm: String,
+    key_id: Option<String>,
+    signature: Bytes,
+    state: Arc<Mutex<MockState>>,
+}
+
+impl Default for MockSigner {
+    fn default() -> Self {
+        Self::builder().build()
+    }
+}
+
+#[bon]
+impl MockSigner {
+    /// Creates a builder for a mock signer.
+    #[builder]
+    pub fn new(
+        /// The descriptive algorithm name. Defaults to `MOCK`.
+        #[builder(into, default = "MOCK")]
+        algorithm: String,
+        /// The JWS algorithm identifier. Defaults to `ES256`.
+        #[builder(into, default = "ES256")]
+        jws_algorithm: String,
+        /// The key ID. Defaults to none.
+        #[builder(into)]
+        key_id: Option<String>,
+        /// The signature returned for every input. Defaults to empty.
+        #[builder(into, default)]
+        signature: Bytes,
+        /// If set, signing fails with a [`MockError`] with this message.
+        #[builder(into)]
+        error: Option<String>,
+    ) -> Self {
+        Self {
+            algorithm,
+            jws_algorithm,
+            key_id,
+            signature,
+            state: Arc::new(Mutex::new(MockState {
+                inputs: Vec::new(),
+                error,
+            })),
+        }
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, MockState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Makes subsequent signing operations fail with the given message, or
+    /// succeed again if `None`.
+    pub fn set_error(&self, message: Option<&str>) {
+        self.state().error = message.map(str::to_owned);
+    }
+
+    /// Returns every input signed so far, in order.
+    ///
+    /// Failed signing operations are recorded too.
+    #[must_use]
+    pub fn inputs(&self) -> Vec<Bytes> {
+        self.state().inputs.clone()
+    }
+
+    /// Returns the number of signing operations so far.
+    #[must_use]
+    pub fn call_count(&self) -> usize {
+        self.state().inputs.len()
+    }
+
+    /// Asserts that exactly `expected` signing operations were made.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of signing operations differs.
+    #[track_caller]
+    pub fn assert_call_count(&self, expected: usize) {
+        let actual = self.call_count();
+        assert_eq!(
+            actual, expected,
+            "expected {expected} signing operations, got {actual}"
+        );
+    }
+
+    /// Asserts that the given input was signed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the input was never signed.
+    #[track_caller]
+    pub fn assert_signed(&self, input: &[u8]) {
+        assert!(
+            self.state().inputs.iter().any(|i| i == input),
+            "input was not signed: {:?}",
+            String::from_utf8_lossy(input)
+        );
+    }
+}
+
+impl JwsSigner for MockSigner {
+    type Error = MockError;
+
+    fn algorithm(&self) -> Cow<'_, str> {
+        Cow::Borrowed(&self.algorithm)
+    }
+
+    fn jws_algorithm(&self) -> Cow<'_, str> {
+        Cow::Borrowed(&self.jws_algorithm)
+    }
+
+    fn key_id(&self) -> Option<Cow<'_, str>> {
+        self.key_id.as_deref().map(Cow::Borrowed)
+    }
+
+    async fn sign_unchecked(&self, input: &[u8]) -> Result<Bytes, Self::Error> {
+        let mut state = self.state();
+        state.inputs.push(Bytes::copy_from_slice(input));
+        match &state.error {
+            Some(message) => MockSnafu { message }.fail(),
+            None => Ok(self.signature.clone()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signer::Error;
+
+    #[tokio::test]
+    async fn test_records_inputs_across_clones() {
+        let signer = MockSigner::builder()
+            .key_id("key-1")
+            .signature(Bytes::from_static(b"sig"))
+            .build();
+
+        let signature = signer
+            .clone()
+            .sign(b"input", "ES256", Some("key-1"))
+            .await
+            .unwrap();
+
+        assert_eq!(signature, "sig");
+        signer.assert_call_count(1);
+        signer.assert_signed(b"input");
+    }
+
+    #[tokio::test]
+    async fn test_configured_error_is_returned() {
+        let signer = MockSigner::default();
+        signer.set_error(Some("offline"));
+
+        let result = signer.sign(b"input", "ES256", None).await;
+
+        assert!(matches!(
+            result,
+            Err(Error::UnderlyingError { source: MockError { message } }) if message == "offline"
+        ));
+    }
+}
@@ -1,7 +1,56 @@
 //! Cryptographic signing traits.
 
+mod audit;
+mod cose;
+mod dynamic;
+pub mod ecdsa;
 mod error;
+mod extensions;
+mod fallback;
+mod header_cache;
+mod http_message;
+mod limit;
+mod metrics;
+#[cfg(any(test, feature = "test-util"))]
+mod mock;
+mod policy;
+mod registry;
+mod resolver;
+mod rotating;
+mod rotation;
+mod signed;
+mod streaming;
+#[cfg(wasm_browser)]
+mod subtle;
+mod threshold;
+mod timeout;
 mod r#trait;
+mod usage;
 
+pub use audit::{AuditSigner, SignAuditHook, SignEvent, SignOutcome};
+pub use cose::{CoseBridgeError, CoseSigner, JwsCoseSigner};
+pub use dynamic::DynJwsSigner;
 pub use error::Error;
+pub use extensions::Extensions;
+pub use fallback::{FallbackError, FallbackSigner};
+pub use header_cache::HeaderCacheSigner;
+pub use http_message::HttpMessageSigner;
+pub use limit::{LimitError, LimitSigner, RateLimit};
+pub use metrics::MetricsSigner;
+#[cfg(any(test, feature = "test-util"))]
+pub use mock::{MockError, MockSigner};
+pub use policy::{PolicyError, PolicySigner};
+pub use registry::{KeySelector, RegistryError, SignerRegistry};
+pub use resolver::{InMemorySignerResolver, ResolveError, SignerResolver};
+pub use rotating::RotatingSigner;
+pub use rotation::{GeneratedKey, KeyGenerator, RotationError, RotationManager};
+pub use signed::SignedBytes;
+pub use streaming::{DigestAlgorithm, JwsStreamingSigner, StreamingSign};
+#[cfg(wasm_browser)]
+pub use subtle::{SubtleCryptoError, SubtleCryptoSigner};
+pub use threshold::{
+    ParticipantId, ShareHolder, ThresholdError, ThresholdProtocol, ThresholdSigner,
+};
+pub use timeout::TimeoutSigner;
 pub use r#trait::{HasPublicKey, JwsSigner};
+pub use usage::{KeyUsageSigner, UsageError};
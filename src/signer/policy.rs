@@ -0,0 +1,254 @@
+//! Signer wrapper enforcing an algorithm allow-list.
+
+use std::{borrow::Cow, sync::Arc};
+
+use bytes::Bytes;
+use snafu::prelude::*;
+
+use crate::{
+    MaybeSendSync,
+    jws::Header,
+    signer::{Error, JwsSigner, SignedBytes},
+};
+
+/// Errors returned by [`PolicySigner`].
+#[derive(Debug, Snafu)]
+pub enum PolicyError<E: std::error::Error + MaybeSendSync + 'static> {
+    /// The signer's algorithm is not in the allow-list.
+    #[snafu(display("Algorithm '{algorithm}' is not allowed by policy"))]
+    DisallowedAlgorithm {
+        /// The disallowed JWS algorithm.
+        algorithm: String,
+    },
+    /// The error from the wrapped signer.
+    #[snafu(display("Signing failed"))]
+    Inner {
+        /// The source error.
+        source: E,
+    },
+}
+
+/// A signer that refuses to sign unless its algorithm is in an allow-list.
+///
+/// This catches misconfiguration at runtime, e.g. a profile such as FAPI that
+/// only permits `PS256` and `ES256` being handed an `RS256` key.
+#[derive(Debug, Clone)]
+pub struct PolicySigner<S> {
+    inner: S,
+    allowed: Arc<[String]>,
+}
+
+impl<S: JwsSigner> PolicySigner<S> {
+    /// Wraps a signer, only allowing the given JWS algorithms.
+    pub fn new<I>(inner: S, allowed_algorithms: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        Self {
+            inner,
+            allowed: allowed_algorithms.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    /// Returns the wrapped signer.
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    /// Returns `true` if the given JWS algorithm is allowed.
+    #[must_use]
+    pub fn is_allowed(&self, jws_algorithm: &str) -> bool {
+        self.allowed.iter().any(|alg| alg == jws_algorithm)
+    }
+
+    fn check(&self, jws_algorithm: &str) -> Result<(), PolicyError<S::Error>> {
+        ensure!(
+            self.is_allowed(jws_algorithm),
+            DisallowedAlgorithmSnafu {
+                algorithm: jws_algorithm
+            }
+        );
+        Ok(())
+    }
+}
+
+impl<S: JwsSigner> JwsSigner for PolicySigner<S> {
+    type Error = PolicyError<S::Error>;
+
+    fn algorithm(&self) -> Cow<'_, str> {
+        self.inner.algorithm()
+    }
+
+    fn jws_algorithm(&self) -> Cow<'_, str> {
+        self.inner.jws_algorithm()
+    }
+
+    fn key_id(&self) -> Option<Cow<'_, str>> {
+        self.inner.key_id()
+    }
+
+    fn critical_headers(&self) -> Cow<'_, [String]> {
+        self.inner.critical_headers()
+    }
+
+    fn supported_algorithms(&self) -> Vec<Cow<'_, str>> {
+        let mut algorithms = self.inner.supported_algorithms();
+        algorithms.retain(|alg| self.is_allowed(alg));
+        algorithms
+    }
+
+    fn allows_precomputed_header(&self) -> bool {
+        self.inner.allows_precomputed_header()
+    }
+
+    async fn health_check(&self) -> Result<(), Self::Error> {
+        self.check(&self.inner.jws_algorithm())?;
+        self.inner.health_check().await.context(InnerSnafu)
+    }
+
+    async fn sign_unchecked(&self, input: &[u8]) -> Result<Bytes, Self::Error> {
+        self.check(&self.inner.jws_algorithm())?;
+        self.inner.sign_unchecked(input).await.context(InnerSnafu)
+    }
+
+    async fn sign(
+        &self,
+        input: &[u8],
+        jws_algorithm: &str,
+        key_id: Option<&str>,
+    ) -> Result<Bytes, Error<Self::Error>> {
+        // The inner signer verifies the algorithm matches, so checking the
+        // requested algorithm is sufficient.
+        self.check(jws_algorithm)
+            .map_err(|source| Error::UnderlyingError { source })?;
+        self.inner
+            .sign(input, jws_algorithm, key_id)
+            .await
+            .map_err(|e| e.map_underlying(|source| PolicyError::Inner { source }))
+    }
+
+    async fn sign_with_metadata(&self, input: &[u8]) -> Result<SignedBytes, Self::Error> {
+        // Checked before signing, so a disallowed key is never used.
+        self.check(&self.inner.jws_algorithm())?;
+        let signed = self
+            .inner
+            .sign_with_metadata(input)
+            .await
+            .context(InnerSnafu)?;
+        // Checked again after signing, in case the inner signer switched keys
+        // (e.g. rotated) in between.
+        self.check(signed.jws_algorithm())?;
+        Ok(signed)
+    }
+
+    async fn sign_with_header(
+        &self,
+        header: &Header,
+        payload: &[u8],
+    ) -> Result<SignedBytes, Error<Self::Error>> {
+        // Checked before and after signing, as in `sign_with_metadata`.
+        self.check(&self.inner.jws_algorithm())
+            .map_err(|source| Error::UnderlyingError { source })?;
+        let signed = self
+            .inner
+            .sign_with_header(header, payload)
+            .await
+            .map_err(|e| e.map_underlying(|source| PolicyError::Inner { source }))?;
+        self.check(signed.jws_algorithm())
+            .map_err(|source| Error::UnderlyingError { source })?;
+        Ok(signed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{borrow::Cow, convert::Infallible};
+
+    use bytes::Bytes;
+
+    use super::*;
+
+    #[derive(Debug, Clone)]
+    struct MockSigner(&'static str);
+
+    impl JwsSigner for MockSigner {
+        type Error = Infallible;
+
+        fn algorithm(&self) -> Cow<'_, str> {
+            "ALG".into()
+        }
+
+        fn jws_algorithm(&self) -> Cow<'_, str> {
+            self.0.into()
+        }
+
+        fn key_id(&self) -> Option<Cow<'_, str>> {
+            None
+        }
+
+        async fn sign_unchecked(&self, _input: &[u8]) -> Result<Bytes, Self::Error> {
+            Ok(Bytes::new())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_allowed_algorithm_signs() {
+        let signer = PolicySigner::new(MockSigner("PS256"), ["PS256", "ES256"]);
+
+        assert!(signer.sign(b"input", "PS256", None).await.is_ok());
+        assert!(signer.sign_with_metadata(b"input").await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_disallowed_algorithm_fails() {
+        let signer = PolicySigner::new(MockSigner("RS256"), ["PS256", "ES256"]);
+
+        let result = signer.sign(b"input", "RS256", None).await;
+        assert!(matches!(
+            result,
+            Err(Error::UnderlyingError {
+                source: PolicyError::DisallowedAlgorithm { .. }
+            })
+        ));
+
+        let result = signer.sign_with_metadata(b"input").await;
+        assert!(matches!(
+            result,
+            Err(PolicyError::DisallowedAlgorithm { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_disallowed_algorithm_is_not_signed() {
+        let inner = crate::signer::MockSigner::builder()
+            .jws_algorithm("RS256")
+            .build();
+        let signer = PolicySigner::new(inner.clone(), ["PS256"]);
+
+        assert!(signer.sign_with_metadata(b"input").await.is_err());
+        assert!(signer.sign(b"input", "RS256", None).await.is_err());
+        inner.assert_call_count(0);
+    }
+
+    #[tokio::test]
+    async fn test_health_check_reports_disallowed_algorithm() {
+        let signer = PolicySigner::new(MockSigner("RS256"), ["PS256", "ES256"]);
+
+        let result = signer.health_check().await;
+
+        assert!(matches!(
+            result,
+            Err(PolicyError::DisallowedAlgorithm { .. })
+        ));
+    }
+
+    #[test]
+    fn test_supported_algorithms_excludes_disallowed() {
+        let allowed = PolicySigner::new(MockSigner("PS256"), ["PS256"]);
+        let disallowed = PolicySigner::new(MockSigner("RS256"), ["PS256"]);
+
+        assert!(allowed.supports_algorithm("PS256"));
+        assert!(disallowed.supported_algorithms().is_empty());
+    }
+}
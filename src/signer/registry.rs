@@ -0,0 +1,329 @@
+//! Registry of signers selectable by key ID or algorithm.
+
+use snafu::prelude::*;
+
+use crate::{
+    MaybeSendSync,
+    jws::Header,
+    signer::{Error, JwsSigner, SignedBytes},
+};
+
+/// Errors returned when signing through a [`SignerRegistry`].
+#[derive(Debug, Snafu)]
+pub enum RegistryError<E: std::error::Error + MaybeSendSync + 'static> {
+    /// No registered signer matches the selector.
+    #[snafu(display("No signer registered matching {selector}"))]
+    NoMatchingSigner {
+        /// A description of the selector that failed to match.
+        selector: String,
+    },
+    /// The selected signer failed to sign.
+    #[snafu(display("Signing failed"))]
+    Signing {
+        /// The error from the selected signer.
+        source: Error<E>,
+    },
+}
+
+/// Selects a signer from a [`SignerRegistry`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeySelector<'a> {
+    /// The signer with the given key ID.
+    KeyId(&'a str),
+    /// The first registered signer with the given JWS algorithm.
+    Algorithm(&'a str),
+    /// The first registered signer whose JWS algorithm is any of the given
+    /// ones (e.g. those allowed by a client's metadata).
+    ///
+    /// Signing uses [`JwsSigner::jws_algorithm`], so a signer supporting other
+    /// algorithms too only matches on that one, and only if it also
+    /// [supports](JwsSigner::supports_algorithm) it. Registration order takes
+    /// precedence over the order of the algorithms.
+    AnyAlgorithm(&'a [&'a str]),
+}
+
+impl std::fmt::Display for KeySelector<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::KeyId(kid) => write!(f, "kid '{kid}'"),
+            Self::Algorithm(alg) => write!(f, "alg '{alg}'"),
+            Self::AnyAlgorithm(algs) => write!(f, "any alg of {algs:?}"),
+        }
+    }
+}
+
+/// A set of active signers, selectable by key ID or algorithm.
+///
+/// Signers are kept in registration order, which is also the order of
+/// preference when selecting by algorithm.
+#[derive(Debug, Clone)]
+pub struct SignerRegistry<S> {
+    signers: Vec<S>,
+}
+
+impl<S> Default for SignerRegistry<S> {
+    fn default() -> Self {
+        Self {
+            signers: Vec::new(),
+        }
+    }
+}
+
+impl<S: JwsSigner> SignerRegistry<S> {
+    /// Creates an empty registry.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a signer.
+    ///
+    /// If a signer with the same key ID is already registered, it is replaced
+    /// (keeping its position) and returned.
+    pub fn register(&mut self, signer: S) -> Option<S> {
+        let existing = signer.key_id().and_then(|kid| {
+            self.signers
+                .iter()
+                .position(|s| s.key_id().as_deref() == Some(kid.as_ref()))
+        });
+        if let Some(index) = existing {
+            Some(std::mem::replace(&mut self.signers[index], signer))
+        } else {
+            self.signers.push(signer);
+            None
+        }
+    }
+
+    /// Removes the signer with the given key ID, returning it.
+    pub fn remove(&mut self, key_id: &str) -> Option<S> {
+        let index = self
+            .signers
+            .iter()
+            .position(|s| s.key_id().as_deref() == Some(key_id))?;
+        Some(self.signers.remove(index))
+    }
+
+    /// Returns the signer matching the selector, if any.
+    #[must_use]
+    pub fn select(&self, selector: KeySelector<'_>) -> Option<&S> {
+        self.signers.iter().find(|s| match selector {
+            KeySelector::KeyId(kid) => s.key_id().as_deref() == Some(kid),
+            KeySelector::Algorithm(alg) => s.jws_algorithm() == alg,
+            KeySelector::AnyAlgorithm(algs) => {
+                let jws_algorithm = s.jws_algorithm();
+                algs.contains(&jws_algorithm.as_ref()) && s.supports_algorithm(&jws_algorithm)
+            }
+        })
+    }
+
+    /// Returns an iterator over the registered signers.
+    pub fn iter(&self) -> impl Iterator<Item = &S> {
+        self.signers.iter()
+    }
+
+    /// Returns the number of registered signers.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.signers.len()
+    }
+
+    /// Returns `true` if no signers are registered.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.signers.is_empty()
+    }
+
+    /// Signs a payload as a JWS with the signer matching the selector.
+    ///
+    /// The selected signer builds the protected header (see
+    /// [`JwsSigner::sign_with_header`]), so its `alg` and `kid` name the key
+    /// that actually signed, even when selecting by algorithm.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no signer matches, the header can't be built, or
+    /// the signing operation fails.
+    pub async fn sign(
+        &self,
+        selector: KeySelector<'_>,
+        header: &Header,
+        payload: &[u8],
+    ) -> Result<SignedBytes, RegistryError<S::Error>> {
+        let signer = self
+            .select(selector)
+            .with_context(|| NoMatchingSignerSnafu {
+                selector: selector.to_string(),
+            })?;
+        signer
+            .sign_with_header(header, payload)
+            .await
+            .context(SigningSnafu)
+    }
+}
+
+impl<S: JwsSigner> FromIterator<S> for SignerRegistry<S> {
+    fn from_iter<I: IntoIterator<Item = S>>(iter: I) -> Self {
+        let mut registry = Self::new();
+        for signer in iter {
+            registry.register(signer);
+        }
+        registry
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{borrow::Cow, convert::Infallible};
+
+    use bytes::Bytes;
+
+    use super::*;
+
+    #[derive(Debug, Clone)]
+    struct MockSigner {
+        alg: &'static str,
+        kid: &'static str,
+        also_supports: &'static [&'static str],
+    }
+
+    impl JwsSigner for MockSigner {
+        type Error = Infallible;
+
+        fn algorithm(&self) -> Cow<'_, str> {
+            "ALG".into()
+        }
+
+        fn jws_algorithm(&self) -> Cow<'_, str> {
+            self.alg.into()
+        }
+
+        fn key_id(&self) -> Option<Cow<'_, str>> {
+            Some(self.kid.into())
+        }
+
+        fn supported_algorithms(&self) -> Vec<Cow<'_, str>> {
+            let also = self.also_supports.iter().map(|&alg| alg.into());
+            std::iter::once(self.jws_algorithm()).chain(also).collect()
+        }
+
+        async fn sign_unchecked(&self, _input: &[u8]) -> Result<Bytes, Self::Error> {
+            Ok(Bytes::from_static(self.kid.as_bytes()))
+        }
+    }
+
+    fn registry() -> SignerRegistry<MockSigner> {
+        [
+            MockSigner {
+                alg: "RS256",
+                kid: "rsa-1",
+                also_supports: &["PS256"],
+            },
+            MockSigner {
+                alg: "ES256",
+                kid: "ec-1",
+                also_supports: &[],
+            },
+            MockSigner {
+                alg: "ES256",
+                kid: "ec-2",
+                also_supports: &[],
+            },
+        ]
+        .into_iter()
+        .collect()
+    }
+
+    #[tokio::test]
+    async fn test_sign_by_key_id() {
+        let signed = registry()
+            .sign(KeySelector::KeyId("ec-2"), &Header::default(), b"payload")
+            .await
+            .unwrap();
+
+        assert_eq!(signed.key_id(), Some("ec-2"));
+        assert_eq!(signed.jws_algorithm(), "ES256");
+        assert_eq!(signed.signature(), "ec-2");
+    }
+
+    #[tokio::test]
+    async fn test_sign_by_algorithm_uses_first_registered() {
+        let signed = registry()
+            .sign(
+                KeySelector::Algorithm("ES256"),
+                &Header::default(),
+                b"payload",
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(signed.key_id(), Some("ec-1"));
+        // {"alg":"ES256","kid":"ec-1"}
+        assert_eq!(
+            signed.protected_header(),
+            Some("eyJhbGciOiJFUzI1NiIsImtpZCI6ImVjLTEifQ")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sign_by_any_algorithm_uses_registration_order() {
+        let signed = registry()
+            .sign(
+                KeySelector::AnyAlgorithm(&["ES256", "RS256"]),
+                &Header::default(),
+                b"payload",
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(signed.key_id(), Some("rsa-1"));
+    }
+
+    #[tokio::test]
+    async fn test_sign_by_any_algorithm_matches_signing_algorithm() {
+        let signed = registry()
+            .sign(
+                KeySelector::AnyAlgorithm(&["PS256", "ES256"]),
+                &Header::default(),
+                b"payload",
+            )
+            .await
+            .unwrap();
+
+        // `rsa-1` supports PS256, but would sign with RS256.
+        assert_eq!(signed.key_id(), Some("ec-1"));
+        assert_eq!(signed.jws_algorithm(), "ES256");
+    }
+
+    #[tokio::test]
+    async fn test_sign_no_match_fails() {
+        let result = registry()
+            .sign(
+                KeySelector::Algorithm("PS256"),
+                &Header::default(),
+                b"payload",
+            )
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(RegistryError::NoMatchingSigner { .. })
+        ));
+    }
+
+    #[test]
+    fn test_register_replaces_same_key_id() {
+        let mut registry = registry();
+        let previous = registry.register(MockSigner {
+            alg: "ES384",
+            kid: "ec-1",
+            also_supports: &[],
+        });
+
+        assert_eq!(previous.map(|s| s.alg), Some("ES256"));
+        assert_eq!(registry.len(), 3);
+        assert_eq!(
+            registry.select(KeySelector::KeyId("ec-1")).map(|s| s.alg),
+            Some("ES384")
+        );
+    }
+}
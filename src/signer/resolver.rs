@@ -0,0 +1,176 @@
+//! Per-tenant signer resolution.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, PoisonError, RwLock},
+};
+
+use snafu::prelude::*;
+
+use crate::{MaybeSend, MaybeSendSync, signer::JwsSigner};
+
+/// Trait for resolving the signer to use for a tenant (or issuer).
+///
+/// This allows generic token issuance code to be used by multi-tenant
+/// authorization servers, where each tenant has its own keys.
+pub trait SignerResolver: MaybeSendSync + Clone {
+    /// The signer type returned by this resolver.
+    type Signer: JwsSigner;
+
+    /// The error type returned by this resolver's operations.
+    type Error: std::error::Error + MaybeSendSync + 'static;
+
+    /// Asynchronously resolves the signer for the given tenant.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the tenant is unknown, or its signer can't be loaded.
+    fn resolve(
+        &self,
+        tenant: &str,
+    ) -> impl Future<Output = Result<Self::Signer, Self::Error>> + MaybeSend;
+}
+
+/// Errors returned by [`InMemorySignerResolver`].
+#[derive(Debug, Snafu)]
+pub enum ResolveError {
+    /// No signer is registered for the tenant.
+    #[snafu(display("No signer registered for tenant '{tenant}'"))]
+    UnknownTenant {
+        /// The tenant that was requested.
+        tenant: String,
+    },
+}
+
+/// A resolver backed by an in-memory map of tenant to signer.
+///
+/// Clones share the same map, so tenants added or removed at runtime are
+/// visible to every clone.
+#[derive(Debug)]
+pub struct InMemorySignerResolver<S> {
+    signers: Arc<RwLock<HashMap<String, S>>>,
+}
+
+impl<S> Clone for InMemorySignerResolver<S> {
+    fn clone(&self) -> Self {
+        Self {
+            signers: Arc::clone(&self.signers),
+        }
+    }
+}
+
+impl<S> Default for InMemorySignerResolver<S> {
+    fn default() -> Self {
+        Self {
+            signers: Arc::default(),
+        }
+    }
+}
+
+impl<S: JwsSigner> InMemorySignerResolver<S> {
+    /// Creates an empty resolver.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the signer for a tenant, returning the previous one.
+    pub fn insert(&self, tenant: impl Into<String>, signer: S) -> Option<S> {
+        self.signers
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(tenant.into(), signer)
+    }
+
+    /// Removes the signer for a tenant, returning it.
+    pub fn remove(&self, tenant: &str) -> Option<S> {
+        self.signers
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(tenant)
+    }
+}
+
+impl<S: JwsSigner, T: Into<String>> FromIterator<(T, S)> for InMemorySignerResolver<S> {
+    fn from_iter<I: IntoIterator<Item = (T, S)>>(iter: I) -> Self {
+        let signers = iter.into_iter().map(|(t, s)| (t.into(), s)).collect();
+        Self {
+            signers: Arc::new(RwLock::new(signers)),
+        }
+    }
+}
+
+impl<S: JwsSigner> SignerResolver for InMemorySignerResolver<S> {
+    type Signer = S;
+    type Error = ResolveError;
+
+    async fn resolve(&self, tenant: &str) -> Result<Self::Signer, Self::Error> {
+        self.signers
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(tenant)
+            .cloned()
+            .context(UnknownTenantSnafu { tenant })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{borrow::Cow, convert::Infallible};
+
+    use bytes::Bytes;
+
+    use super::*;
+
+    #[derive(Debug, Clone)]
+    struct MockSigner(&'static str);
+
+    impl JwsSigner for MockSigner {
+        type Error = Infallible;
+
+        fn algorithm(&self) -> Cow<'_, str> {
+            "ALG".into()
+        }
+
+        fn jws_algorithm(&self) -> Cow<'_, str> {
+            "JWS-ALG".into()
+        }
+
+        fn key_id(&self) -> Option<Cow<'_, str>> {
+            Some(self.0.into())
+        }
+
+        async fn sign_unchecked(&self, _input: &[u8]) -> Result<Bytes, Self::Error> {
+            Ok(Bytes::new())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_resolve_known_tenant() {
+        let resolver: InMemorySignerResolver<_> =
+            [("acme", MockSigner("acme-key"))].into_iter().collect();
+
+        let signer = resolver.resolve("acme").await.unwrap();
+
+        assert_eq!(signer.key_id().as_deref(), Some("acme-key"));
+    }
+
+    #[tokio::test]
+    async fn test_resolve_unknown_tenant_fails() {
+        let resolver = InMemorySignerResolver::<MockSigner>::new();
+
+        let result = resolver.resolve("acme").await;
+
+        assert!(matches!(result, Err(ResolveError::UnknownTenant { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_insert_is_visible_to_clones() {
+        let resolver = InMemorySignerResolver::new();
+        let clone = resolver.clone();
+
+        resolver.insert("acme", MockSigner("acme-key"));
+
+        assert!(clone.resolve("acme").await.is_ok());
+    }
+}
@@ -0,0 +1,177 @@
+//! Signer wrapper supporting atomic key rotation.
+
+use std::{borrow::Cow, sync::Arc};
+
+use arc_swap::ArcSwap;
+use bytes::Bytes;
+
+use crate::{
+    jws::Header,
+    signer::{JwsSigner, SignedBytes},
+};
+
+/// A signer whose underlying signer can be atomically swapped at runtime.
+///
+/// Clones share the same underlying slot, so a rotation is visible to every
+/// clone. Each signing call works against a single snapshot of the current
+/// signer: [`JwsSigner::sign`] checks the expected algorithm and key ID and
+/// produces the signature with the same key, so a rotation that happens
+/// between building a header and signing it results in
+/// [`Error::MismatchedKeyInfo`](crate::signer::Error::MismatchedKeyInfo)
+/// rather than a signature that doesn't match its header.
+#[derive(Debug)]
+pub struct RotatingSigner<S> {
+    current: Arc<ArcSwap<S>>,
+}
+
+impl<S> Clone for RotatingSigner<S> {
+    fn clone(&self) -> Self {
+        Self {
+            current: Arc::clone(&self.current),
+        }
+    }
+}
+
+impl<S: JwsSigner> RotatingSigner<S> {
+    /// Creates a rotating signer starting with the given signer.
+    pub fn new(signer: S) -> Self {
+        Self {
+            current: Arc::new(ArcSwap::from_pointee(signer)),
+        }
+    }
+
+    /// Returns a snapshot of the current signer.
+    #[must_use]
+    pub fn current(&self) -> Arc<S> {
+        self.current.load_full()
+    }
+
+    /// Replaces the current signer, returning the previous one.
+    pub fn rotate(&self, signer: S) -> Arc<S> {
+        self.current.swap(Arc::new(signer))
+    }
+}
+
+impl<S: JwsSigner> JwsSigner for RotatingSigner<S> {
+    type Error = S::Error;
+
+    fn algorithm(&self) -> Cow<'_, str> {
+        Cow::Owned(self.current.load().algorithm().into_owned())
+    }
+
+    fn jws_algorithm(&self) -> Cow<'_, str> {
+        Cow::Owned(self.current.load().jws_algorithm().into_owned())
+    }
+
+    fn key_id(&self) -> Option<Cow<'_, str>> {
+        self.current
+            .load()
+            .key_id()
+            .map(|kid| Cow::Owned(kid.into_owned()))
+    }
+
+    fn critical_headers(&self) -> Cow<'_, [String]> {
+        Cow::Owned(self.current.load().critical_headers().into_owned())
+    }
+
+    fn supported_algorithms(&self) -> Vec<Cow<'_, str>> {
+        self.current
+            .load()
+            .supported_algorithms()
+            .into_iter()
+            .map(|alg| Cow::Owned(alg.into_owned()))
+            .collect()
+    }
+
+    fn allows_precomputed_header(&self) -> bool {
+        self.current.load().allows_precomputed_header()
+    }
+
+    async fn health_check(&self) -> Result<(), Self::Error> {
+        self.current().health_check().await
+    }
+
+    async fn sign_unchecked(&self, input: &[u8]) -> Result<Bytes, Self::Error> {
+        self.current().sign_unchecked(input).await
+    }
+
+    async fn sign(
+        &self,
+        input: &[u8],
+        jws_algorithm: &str,
+        key_id: Option<&str>,
+    ) -> Result<Bytes, crate::signer::Error<Self::Error>> {
+        self.current().sign(input, jws_algorithm, key_id).await
+    }
+
+    async fn sign_with_metadata(&self, input: &[u8]) -> Result<SignedBytes, Self::Error> {
+        self.current().sign_with_metadata(input).await
+    }
+
+    async fn sign_with_header(
+        &self,
+        header: &Header,
+        payload: &[u8],
+    ) -> Result<SignedBytes, crate::signer::Error<Self::Error>> {
+        self.current().sign_with_header(header, payload).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{borrow::Cow, convert::Infallible};
+
+    use bytes::Bytes;
+
+    use super::RotatingSigner;
+    use crate::signer::JwsSigner;
+
+    #[derive(Debug, Clone)]
+    struct MockSigner(&'static str);
+
+    impl JwsSigner for MockSigner {
+        type Error = Infallible;
+
+        fn algorithm(&self) -> Cow<'_, str> {
+            "ALG".into()
+        }
+
+        fn jws_algorithm(&self) -> Cow<'_, str> {
+            "JWS-ALG".into()
+        }
+
+        fn key_id(&self) -> Option<Cow<'_, str>> {
+            Some(self.0.into())
+        }
+
+        async fn sign_unchecked(&self, _input: &[u8]) -> Result<Bytes, Self::Error> {
+            Ok(Bytes::from_static(self.0.as_bytes()))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rotation_is_visible_to_clones() {
+        let signer = RotatingSigner::new(MockSigner("key-1"));
+        let clone = signer.clone();
+
+        let previous = signer.rotate(MockSigner("key-2"));
+
+        assert_eq!(previous.0, "key-1");
+        assert_eq!(clone.key_id().as_deref(), Some("key-2"));
+        assert_eq!(clone.sign_unchecked(&[]).await.unwrap(), "key-2");
+    }
+
+    #[tokio::test]
+    async fn test_sign_with_stale_key_id_fails() {
+        let signer = RotatingSigner::new(MockSigner("key-1"));
+        let kid = signer.key_id().map(Cow::into_owned);
+
+        signer.rotate(MockSigner("key-2"));
+        let result = signer.sign(&[], "JWS-ALG", kid.as_deref()).await;
+
+        assert!(matches!(
+            result,
+            Err(crate::signer::Error::MismatchedKeyInfo)
+        ));
+    }
+}
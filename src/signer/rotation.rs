@@ -0,0 +1,412 @@
+//! Scheduled key rotation, coordinating generation, persistence and
+//! publication.
+
+use std::{
+    sync::{Arc, Mutex, PoisonError},
+    time::Duration,
+};
+
+use futures_timer::Delay;
+use secrecy::{ExposeSecret, SecretBox};
+use snafu::prelude::*;
+use web_time::SystemTime;
+
+use crate::{
+    MaybeSend, MaybeSendSync,
+    clock::{Clock, SystemClock},
+    jwk::{PublicJwk, PublicJwks},
+    secrets::SecretSink,
+    signer::{HasPublicKey, JwsSigner, RotatingSigner},
+    verifier::JwksProvider,
+};
+
+/// A newly generated key: the signer using it, and its private key in the
+/// form persisted through a [`SecretSink`].
+pub struct GeneratedKey<S> {
+    signer: S,
+    private_key: SecretBox<[u8]>,
+}
+
+impl<S> GeneratedKey<S> {
+    /// Creates a generated key. The private key should be in the form the
+    /// secret source that loads it on startup decodes (e.g. PEM text).
+    pub fn new(signer: S, private_key: SecretBox<[u8]>) -> Self {
+        Self {
+            signer,
+            private_key,
+        }
+    }
+
+    /// Returns the signer using the key.
+    pub fn signer(&self) -> &S {
+        &self.signer
+    }
+}
+
+impl<S: std::fmt::Debug> std::fmt::Debug for GeneratedKey<S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GeneratedKey")
+            .field("signer", &self.signer)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Trait for generating new signing keys for a [`RotationManager`].
+///
+/// The crate has no built-in cryptography, so implementations generate the
+/// key with the backend of their choice (a local library, or a KMS) and
+/// return a signer for it.
+pub trait KeyGenerator: MaybeSendSync {
+    /// The signer using generated keys.
+    type Signer: JwsSigner + HasPublicKey;
+    /// The error type returned when generation fails.
+    type Error: std::error::Error + MaybeSendSync + 'static;
+
+    /// Asynchronously generates a new key, which should have a new key ID.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the key can't be generated.
+    fn generate(
+        &self,
+    ) -> impl Future<Output = Result<GeneratedKey<Self::Signer>, Self::Error>> + MaybeSend;
+}
+
+/// Errors returned by [`RotationManager::rotate`].
+#[derive(Debug, Snafu)]
+pub enum RotationError<G, K>
+where
+    G: std::error::Error + MaybeSendSync + 'static,
+    K: std::error::Error + MaybeSendSync + 'static,
+{
+    /// The new key could not be generated.
+    #[snafu(display("Failed to generate key"))]
+    Generate {
+        /// The error from the generator.
+        source: G,
+    },
+    /// The new key could not be persisted. The current key is still in use.
+    #[snafu(display("Failed to persist key"))]
+    Persist {
+        /// The error from the sink.
+        source: K,
+    },
+}
+
+struct Inner<G, K> {
+    generator: G,
+    sink: K,
+    grace_period: Duration,
+    /// Public keys of previous signers, with the time they stop being
+    /// published, or `None` if they never do.
+    retired: Mutex<Vec<(PublicJwk, Option<SystemTime>)>>,
+}
+
+/// Rotates the key of a [`RotatingSigner`] on a schedule.
+///
+/// Each rotation generates a new key with a [`KeyGenerator`], persists it
+/// through a [`SecretSink`] (so a restarted process loads the new key), and
+/// then swaps it into the signer. The previous public key stays in
+/// [`RotationManager::jwks`] for a grace period, so verifiers can still
+/// validate tokens signed before the rotation until they expire. The grace
+/// period should be at least the lifetime of issued tokens plus the time
+/// verifiers cache the JWKS.
+///
+/// The crate doesn't spawn tasks itself: the caller spawns the future returned
+/// by [`RotationManager::run`] on their runtime. Clones share the same
+/// retired keys. Grace periods are measured with the system clock, unless
+/// another [`Clock`] is set with [`RotationManager::with_clock`].
+pub struct RotationManager<G: KeyGenerator, K, C = SystemClock> {
+    signer: RotatingSigner<G::Signer>,
+    inner: Arc<Inner<G, K>>,
+    clock: C,
+}
+
+impl<G: KeyGenerator, K, C: Clone> Clone for RotationManager<G, K, C> {
+    fn clone(&self) -> Self {
+        Self {
+            signer: self.signer.clone(),
+            inner: Arc::clone(&self.inner),
+            clock: self.clock.clone(),
+        }
+    }
+}
+
+impl<G, K, C> std::fmt::Debug for RotationManager<G, K, C>
+where
+    G: KeyGenerator,
+    G::Signer: std::fmt::Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RotationManager")
+            .field("signer", &self.signer)
+            .field("grace_period", &self.inner.grace_period)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<G: KeyGenerator, K: SecretSink> RotationManager<G, K> {
+    /// Creates a manager rotating the given signer, keeping previous public
+    /// keys published for `grace_period`.
+    pub fn new(
+        signer: RotatingSigner<G::Signer>,
+        generator: G,
+        sink: K,
+        grace_period: Duration,
+    ) -> Self {
+        Self {
+            signer,
+            inner: Arc::new(Inner {
+                generator,
+                sink,
+                grace_period,
+                retired: Mutex::new(Vec::new()),
+            }),
+            clock: SystemClock,
+        }
+    }
+}
+
+impl<G: KeyGenerator, K: SecretSink, C: Clock + Clone> RotationManager<G, K, C> {
+    /// Measures grace periods with the given clock.
+    pub fn with_clock<C2: Clock + Clone>(self, clock: C2) -> RotationManager<G, K, C2> {
+        RotationManager {
+            signer: self.signer,
+            inner: self.inner,
+            clock,
+        }
+    }
+
+    /// Returns the rotating signer.
+    #[must_use]
+    pub fn signer(&self) -> &RotatingSigner<G::Signer> {
+        &self.signer
+    }
+
+    /// Generates and persists a new key, and swaps it into the signer,
+    /// returning its public key.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the key can't be generated or persisted. The
+    /// current key is kept.
+    pub async fn rotate(&self) -> Result<PublicJwk, RotationError<G::Error, K::Error>> {
+        let key = self
+            .inner
+            .generator
+            .generate()
+            .await
+            .context(GenerateSnafu)?;
+        self.inner
+            .sink
+            .put_secret(key.private_key.expose_secret())
+            .await
+            .context(PersistSnafu)?;
+
+        let public_key = key.signer.public_key_jwk().clone();
+        let previous = self.signer.rotate(key.signer);
+        // Grace periods too long to add to the current time keep the key
+        // published indefinitely, rather than overflowing.
+        let expires_at = self.clock.now().checked_add(self.inner.grace_period);
+        self.retired()
+            .push((previous.public_key_jwk().clone(), expires_at));
+        Ok(public_key)
+    }
+
+    /// Returns the current public key, followed by previous keys still
+    /// within their grace period.
+    #[must_use]
+    pub fn jwks(&self) -> PublicJwks {
+        let now = self.clock.now();
+        let mut retired = self.retired();
+        retired.retain(|(_, expires_at)| expires_at.is_none_or(|expires_at| expires_at > now));
+
+        let current = self.signer.current().public_key_jwk().clone();
+        let keys = std::iter::once(current)
+            .chain(retired.iter().rev().map(|(key, _)| key.clone()))
+            .collect();
+        PublicJwks { keys }
+    }
+
+    /// Returns a future that rotates the key every `interval`, starting after
+    /// the first interval. It never completes, so drop or abort it to stop
+    /// rotating.
+    ///
+    /// Failed rotations keep the current key, and are retried at the next
+    /// interval. Use [`RotationManager::rotate`] in a custom loop to observe
+    /// errors or back off.
+    pub fn run(&self, interval: Duration) -> impl Future<Output = ()> + MaybeSend + 'static
+    where
+        G: 'static,
+        K: 'static,
+        C: 'static,
+    {
+        let manager = self.clone();
+        async move {
+            loop {
+                Delay::new(interval).await;
+                let _ = manager.rotate().await;
+            }
+        }
+    }
+
+    fn retired(&self) -> std::sync::MutexGuard<'_, Vec<(PublicJwk, Option<SystemTime>)>> {
+        self.inner
+            .retired
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Serves the published keys to in-process verifiers, such as a
+/// [`JwksVerifier`](crate::verifier::JwksVerifier).
+impl<G, K, C> JwksProvider for RotationManager<G, K, C>
+where
+    G: KeyGenerator,
+    K: SecretSink,
+    C: Clock + Clone,
+{
+    type Error = std::convert::Infallible;
+
+    async fn fetch(&self) -> Result<PublicJwks, Self::Error> {
+        Ok(self.jwks())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        borrow::Cow,
+        convert::Infallible,
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+
+    use bytes::Bytes;
+
+    use super::*;
+    use crate::{clock::FixedClock, jwk::OkpPublicKey, secrets::DecodingError};
+
+    #[derive(Debug, Clone)]
+    struct TestSigner(PublicJwk);
+
+    impl TestSigner {
+        fn new(n: usize) -> Self {
+            Self(
+                PublicJwk::builder()
+                    .key(OkpPublicKey::builder().crv("Ed25519").x([0]))
+                    .kid(format!("key-{n}"))
+                    .build(),
+            )
+        }
+    }
+
+    impl JwsSigner for TestSigner {
+        type Error = Infallible;
+
+        fn algorithm(&self) -> Cow<'_, str> {
+            "Ed25519".into()
+        }
+
+        fn jws_algorithm(&self) -> Cow<'_, str> {
+            "EdDSA".into()
+        }
+
+        fn key_id(&self) -> Option<Cow<'_, str>> {
+            self.0.kid().map(Cow::Borrowed)
+        }
+
+        async fn sign_unchecked(&self, _input: &[u8]) -> Result<Bytes, Self::Error> {
+            Ok(Bytes::new())
+        }
+    }
+
+    impl HasPublicKey for TestSigner {
+        fn public_key_jwk(&self) -> &PublicJwk {
+            &self.0
+        }
+    }
+
+    #[derive(Debug, Default)]
+    struct CountingGenerator(AtomicUsize);
+
+    impl KeyGenerator for CountingGenerator {
+        type Signer = TestSigner;
+        type Error = Infallible;
+
+        async fn generate(&self) -> Result<GeneratedKey<TestSigner>, Infallible> {
+            let n = self.0.fetch_add(1, Ordering::Relaxed) + 2;
+            let private_key = SecretBox::from(format!("private-{n}").into_bytes());
+            Ok(GeneratedKey::new(TestSigner::new(n), private_key))
+        }
+    }
+
+    #[derive(Debug, Clone, Default)]
+    struct MemorySink(Arc<Mutex<Vec<Vec<u8>>>>);
+
+    impl SecretSink for MemorySink {
+        type Error = DecodingError;
+
+        async fn put_secret(&self, value: &[u8]) -> Result<(), Self::Error> {
+            self.0.lock().unwrap().push(value.to_vec());
+            Ok(())
+        }
+    }
+
+    fn kids(jwks: &PublicJwks) -> Vec<&str> {
+        jwks.keys.iter().filter_map(PublicJwk::kid).collect()
+    }
+
+    #[tokio::test]
+    async fn test_rotate_persists_swaps_and_publishes_previous_key() {
+        let sink = MemorySink::default();
+        let manager = RotationManager::new(
+            RotatingSigner::new(TestSigner::new(1)),
+            CountingGenerator::default(),
+            sink.clone(),
+            Duration::from_secs(3600),
+        );
+
+        let new_key = manager.rotate().await.unwrap();
+
+        assert_eq!(new_key.kid(), Some("key-2"));
+        assert_eq!(manager.signer().key_id().as_deref(), Some("key-2"));
+        assert_eq!(*sink.0.lock().unwrap(), [b"private-2".to_vec()]);
+        manager.rotate().await.unwrap();
+        assert_eq!(kids(&manager.jwks()), ["key-3", "key-2", "key-1"]);
+    }
+
+    #[tokio::test]
+    async fn test_keys_past_grace_period_are_unpublished() {
+        let clock = FixedClock::new(SystemTime::UNIX_EPOCH);
+        let manager = RotationManager::new(
+            RotatingSigner::new(TestSigner::new(1)),
+            CountingGenerator::default(),
+            MemorySink::default(),
+            Duration::from_secs(3600),
+        )
+        .with_clock(clock.clone());
+
+        manager.rotate().await.unwrap();
+        assert_eq!(kids(&manager.fetch().await.unwrap()), ["key-2", "key-1"]);
+        clock.advance(Duration::from_secs(3600));
+
+        assert_eq!(kids(&manager.fetch().await.unwrap()), ["key-2"]);
+    }
+
+    #[tokio::test]
+    async fn test_unbounded_grace_period_keeps_keys_published() {
+        let clock = FixedClock::new(SystemTime::UNIX_EPOCH);
+        let manager = RotationManager::new(
+            RotatingSigner::new(TestSigner::new(1)),
+            CountingGenerator::default(),
+            MemorySink::default(),
+            Duration::MAX,
+        )
+        .with_clock(clock.clone());
+
+        manager.rotate().await.unwrap();
+        clock.advance(Duration::from_secs(100 * 365 * 24 * 3600));
+
+        assert_eq!(kids(&manager.fetch().await.unwrap()), ["key-2", "key-1"]);
+    }
+}
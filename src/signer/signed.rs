@@ -0,0 +1,98 @@
+use base64::{Engine, prelude::BASE64_URL_SAFE_NO_PAD};
+use bon::Builder;
+use bytes::Bytes;
+
+use crate::{jws, signer::Extensions};
+
+/// A signature together with the metadata of the key that produced it.
+///
+/// Equality compares the signature and its metadata, but not the
+/// [`extensions`](SignedBytes::extensions).
+#[derive(Debug, Clone, Builder)]
+#[builder(builder_type(
+    doc {
+        /// Builder for creating a [`SignedBytes`] value.
+    }
+))]
+pub struct SignedBytes {
+    #[builder(into)]
+    signature: Bytes,
+    #[builder(into)]
+    jws_algorithm: String,
+    #[builder(into)]
+    key_id: Option<String>,
+    #[builder(into)]
+    protected_header: Option<String>,
+    #[builder(default)]
+    extensions: Extensions,
+}
+
+impl PartialEq for SignedBytes {
+    fn eq(&self, other: &Self) -> bool {
+        self.signature == other.signature
+            && self.jws_algorithm == other.jws_algorithm
+            && self.key_id == other.key_id
+            && self.protected_header == other.protected_header
+    }
+}
+
+impl Eq for SignedBytes {}
+
+impl SignedBytes {
+    /// Returns the signature bytes.
+    #[must_use]
+    pub fn signature(&self) -> &Bytes {
+        &self.signature
+    }
+
+    /// Returns the JWS algorithm identifier of the key that produced the signature.
+    #[must_use]
+    pub fn jws_algorithm(&self) -> &str {
+        &self.jws_algorithm
+    }
+
+    /// Returns the key ID of the key that produced the signature, if any.
+    #[must_use]
+    pub fn key_id(&self) -> Option<&str> {
+        self.key_id.as_deref()
+    }
+
+    /// Returns the base64url-encoded JWS protected header that was signed, if any.
+    ///
+    /// This is exactly the header segment that was part of the signing input,
+    /// so it should be used as-is rather than re-serializing the header.
+    #[must_use]
+    pub fn protected_header(&self) -> Option<&str> {
+        self.protected_header.as_deref()
+    }
+
+    /// Returns provenance information attached by the signer (e.g. an HSM
+    /// attestation, or the KMS key that was used).
+    #[must_use]
+    pub fn extensions(&self) -> &Extensions {
+        &self.extensions
+    }
+
+    /// Returns a mutable reference to the attached provenance information.
+    pub fn extensions_mut(&mut self) -> &mut Extensions {
+        &mut self.extensions
+    }
+
+    /// Assembles the JWS compact serialization for the given payload.
+    ///
+    /// Returns `None` if the signature was not produced over a protected header.
+    #[must_use]
+    pub fn to_compact(&self, payload: &[u8]) -> Option<String> {
+        let header = self.protected_header.as_deref()?;
+        let mut compact = jws::signing_input(header, payload);
+        compact.push('.');
+        BASE64_URL_SAFE_NO_PAD.encode_string(&self.signature, &mut compact);
+        Some(compact)
+    }
+
+    /// Consumes this value, returning the signature bytes.
+    #[must_use]
+    pub fn into_signature(self) -> Bytes {
+        self.signature
+    }
+}
@@ -0,0 +1,324 @@
+//! Digest-then-sign streaming signer trait.
+
+use bytes::Bytes;
+use sha2::{Digest, Sha256, Sha384, Sha512};
+use snafu::prelude::*;
+
+use crate::{
+    MaybeSend,
+    jws::HeaderError,
+    signer::{
+        Error, JwsSigner,
+        error::{InvalidHeaderSnafu, MismatchedKeyInfoSnafu, UnderlyingSnafu},
+    },
+};
+
+/// Hash algorithm used to digest the signing input before it is signed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DigestAlgorithm {
+    /// SHA-256 (used by `RS256`, `PS256` and `ES256`).
+    Sha256,
+    /// SHA-384 (used by `RS384`, `PS384` and `ES384`).
+    Sha384,
+    /// SHA-512 (used by `RS512`, `PS512` and `ES512`).
+    Sha512,
+}
+
+impl DigestAlgorithm {
+    /// Returns the digest algorithm for a JWS algorithm that signs a prehashed input.
+    ///
+    /// Returns `None` for algorithms that can't sign a digest, such as `EdDSA`
+    /// and the HMAC algorithms.
+    #[must_use]
+    pub fn for_jws_algorithm(jws_algorithm: &str) -> Option<Self> {
+        match jws_algorithm {
+            "RS256" | "PS256" | "ES256" | "ES256K" => Some(Self::Sha256),
+            "RS384" | "PS384" | "ES384" => Some(Self::Sha384),
+            "RS512" | "PS512" | "ES512" => Some(Self::Sha512),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Hasher {
+    Sha256(Sha256),
+    Sha384(Sha384),
+    Sha512(Sha512),
+}
+
+impl Hasher {
+    fn new(algorithm: DigestAlgorithm) -> Self {
+        match algorithm {
+            DigestAlgorithm::Sha256 => Self::Sha256(Sha256::new()),
+            DigestAlgorithm::Sha384 => Self::Sha384(Sha384::new()),
+            DigestAlgorithm::Sha512 => Self::Sha512(Sha512::new()),
+        }
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        match self {
+            Self::Sha256(h) => h.update(data),
+            Self::Sha384(h) => h.update(data),
+            Self::Sha512(h) => h.update(data),
+        }
+    }
+
+    fn finalize(self) -> Vec<u8> {
+        match self {
+            Self::Sha256(h) => h.finalize().to_vec(),
+            Self::Sha384(h) => h.finalize().to_vec(),
+            Self::Sha512(h) => h.finalize().to_vec(),
+        }
+    }
+}
+
+/// Trait for signers that can sign a precomputed digest of the signing input.
+///
+/// This allows large payloads (e.g. detached JWS over multi-megabyte documents)
+/// to be fed to the signer in chunks, rather than buffered contiguously.
+pub trait JwsStreamingSigner: JwsSigner {
+    /// Returns the hash algorithm this signer expects the signing input to be digested with.
+    fn digest_algorithm(&self) -> DigestAlgorithm;
+
+    /// Asynchronously signs a digest of the signing input.
+    ///
+    /// The signature must be identical to the one [`JwsSigner::sign_unchecked`]
+    /// would produce over the full input. As with `sign_unchecked`, this does
+    /// not verify the algorithm and key ID.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the signing operation fails.
+    fn sign_digest_unchecked(
+        &self,
+        digest: &[u8],
+    ) -> impl Future<Output = Result<Bytes, Self::Error>> + MaybeSend;
+
+    /// Starts a streaming signing operation.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the key metadata is mismatched, or the signer's
+    /// digest algorithm isn't the one the JWS algorithm signs.
+    fn start_signing(
+        &self,
+        jws_algorithm: &str,
+        key_id: Option<&str>,
+    ) -> Result<StreamingSign<'_, Self>, Error<Self::Error>> {
+        ensure!(
+            jws_algorithm == self.jws_algorithm().as_ref() && key_id == self.key_id().as_deref(),
+            MismatchedKeyInfoSnafu
+        );
+        let digest_algorithm = self.digest_algorithm();
+        DigestAlgorithm::for_jws_algorithm(jws_algorithm)
+            .filter(|expected| *expected == digest_algorithm)
+            .ok_or(HeaderError::UnsupportedAlgorithm {
+                algorithm: jws_algorithm.to_owned(),
+            })
+            .context(InvalidHeaderSnafu)?;
+        Ok(StreamingSign {
+            signer: self,
+            jws_algorithm: jws_algorithm.to_owned(),
+            key_id: key_id.map(str::to_owned),
+            digest_algorithm,
+            hasher: Hasher::new(digest_algorithm),
+        })
+    }
+}
+
+/// An in-progress streaming signing operation.
+///
+/// Created by [`JwsStreamingSigner::start_signing`].
+#[derive(Debug)]
+pub struct StreamingSign<'a, S: ?Sized> {
+    signer: &'a S,
+    jws_algorithm: String,
+    key_id: Option<String>,
+    digest_algorithm: DigestAlgorithm,
+    hasher: Hasher,
+}
+
+impl<S: JwsStreamingSigner> StreamingSign<'_, S> {
+    /// Feeds the next chunk of the signing input.
+    pub fn update(&mut self, chunk: &[u8]) {
+        self.hasher.update(chunk);
+    }
+
+    /// Signs the digest of all chunks fed so far.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the key metadata changed since signing started
+    /// (e.g. the key was rotated), or the signing operation fails.
+    pub async fn finish(self) -> Result<Bytes, Error<S::Error>> {
+        ensure!(
+            self.jws_algorithm == self.signer.jws_algorithm()
+                && self.key_id.as_deref() == self.signer.key_id().as_deref()
+                && self.digest_algorithm == self.signer.digest_algorithm(),
+            MismatchedKeyInfoSnafu
+        );
+        let digest = self.hasher.finalize();
+        self.signer
+            .sign_digest_unchecked(&digest)
+            .await
+            .context(UnderlyingSnafu)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        borrow::Cow,
+        convert::Infallible,
+        sync::{
+            Arc,
+            atomic::{AtomicBool, Ordering},
+        },
+    };
+
+    use bytes::Bytes;
+    use sha2::{Digest, Sha256};
+
+    use super::*;
+
+    #[derive(Debug, Clone)]
+    struct DigestEchoSigner;
+
+    impl JwsSigner for DigestEchoSigner {
+        type Error = Infallible;
+
+        fn algorithm(&self) -> Cow<'_, str> {
+            "ALG".into()
+        }
+
+        fn jws_algorithm(&self) -> Cow<'_, str> {
+            "ES256".into()
+        }
+
+        fn key_id(&self) -> Option<Cow<'_, str>> {
+            None
+        }
+
+        async fn sign_unchecked(&self, input: &[u8]) -> Result<Bytes, Self::Error> {
+            Ok(Bytes::from(Sha256::digest(input).to_vec()))
+        }
+    }
+
+    impl JwsStreamingSigner for DigestEchoSigner {
+        fn digest_algorithm(&self) -> DigestAlgorithm {
+            DigestAlgorithm::Sha256
+        }
+
+        async fn sign_digest_unchecked(&self, digest: &[u8]) -> Result<Bytes, Self::Error> {
+            Ok(Bytes::copy_from_slice(digest))
+        }
+    }
+
+    /// Signs with `ES256` until switched to `ES384`, as if rotated.
+    #[derive(Debug, Clone)]
+    struct SwitchingSigner {
+        digest_algorithm: DigestAlgorithm,
+        switched: Arc<AtomicBool>,
+    }
+
+    impl SwitchingSigner {
+        fn new(digest_algorithm: DigestAlgorithm) -> Self {
+            Self {
+                digest_algorithm,
+                switched: Arc::default(),
+            }
+        }
+    }
+
+    impl JwsSigner for SwitchingSigner {
+        type Error = Infallible;
+
+        fn algorithm(&self) -> Cow<'_, str> {
+            "ALG".into()
+        }
+
+        fn jws_algorithm(&self) -> Cow<'_, str> {
+            if self.switched.load(Ordering::Acquire) {
+                "ES384".into()
+            } else {
+                "ES256".into()
+            }
+        }
+
+        fn key_id(&self) -> Option<Cow<'_, str>> {
+            None
+        }
+
+        async fn sign_unchecked(&self, _input: &[u8]) -> Result<Bytes, Self::Error> {
+            Ok(Bytes::new())
+        }
+    }
+
+    impl JwsStreamingSigner for SwitchingSigner {
+        fn digest_algorithm(&self) -> DigestAlgorithm {
+            self.digest_algorithm
+        }
+
+        async fn sign_digest_unchecked(&self, _digest: &[u8]) -> Result<Bytes, Self::Error> {
+            Ok(Bytes::new())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_streaming_matches_contiguous_signature() {
+        let mut signing = DigestEchoSigner.start_signing("ES256", None).unwrap();
+        signing.update(b"header.");
+        signing.update(b"payload");
+        let streamed = signing.finish().await.unwrap();
+
+        let contiguous = DigestEchoSigner
+            .sign(b"header.payload", "ES256", None)
+            .await
+            .unwrap();
+
+        assert_eq!(streamed, contiguous);
+    }
+
+    #[test]
+    fn test_start_signing_mismatch_fails() {
+        let result = DigestEchoSigner.start_signing("ES384", None);
+
+        assert!(matches!(result, Err(Error::MismatchedKeyInfo)));
+    }
+
+    #[tokio::test]
+    async fn test_finish_fails_if_key_changed() {
+        let signer = SwitchingSigner::new(DigestAlgorithm::Sha256);
+        let mut signing = signer.start_signing("ES256", None).unwrap();
+        signing.update(b"header.payload");
+
+        signer.switched.store(true, Ordering::Release);
+        let result = signing.finish().await;
+
+        assert!(matches!(result, Err(Error::MismatchedKeyInfo)));
+    }
+
+    #[test]
+    fn test_start_signing_rejects_mismatched_digest_algorithm() {
+        let signer = SwitchingSigner::new(DigestAlgorithm::Sha384);
+
+        let result = signer.start_signing("ES256", None);
+
+        assert!(matches!(
+            result,
+            Err(Error::InvalidHeader {
+                source: HeaderError::UnsupportedAlgorithm { algorithm }
+            }) if algorithm == "ES256"
+        ));
+    }
+
+    #[test]
+    fn test_digest_algorithm_for_eddsa_is_none() {
+        assert_eq!(DigestAlgorithm::for_jws_algorithm("EdDSA"), None);
+        assert_eq!(
+            DigestAlgorithm::for_jws_algorithm("PS384"),
+            Some(DigestAlgorithm::Sha384)
+        );
+    }
+}
@@ -0,0 +1,220 @@
+//! Web Crypto (`SubtleCrypto`) signer for browser WASM.
+
+use std::borrow::Cow;
+
+use bytes::Bytes;
+use js_sys::{Object, Reflect, Uint8Array};
+use snafu::prelude::*;
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{Crypto, CryptoKey, SubtleCrypto};
+
+use crate::signer::JwsSigner;
+
+/// Errors returned by [`SubtleCryptoSigner`].
+#[derive(Debug, Snafu)]
+pub enum SubtleCryptoError {
+    /// The `crypto.subtle` API is not available in the current global scope.
+    #[snafu(display("SubtleCrypto is not available"))]
+    Unavailable,
+    /// The `CryptoKey` can't be used to sign with the requested algorithm.
+    #[snafu(display("CryptoKey can't be used for {jws_algorithm}: {reason}"))]
+    InvalidKey {
+        /// The requested JWS algorithm.
+        jws_algorithm: &'static str,
+        /// Why the key was rejected.
+        reason: &'static str,
+    },
+    /// The Web Crypto operation failed.
+    #[snafu(display("SubtleCrypto operation failed: {message}"))]
+    Operation {
+        /// The stringified JavaScript error.
+        message: String,
+    },
+}
+
+impl SubtleCryptoError {
+    fn from_js(value: &JsValue) -> Self {
+        let message = value
+            .dyn_ref::<js_sys::Error>()
+            .map_or_else(|| format!("{value:?}"), |e| String::from(e.message()));
+        Self::Operation { message }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SubtleAlgorithm {
+    Es256,
+    Rs256,
+}
+
+impl SubtleAlgorithm {
+    fn jws_algorithm(self) -> &'static str {
+        match self {
+            Self::Es256 => "ES256",
+            Self::Rs256 => "RS256",
+        }
+    }
+
+    /// Returns `true` if the key's `algorithm` dictionary matches.
+    fn matches(self, key_algorithm: &JsValue) -> bool {
+        let name = get_string(key_algorithm, "name");
+        match self {
+            Self::Es256 => {
+                name.as_deref() == Some("ECDSA")
+                    && get_string(key_algorithm, "namedCurve").as_deref() == Some("P-256")
+            }
+            Self::Rs256 => {
+                let hash = Reflect::get(key_algorithm, &"hash".into()).unwrap_or_default();
+                name.as_deref() == Some("RSASSA-PKCS1-v1_5")
+                    && get_string(&hash, "name").as_deref() == Some("SHA-256")
+            }
+        }
+    }
+}
+
+/// A signer backed by a Web Crypto `CryptoKey`.
+///
+/// The key should be created (or imported) as non-extractable with the `sign`
+/// usage, so the private key material never becomes accessible to JavaScript.
+/// `SubtleCrypto` produces ECDSA signatures in the raw `r || s` form, which is
+/// what JWS expects, so no conversion is needed.
+#[derive(Debug, Clone)]
+pub struct SubtleCryptoSigner {
+    key: CryptoKey,
+    algorithm: SubtleAlgorithm,
+    key_id: Option<String>,
+}
+
+impl SubtleCryptoSigner {
+    /// Creates an `ES256` signer from an `ECDSA` P-256 private key.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the key isn't an `ECDSA` P-256 private key with the
+    /// `sign` usage.
+    pub fn es256(key: CryptoKey, key_id: Option<String>) -> Result<Self, SubtleCryptoError> {
+        Self::new(key, SubtleAlgorithm::Es256, key_id)
+    }
+
+    /// Creates an `RS256` signer from an `RSASSA-PKCS1-v1_5` SHA-256 private key.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the key isn't an `RSASSA-PKCS1-v1_5` SHA-256 private
+    /// key with the `sign` usage.
+    pub fn rs256(key: CryptoKey, key_id: Option<String>) -> Result<Self, SubtleCryptoError> {
+        Self::new(key, SubtleAlgorithm::Rs256, key_id)
+    }
+
+    fn new(
+        key: CryptoKey,
+        algorithm: SubtleAlgorithm,
+        key_id: Option<String>,
+    ) -> Result<Self, SubtleCryptoError> {
+        let jws_algorithm = algorithm.jws_algorithm();
+        let key_algorithm = key
+            .algorithm()
+            .map_err(|e| SubtleCryptoError::from_js(&e))?;
+        ensure!(
+            algorithm.matches(&key_algorithm),
+            InvalidKeySnafu {
+                jws_algorithm,
+                reason: "key algorithm doesn't match",
+            }
+        );
+        ensure!(
+            key.type_() == "private",
+            InvalidKeySnafu {
+                jws_algorithm,
+                reason: "not a private key",
+            }
+        );
+        ensure!(
+            key.usages().includes(&"sign".into(), 0),
+            InvalidKeySnafu {
+                jws_algorithm,
+                reason: "key usages don't include sign",
+            }
+        );
+        Ok(Self {
+            key,
+            algorithm,
+            key_id,
+        })
+    }
+
+    /// Returns the wrapped `CryptoKey`.
+    #[must_use]
+    pub fn crypto_key(&self) -> &CryptoKey {
+        &self.key
+    }
+
+    fn sign_params(&self) -> Result<Object, SubtleCryptoError> {
+        let params = Object::new();
+        match self.algorithm {
+            SubtleAlgorithm::Es256 => {
+                set(&params, "name", "ECDSA")?;
+                set(&params, "hash", "SHA-256")?;
+            }
+            SubtleAlgorithm::Rs256 => {
+                set(&params, "name", "RSASSA-PKCS1-v1_5")?;
+            }
+        }
+        Ok(params)
+    }
+}
+
+fn set(target: &Object, key: &str, value: &str) -> Result<(), SubtleCryptoError> {
+    Reflect::set(target, &key.into(), &value.into())
+        .map(|_| ())
+        .map_err(|e| SubtleCryptoError::from_js(&e))
+}
+
+fn get_string(target: &JsValue, key: &str) -> Option<String> {
+    Reflect::get(target, &key.into()).ok()?.as_string()
+}
+
+fn subtle_crypto() -> Result<SubtleCrypto, SubtleCryptoError> {
+    // Works in both window and worker scopes, unlike `web_sys::window()`.
+    let crypto = Reflect::get(&js_sys::global(), &"crypto".into())
+        .ok()
+        .and_then(|c| c.dyn_into::<Crypto>().ok())
+        .context(UnavailableSnafu)?;
+    Ok(crypto.subtle())
+}
+
+impl JwsSigner for SubtleCryptoSigner {
+    type Error = SubtleCryptoError;
+
+    fn algorithm(&self) -> Cow<'_, str> {
+        match self.algorithm {
+            SubtleAlgorithm::Es256 => "ECDSA_P256_SHA256".into(),
+            SubtleAlgorithm::Rs256 => "RSASSA_PKCS1_V1_5_SHA256".into(),
+        }
+    }
+
+    fn jws_algorithm(&self) -> Cow<'_, str> {
+        self.algorithm.jws_algorithm().into()
+    }
+
+    fn key_id(&self) -> Option<Cow<'_, str>> {
+        self.key_id.as_deref().map(Cow::Borrowed)
+    }
+
+    async fn health_check(&self) -> Result<(), Self::Error> {
+        subtle_crypto().map(|_| ())
+    }
+
+    async fn sign_unchecked(&self, input: &[u8]) -> Result<Bytes, Self::Error> {
+        let subtle = subtle_crypto()?;
+        let params = self.sign_params()?;
+        let promise = subtle
+            .sign_with_object_and_u8_array(&params, &self.key, input)
+            .map_err(|e| SubtleCryptoError::from_js(&e))?;
+        let buffer = JsFuture::from(promise)
+            .await
+            .map_err(|e| SubtleCryptoError::from_js(&e))?;
+        Ok(Uint8Array::new(&buffer).to_vec().into())
+    }
+}
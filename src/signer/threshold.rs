@@ -0,0 +1,338 @@
+//! Threshold (multi-party) signing.
+
+use std::{borrow::Cow, num::NonZeroUsize, sync::Arc};
+
+use bon::bon;
+use bytes::Bytes;
+use snafu::prelude::*;
+
+use crate::{MaybeSend, MaybeSendSync, signer::JwsSigner};
+
+/// A participant identifier in a threshold signing group.
+pub type ParticipantId = u16;
+
+/// Trait for threshold signature schemes (e.g. FROST), which combine
+/// signature shares into the final signature.
+///
+/// Signing takes two rounds: every selected participant first publishes a
+/// commitment, then produces a signature share over the input and the full
+/// set of commitments.
+pub trait ThresholdProtocol: MaybeSendSync + Clone {
+    /// A participant's round one commitment.
+    type Commitment: MaybeSendSync;
+
+    /// A participant's round two signature share.
+    type Share: MaybeSendSync;
+
+    /// The error type returned when shares can't be combined.
+    type Error: std::error::Error + MaybeSendSync + 'static;
+
+    /// Returns a descriptive name for the algorithm of the scheme.
+    fn algorithm(&self) -> Cow<'_, str>;
+
+    /// Returns the JWS algorithm identifier of the combined signature.
+    fn jws_algorithm(&self) -> Cow<'_, str>;
+
+    /// Combines the signature shares into the final JWS signature.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the shares are invalid or inconsistent.
+    fn aggregate(
+        &self,
+        input: &[u8],
+        commitments: &[(ParticipantId, Self::Commitment)],
+        shares: &[(ParticipantId, Self::Share)],
+    ) -> Result<Bytes, Self::Error>;
+}
+
+/// Trait for a holder of a key share, participating in threshold signing.
+///
+/// Implementations are typically clients for a remote party.
+pub trait ShareHolder<P: ThresholdProtocol>: MaybeSendSync {
+    /// The error type returned by this holder's operations.
+    type Error: std::error::Error + MaybeSendSync + 'static;
+
+    /// Returns this holder's identifier within the group.
+    fn participant_id(&self) -> ParticipantId;
+
+    /// Asynchronously produces a round one commitment.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the holder is unavailable.
+    fn commit(&self) -> impl Future<Output = Result<P::Commitment, Self::Error>> + MaybeSend;
+
+    /// Asynchronously produces a round two signature share.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the holder is unavailable, or refuses to sign.
+    fn sign_share(
+        &self,
+        input: &[u8],
+        commitments: &[(ParticipantId, P::Commitment)],
+    ) -> impl Future<Output = Result<P::Share, Self::Error>> + MaybeSend;
+}
+
+/// Errors returned by [`ThresholdSigner`].
+#[derive(Debug, Snafu)]
+pub enum ThresholdError<H, P>
+where
+    H: std::error::Error + MaybeSendSync + 'static,
+    P: std::error::Error + MaybeSendSync + 'static,
+{
+    /// Fewer participants than the threshold produced a commitment.
+    #[snafu(display("Only {committed} of the {threshold} required participants are available"))]
+    InsufficientParticipants {
+        /// The number of participants that produced a commitment.
+        committed: usize,
+        /// The number of participants required.
+        threshold: usize,
+    },
+    /// A selected participant failed to produce a signature share.
+    #[snafu(display("Participant {participant} failed to produce a signature share"))]
+    Share {
+        /// The failing participant.
+        participant: ParticipantId,
+        /// The error from the share holder.
+        source: H,
+    },
+    /// The signature shares could not be combined.
+    #[snafu(display("Failed to aggregate signature shares"))]
+    Aggregate {
+        /// The error from the protocol.
+        source: P,
+    },
+}
+
+/// A signer whose signatures are assembled from the shares of a threshold of
+/// participants.
+///
+/// Holders are asked for commitments in order, until the threshold is met;
+/// holders that fail to commit are skipped. The committed participants must
+/// then all produce a signature share.
+#[derive(Debug)]
+pub struct ThresholdSigner<P, H> {
+    protocol: P,
+    holders: Arc<[H]>,
+    threshold: NonZeroUsize,
+    key_id: Option<String>,
+}
+
+impl<P: Clone, H> Clone for ThresholdSigner<P, H> {
+    fn clone(&self) -> Self {
+        Self {
+            protocol: self.protocol.clone(),
+            holders: Arc::clone(&self.holders),
+            threshold: self.threshold,
+            key_id: self.key_id.clone(),
+        }
+    }
+}
+
+#[bon]
+impl<P: ThresholdProtocol, H: ShareHolder<P>> ThresholdSigner<P, H> {
+    /// Creates a builder for a signer using the given protocol.
+    #[builder]
+    pub fn new(
+        #[builder(start_fn)] protocol: P,
+        /// The share holders, in order of preference.
+        holders: Vec<H>,
+        /// The number of participants required to sign.
+        threshold: NonZeroUsize,
+        /// The key ID of the group public key.
+        #[builder(into)]
+        key_id: Option<String>,
+    ) -> Self {
+        Self {
+            protocol,
+            holders: holders.into(),
+            threshold,
+            key_id,
+        }
+    }
+
+    /// Returns the threshold scheme.
+    pub fn protocol(&self) -> &P {
+        &self.protocol
+    }
+
+    /// Returns the share holders.
+    pub fn holders(&self) -> &[H] {
+        &self.holders
+    }
+
+    /// Returns the number of participants required to sign.
+    pub fn threshold(&self) -> NonZeroUsize {
+        self.threshold
+    }
+}
+
+impl<P: ThresholdProtocol, H: ShareHolder<P>> JwsSigner for ThresholdSigner<P, H> {
+    type Error = ThresholdError<H::Error, P::Error>;
+
+    fn algorithm(&self) -> Cow<'_, str> {
+        self.protocol.algorithm()
+    }
+
+    fn jws_algorithm(&self) -> Cow<'_, str> {
+        self.protocol.jws_algorithm()
+    }
+
+    fn key_id(&self) -> Option<Cow<'_, str>> {
+        self.key_id.as_deref().map(Cow::Borrowed)
+    }
+
+    async fn health_check(&self) -> Result<(), Self::Error> {
+        let threshold = self.threshold.get();
+        ensure!(
+            self.holders.len() >= threshold,
+            InsufficientParticipantsSnafu {
+                committed: self.holders.len(),
+                threshold,
+            }
+        );
+        Ok(())
+    }
+
+    async fn sign_unchecked(&self, input: &[u8]) -> Result<Bytes, Self::Error> {
+        let threshold = self.threshold.get();
+        let mut signers = Vec::with_capacity(threshold);
+        let mut commitments = Vec::with_capacity(threshold);
+        for holder in self.holders.iter() {
+            if commitments.len() == threshold {
+                break;
+            }
+            if let Ok(commitment) = holder.commit().await {
+                signers.push(holder);
+                commitments.push((holder.participant_id(), commitment));
+            }
+        }
+        ensure!(
+            commitments.len() == threshold,
+            InsufficientParticipantsSnafu {
+                committed: commitments.len(),
+                threshold,
+            }
+        );
+
+        let mut shares = Vec::with_capacity(threshold);
+        for holder in signers {
+            let participant = holder.participant_id();
+            let share = holder
+                .sign_share(input, &commitments)
+                .await
+                .context(ShareSnafu { participant })?;
+            shares.push((participant, share));
+        }
+
+        self.protocol
+            .aggregate(input, &commitments, &shares)
+            .context(AggregateSnafu)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{borrow::Cow, convert::Infallible};
+
+    use super::*;
+
+    #[derive(Debug, Snafu)]
+    #[snafu(display("Holder offline"))]
+    struct Offline;
+
+    /// Toy scheme: the signature is the XOR of every share.
+    #[derive(Debug, Clone)]
+    struct XorProtocol;
+
+    impl ThresholdProtocol for XorProtocol {
+        type Commitment = ();
+        type Share = u8;
+        type Error = Infallible;
+
+        fn algorithm(&self) -> Cow<'_, str> {
+            "XOR".into()
+        }
+
+        fn jws_algorithm(&self) -> Cow<'_, str> {
+            "JWS-XOR".into()
+        }
+
+        fn aggregate(
+            &self,
+            _input: &[u8],
+            _commitments: &[(ParticipantId, ())],
+            shares: &[(ParticipantId, u8)],
+        ) -> Result<Bytes, Self::Error> {
+            let combined = shares.iter().fold(0, |acc, (_, share)| acc ^ share);
+            Ok(Bytes::from(vec![combined]))
+        }
+    }
+
+    #[derive(Debug)]
+    struct Holder {
+        id: ParticipantId,
+        share: u8,
+        online: bool,
+    }
+
+    impl ShareHolder<XorProtocol> for Holder {
+        type Error = Offline;
+
+        fn participant_id(&self) -> ParticipantId {
+            self.id
+        }
+
+        async fn commit(&self) -> Result<(), Self::Error> {
+            if self.online { Ok(()) } else { Err(Offline) }
+        }
+
+        async fn sign_share(
+            &self,
+            _input: &[u8],
+            commitments: &[(ParticipantId, ())],
+        ) -> Result<u8, Self::Error> {
+            assert!(commitments.iter().any(|(id, ())| *id == self.id));
+            Ok(self.share)
+        }
+    }
+
+    fn signer(online: [bool; 3]) -> ThresholdSigner<XorProtocol, Holder> {
+        let holders = [0b001, 0b010, 0b100]
+            .into_iter()
+            .zip(online)
+            .zip(1..)
+            .map(|((share, online), id)| Holder { id, share, online })
+            .collect();
+        ThresholdSigner::builder(XorProtocol)
+            .holders(holders)
+            .threshold(NonZeroUsize::new(2).unwrap())
+            .key_id("group")
+            .build()
+    }
+
+    #[tokio::test]
+    async fn test_signature_uses_first_available_participants() {
+        let signature = signer([true, false, true])
+            .sign(b"input", "JWS-XOR", Some("group"))
+            .await
+            .unwrap();
+
+        assert_eq!(signature, [0b101].as_slice());
+    }
+
+    #[tokio::test]
+    async fn test_too_few_participants_fails() {
+        let result = signer([false, false, true]).sign_unchecked(b"input").await;
+
+        assert!(matches!(
+            result,
+            Err(ThresholdError::InsufficientParticipants {
+                committed: 1,
+                threshold: 2
+            })
+        ));
+    }
+}
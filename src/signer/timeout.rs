@@ -0,0 +1,181 @@
+//! Signer wrapper imposing a deadline on signing operations.
+
+use std::{borrow::Cow, time::Duration};
+
+use bytes::Bytes;
+
+use crate::{
+    TimeoutError,
+    jws::Header,
+    signer::{Error, JwsSigner, SignedBytes},
+    timeout::with_timeout,
+};
+
+/// A signer that fails signing operations that don't complete within a deadline.
+///
+/// Remote signers (e.g. a KMS) can otherwise hang indefinitely on network
+/// issues. On expiry, the in-flight signing future is dropped and
+/// [`TimeoutError::Elapsed`] is returned.
+#[derive(Debug, Clone)]
+pub struct TimeoutSigner<S> {
+    inner: S,
+    timeout: Duration,
+}
+
+impl<S: JwsSigner> TimeoutSigner<S> {
+    /// Wraps a signer with the given timeout per signing operation.
+    pub fn new(inner: S, timeout: Duration) -> Self {
+        Self { inner, timeout }
+    }
+
+    /// Returns the wrapped signer.
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    fn elapsed(&self) -> TimeoutError<S::Error> {
+        TimeoutError::Elapsed {
+            duration: self.timeout,
+        }
+    }
+}
+
+impl<S: JwsSigner> JwsSigner for TimeoutSigner<S> {
+    type Error = TimeoutError<S::Error>;
+
+    fn algorithm(&self) -> Cow<'_, str> {
+        self.inner.algorithm()
+    }
+
+    fn jws_algorithm(&self) -> Cow<'_, str> {
+        self.inner.jws_algorithm()
+    }
+
+    fn key_id(&self) -> Option<Cow<'_, str>> {
+        self.inner.key_id()
+    }
+
+    fn critical_headers(&self) -> Cow<'_, [String]> {
+        self.inner.critical_headers()
+    }
+
+    fn supported_algorithms(&self) -> Vec<Cow<'_, str>> {
+        self.inner.supported_algorithms()
+    }
+
+    fn allows_precomputed_header(&self) -> bool {
+        self.inner.allows_precomputed_header()
+    }
+
+    async fn health_check(&self) -> Result<(), Self::Error> {
+        with_timeout(self.timeout, self.inner.health_check())
+            .await
+            .ok_or_else(|| self.elapsed())?
+            .map_err(|source| TimeoutError::Inner { source })
+    }
+
+    async fn sign_unchecked(&self, input: &[u8]) -> Result<Bytes, Self::Error> {
+        with_timeout(self.timeout, self.inner.sign_unchecked(input))
+            .await
+            .ok_or_else(|| self.elapsed())?
+            .map_err(|source| TimeoutError::Inner { source })
+    }
+
+    async fn sign(
+        &self,
+        input: &[u8],
+        jws_algorithm: &str,
+        key_id: Option<&str>,
+    ) -> Result<Bytes, Error<Self::Error>> {
+        match with_timeout(self.timeout, self.inner.sign(input, jws_algorithm, key_id)).await {
+            Some(result) => {
+                result.map_err(|e| e.map_underlying(|source| TimeoutError::Inner { source }))
+            }
+            None => Err(Error::UnderlyingError {
+                source: self.elapsed(),
+            }),
+        }
+    }
+
+    async fn sign_with_metadata(&self, input: &[u8]) -> Result<SignedBytes, Self::Error> {
+        with_timeout(self.timeout, self.inner.sign_with_metadata(input))
+            .await
+            .ok_or_else(|| self.elapsed())?
+            .map_err(|source| TimeoutError::Inner { source })
+    }
+
+    async fn sign_with_header(
+        &self,
+        header: &Header,
+        payload: &[u8],
+    ) -> Result<SignedBytes, Error<Self::Error>> {
+        match with_timeout(self.timeout, self.inner.sign_with_header(header, payload)).await {
+            Some(result) => {
+                result.map_err(|e| e.map_underlying(|source| TimeoutError::Inner { source }))
+            }
+            None => Err(Error::UnderlyingError {
+                source: self.elapsed(),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{borrow::Cow, convert::Infallible, time::Duration};
+
+    use bytes::Bytes;
+
+    use super::*;
+
+    #[derive(Debug, Clone)]
+    struct MockSigner {
+        hang: bool,
+    }
+
+    impl JwsSigner for MockSigner {
+        type Error = Infallible;
+
+        fn algorithm(&self) -> Cow<'_, str> {
+            "ALG".into()
+        }
+
+        fn jws_algorithm(&self) -> Cow<'_, str> {
+            "JWS-ALG".into()
+        }
+
+        fn key_id(&self) -> Option<Cow<'_, str>> {
+            None
+        }
+
+        async fn sign_unchecked(&self, _input: &[u8]) -> Result<Bytes, Self::Error> {
+            if self.hang {
+                std::future::pending::<()>().await;
+            }
+            Ok(Bytes::from_static(b"signature"))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_completes_within_timeout() {
+        let signer = TimeoutSigner::new(MockSigner { hang: false }, Duration::from_secs(5));
+
+        let signature = signer.sign(b"input", "JWS-ALG", None).await.unwrap();
+
+        assert_eq!(signature, "signature");
+    }
+
+    #[tokio::test]
+    async fn test_hanging_signer_times_out() {
+        let signer = TimeoutSigner::new(MockSigner { hang: true }, Duration::from_millis(10));
+
+        let result = signer.sign(b"input", "JWS-ALG", None).await;
+
+        assert!(matches!(
+            result,
+            Err(Error::UnderlyingError {
+                source: TimeoutError::Elapsed { .. }
+            })
+        ));
+    }
+}
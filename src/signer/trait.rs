@@ -1,14 +1,19 @@
 //! Asynchronous cryptographic signing traits.
 
-use std::borrow::Cow;
+use std::{borrow::Cow, sync::Arc};
 
 use bytes::Bytes;
+use serde::Serialize;
 use snafu::prelude::*;
 
 use crate::{
-    MaybeSend, MaybeSendSync,
+    MaybeSend, MaybeSendSync, jwa,
     jwk::PublicJwk,
-    signer::error::{MismatchedKeyInfoSnafu, UnderlyingSnafu},
+    jws::{self, Header},
+    signer::{
+        SignedBytes,
+        error::{InvalidClaimsSnafu, InvalidHeaderSnafu, MismatchedKeyInfoSnafu, UnderlyingSnafu},
+    },
 };
 
 /// Trait for signers that produce RFC 7515 (JWS) / RFC 7518 (JWA) compatible signatures.
@@ -32,6 +37,66 @@ pub trait JwsSigner: MaybeSendSync + Clone {
     /// `kid` value, and may require transformation before use.
     fn key_id(&self) -> Option<Cow<'_, str>>;
 
+    /// Returns the names of extension header parameters this signer requires
+    /// to be understood by recipients.
+    ///
+    /// These are emitted as the `crit` header parameter by
+    /// [`JwsSigner::sign_with_header`], which also checks that the header
+    /// includes each of them. The default implementation requires none.
+    fn critical_headers(&self) -> Cow<'_, [String]> {
+        Cow::Borrowed(&[])
+    }
+
+    /// Returns the JWS algorithms this signer will currently sign with.
+    ///
+    /// This lets registries and negotiation code (e.g. matching a client's
+    /// allowed algorithms) pick a signer up front, rather than by trial and
+    /// error. Wrappers that restrict signing, such as [`PolicySigner`](super::PolicySigner),
+    /// exclude the algorithms they would refuse. The default implementation
+    /// returns [`JwsSigner::jws_algorithm`].
+    fn supported_algorithms(&self) -> Vec<Cow<'_, str>> {
+        vec![self.jws_algorithm()]
+    }
+
+    /// Returns `true` if [`JwsSigner::sign_with_header`] signs with the key
+    /// named by [`JwsSigner::jws_algorithm`] and [`JwsSigner::key_id`], as the
+    /// default implementation does.
+    ///
+    /// Wrappers that build the protected header themselves, such as
+    /// [`HeaderCacheSigner`](super::HeaderCacheSigner), only do so when this
+    /// returns `true`. Signers that pick the key while signing (e.g.
+    /// [`FallbackSigner`](super::FallbackSigner)) return `false`. The default
+    /// implementation returns `true`.
+    fn allows_precomputed_header(&self) -> bool {
+        true
+    }
+
+    /// Returns `true` if this signer will currently sign with the given JWS algorithm.
+    fn supports_algorithm(&self, jws_algorithm: &str) -> bool {
+        self.supported_algorithms()
+            .iter()
+            .any(|alg| alg == jws_algorithm)
+    }
+
+    /// Returns the registry entry for the signer's JWS algorithm, or `None` if
+    /// the algorithm isn't registered in [`jwa`].
+    fn jws_algorithm_info(&self) -> Option<&'static jwa::JwsAlgorithm> {
+        jwa::lookup(&self.jws_algorithm())
+    }
+
+    /// Asynchronously checks that the signer is ready to sign.
+    ///
+    /// Remote implementations can use this to validate credentials and open
+    /// sessions at startup, rather than failing on the first signing operation.
+    /// The default implementation always succeeds.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the signer is not able to sign.
+    fn health_check(&self) -> impl Future<Output = Result<(), Self::Error>> + MaybeSend {
+        async { Ok(()) }
+    }
+
     /// Asynchronously signs the given input data and returns the signature.
     ///
     /// This should not be called directly, as it does not verify that the algorithm
@@ -65,6 +130,98 @@ pub trait JwsSigner: MaybeSendSync + Clone {
             }
         }
     }
+
+    /// Asynchronously signs the given input data, returning the signature along with
+    /// the algorithm and key ID that produced it.
+    ///
+    /// This is for callers that don't know the key metadata up front (e.g. when the
+    /// signer was selected by algorithm), and record it alongside the signature.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the signing operation fails.
+    fn sign_with_metadata(
+        &self,
+        input: &[u8],
+    ) -> impl Future<Output = Result<SignedBytes, Self::Error>> + MaybeSend {
+        async move {
+            let jws_algorithm = self.jws_algorithm().into_owned();
+            let key_id = self.key_id().map(Cow::into_owned);
+            let signature = self.sign_unchecked(input).await?;
+            Ok(SignedBytes::builder()
+                .signature(signature)
+                .jws_algorithm(jws_algorithm)
+                .maybe_key_id(key_id)
+                .build())
+        }
+    }
+
+    /// Asynchronously signs a payload as a JWS, with the algorithm and key ID
+    /// in the protected header filled in from this signer.
+    ///
+    /// The returned [`SignedBytes`] includes the exact encoded protected header
+    /// that was signed, so the compact serialization can be assembled without
+    /// re-serializing the header.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the header can't be built, the key metadata changed
+    /// while signing, or the signing operation fails.
+    fn sign_with_header(
+        &self,
+        header: &Header,
+        payload: &[u8],
+    ) -> impl Future<Output = Result<SignedBytes, super::Error<Self::Error>>> + MaybeSend {
+        async move {
+            let jws_algorithm = self.jws_algorithm().into_owned();
+            let key_id = self.key_id().map(Cow::into_owned);
+            let encoded_header = jws::encode_protected_header(
+                &jws_algorithm,
+                key_id.as_deref(),
+                &self.critical_headers(),
+                header,
+            )
+            .context(InvalidHeaderSnafu)?;
+            let input = jws::signing_input(&encoded_header, payload);
+            let signature = self
+                .sign(input.as_bytes(), &jws_algorithm, key_id.as_deref())
+                .await?;
+            Ok(SignedBytes::builder()
+                .signature(signature)
+                .jws_algorithm(jws_algorithm)
+                .maybe_key_id(key_id)
+                .protected_header(encoded_header)
+                .build())
+        }
+    }
+
+    /// Asynchronously signs a set of claims as a JWT, returning the compact
+    /// serialization.
+    ///
+    /// The claims are serialized to JSON, and `typ` defaults to `JWT` if not
+    /// set in the header. Signing goes through [`JwsSigner::sign_with_header`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the claims can't be serialized, the header can't be
+    /// built, the key metadata changed while signing, or the signing operation fails.
+    fn sign_jwt<C: Serialize + ?Sized>(
+        &self,
+        claims: &C,
+        header: &Header,
+    ) -> impl Future<Output = Result<String, super::Error<Self::Error>>> + MaybeSend {
+        // Serialized up front, so the claims needn't be `Sync`.
+        let payload = serde_json::to_vec(claims).context(InvalidClaimsSnafu);
+        let header = header.clone().or_typ("JWT");
+        async move {
+            let payload = payload?;
+            let signed = self.sign_with_header(&header, &payload).await?;
+            signed
+                .to_compact(&payload)
+                .ok_or(jws::HeaderError::MissingProtectedHeader)
+                .context(InvalidHeaderSnafu)
+        }
+    }
 }
 
 /// Trait for asymmetric keys that provides its public key in JWK (RFC 7517) format.
@@ -73,6 +230,82 @@ pub trait HasPublicKey: MaybeSendSync + Clone {
     fn public_key_jwk(&self) -> &PublicJwk;
 }
 
+macro_rules! forward_jws_signer {
+    ($($ty:ty),+) => {$(
+        impl<S: JwsSigner> JwsSigner for $ty {
+            type Error = S::Error;
+
+            fn algorithm(&self) -> Cow<'_, str> {
+                (**self).algorithm()
+            }
+
+            fn jws_algorithm(&self) -> Cow<'_, str> {
+                (**self).jws_algorithm()
+            }
+
+            fn key_id(&self) -> Option<Cow<'_, str>> {
+                (**self).key_id()
+            }
+
+            fn critical_headers(&self) -> Cow<'_, [String]> {
+                (**self).critical_headers()
+            }
+
+            fn supported_algorithms(&self) -> Vec<Cow<'_, str>> {
+                (**self).supported_algorithms()
+            }
+
+            fn allows_precomputed_header(&self) -> bool {
+                (**self).allows_precomputed_header()
+            }
+
+            fn health_check(&self) -> impl Future<Output = Result<(), Self::Error>> + MaybeSend {
+                (**self).health_check()
+            }
+
+            fn sign_unchecked(
+                &self,
+                input: &[u8],
+            ) -> impl Future<Output = Result<Bytes, Self::Error>> + MaybeSend {
+                (**self).sign_unchecked(input)
+            }
+
+            fn sign(
+                &self,
+                input: &[u8],
+                jws_algorithm: &str,
+                key_id: Option<&str>,
+            ) -> impl Future<Output = Result<Bytes, super::Error<Self::Error>>> + MaybeSend {
+                (**self).sign(input, jws_algorithm, key_id)
+            }
+
+            fn sign_with_metadata(
+                &self,
+                input: &[u8],
+            ) -> impl Future<Output = Result<SignedBytes, Self::Error>> + MaybeSend {
+                (**self).sign_with_metadata(input)
+            }
+
+            fn sign_with_header(
+                &self,
+                header: &Header,
+                payload: &[u8],
+            ) -> impl Future<Output = Result<SignedBytes, super::Error<Self::Error>>> + MaybeSend
+            {
+                (**self).sign_with_header(header, payload)
+            }
+        }
+
+        impl<S: HasPublicKey> HasPublicKey for $ty {
+            fn public_key_jwk(&self) -> &PublicJwk {
+                (**self).public_key_jwk()
+            }
+        }
+    )+};
+}
+
+forward_jws_signer!(Arc<S>, &S);
+
 #[cfg(test)]
 mod tests {
     use std::convert::Infallible;
@@ -117,7 +350,52 @@ mod tests {
         assert!(matches!(
             result,
             Err(crate::signer::Error::MismatchedKeyInfo)
-        ))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_default_health_check_succeeds() {
+        MockSigner.health_check().await.expect("healthy");
+    }
+
+    #[tokio::test]
+    async fn test_shared_signer_signs() {
+        async fn sign_with(signer: impl JwsSigner) {
+            signer
+                .sign(&[], "JWS-ALG", None)
+                .await
+                .expect("no mismatch");
+        }
+
+        let signer = std::sync::Arc::new(MockSigner);
+
+        sign_with(&signer).await;
+        sign_with(signer).await;
+    }
+
+    #[tokio::test]
+    async fn test_sign_with_header_compact() {
+        let signed = MockSigner
+            .sign_with_header(&crate::jws::Header::default(), b"{}")
+            .await
+            .expect("signed");
+
+        // {"alg":"JWS-ALG"} with an empty signature
+        assert_eq!(
+            signed.to_compact(b"{}").as_deref(),
+            Some("eyJhbGciOiJKV1MtQUxHIn0.e30.")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sign_jwt_defaults_typ() {
+        let jwt = MockSigner
+            .sign_jwt(&serde_json::json!({}), &crate::jws::Header::default())
+            .await
+            .expect("signed");
+
+        // {"alg":"JWS-ALG","typ":"JWT"} with an empty signature
+        assert_eq!(jwt, "eyJhbGciOiJKV1MtQUxHIiwidHlwIjoiSldUIn0.e30.");
     }
 
     #[tokio::test]
@@ -127,6 +405,6 @@ mod tests {
         assert!(matches!(
             result,
             Err(crate::signer::Error::MismatchedKeyInfo)
-        ))
+        ));
     }
 }
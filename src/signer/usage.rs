@@ -0,0 +1,381 @@
+//! Signer wrapper enforcing key usage limits.
+
+use std::{
+    borrow::Cow,
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+};
+
+use bon::bon;
+use bytes::Bytes;
+use snafu::prelude::*;
+use web_time::SystemTime;
+
+use crate::{
+    MaybeSendSync,
+    clock::{Clock, SystemClock},
+    jws::Header,
+    signer::{Error, JwsSigner, SignedBytes},
+};
+
+/// Errors returned by [`KeyUsageSigner`].
+#[derive(Debug, Snafu)]
+pub enum UsageError<E: std::error::Error + MaybeSendSync + 'static> {
+    /// The key has reached its maximum number of signing operations.
+    #[snafu(display("Key has reached its limit of {limit} signatures"))]
+    OperationLimitReached {
+        /// The configured maximum number of operations.
+        limit: u64,
+    },
+    /// The key is past its not-after time.
+    #[snafu(display("Key expired at {not_after:?}"))]
+    KeyExpired {
+        /// The configured not-after time.
+        not_after: SystemTime,
+    },
+    /// The error from the wrapped signer.
+    #[snafu(display("Signing failed"))]
+    Inner {
+        /// The source error.
+        source: E,
+    },
+}
+
+/// A signer that refuses to sign once its key has been used too many times, or
+/// is past a not-after time.
+///
+/// These errors signal that rotation is overdue. Usage is counted across
+/// clones; failed or cancelled signing operations are not counted. To track
+/// each key separately under rotation, wrap the individual keys rather than
+/// the rotating signer (e.g. `RotatingSigner<KeyUsageSigner<S>>`).
+///
+/// The not-after time is compared with the system clock, unless another
+/// [`Clock`] is set with [`KeyUsageSigner::with_clock`].
+#[derive(Debug, Clone)]
+pub struct KeyUsageSigner<S, C = SystemClock> {
+    inner: S,
+    max_operations: Option<u64>,
+    not_after: Option<SystemTime>,
+    operations: Arc<AtomicU64>,
+    clock: C,
+}
+
+#[bon]
+impl<S: JwsSigner> KeyUsageSigner<S> {
+    /// Creates a builder wrapping the given signer.
+    #[builder]
+    pub fn new(
+        #[builder(start_fn)] inner: S,
+        /// The maximum number of signatures the key may produce.
+        max_operations: Option<u64>,
+        /// The time after which the key may no longer sign.
+        not_after: Option<SystemTime>,
+    ) -> Self {
+        Self {
+            inner,
+            max_operations,
+            not_after,
+            operations: Arc::default(),
+            clock: SystemClock,
+        }
+    }
+}
+
+impl<S: JwsSigner, C: Clock + Clone> KeyUsageSigner<S, C> {
+    /// Compares the not-after time with the given clock.
+    pub fn with_clock<C2: Clock + Clone>(self, clock: C2) -> KeyUsageSigner<S, C2> {
+        KeyUsageSigner {
+            inner: self.inner,
+            max_operations: self.max_operations,
+            not_after: self.not_after,
+            operations: self.operations,
+            clock,
+        }
+    }
+
+    /// Returns the wrapped signer.
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    /// Returns the number of signatures produced so far.
+    #[must_use]
+    pub fn operations(&self) -> u64 {
+        self.operations.load(Ordering::Acquire)
+    }
+
+    fn check_expiry(&self) -> Result<(), UsageError<S::Error>> {
+        if let Some(not_after) = self.not_after {
+            ensure!(self.clock.now() <= not_after, KeyExpiredSnafu { not_after });
+        }
+        Ok(())
+    }
+
+    /// Reserves one operation, released unless signing succeeds.
+    fn reserve(&self) -> Result<Reservation<'_>, UsageError<S::Error>> {
+        self.check_expiry()?;
+        if let Some(limit) = self.max_operations {
+            self.operations
+                .fetch_update(Ordering::AcqRel, Ordering::Acquire, |n| {
+                    (n < limit).then_some(n + 1)
+                })
+                .ok()
+                .context(OperationLimitReachedSnafu { limit })?;
+        } else {
+            self.operations.fetch_add(1, Ordering::AcqRel);
+        }
+        Ok(Reservation {
+            operations: &self.operations,
+            committed: false,
+        })
+    }
+}
+
+/// A reserved signing operation.
+///
+/// Released when dropped, unless committed by a successful signature, so
+/// failed and cancelled (e.g. timed out) signing operations aren't counted.
+struct Reservation<'a> {
+    operations: &'a AtomicU64,
+    committed: bool,
+}
+
+impl Reservation<'_> {
+    /// Keeps the reservation if `result` is a success.
+    fn track<T, E>(mut self, result: Result<T, E>) -> Result<T, E> {
+        self.committed = result.is_ok();
+        result
+    }
+}
+
+impl Drop for Reservation<'_> {
+    fn drop(&mut self) {
+        if !self.committed {
+            self.operations.fetch_sub(1, Ordering::AcqRel);
+        }
+    }
+}
+
+impl<S: JwsSigner, C: Clock + Clone> JwsSigner for KeyUsageSigner<S, C> {
+    type Error = UsageError<S::Error>;
+
+    fn algorithm(&self) -> Cow<'_, str> {
+        self.inner.algorithm()
+    }
+
+    fn jws_algorithm(&self) -> Cow<'_, str> {
+        self.inner.jws_algorithm()
+    }
+
+    fn key_id(&self) -> Option<Cow<'_, str>> {
+        self.inner.key_id()
+    }
+
+    fn critical_headers(&self) -> Cow<'_, [String]> {
+        self.inner.critical_headers()
+    }
+
+    fn supported_algorithms(&self) -> Vec<Cow<'_, str>> {
+        self.inner.supported_algorithms()
+    }
+
+    fn allows_precomputed_header(&self) -> bool {
+        self.inner.allows_precomputed_header()
+    }
+
+    async fn health_check(&self) -> Result<(), Self::Error> {
+        self.check_expiry()?;
+        if let Some(limit) = self.max_operations {
+            ensure!(
+                self.operations() < limit,
+                OperationLimitReachedSnafu { limit }
+            );
+        }
+        self.inner.health_check().await.context(InnerSnafu)
+    }
+
+    async fn sign_unchecked(&self, input: &[u8]) -> Result<Bytes, Self::Error> {
+        let reservation = self.reserve()?;
+        let result = self.inner.sign_unchecked(input).await;
+        reservation.track(result).context(InnerSnafu)
+    }
+
+    async fn sign(
+        &self,
+        input: &[u8],
+        jws_algorithm: &str,
+        key_id: Option<&str>,
+    ) -> Result<Bytes, Error<Self::Error>> {
+        let reservation = self
+            .reserve()
+            .map_err(|source| Error::UnderlyingError { source })?;
+        let result = self.inner.sign(input, jws_algorithm, key_id).await;
+        reservation
+            .track(result)
+            .map_err(|e| e.map_underlying(|source| UsageError::Inner { source }))
+    }
+
+    async fn sign_with_metadata(&self, input: &[u8]) -> Result<SignedBytes, Self::Error> {
+        let reservation = self.reserve()?;
+        let result = self.inner.sign_with_metadata(input).await;
+        reservation.track(result).context(InnerSnafu)
+    }
+
+    async fn sign_with_header(
+        &self,
+        header: &Header,
+        payload: &[u8],
+    ) -> Result<SignedBytes, Error<Self::Error>> {
+        let reservation = self
+            .reserve()
+            .map_err(|source| Error::UnderlyingError { source })?;
+        let result = self.inner.sign_with_header(header, payload).await;
+        reservation
+            .track(result)
+            .map_err(|e| e.map_underlying(|source| UsageError::Inner { source }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{borrow::Cow, convert::Infallible, time::Duration};
+
+    use bytes::Bytes;
+    use futures_util::FutureExt;
+    use web_time::UNIX_EPOCH;
+
+    use super::*;
+    use crate::clock::FixedClock;
+
+    #[derive(Debug, Clone)]
+    struct MockSigner;
+
+    impl JwsSigner for MockSigner {
+        type Error = Infallible;
+
+        fn algorithm(&self) -> Cow<'_, str> {
+            "ALG".into()
+        }
+
+        fn jws_algorithm(&self) -> Cow<'_, str> {
+            "JWS-ALG".into()
+        }
+
+        fn key_id(&self) -> Option<Cow<'_, str>> {
+            None
+        }
+
+        async fn sign_unchecked(&self, _input: &[u8]) -> Result<Bytes, Self::Error> {
+            Ok(Bytes::new())
+        }
+    }
+
+    #[derive(Debug, Clone)]
+    struct PendingSigner;
+
+    impl JwsSigner for PendingSigner {
+        type Error = Infallible;
+
+        fn algorithm(&self) -> Cow<'_, str> {
+            "ALG".into()
+        }
+
+        fn jws_algorithm(&self) -> Cow<'_, str> {
+            "JWS-ALG".into()
+        }
+
+        fn key_id(&self) -> Option<Cow<'_, str>> {
+            None
+        }
+
+        async fn sign_unchecked(&self, _input: &[u8]) -> Result<Bytes, Self::Error> {
+            std::future::pending().await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_operation_limit_is_enforced() {
+        let signer = KeyUsageSigner::builder(MockSigner)
+            .max_operations(2)
+            .build();
+
+        signer.sign(b"input", "JWS-ALG", None).await.unwrap();
+        signer
+            .clone()
+            .sign(b"input", "JWS-ALG", None)
+            .await
+            .unwrap();
+        let result = signer.sign(b"input", "JWS-ALG", None).await;
+
+        assert!(matches!(
+            result,
+            Err(Error::UnderlyingError {
+                source: UsageError::OperationLimitReached { limit: 2 }
+            })
+        ));
+        assert_eq!(signer.operations(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_mismatch_does_not_count() {
+        let signer = KeyUsageSigner::builder(MockSigner)
+            .max_operations(1)
+            .build();
+
+        let _ = signer.sign(b"input", "OTHER", None).await;
+
+        assert_eq!(signer.operations(), 0);
+    }
+
+    #[test]
+    fn test_dropped_sign_does_not_count() {
+        let signer = KeyUsageSigner::builder(PendingSigner)
+            .max_operations(1)
+            .build();
+
+        // Polled once (reserving the operation), then dropped.
+        assert!(
+            signer
+                .sign(b"input", "JWS-ALG", None)
+                .now_or_never()
+                .is_none()
+        );
+
+        assert_eq!(signer.operations(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_not_after_uses_clock() {
+        let not_after = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let clock = FixedClock::new(not_after);
+        let signer = KeyUsageSigner::builder(MockSigner)
+            .not_after(not_after)
+            .build()
+            .with_clock(clock.clone());
+
+        assert!(signer.sign_unchecked(b"input").await.is_ok());
+        clock.advance(Duration::from_secs(1));
+        assert!(matches!(
+            signer.sign_unchecked(b"input").await,
+            Err(UsageError::KeyExpired { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_expired_key_refuses_to_sign() {
+        let signer = KeyUsageSigner::builder(MockSigner)
+            .not_after(SystemTime::now() - Duration::from_secs(1))
+            .build();
+
+        assert!(matches!(
+            signer.sign_unchecked(b"input").await,
+            Err(UsageError::KeyExpired { .. })
+        ));
+        assert!(matches!(
+            signer.health_check().await,
+            Err(UsageError::KeyExpired { .. })
+        ));
+    }
+}
@@ -0,0 +1,41 @@
+//! Deadline support shared by the timeout wrappers.
+
+use std::{pin::pin, task::Poll, time::Duration};
+
+use futures_timer::Delay;
+use snafu::Snafu;
+
+use crate::MaybeSendSync;
+
+/// The error returned by operations wrapped with a timeout.
+#[derive(Debug, Snafu)]
+pub enum TimeoutError<E: std::error::Error + MaybeSendSync + 'static> {
+    /// The operation did not complete before the deadline.
+    #[snafu(display("Operation timed out after {duration:?}"))]
+    Elapsed {
+        /// The configured timeout.
+        duration: Duration,
+    },
+    /// The error from the wrapped operation.
+    #[snafu(display("Operation failed"))]
+    Inner {
+        /// The source error.
+        source: E,
+    },
+}
+
+/// Runs the future to completion, or returns `None` if the duration elapses first.
+pub(crate) async fn with_timeout<F: Future>(duration: Duration, future: F) -> Option<F::Output> {
+    let mut future = pin!(future);
+    let mut delay = pin!(Delay::new(duration));
+    std::future::poll_fn(|cx| {
+        if let Poll::Ready(output) = future.as_mut().poll(cx) {
+            Poll::Ready(Some(output))
+        } else if delay.as_mut().poll(cx).is_ready() {
+            Poll::Ready(None)
+        } else {
+            Poll::Pending
+        }
+    })
+    .await
+}
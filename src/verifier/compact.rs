@@ -0,0 +1,57 @@
+//! Verification of compact JWS by any key source.
+
+use crate::{
+    MaybeSend, MaybeSendSync,
+    jwk::PublicJwk,
+    verifier::{Error, JwksError, JwksProvider, JwksVerifier, JwsVerifier, VerifiedBytes},
+};
+
+/// Trait for verifying a JWS in the compact serialization, whether with a
+/// single key ([`JwsVerifier`]) or by resolving the key from a JWKS
+/// ([`JwksVerifier`]).
+///
+/// This is the extension point for components such as
+/// [`JwtValidator`](crate::jwt::JwtValidator) that don't care where the key
+/// comes from.
+pub trait CompactVerifier: MaybeSendSync {
+    /// The error type returned when verification fails.
+    type Error: std::error::Error + MaybeSendSync + 'static;
+
+    /// Asynchronously verifies a JWS in the compact serialization.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the JWS is malformed, no key matches it, or the
+    /// signature is invalid.
+    fn verify_jws(
+        &self,
+        compact: &str,
+    ) -> impl Future<Output = Result<VerifiedBytes, Self::Error>> + MaybeSend;
+}
+
+impl<V: JwsVerifier> CompactVerifier for V {
+    type Error = Error<V::Error>;
+
+    fn verify_jws(
+        &self,
+        compact: &str,
+    ) -> impl Future<Output = Result<VerifiedBytes, Self::Error>> + MaybeSend {
+        self.verify_compact(compact)
+    }
+}
+
+impl<P, F, V> CompactVerifier for JwksVerifier<P, F, V>
+where
+    P: JwksProvider,
+    F: Fn(&PublicJwk) -> Option<V> + MaybeSendSync,
+    V: JwsVerifier,
+{
+    type Error = JwksError<P::Error, V::Error>;
+
+    fn verify_jws(
+        &self,
+        compact: &str,
+    ) -> impl Future<Output = Result<VerifiedBytes, Self::Error>> + MaybeSend {
+        self.verify_compact(compact)
+    }
+}
@@ -0,0 +1,60 @@
+use snafu::Snafu;
+
+use crate::MaybeSendSync;
+
+/// The error type returned by verification operations.
+#[derive(Debug, Snafu)]
+#[snafu(visibility(pub(super)))]
+pub enum Error<E: std::error::Error + MaybeSendSync + 'static> {
+    /// The JWS is not well-formed.
+    #[snafu(display("Malformed JWS: {reason}"))]
+    Malformed {
+        /// What is wrong with the JWS.
+        reason: &'static str,
+    },
+    /// The `alg` header parameter doesn't match the verifier's algorithm.
+    #[snafu(display("JWS algorithm '{actual}' doesn't match expected '{expected}'"))]
+    AlgorithmMismatch {
+        /// The verifier's JWS algorithm.
+        expected: String,
+        /// The `alg` header parameter.
+        actual: String,
+    },
+    /// The `kid` header parameter doesn't match the verifier's key ID.
+    #[snafu(display("Key ID doesn't match the verifier's key"))]
+    KeyIdMismatch,
+    /// The `crit` header parameter lists an extension this verifier doesn't understand.
+    #[snafu(display("Unsupported critical header parameter '{name}'"))]
+    UnsupportedCriticalHeader {
+        /// The name of the parameter.
+        name: String,
+    },
+    /// The signature is not valid for the input.
+    #[snafu(display("Invalid signature"))]
+    InvalidSignature,
+    /// The error from the underlying implementation.
+    UnderlyingError {
+        /// The source error.
+        source: E,
+    },
+}
+
+impl<E: std::error::Error + MaybeSendSync + 'static> Error<E> {
+    /// Maps the underlying error, keeping other variants as-is.
+    pub(crate) fn map_underlying<F, O>(self, f: F) -> Error<O>
+    where
+        F: FnOnce(E) -> O,
+        O: std::error::Error + MaybeSendSync + 'static,
+    {
+        match self {
+            Self::Malformed { reason } => Error::Malformed { reason },
+            Self::AlgorithmMismatch { expected, actual } => {
+                Error::AlgorithmMismatch { expected, actual }
+            }
+            Self::KeyIdMismatch => Error::KeyIdMismatch,
+            Self::UnsupportedCriticalHeader { name } => Error::UnsupportedCriticalHeader { name },
+            Self::InvalidSignature => Error::InvalidSignature,
+            Self::UnderlyingError { source } => Error::UnderlyingError { source: f(source) },
+        }
+    }
+}
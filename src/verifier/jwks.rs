@@ -0,0 +1,309 @@
+//! Verifier resolving keys from a JWKS.
+
+use std::{sync::Arc, time::Duration};
+
+use arc_swap::ArcSwap;
+use bon::bon;
+use serde_json::Value;
+use snafu::prelude::*;
+use tokio::sync::Mutex;
+use web_time::Instant;
+
+use crate::{
+    MaybeSend, MaybeSendSync,
+    jwk::{PublicJwk, PublicJwks},
+    verifier::{Error, JwsVerifier, VerifiedBytes, r#trait::decode_header},
+};
+
+/// Trait for fetching the current JWKS (e.g. from an issuer's `jwks_uri`).
+pub trait JwksProvider: MaybeSendSync {
+    /// The error type returned when fetching fails.
+    type Error: std::error::Error + MaybeSendSync + 'static;
+
+    /// Asynchronously fetches the current JWKS.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the JWKS can't be fetched.
+    fn fetch(&self) -> impl Future<Output = Result<PublicJwks, Self::Error>> + MaybeSend;
+}
+
+/// Errors returned by [`JwksVerifier`].
+#[derive(Debug, Snafu)]
+pub enum JwksError<P, V>
+where
+    P: std::error::Error + MaybeSendSync + 'static,
+    V: std::error::Error + MaybeSendSync + 'static,
+{
+    /// No key in the JWKS matches the header, even after refreshing.
+    #[snafu(display("No key found for kid {kid:?} and alg '{alg}'"))]
+    UnknownKey {
+        /// The `kid` header parameter.
+        kid: Option<String>,
+        /// The `alg` header parameter.
+        alg: String,
+    },
+    /// The JWKS could not be fetched.
+    #[snafu(display("Failed to refresh JWKS"))]
+    Refresh {
+        /// The error from the provider.
+        source: P,
+    },
+    /// The JWS could not be verified.
+    #[snafu(display("Verification failed"))]
+    Verify {
+        /// The verification error.
+        source: Error<V>,
+    },
+}
+
+struct Inner<P, F, V> {
+    provider: P,
+    factory: F,
+    verifiers: ArcSwap<Vec<V>>,
+    /// The time of the last refresh, locked for the duration of a refresh.
+    last_refresh: Mutex<Option<Instant>>,
+    min_refresh_interval: Duration,
+}
+
+/// A verifier that resolves keys by `kid` and `alg` from a JWKS, refreshing
+/// the JWKS when it doesn't contain a key.
+///
+/// This is the standard pattern for `OpenID` Connect relying parties and
+/// resource servers: keys are fetched lazily, and an unknown `kid` (e.g. after
+/// the issuer rotates its keys) triggers a refresh. Refreshes are rate-limited,
+/// so tokens with bogus key IDs can't be used to flood the issuer. Clones
+/// share the same keys.
+///
+/// The crate has no built-in cryptography, so a factory converts each JWK
+/// into a [`JwsVerifier`]. Keys the factory returns `None` for are ignored.
+pub struct JwksVerifier<P, F, V> {
+    inner: Arc<Inner<P, F, V>>,
+}
+
+impl<P, F, V> Clone for JwksVerifier<P, F, V> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: Arc::clone(&self.inner),
+        }
+    }
+}
+
+impl<P, F, V: std::fmt::Debug> std::fmt::Debug for JwksVerifier<P, F, V> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("JwksVerifier")
+            .field("verifiers", &self.inner.verifiers.load())
+            .field("min_refresh_interval", &self.inner.min_refresh_interval)
+            .finish_non_exhaustive()
+    }
+}
+
+#[bon]
+impl<P, F, V> JwksVerifier<P, F, V>
+where
+    P: JwksProvider,
+    F: Fn(&PublicJwk) -> Option<V> + MaybeSendSync,
+    V: JwsVerifier,
+{
+    /// Creates a builder for a verifier using the given provider and factory.
+    #[builder]
+    pub fn new(
+        #[builder(start_fn)] provider: P,
+        #[builder(start_fn)] factory: F,
+        /// The minimum time between refreshes. Defaults to 30 seconds.
+        #[builder(default = Duration::from_secs(30))]
+        min_refresh_interval: Duration,
+    ) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                provider,
+                factory,
+                verifiers: ArcSwap::default(),
+                last_refresh: Mutex::new(None),
+                min_refresh_interval,
+            }),
+        }
+    }
+
+    /// Fetches the JWKS now, regardless of the rate limit.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the JWKS can't be fetched.
+    pub async fn refresh(&self) -> Result<(), P::Error> {
+        let mut last_refresh = self.inner.last_refresh.lock().await;
+        self.fetch(&mut last_refresh).await
+    }
+
+    async fn fetch(&self, last_refresh: &mut Option<Instant>) -> Result<(), P::Error> {
+        *last_refresh = Some(Instant::now());
+        let jwks = self.inner.provider.fetch().await?;
+        let verifiers = jwks.keys.iter().filter_map(&self.inner.factory).collect();
+        self.inner.verifiers.store(Arc::new(verifiers));
+        Ok(())
+    }
+
+    fn find(&self, kid: Option<&str>, alg: &str) -> Option<V> {
+        self.inner
+            .verifiers
+            .load()
+            .iter()
+            .find(|v| v.jws_algorithm() == alg && (kid.is_none() || v.key_id().as_deref() == kid))
+            .cloned()
+    }
+
+    /// Refreshes the JWKS, unless refreshed too recently, and finds the key again.
+    async fn refresh_and_find(&self, kid: Option<&str>, alg: &str) -> Result<Option<V>, P::Error> {
+        let mut last_refresh = self.inner.last_refresh.lock().await;
+        // Another task may have refreshed while this one waited for the lock.
+        if let Some(verifier) = self.find(kid, alg) {
+            return Ok(Some(verifier));
+        }
+        let recently_refreshed = last_refresh
+            .is_some_and(|refreshed| refreshed.elapsed() < self.inner.min_refresh_interval);
+        if recently_refreshed {
+            return Ok(None);
+        }
+        self.fetch(&mut last_refresh).await?;
+        Ok(self.find(kid, alg))
+    }
+
+    /// Asynchronously verifies a JWS in the compact serialization, using the
+    /// key matching its `kid` and `alg` header parameters.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no key matches, the JWKS can't be refreshed, or
+    /// the JWS fails verification.
+    pub async fn verify_compact(
+        &self,
+        compact: &str,
+    ) -> Result<VerifiedBytes, JwksError<P::Error, V::Error>> {
+        let encoded_header = compact.split('.').next().unwrap_or_default();
+        let header = decode_header(encoded_header).context(VerifySnafu)?;
+        let kid = header.get("kid").and_then(Value::as_str);
+        let alg = header
+            .get("alg")
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+
+        let verifier = match self.find(kid, alg) {
+            Some(verifier) => verifier,
+            None => self
+                .refresh_and_find(kid, alg)
+                .await
+                .context(RefreshSnafu)?
+                .with_context(|| UnknownKeySnafu {
+                    kid: kid.map(str::to_owned),
+                    alg,
+                })?,
+        };
+        verifier.verify_compact(compact).await.context(VerifySnafu)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        borrow::Cow,
+        convert::Infallible,
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+
+    use super::*;
+    use crate::{
+        jwk::OkpPublicKey,
+        signer::{JwsSigner, MockSigner},
+    };
+
+    #[derive(Debug, Clone)]
+    struct KeyVerifier(String);
+
+    impl JwsVerifier for KeyVerifier {
+        type Error = Infallible;
+
+        fn jws_algorithm(&self) -> Cow<'_, str> {
+            "ES256".into()
+        }
+
+        fn key_id(&self) -> Option<Cow<'_, str>> {
+            Some(Cow::Borrowed(&self.0))
+        }
+
+        async fn verify_unchecked(
+            &self,
+            _input: &[u8],
+            _signature: &[u8],
+        ) -> Result<bool, Self::Error> {
+            Ok(true)
+        }
+    }
+
+    /// Serves `key-1` first, and `key-1` and `key-2` after.
+    #[derive(Debug, Default)]
+    struct RotatingProvider {
+        fetches: AtomicUsize,
+    }
+
+    impl JwksProvider for RotatingProvider {
+        type Error = Infallible;
+
+        async fn fetch(&self) -> Result<PublicJwks, Self::Error> {
+            let fetches = self.fetches.fetch_add(1, Ordering::SeqCst) + 1;
+            let keys = (1..=fetches.min(2))
+                .map(|n| {
+                    PublicJwk::builder()
+                        .key(OkpPublicKey::builder().crv("Ed25519").x([0]))
+                        .kid(format!("key-{n}"))
+                        .build()
+                })
+                .collect();
+            Ok(PublicJwks { keys })
+        }
+    }
+
+    type TestVerifier =
+        JwksVerifier<RotatingProvider, fn(&PublicJwk) -> Option<KeyVerifier>, KeyVerifier>;
+
+    fn verifier(min_refresh_interval: Duration) -> TestVerifier {
+        JwksVerifier::builder(
+            RotatingProvider::default(),
+            (|jwk: &PublicJwk| jwk.kid().map(|kid| KeyVerifier(kid.to_owned())))
+                as fn(&PublicJwk) -> Option<KeyVerifier>,
+        )
+        .min_refresh_interval(min_refresh_interval)
+        .build()
+    }
+
+    async fn compact(kid: &str) -> String {
+        MockSigner::builder()
+            .key_id(kid)
+            .build()
+            .sign_jwt(&serde_json::json!({}), &crate::jws::Header::default())
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_unknown_kid_triggers_refresh() {
+        let verifier = verifier(Duration::ZERO);
+
+        let first = verifier.verify_compact(&compact("key-1").await).await;
+        let rotated = verifier.verify_compact(&compact("key-2").await).await;
+
+        assert_eq!(first.unwrap().key_id(), Some("key-1"));
+        assert_eq!(rotated.unwrap().key_id(), Some("key-2"));
+        assert_eq!(verifier.inner.provider.fetches.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_refresh_is_rate_limited() {
+        let verifier = verifier(Duration::from_secs(30));
+        verifier.refresh().await.unwrap();
+
+        let result = verifier.verify_compact(&compact("key-2").await).await;
+
+        assert!(matches!(result, Err(JwksError::UnknownKey { .. })));
+        assert_eq!(verifier.inner.provider.fetches.load(Ordering::SeqCst), 1);
+    }
+}
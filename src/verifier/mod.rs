@@ -0,0 +1,15 @@
+//! Cryptographic verification traits.
+
+mod compact;
+mod error;
+mod jwks;
+mod policy;
+mod r#trait;
+mod verified;
+
+pub use compact::CompactVerifier;
+pub use error::Error;
+pub use jwks::{JwksError, JwksProvider, JwksVerifier};
+pub use policy::{PolicyError, PolicyVerifier};
+pub use r#trait::JwsVerifier;
+pub use verified::VerifiedBytes;
@@ -0,0 +1,298 @@
+//! Verifier wrapper enforcing an algorithm and key policy.
+
+use std::{borrow::Cow, sync::Arc};
+
+use bon::bon;
+use snafu::prelude::*;
+
+use crate::{
+    MaybeSendSync, jwa,
+    jwk::PublicJwk,
+    verifier::{Error, JwsVerifier, VerifiedBytes},
+};
+
+/// Errors returned by [`PolicyVerifier`].
+#[derive(Debug, Snafu)]
+pub enum PolicyError<E: std::error::Error + MaybeSendSync + 'static> {
+    /// The algorithm is not in the allow-list, or is `none`.
+    #[snafu(display("Algorithm '{algorithm}' is not allowed by policy"))]
+    DisallowedAlgorithm {
+        /// The disallowed JWS algorithm.
+        algorithm: String,
+    },
+    /// The algorithm can't be used with the verifier's key type.
+    #[snafu(display("Algorithm '{algorithm}' can't be used with a '{kty}' key"))]
+    KeyTypeMismatch {
+        /// The JWS algorithm.
+        algorithm: String,
+        /// The key type of the verifier's key.
+        kty: String,
+    },
+    /// The key ID is not the pinned key ID.
+    #[snafu(display("Key ID doesn't match the pinned key ID"))]
+    UnpinnedKeyId,
+    /// The key thumbprint is not the pinned thumbprint.
+    #[snafu(display("Key thumbprint doesn't match the pinned thumbprint"))]
+    UnpinnedThumbprint,
+    /// The error from the wrapped verifier.
+    #[snafu(display("Verification failed"))]
+    Inner {
+        /// The source error.
+        source: E,
+    },
+}
+
+/// Returns `false` if the JWS algorithm is known not to apply to the key type.
+///
+/// This prevents algorithm confusion, such as a token signed with `HS256`
+/// using an RSA public key as the HMAC secret.
+fn key_type_matches(jws_algorithm: &str, kty: &str) -> bool {
+    // Symmetric algorithms use `oct` keys, so never match a public key.
+    jwa::lookup(jws_algorithm).is_none_or(|alg| alg.key_type() == kty)
+}
+
+/// A verifier that only accepts signatures meeting a policy.
+///
+/// The policy always rejects `alg: none`, and algorithms that don't apply to
+/// the verifier's key type (when the verifier exposes its public key). It can
+/// also pin the key ID or JWK thumbprint of the key that verified.
+#[derive(Debug, Clone)]
+pub struct PolicyVerifier<V> {
+    inner: V,
+    allowed: Arc<[String]>,
+    pinned_key_id: Option<String>,
+    pinned_thumbprint: Option<String>,
+}
+
+#[bon]
+impl<V: JwsVerifier> PolicyVerifier<V> {
+    /// Creates a builder wrapping the given verifier.
+    #[builder]
+    pub fn new<I>(
+        #[builder(start_fn)] inner: V,
+        /// The JWS algorithms to accept.
+        allowed_algorithms: I,
+        /// The only key ID to accept.
+        #[builder(into)]
+        pinned_key_id: Option<String>,
+        /// The only JWK thumbprint (RFC 7638) to accept.
+        #[builder(into)]
+        pinned_thumbprint: Option<String>,
+    ) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        Self {
+            inner,
+            allowed: allowed_algorithms.into_iter().map(Into::into).collect(),
+            pinned_key_id,
+            pinned_thumbprint,
+        }
+    }
+
+    /// Returns the wrapped verifier.
+    pub fn inner(&self) -> &V {
+        &self.inner
+    }
+
+    /// Returns `true` if the given JWS algorithm is allowed.
+    #[must_use]
+    pub fn is_allowed(&self, jws_algorithm: &str) -> bool {
+        jws_algorithm != "none" && self.allowed.iter().any(|alg| alg == jws_algorithm)
+    }
+
+    fn check_algorithm(&self, jws_algorithm: &str) -> Result<(), PolicyError<V::Error>> {
+        ensure!(
+            self.is_allowed(jws_algorithm),
+            DisallowedAlgorithmSnafu {
+                algorithm: jws_algorithm
+            }
+        );
+        Ok(())
+    }
+
+    /// Checks the verifier's own algorithm and key, before verifying.
+    fn check_key(&self) -> Result<(), PolicyError<V::Error>> {
+        let jws_algorithm = self.inner.jws_algorithm();
+        self.check_algorithm(&jws_algorithm)?;
+        if let Some(kty) = self.inner.public_key_jwk().and_then(|jwk| jwk.key().kty()) {
+            ensure!(
+                key_type_matches(&jws_algorithm, kty),
+                KeyTypeMismatchSnafu {
+                    algorithm: jws_algorithm.as_ref(),
+                    kty,
+                }
+            );
+        }
+        Ok(())
+    }
+
+    /// Checks the result, as reported by the verifier that actually verified.
+    fn check_verified(&self, verified: &VerifiedBytes) -> Result<(), PolicyError<V::Error>> {
+        self.check_algorithm(verified.jws_algorithm())?;
+        if let Some(pinned) = &self.pinned_key_id {
+            ensure!(
+                verified.key_id() == Some(pinned.as_str()),
+                UnpinnedKeyIdSnafu
+            );
+        }
+        if let Some(pinned) = &self.pinned_thumbprint {
+            ensure!(
+                verified.thumbprint() == Some(pinned.as_str()),
+                UnpinnedThumbprintSnafu
+            );
+        }
+        Ok(())
+    }
+}
+
+impl<V: JwsVerifier> JwsVerifier for PolicyVerifier<V> {
+    type Error = PolicyError<V::Error>;
+
+    fn jws_algorithm(&self) -> Cow<'_, str> {
+        self.inner.jws_algorithm()
+    }
+
+    fn key_id(&self) -> Option<Cow<'_, str>> {
+        self.inner.key_id()
+    }
+
+    fn public_key_jwk(&self) -> Option<Cow<'_, PublicJwk>> {
+        self.inner.public_key_jwk()
+    }
+
+    async fn verify_unchecked(&self, input: &[u8], signature: &[u8]) -> Result<bool, Self::Error> {
+        self.check_key()?;
+        self.inner
+            .verify_unchecked(input, signature)
+            .await
+            .context(InnerSnafu)
+    }
+
+    async fn verify_compact(&self, compact: &str) -> Result<VerifiedBytes, Error<Self::Error>> {
+        self.check_key()
+            .map_err(|source| Error::UnderlyingError { source })?;
+        let verified = self
+            .inner
+            .verify_compact(compact)
+            .await
+            .map_err(|e| e.map_underlying(|source| PolicyError::Inner { source }))?;
+        self.check_verified(&verified)
+            .map_err(|source| Error::UnderlyingError { source })?;
+        Ok(verified)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+
+    use super::*;
+    use crate::{
+        jwk::RsaPublicKey,
+        signer::{JwsSigner, MockSigner},
+    };
+
+    #[derive(Debug, Clone)]
+    struct MockVerifier(&'static str);
+
+    impl JwsVerifier for MockVerifier {
+        type Error = Infallible;
+
+        fn jws_algorithm(&self) -> Cow<'_, str> {
+            self.0.into()
+        }
+
+        fn key_id(&self) -> Option<Cow<'_, str>> {
+            Some("key-1".into())
+        }
+
+        fn public_key_jwk(&self) -> Option<Cow<'_, PublicJwk>> {
+            let key = RsaPublicKey::builder().n([1, 0, 1]).e([1, 0, 1]);
+            Some(Cow::Owned(PublicJwk::builder().key(key).build()))
+        }
+
+        async fn verify_unchecked(
+            &self,
+            _input: &[u8],
+            _signature: &[u8],
+        ) -> Result<bool, Self::Error> {
+            Ok(true)
+        }
+    }
+
+    async fn compact(jws_algorithm: &str) -> String {
+        MockSigner::builder()
+            .jws_algorithm(jws_algorithm)
+            .key_id("key-1")
+            .build()
+            .sign_jwt(&serde_json::json!({}), &crate::jws::Header::default())
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_allowed_algorithm_verifies() {
+        let verifier = PolicyVerifier::builder(MockVerifier("RS256"))
+            .allowed_algorithms(["RS256"])
+            .pinned_key_id("key-1")
+            .build();
+
+        assert!(
+            verifier
+                .verify_compact(&compact("RS256").await)
+                .await
+                .is_ok()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_none_is_always_rejected() {
+        let verifier = PolicyVerifier::builder(MockVerifier("none"))
+            .allowed_algorithms(["none"])
+            .build();
+
+        let result = verifier.verify_compact(&compact("none").await).await;
+
+        assert!(matches!(
+            result,
+            Err(Error::UnderlyingError {
+                source: PolicyError::DisallowedAlgorithm { .. }
+            })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_hmac_with_rsa_key_is_rejected() {
+        let verifier = PolicyVerifier::builder(MockVerifier("HS256"))
+            .allowed_algorithms(["HS256", "RS256"])
+            .build();
+
+        let result = verifier.verify_compact(&compact("HS256").await).await;
+
+        assert!(matches!(
+            result,
+            Err(Error::UnderlyingError {
+                source: PolicyError::KeyTypeMismatch { .. }
+            })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_unpinned_thumbprint_is_rejected() {
+        let verifier = PolicyVerifier::builder(MockVerifier("RS256"))
+            .allowed_algorithms(["RS256"])
+            .pinned_thumbprint("other")
+            .build();
+
+        let result = verifier.verify_compact(&compact("RS256").await).await;
+
+        assert!(matches!(
+            result,
+            Err(Error::UnderlyingError {
+                source: PolicyError::UnpinnedThumbprint
+            })
+        ));
+    }
+}
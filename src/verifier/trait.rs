@@ -0,0 +1,219 @@
+//! Asynchronous signature verification traits.
+
+use std::borrow::Cow;
+
+use base64::{Engine, prelude::BASE64_URL_SAFE_NO_PAD};
+use bytes::Bytes;
+use serde_json::{Map, Value};
+use snafu::prelude::*;
+
+use crate::{
+    MaybeSend, MaybeSendSync,
+    jwk::PublicJwk,
+    verifier::{
+        VerifiedBytes,
+        error::{
+            AlgorithmMismatchSnafu, Error, InvalidSignatureSnafu, KeyIdMismatchSnafu,
+            MalformedSnafu, UnderlyingSnafu, UnsupportedCriticalHeaderSnafu,
+        },
+    },
+};
+
+/// Trait for verifiers of RFC 7515 (JWS) / RFC 7518 (JWA) compatible signatures.
+pub trait JwsVerifier: MaybeSendSync + Clone {
+    /// The error type returned by this verifier's operations.
+    type Error: std::error::Error + MaybeSendSync + 'static;
+
+    /// Returns the JWS algorithm identifier this verifier accepts.
+    fn jws_algorithm(&self) -> Cow<'_, str>;
+
+    /// Returns the key ID of the verifier's key.
+    fn key_id(&self) -> Option<Cow<'_, str>>;
+
+    /// Returns the public key of the verifier, if known.
+    ///
+    /// This is used to report the key thumbprint in [`VerifiedBytes`]. The
+    /// default implementation returns `None`.
+    fn public_key_jwk(&self) -> Option<Cow<'_, PublicJwk>> {
+        None
+    }
+
+    /// Asynchronously checks the signature over the given input.
+    ///
+    /// This should not be called directly, as it does not check the header
+    /// against the verifier's algorithm and key ID.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the verification operation fails. An invalid
+    /// signature is not an error, and returns `Ok(false)`.
+    fn verify_unchecked(
+        &self,
+        input: &[u8],
+        signature: &[u8],
+    ) -> impl Future<Output = Result<bool, Self::Error>> + MaybeSend;
+
+    /// Asynchronously verifies a JWS in the compact serialization.
+    ///
+    /// The `alg` header parameter must match this verifier's algorithm, and
+    /// the `kid` parameter (if both are present) its key ID. Any `crit`
+    /// parameter is rejected, as no extensions are understood.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the JWS is malformed, its header doesn't match this
+    /// verifier, the signature is invalid, or the verification operation fails.
+    fn verify_compact(
+        &self,
+        compact: &str,
+    ) -> impl Future<Output = Result<VerifiedBytes, Error<Self::Error>>> + MaybeSend {
+        async move {
+            let (input, encoded_signature) = compact.rsplit_once('.').context(MalformedSnafu {
+                reason: "expected three segments",
+            })?;
+            let (encoded_header, encoded_payload) =
+                input.split_once('.').context(MalformedSnafu {
+                    reason: "expected three segments",
+                })?;
+            let protected_header = decode_header(encoded_header)?;
+
+            let jws_algorithm = self.jws_algorithm().into_owned();
+            let alg = protected_header.get("alg").and_then(Value::as_str);
+            ensure!(
+                alg == Some(jws_algorithm.as_str()),
+                AlgorithmMismatchSnafu {
+                    expected: jws_algorithm.as_str(),
+                    actual: alg.unwrap_or_default(),
+                }
+            );
+            let key_id = self.key_id().map(Cow::into_owned);
+            if let (Some(kid), Some(expected)) = (protected_header.get("kid"), &key_id) {
+                ensure!(kid.as_str() == Some(expected.as_str()), KeyIdMismatchSnafu);
+            }
+            if let Some(crit) = protected_header.get("crit") {
+                let name = crit
+                    .as_array()
+                    .and_then(|names| names.first())
+                    .and_then(Value::as_str)
+                    .unwrap_or_default();
+                return UnsupportedCriticalHeaderSnafu { name }.fail();
+            }
+
+            let payload = BASE64_URL_SAFE_NO_PAD
+                .decode(encoded_payload)
+                .ok()
+                .context(MalformedSnafu {
+                    reason: "payload is not base64url",
+                })?;
+            let signature = BASE64_URL_SAFE_NO_PAD
+                .decode(encoded_signature)
+                .ok()
+                .context(MalformedSnafu {
+                    reason: "signature is not base64url",
+                })?;
+            let valid = self
+                .verify_unchecked(input.as_bytes(), &signature)
+                .await
+                .context(UnderlyingSnafu)?;
+            ensure!(valid, InvalidSignatureSnafu);
+
+            Ok(VerifiedBytes {
+                payload: Bytes::from(payload),
+                protected_header,
+                jws_algorithm,
+                key_id,
+                thumbprint: self.public_key_jwk().and_then(|jwk| jwk.thumbprint()),
+            })
+        }
+    }
+}
+
+pub(super) fn decode_header<E>(encoded_header: &str) -> Result<Map<String, Value>, Error<E>>
+where
+    E: std::error::Error + MaybeSendSync + 'static,
+{
+    let json = BASE64_URL_SAFE_NO_PAD
+        .decode(encoded_header)
+        .ok()
+        .context(MalformedSnafu {
+            reason: "header is not base64url",
+        })?;
+    serde_json::from_slice(&json).ok().context(MalformedSnafu {
+        reason: "header is not a JSON object",
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+
+    use super::*;
+    use crate::signer::{JwsSigner, MockSigner};
+
+    /// Accepts signatures equal to a fixed value.
+    #[derive(Debug, Clone)]
+    struct MockVerifier;
+
+    impl JwsVerifier for MockVerifier {
+        type Error = Infallible;
+
+        fn jws_algorithm(&self) -> Cow<'_, str> {
+            "ES256".into()
+        }
+
+        fn key_id(&self) -> Option<Cow<'_, str>> {
+            Some("key-1".into())
+        }
+
+        async fn verify_unchecked(
+            &self,
+            _input: &[u8],
+            signature: &[u8],
+        ) -> Result<bool, Self::Error> {
+            Ok(signature == b"sig")
+        }
+    }
+
+    async fn compact(signature: &'static [u8]) -> String {
+        MockSigner::builder()
+            .key_id("key-1")
+            .signature(signature)
+            .build()
+            .sign_jwt(
+                &serde_json::json!({"sub": "alice"}),
+                &crate::jws::Header::default(),
+            )
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_verify_compact() {
+        let verified = MockVerifier
+            .verify_compact(&compact(b"sig").await)
+            .await
+            .unwrap();
+
+        assert_eq!(verified.payload(), r#"{"sub":"alice"}"#);
+        assert_eq!(verified.jws_algorithm(), "ES256");
+        assert_eq!(verified.key_id(), Some("key-1"));
+        assert_eq!(
+            verified.protected_header().get("typ"),
+            Some(&Value::from("JWT"))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_invalid_signature_fails() {
+        let result = MockVerifier.verify_compact(&compact(b"bad").await).await;
+
+        assert!(matches!(result, Err(Error::InvalidSignature)));
+    }
+
+    #[tokio::test]
+    async fn test_malformed_fails() {
+        let result = MockVerifier.verify_compact("not-a-jws").await;
+
+        assert!(matches!(result, Err(Error::Malformed { .. })));
+    }
+}
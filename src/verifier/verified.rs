@@ -0,0 +1,53 @@
+use bytes::Bytes;
+use serde_json::{Map, Value};
+
+/// A verified payload together with the metadata of the key that verified it.
+///
+/// This mirrors [`SignedBytes`](crate::signer::SignedBytes) on the signing side.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerifiedBytes {
+    pub(super) payload: Bytes,
+    pub(super) protected_header: Map<String, Value>,
+    pub(super) jws_algorithm: String,
+    pub(super) key_id: Option<String>,
+    pub(super) thumbprint: Option<String>,
+}
+
+impl VerifiedBytes {
+    /// Returns the verified payload.
+    #[must_use]
+    pub fn payload(&self) -> &Bytes {
+        &self.payload
+    }
+
+    /// Returns the parsed protected header.
+    #[must_use]
+    pub fn protected_header(&self) -> &Map<String, Value> {
+        &self.protected_header
+    }
+
+    /// Returns the JWS algorithm the signature was verified with.
+    #[must_use]
+    pub fn jws_algorithm(&self) -> &str {
+        &self.jws_algorithm
+    }
+
+    /// Returns the key ID of the key that verified the signature, if any.
+    #[must_use]
+    pub fn key_id(&self) -> Option<&str> {
+        self.key_id.as_deref()
+    }
+
+    /// Returns the JWK thumbprint (RFC 7638) of the key that verified the
+    /// signature, if the verifier exposes its public key.
+    #[must_use]
+    pub fn thumbprint(&self) -> Option<&str> {
+        self.thumbprint.as_deref()
+    }
+
+    /// Consumes this value, returning the payload.
+    #[must_use]
+    pub fn into_payload(self) -> Bytes {
+        self.payload
+    }
+}
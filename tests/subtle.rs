@@ -0,0 +1,111 @@
+//! Web Crypto tests for `SubtleCryptoSigner`, run with `wasm-pack test --node -- --test subtle`.
+
+#![cfg(wasm_browser)]
+
+use chewie_crypto::signer::{JwsSigner, SubtleCryptoError, SubtleCryptoSigner};
+use js_sys::{Array, Object, Reflect, Uint8Array};
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use wasm_bindgen_test::wasm_bindgen_test;
+use web_sys::{Crypto, CryptoKey, SubtleCrypto};
+
+fn subtle() -> SubtleCrypto {
+    Reflect::get(&js_sys::global(), &"crypto".into())
+        .unwrap()
+        .dyn_into::<Crypto>()
+        .unwrap()
+        .subtle()
+}
+
+fn params(entries: &[(&str, JsValue)]) -> Object {
+    let params = Object::new();
+    for (key, value) in entries {
+        Reflect::set(&params, &(*key).into(), value).unwrap();
+    }
+    params
+}
+
+fn ec_params() -> Object {
+    params(&[
+        ("name", "ECDSA".into()),
+        ("namedCurve", "P-256".into()),
+        ("hash", "SHA-256".into()),
+    ])
+}
+
+fn rsa_params() -> Object {
+    params(&[
+        ("name", "RSASSA-PKCS1-v1_5".into()),
+        ("hash", "SHA-256".into()),
+        ("modulusLength", 2048.into()),
+        ("publicExponent", Uint8Array::from(&[1, 0, 1][..]).into()),
+    ])
+}
+
+/// Generates a key pair, returning the non-extractable private key and the public key.
+async fn generate(params: &Object) -> (CryptoKey, CryptoKey) {
+    let usages = Array::of2(&"sign".into(), &"verify".into());
+    let promise = subtle()
+        .generate_key_with_object(params, false, &usages)
+        .unwrap();
+    let pair = JsFuture::from(promise).await.unwrap();
+    let get = |name: &str| {
+        Reflect::get(&pair, &name.into())
+            .unwrap()
+            .dyn_into::<CryptoKey>()
+            .unwrap()
+    };
+    (get("privateKey"), get("publicKey"))
+}
+
+async fn verify(params: &Object, public: &CryptoKey, signature: &[u8], input: &[u8]) -> bool {
+    let promise = subtle()
+        .verify_with_object_and_u8_array_and_u8_slice(
+            params,
+            public,
+            &Uint8Array::from(signature),
+            input,
+        )
+        .unwrap();
+    JsFuture::from(promise).await.unwrap().is_truthy()
+}
+
+#[wasm_bindgen_test]
+async fn test_es256_signs() {
+    let (private, public) = generate(&ec_params()).await;
+    let signer = SubtleCryptoSigner::es256(private, Some("key-1".into())).unwrap();
+
+    let signature = signer.sign(b"input", "ES256", Some("key-1")).await.unwrap();
+
+    assert_eq!(signature.len(), 64);
+    assert!(verify(&ec_params(), &public, &signature, b"input").await);
+}
+
+#[wasm_bindgen_test]
+async fn test_rs256_signs() {
+    let (private, public) = generate(&rsa_params()).await;
+    let signer = SubtleCryptoSigner::rs256(private, None).unwrap();
+
+    let signature = signer.sign(b"input", "RS256", None).await.unwrap();
+
+    assert_eq!(signature.len(), 256);
+    assert!(verify(&rsa_params(), &public, &signature, b"input").await);
+}
+
+#[wasm_bindgen_test]
+async fn test_mismatched_key_is_rejected() {
+    let (rsa_private, _) = generate(&rsa_params()).await;
+    let (_, ec_public) = generate(&ec_params()).await;
+
+    assert!(matches!(
+        SubtleCryptoSigner::es256(rsa_private, None),
+        Err(SubtleCryptoError::InvalidKey {
+            jws_algorithm: "ES256",
+            ..
+        })
+    ));
+    assert!(matches!(
+        SubtleCryptoSigner::es256(ec_public, None),
+        Err(SubtleCryptoError::InvalidKey { .. })
+    ));
+}